@@ -9,7 +9,6 @@ import java.util.concurrent.ExecutorService;
 import java.util.concurrent.Executors;
 import java.util.concurrent.TimeUnit;
 import java.util.List;
-import java.util.ArrayList;
 
 public class ToolClient {
     private static final String HOST = "127.0.0.1";
@@ -38,21 +37,22 @@ public class ToolClient {
             sendPdu(output, SmppCodecFfiKt.encodeBindRequest(bindReq));
             readResponse(input, CMD_BIND_TRANSCEIVER_RESP);
 
-            // 2. Multi-threaded SubmitSm
+            // 2. Multi-threaded SubmitSm (the builder draws unique sequence numbers)
             System.out.println("\n--- 2. Multi-threaded SubmitSm ---");
             ExecutorService executor = Executors.newFixedThreadPool(5);
             
             for (int i = 0; i < 10; i++) {
-                final int seq = 100 + i;
+                final int n = i;
                 executor.submit(() -> {
                     try {
-                        SubmitSmRequest submitReq = new SubmitSmRequest(
-                            seq, "CMT", Ton.INTERNATIONAL, Npi.ISDN, "123456",
-                            Ton.NATIONAL, Npi.ISDN, "9876543210", (short)0, (short)0, (short)1,
-                            null, null, (short)1, (short)0, (short)0, (short)0,
-                            convertBytes(seq + ": Hello from Java Thread"),
-                            new ArrayList<>()
-                        );
+                        SubmitSmRequest submitReq = new SubmitSmBuilder()
+                            .serviceType("CMT")
+                            .source(SmppCodecFfiKt.addressFromE164("+123456"))
+                            .destination(SmppCodecFfiKt.addressNew(Ton.NATIONAL, Npi.ISDN, "9876543210"))
+                            .priority((short)1)
+                            .text(n + ": Hello from Java Thread")
+                            .deliveryReceipt(DeliveryReceiptPolicy.ON_SUCCESS_OR_FAILURE)
+                            .build();
                         
                         synchronized (output) {
                             sendPdu(output, SmppCodecFfiKt.encodeSubmitSmRequest(submitReq));
@@ -84,15 +84,6 @@ public class ToolClient {
         }
     }
 
-    private static List<UByte> convertBytes(String s) {
-        byte[] bytes = s.getBytes();
-        List<UByte> list = new ArrayList<>();
-        for (byte b : bytes) {
-            list.add(new UByte((short)(b & 0xFF)));
-        }
-        return list;
-    }
-
     private static void sendPdu(OutputStream output, List<UByte> data) throws Exception {
         byte[] bytes = new byte[data.size()];
         for (int i = 0; i < data.size(); i++) {
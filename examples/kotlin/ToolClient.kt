@@ -53,27 +53,13 @@ fun main() {
         
         for (i in splitResult.parts.indices) {
             val part = splitResult.parts[i]
-            val partReq = SubmitSmRequest(
-                sequenceNumber = (100 + i).toUInt(),
-                serviceType = "CMT",
-                sourceAddrTon = Ton.INTERNATIONAL,
-                sourceAddrNpi = Npi.ISDN,
-                sourceAddr = "123456",
-                destAddrTon = Ton.NATIONAL,
-                destAddrNpi = Npi.ISDN,
-                destinationAddr = "9876543210",
-                esmClass = 0x40u, // Indicating UDH is present
-                protocolId = 0u,
-                priorityFlag = 0u,
-                scheduleDeliveryTime = null,
-                validityPeriod = null,
-                registeredDelivery = 0u,
-                replaceIfPresentFlag = 0u,
-                dataCoding = splitResult.dataCoding,
-                smDefaultMsgId = 0u,
-                shortMessage = part.toList(), // Convert to List<UByte>
-                tlvs = emptyList()
-            )
+            val partReq = SubmitSmBuilder()
+                .serviceType("CMT")
+                .source(addressFromE164("+123456"))
+                .destination(addressNew(Ton.NATIONAL, Npi.ISDN, "9876543210"))
+                .esmClass(0x40u) // Indicating UDH is present
+                .shortMessage(part, splitResult.dataCoding)
+                .build()
             println("Sending part ${i + 1}...")
             sendPdu(output, encodeSubmitSmRequest(partReq))
             readResponse(input, CMD_SUBMIT_SM_RESP)
@@ -91,31 +77,15 @@ fun main() {
         
         for (i in splitResultSar.parts.indices) {
             val part = splitResultSar.parts[i]
-            val sarReq = SubmitSmRequest(
-                sequenceNumber = (200 + i).toUInt(),
-                serviceType = "CMT",
-                sourceAddrTon = Ton.INTERNATIONAL,
-                sourceAddrNpi = Npi.ISDN,
-                sourceAddr = "123456",
-                destAddrTon = Ton.NATIONAL,
-                destAddrNpi = Npi.ISDN,
-                destinationAddr = "9876543210",
-                esmClass = 0u,
-                protocolId = 0u,
-                priorityFlag = 0u,
-                scheduleDeliveryTime = null,
-                validityPeriod = null,
-                registeredDelivery = 0u,
-                replaceIfPresentFlag = 0u,
-                dataCoding = splitResultSar.dataCoding,
-                smDefaultMsgId = 0u,
-                shortMessage = part.toList(), // Convert to List<UByte>
-                tlvs = listOf(
-                    tlvNewU16(Tags.SAR_MSG_REF_NUM, refNum.toUShort()),
-                    tlvNewU8(Tags.SAR_TOTAL_SEGMENTS, totalParts),
-                    tlvNewU8(Tags.SAR_SEGMENT_SEQNUM, (i + 1).toUByte())
-                )
-            )
+            val sarReq = SubmitSmBuilder()
+                .serviceType("CMT")
+                .source(addressFromE164("+123456"))
+                .destination(addressNew(Ton.NATIONAL, Npi.ISDN, "9876543210"))
+                .shortMessage(part, splitResultSar.dataCoding)
+                .tlv(tlvNewU16(Tags.SAR_MSG_REF_NUM, refNum.toUShort()))
+                .tlv(tlvNewU8(Tags.SAR_TOTAL_SEGMENTS, totalParts))
+                .tlv(tlvNewU8(Tags.SAR_SEGMENT_SEQNUM, (i + 1).toUByte()))
+                .build()
             println("Sending part ${i + 1} (SAR)...")
             sendPdu(output, encodeSubmitSmRequest(sarReq))
             readResponse(input, CMD_SUBMIT_SM_RESP)
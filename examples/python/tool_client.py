@@ -21,7 +21,7 @@ CMD_BIND_TRANSCEIVER_RESP = 0x80000009
 CMD_ENQUIRE_LINK_RESP = 0x80000015
 CMD_SUBMIT_MULTI_SM_RESP = 0x80000021
 CMD_DATA_SM_RESP = 0x80000103
-CMD_BROADCAST_SM_RESP = 0x80000112 # Note: Using 0x80000112 based on common.rs standard, but check if different
+CMD_BROADCAST_SM_RESP = 0x80000111 # SMPP v5.0; 0x80000112 is query_broadcast_sm_resp
 CMD_QUERY_BROADCAST_SM_RESP = 0x80000112
 CMD_CANCEL_BROADCAST_SM_RESP = 0x80000113
 
@@ -197,12 +197,28 @@ def main():
             replace_if_present_flag=0,
             data_coding=0,
             sm_default_msg_id=0,
-            broadcast_area_identifier=smpp_codec_ffi.tlv_new(smpp_codec_ffi.Tags.BROADCAST_AREA_IDENTIFIER, [1, 2, 3]),
-            broadcast_content_type=smpp_codec_ffi.tlv_new(smpp_codec_ffi.Tags.BROADCAST_CONTENT_TYPE, [0, 0, 0]), # Dummy
-            broadcast_rep_num=smpp_codec_ffi.tlv_new_u16(smpp_codec_ffi.Tags.BROADCAST_REP_NUM, 1),
-            broadcast_frequency_interval=smpp_codec_ffi.tlv_new_u16(smpp_codec_ffi.Tags.BROADCAST_FREQUENCY_INTERVAL, 0),
-            short_message=b"Hello Broadcast",
-            tlvs=[]
+            optional_params=[
+                smpp_codec_ffi.tlv_new_broadcast_area_identifier(
+                    smpp_codec_ffi.BroadcastAreaIdentifier(
+                        format=smpp_codec_ffi.BroadcastAreaFormat.ALIAS_NAME,
+                        area=b"AREA1",
+                    )
+                ),
+                smpp_codec_ffi.tlv_new_broadcast_content_type(
+                    smpp_codec_ffi.BroadcastContentType(
+                        network_type=smpp_codec_ffi.BroadcastNetworkType.GSM,
+                        service=0x0001,
+                    )
+                ),
+                smpp_codec_ffi.tlv_new_broadcast_rep_num(1),
+                smpp_codec_ffi.tlv_new_broadcast_frequency_interval(
+                    smpp_codec_ffi.BroadcastFrequencyInterval(
+                        unit=smpp_codec_ffi.BroadcastFrequencyUnit.MINUTES,
+                        value=5,
+                    )
+                ),
+                smpp_codec_ffi.tlv_new_payload(smpp_codec_ffi.Tags.MESSAGE_PAYLOAD, b"Hello Broadcast"),
+            ]
         )
         send_pdu(sock, smpp_codec_ffi.encode_broadcast_sm_request(broadcast_req))
         read_response(sock, CMD_BROADCAST_SM_RESP)
@@ -210,9 +226,7 @@ def main():
         # 5. SubmitMulti
         print("\n--- 5. SubmitMulti ---")
         dest1 = smpp_codec_ffi.Destination.SME_ADDRESS(
-            ton=smpp_codec_ffi.Ton.INTERNATIONAL,
-            npi=smpp_codec_ffi.Npi.ISDN,
-            address="111111"
+            address=smpp_codec_ffi.address_from_e164("+111111")
         )
         dest2 = smpp_codec_ffi.Destination.DISTRIBUTION_LIST(name="MyList")
         multi_req = smpp_codec_ffi.SubmitMultiRequest(
@@ -180,28 +180,12 @@ func runClient() {
     for i in 0..<5 {
         group.enter()
         Task {
-            let seq = UInt32(300 + i)
-            let concurrentReq = SubmitSmRequest(
-                sequenceNumber: seq,
-                serviceType: "CMT",
-                sourceAddrTon: .international,
-                sourceAddrNpi: .isdn,
-                sourceAddr: "123456",
-                destAddrTon: .national,
-                destAddrNpi: .isdn,
-                destinationAddr: "9876543210",
-                esmClass: 0,
-                protocolId: 0,
-                priorityFlag: 0,
-                scheduleDeliveryTime: nil,
-                validityPeriod: nil,
-                registeredDelivery: 0,
-                replaceIfPresentFlag: 0,
-                dataCoding: 0,
-                smDefaultMsgId: 0,
-                shortMessage: Data("Hello from Swift Thread \(i)".utf8),
-                tlvs: []
-            )
+            let concurrentReq = try! SubmitSmBuilder()
+                .serviceType(serviceType: "CMT")
+                .source(address: addressFromE164(number: "+123456"))
+                .destination(address: addressNew(ton: .national, npi: .isdn, value: "9876543210"))
+                .text(text: "Hello from Swift Thread \(i)")
+                .build()
             
             print("Sending concurrent request \(i)...")
             sendData(encodeSubmitSmRequest(request: concurrentReq))
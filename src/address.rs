@@ -0,0 +1,203 @@
+//! # SME Addresses
+//!
+//! A single record for the `*_ton`, `*_npi` and `*_addr` triples carried by
+//! most PDUs, with validation per TON/NPI and E.164 normalization helpers.
+use crate::common::{Npi, SmppFfiError, Ton};
+use smpp_codec::encoding;
+
+/// An SME address: Type of Number, Numbering Plan Indicator and value.
+#[derive(uniffi::Record, Clone, Debug, PartialEq, Eq)]
+pub struct Address {
+    /// Type of Number.
+    pub ton: Ton,
+    /// Numbering Plan Indicator.
+    pub npi: Npi,
+    /// The address value.
+    pub value: String,
+}
+
+/// The PDU field an address is carried in, which determines its maximum size.
+#[derive(uniffi::Enum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AddressField {
+    /// `source_addr`/`destination_addr` of submit_sm, deliver_sm, submit_multi,
+    /// query_sm, cancel_sm, replace_sm and broadcast_sm (21 octets).
+    ShortMessage,
+    /// `source_addr`/`dest_addr` of data_sm (65 octets).
+    DataSm,
+    /// `source_addr`/`esme_addr` of alert_notification (65 octets).
+    AlertNotification,
+    /// `address_range` of the bind operations (41 octets).
+    AddressRange,
+}
+
+impl AddressField {
+    /// Returns the maximum C-Octet String size of the field, including the NULL.
+    pub fn max_len(self) -> usize {
+        match self {
+            AddressField::ShortMessage => 21,
+            AddressField::DataSm | AddressField::AlertNotification => 65,
+            AddressField::AddressRange => 41,
+        }
+    }
+}
+
+/// Maximum number of digits in an E.164 number.
+const E164_MAX_DIGITS: usize = 15;
+/// Maximum number of GSM characters in an alphanumeric sender.
+const ALPHANUMERIC_MAX_CHARS: usize = 11;
+
+/// Creates a new address.
+#[uniffi::export]
+pub fn address_new(ton: Ton, npi: Npi, value: String) -> Address {
+    Address { ton, npi, value }
+}
+
+/// Creates an alphanumeric address (TON Alphanumeric, NPI Unknown).
+///
+/// # Errors
+///
+/// Returns an error if the value is not a valid alphanumeric address.
+#[uniffi::export]
+pub fn address_alphanumeric(value: String) -> Result<Address, SmppFfiError> {
+    let address = Address {
+        ton: Ton::Alphanumeric,
+        npi: Npi::Unknown,
+        value,
+    };
+    address_validate(&address, AddressField::ShortMessage)?;
+    Ok(address)
+}
+
+/// Creates an International/ISDN address from an E.164 number.
+///
+/// Accepts a leading `+` or `00` prefix and common separators (spaces,
+/// dashes, dots and parentheses), which are stripped.
+///
+/// # Errors
+///
+/// Returns an error if the number is not a valid E.164 number.
+#[uniffi::export]
+pub fn address_from_e164(number: String) -> Result<Address, SmppFfiError> {
+    let cleaned = strip_separators(&number);
+    let digits = if let Some(rest) = cleaned.strip_prefix('+') {
+        rest
+    } else if let Some(rest) = cleaned.strip_prefix("00") {
+        rest
+    } else {
+        cleaned.as_str()
+    };
+    let address = Address {
+        ton: Ton::International,
+        npi: Npi::Isdn,
+        value: digits.to_string(),
+    };
+    address_validate(&address, AddressField::ShortMessage)?;
+    Ok(address)
+}
+
+/// Normalizes an address.
+///
+/// Separators are removed from numeric addresses, and a value with a leading
+/// `+` becomes an International/ISDN address without the `+`.
+/// Alphanumeric addresses are returned unchanged.
+#[uniffi::export]
+pub fn address_normalize(address: &Address) -> Address {
+    if address.ton == Ton::Alphanumeric {
+        return address.clone();
+    }
+    let cleaned = strip_separators(&address.value);
+    match cleaned.strip_prefix('+') {
+        Some(digits) => Address {
+            ton: Ton::International,
+            npi: Npi::Isdn,
+            value: digits.to_string(),
+        },
+        None => Address {
+            ton: address.ton,
+            npi: address.npi,
+            value: cleaned,
+        },
+    }
+}
+
+/// Validates an address against its TON/NPI rules and the size of its field.
+///
+/// An empty value is accepted, as the SMSC then applies its default address.
+///
+/// # Errors
+///
+/// Returns an error describing the first rule the address breaks.
+#[uniffi::export]
+pub fn address_validate(address: &Address, field: AddressField) -> Result<(), SmppFfiError> {
+    let value = &address.value;
+    if value.len() + 1 > field.max_len() {
+        return Err(SmppFfiError::Generic {
+            msg: format!(
+                "Address '{}' is {} octets, maximum is {} for this field",
+                value,
+                value.len(),
+                field.max_len() - 1
+            ),
+        });
+    }
+    if value.is_empty() || field == AddressField::AddressRange {
+        return Ok(());
+    }
+
+    match address.ton {
+        Ton::Alphanumeric => {
+            let septets = encoding::gsm_7bit_encode(value).map_err(|e| SmppFfiError::Generic {
+                msg: format!("Alphanumeric address '{}' is not GSM 7-bit: {}", value, e),
+            })?;
+            if septets.len() > ALPHANUMERIC_MAX_CHARS {
+                return Err(SmppFfiError::Generic {
+                    msg: format!(
+                        "Alphanumeric address '{}' exceeds {} GSM characters",
+                        value, ALPHANUMERIC_MAX_CHARS
+                    ),
+                });
+            }
+        }
+        Ton::International => {
+            check_digits(value)?;
+            if address.npi == Npi::Isdn && value.len() > E164_MAX_DIGITS {
+                return Err(SmppFfiError::Generic {
+                    msg: format!(
+                        "International address '{}' exceeds {} digits",
+                        value, E164_MAX_DIGITS
+                    ),
+                });
+            }
+        }
+        _ => match address.npi {
+            Npi::Isdn | Npi::Data | Npi::Telex | Npi::LandMobile | Npi::National => {
+                check_digits(value)?
+            }
+            _ => {
+                if !value.chars().all(|c| c.is_ascii_graphic()) {
+                    return Err(SmppFfiError::Generic {
+                        msg: format!("Address '{}' contains non-printable characters", value),
+                    });
+                }
+            }
+        },
+    }
+    Ok(())
+}
+
+fn check_digits(value: &str) -> Result<(), SmppFfiError> {
+    if !value.chars().all(|c| c.is_ascii_digit()) {
+        return Err(SmppFfiError::Generic {
+            msg: format!("Address '{}' must contain digits only", value),
+        });
+    }
+    Ok(())
+}
+
+fn strip_separators(value: &str) -> String {
+    value
+        .trim()
+        .chars()
+        .filter(|c| !matches!(c, ' ' | '-' | '.' | '(' | ')'))
+        .collect()
+}
@@ -0,0 +1,110 @@
+//! # Address Ranges
+//!
+//! Evaluates the `address_range` of a bind: a UNIX regular expression over
+//! SME addresses, qualified by `addr_ton` and `addr_npi`. An SMSC uses it to
+//! pick the receiver binds that mobile-originated messages are routed to;
+//! an ESME can use it to check a range before binding.
+use crate::address::Address;
+use crate::common::{Npi, SmppFfiError, Ton};
+use crate::session_pdu_ffi::BindRequest;
+use regex_lite::Regex;
+use std::sync::Arc;
+
+/// Maximum size of `address_range` in octets, excluding the NULL.
+const ADDRESS_RANGE_MAX_LEN: usize = 40;
+
+/// A compiled `address_range`.
+///
+/// The expression matches anywhere in an address unless anchored with `^`
+/// and `$`; an empty range matches every address. `Ton::Unknown` and
+/// `Npi::Unknown` accept any TON and NPI.
+#[derive(uniffi::Object, Debug)]
+pub struct AddressRange {
+    ton: Ton,
+    npi: Npi,
+    pattern: String,
+    regex: Option<Regex>,
+}
+
+#[uniffi::export]
+impl AddressRange {
+    /// Compiles an address range.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the range is longer than 40 octets or is not a valid regular expression.
+    #[uniffi::constructor]
+    pub fn new(ton: Ton, npi: Npi, address_range: String) -> Result<Arc<Self>, SmppFfiError> {
+        Self::compile(ton, npi, address_range).map(Arc::new)
+    }
+
+    /// Compiles the `addr_ton`, `addr_npi` and `address_range` of a bind request.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error like `new`.
+    #[uniffi::constructor]
+    pub fn from_bind(request: BindRequest) -> Result<Arc<Self>, SmppFfiError> {
+        Self::new(request.addr_ton, request.addr_npi, request.address_range)
+    }
+
+    /// Returns true if the range covers an address.
+    pub fn matches(&self, address: Address) -> bool {
+        self.covers(address.ton, address.npi, &address.value)
+    }
+
+    /// Returns the expression the range was compiled from.
+    pub fn pattern(&self) -> String {
+        self.pattern.clone()
+    }
+}
+
+impl AddressRange {
+    pub(crate) fn compile(ton: Ton, npi: Npi, address_range: String) -> Result<Self, SmppFfiError> {
+        if address_range.len() > ADDRESS_RANGE_MAX_LEN {
+            return Err(SmppFfiError::Generic {
+                msg: format!(
+                    "Address range '{}' is {} octets, maximum is {}",
+                    address_range,
+                    address_range.len(),
+                    ADDRESS_RANGE_MAX_LEN
+                ),
+            });
+        }
+        let regex = if address_range.is_empty() {
+            None
+        } else {
+            Some(
+                Regex::new(&address_range).map_err(|e| SmppFfiError::Generic {
+                    msg: format!("Invalid address range '{}': {}", address_range, e),
+                })?,
+            )
+        };
+        Ok(Self {
+            ton,
+            npi,
+            pattern: address_range,
+            regex,
+        })
+    }
+
+    /// Returns true if the range covers the address given as its three fields.
+    pub(crate) fn covers(&self, ton: Ton, npi: Npi, value: &str) -> bool {
+        (self.ton == Ton::Unknown || self.ton == ton)
+            && (self.npi == Npi::Unknown || self.npi == npi)
+            && self
+                .regex
+                .as_ref()
+                .is_none_or(|regex| regex.is_match(value))
+    }
+}
+
+/// Checks that an address range compiles, e.g. before binding with it.
+///
+/// # Errors
+///
+/// Returns an error like `AddressRange::new`.
+#[uniffi::export]
+pub fn address_range_validate(address_range: String) -> Result<(), SmppFfiError> {
+    AddressRange::compile(Ton::Unknown, Npi::Unknown, address_range).map(|_| ())
+}
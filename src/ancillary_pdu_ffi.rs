@@ -11,24 +11,49 @@ use smpp_codec::pdus::{
 };
 
 /// Represents the state of a message.
-#[derive(uniffi::Enum, Clone, Debug, PartialEq)]
+///
+/// The discriminants are the `message_state` values on the wire.
+#[derive(uniffi::Enum, Clone, Copy, Debug, PartialEq, Eq, Hash)]
 pub enum MessageState {
+    /// Message is waiting for its scheduled delivery time (SMPP 5.0)
+    Scheduled = 0,
     /// Message is in enroute state
-    Enroute,
+    Enroute = 1,
     /// Message is delivered
-    Delivered,
+    Delivered = 2,
     /// Message validity period has expired
-    Expired,
+    Expired = 3,
     /// Message has been deleted
-    Deleted,
+    Deleted = 4,
     /// Message is undeliverable
-    Undeliverable,
+    Undeliverable = 5,
     /// Message is in accepted state
-    Accepted,
+    Accepted = 6,
     /// Message is in invalid state
-    Unknown,
+    Unknown = 7,
     /// Message is in rejected state
-    Rejected,
+    Rejected = 8,
+    /// Message was skipped by the SMSC (SMPP 5.0)
+    Skipped = 9,
+}
+
+impl MessageState {
+    /// Returns the state for a `message_state` value.
+    pub(crate) fn from_u8(value: u8) -> Option<Self> {
+        Some(match value {
+            0 => Self::Scheduled,
+            1 => Self::Enroute,
+            2 => Self::Delivered,
+            3 => Self::Expired,
+            4 => Self::Deleted,
+            5 => Self::Undeliverable,
+            6 => Self::Accepted,
+            7 => Self::Unknown,
+            8 => Self::Rejected,
+            9 => Self::Skipped,
+            _ => return None,
+        })
+    }
 }
 
 impl From<NativeMessageState> for MessageState {
@@ -55,8 +80,11 @@ impl From<MessageState> for NativeMessageState {
             MessageState::Deleted => NativeMessageState::Deleted,
             MessageState::Undeliverable => NativeMessageState::Undeliverable,
             MessageState::Accepted => NativeMessageState::Accepted,
-            MessageState::Unknown => NativeMessageState::Unknown,
             MessageState::Rejected => NativeMessageState::Rejected,
+            // The codec predates the SMPP 5.0 states.
+            MessageState::Unknown | MessageState::Scheduled | MessageState::Skipped => {
+                NativeMessageState::Unknown
+            }
         }
     }
 }
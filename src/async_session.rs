@@ -0,0 +1,553 @@
+//! # Async ESME Session
+//!
+//! A non-blocking client session on a shared Tokio runtime. Its operations
+//! are exported as async functions, which the bindings expose as Kotlin
+//! `suspend` functions, Swift `async` methods and Python awaitables. A
+//! request waiting for its response holds no thread, so thousands of submits
+//! can be in flight at once. Keepalive, reconnection and throttling remain
+//! with the blocking `EsmeSession`.
+use crate::ancillary_pdu_ffi::{encode_query_sm_request, QuerySmRequest, QuerySmResponse};
+use crate::common::*;
+use crate::delivery_pdu_ffi::{encode_delivery_sm_response, DeliverSmResponse};
+use crate::esme_session::{
+    closed_error, not_bound_error, reject_generic_nack, status_error, unexpected_response,
+    DeliverSmListener, EsmeSessionConfig,
+};
+use crate::framer::check_command_length;
+use crate::pdu::*;
+use crate::request_window::{RequestWindowConfig, Window};
+use crate::sequence::SequenceGenerator;
+use crate::session_pdu_ffi::*;
+use crate::submission_pdu_ffi::{encode_submit_sm_request, SubmitSmRequest, SubmitSmResponse};
+use crate::tls::connect_async;
+use smpp_codec::common::get_status_description;
+use std::future::Future;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::{Duration, Instant};
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+use tokio::net::TcpStream;
+use tokio::runtime::{Builder, Runtime};
+use tokio::sync::{oneshot, Semaphore};
+use tokio::task::JoinHandle;
+use tokio::time::timeout;
+
+type Reply = (PduHeader, Result<Pdu, SmppFfiError>);
+
+/// An ESME session whose operations are awaited instead of blocking.
+///
+/// Created unbound; `bind` connects and binds it. Only the host, port,
+/// timeouts, `max_in_flight` and TLS settings of its `EsmeSessionConfig` apply.
+#[derive(uniffi::Object)]
+pub struct AsyncEsmeSession {
+    config: EsmeSessionConfig,
+    listener: Option<Arc<dyn DeliverSmListener>>,
+    sequence: Arc<SequenceGenerator>,
+    link: Mutex<Option<Arc<Link>>>,
+}
+
+#[uniffi::export]
+impl AsyncEsmeSession {
+    /// Creates an unbound session. Inbound `deliver_sm` goes to `listener`.
+    #[uniffi::constructor]
+    pub fn new(
+        config: EsmeSessionConfig,
+        listener: Option<Arc<dyn DeliverSmListener>>,
+    ) -> Arc<Self> {
+        Self::with_sequence_generator(config, listener, SequenceGenerator::new())
+    }
+
+    /// Creates an unbound session drawing sequence numbers from `sequence`.
+    #[uniffi::constructor]
+    pub fn with_sequence_generator(
+        config: EsmeSessionConfig,
+        listener: Option<Arc<dyn DeliverSmListener>>,
+        sequence: Arc<SequenceGenerator>,
+    ) -> Arc<Self> {
+        Arc::new(Self {
+            config,
+            listener,
+            sequence,
+            link: Mutex::new(None),
+        })
+    }
+
+    /// Connects to the SMSC and binds with the given request.
+    ///
+    /// The sequence number of the bind request is assigned by the session.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the session is already bound, the connection
+    /// fails or the SMSC rejects the bind.
+    pub async fn bind(self: Arc<Self>, bind: BindRequest) -> Result<(), SmppFfiError> {
+        on_runtime(async move {
+            if self.current_link().is_some() {
+                return Err(SmppFfiError::Generic {
+                    msg: "Session is already bound".to_string(),
+                });
+            }
+            let link = self.connect().await?;
+            let mut bind = bind;
+            let response = link
+                .request(|sequence_number| {
+                    bind.sequence_number = sequence_number;
+                    encode_bind_request(&bind)
+                })
+                .await;
+            let failure = match response {
+                Ok(Pdu::BindResponse { pdu }) if pdu.command_status == COMMAND_STATUS_OK => {
+                    link.bound.store(true, Ordering::SeqCst);
+                    *self.link.lock().unwrap() = Some(link);
+                    return Ok(());
+                }
+                Ok(Pdu::BindResponse { pdu }) => status_error("Bind", pdu.command_status),
+                Ok(other) => unexpected_response("Bind", &other),
+                Err(e) => e,
+            };
+            link.close().await;
+            Err(failure)
+        })
+        .await
+    }
+
+    /// Submits a message and resolves with its `submit_sm_resp`.
+    ///
+    /// The sequence number of the request is assigned by the session.
+    /// A response with a non-zero `command_status` is returned as is.
+    /// Concurrent calls are pipelined; beyond `max_in_flight` they wait for a free slot.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the session is not bound, the link fails, the
+    /// response times out or the SMSC answers with `generic_nack`.
+    pub async fn submit(
+        self: Arc<Self>,
+        request: SubmitSmRequest,
+    ) -> Result<SubmitSmResponse, SmppFfiError> {
+        on_runtime(async move {
+            let mut request = request;
+            let response = self
+                .bound_link()?
+                .request(|sequence_number| {
+                    request.sequence_number = sequence_number;
+                    encode_submit_sm_request(&request)
+                })
+                .await?;
+            match response {
+                Pdu::SubmitSmResponse { pdu } => Ok(pdu),
+                other => Err(unexpected_response("SubmitSm", &other)),
+            }
+        })
+        .await
+    }
+
+    /// Queries the state of a submitted message and resolves with its `query_sm_resp`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error like `submit`.
+    pub async fn query(
+        self: Arc<Self>,
+        request: QuerySmRequest,
+    ) -> Result<QuerySmResponse, SmppFfiError> {
+        on_runtime(async move {
+            let mut request = request;
+            let response = self
+                .bound_link()?
+                .request(|sequence_number| {
+                    request.sequence_number = sequence_number;
+                    encode_query_sm_request(&request)
+                })
+                .await?;
+            match response {
+                Pdu::QuerySmResponse { pdu } => Ok(pdu),
+                other => Err(unexpected_response("QuerySm", &other)),
+            }
+        })
+        .await
+    }
+
+    /// Unbinds from the SMSC and closes the connection.
+    ///
+    /// Calling it on a session that is no longer bound only closes the connection.
+    /// The session can be bound again afterwards.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the SMSC does not acknowledge the unbind.
+    pub async fn unbind(self: Arc<Self>) -> Result<(), SmppFfiError> {
+        on_runtime(async move {
+            let Some(link) = self.link.lock().unwrap().take() else {
+                return Ok(());
+            };
+            let result = if link.bound.swap(false, Ordering::SeqCst) {
+                match link
+                    .request(|sequence_number| encode_unbind(&Unbind { sequence_number }))
+                    .await
+                {
+                    Ok(Pdu::UnbindResponse { .. }) => Ok(()),
+                    Ok(other) => Err(unexpected_response("Unbind", &other)),
+                    Err(e) => Err(e),
+                }
+            } else {
+                Ok(())
+            };
+            link.close().await;
+            result
+        })
+        .await
+    }
+
+    /// Returns true while the session is bound.
+    pub fn is_bound(&self) -> bool {
+        self.current_link()
+            .is_some_and(|link| link.bound.load(Ordering::SeqCst))
+    }
+
+    /// Returns the generator that assigns this session's sequence numbers.
+    pub fn sequence_generator(&self) -> Arc<SequenceGenerator> {
+        self.sequence.clone()
+    }
+
+    /// Returns the number of requests awaiting a response.
+    pub fn in_flight(&self) -> u32 {
+        self.current_link()
+            .map_or(0, |link| link.window.lock().unwrap().len() as u32)
+    }
+
+    /// Returns the number of responses that matched no request, on the current link.
+    pub fn orphan_responses(&self) -> u64 {
+        self.current_link()
+            .map_or(0, |link| link.orphan_responses.load(Ordering::SeqCst))
+    }
+}
+
+impl AsyncEsmeSession {
+    fn current_link(&self) -> Option<Arc<Link>> {
+        self.link
+            .lock()
+            .unwrap()
+            .clone()
+            .filter(|link| link.open.load(Ordering::SeqCst))
+    }
+
+    fn bound_link(&self) -> Result<Arc<Link>, SmppFfiError> {
+        self.current_link()
+            .filter(|link| link.bound.load(Ordering::SeqCst))
+            .ok_or_else(not_bound_error)
+    }
+
+    /// Opens a connection, completing the TLS handshake if configured, and starts reading from it.
+    async fn connect(&self) -> Result<Arc<Link>, SmppFfiError> {
+        let connect_timeout = Duration::from_millis(self.config.connect_timeout_ms);
+        let (reader, writer) = match timeout(connect_timeout, self.open()).await {
+            Ok(Ok(halves)) => halves,
+            Ok(Err(e)) => return Err(e),
+            Err(_) => {
+                return Err(SmppFfiError::Generic {
+                    msg: format!(
+                        "Timed out connecting to {}:{}",
+                        self.config.host, self.config.port
+                    ),
+                })
+            }
+        };
+        let link = Arc::new(Link {
+            writer: tokio::sync::Mutex::new(writer),
+            window: Mutex::new(Window::new(RequestWindowConfig {
+                max_in_flight: self.config.max_in_flight,
+                response_timeout_ms: self.config.response_timeout_ms,
+            })),
+            slots: Semaphore::new(self.config.max_in_flight as usize),
+            started: Instant::now(),
+            sequence: self.sequence.clone(),
+            open: AtomicBool::new(true),
+            bound: AtomicBool::new(false),
+            response_timeout: Duration::from_millis(self.config.response_timeout_ms),
+            listener: self.listener.clone(),
+            orphan_responses: AtomicU64::new(0),
+            reader: Mutex::new(None),
+        });
+        let reader = tokio::spawn(link.clone().read_loop(reader));
+        *link.reader.lock().unwrap() = Some(reader);
+        Ok(link)
+    }
+}
+
+impl AsyncEsmeSession {
+    async fn open(&self) -> Result<crate::tls::AsyncHalves, SmppFfiError> {
+        let stream = TcpStream::connect((self.config.host.as_str(), self.config.port))
+            .await
+            .map_err(|e| SmppFfiError::Generic {
+                msg: format!(
+                    "Failed to connect to {}:{}: {}",
+                    self.config.host, self.config.port, e
+                ),
+            })?;
+        let _ = stream.set_nodelay(true);
+        connect_async(stream, self.config.tls.as_ref(), &self.config.host).await
+    }
+}
+
+impl Drop for AsyncEsmeSession {
+    fn drop(&mut self) {
+        if let Some(link) = self.link.get_mut().unwrap().take() {
+            link.finish();
+            if let Some(reader) = link.reader.lock().unwrap().take() {
+                reader.abort();
+            }
+        }
+    }
+}
+
+/// Returns the runtime that drives every async session.
+///
+/// # Panics
+///
+/// Panics if the runtime cannot be started.
+fn runtime() -> &'static Runtime {
+    static RUNTIME: OnceLock<Runtime> = OnceLock::new();
+    RUNTIME.get_or_init(|| {
+        Builder::new_multi_thread()
+            .enable_all()
+            .thread_name("smpp-async")
+            .build()
+            .expect("Failed to start the async runtime")
+    })
+}
+
+/// Runs `future` on the session runtime, whichever executor awaits the result.
+async fn on_runtime<T: Send + 'static>(
+    future: impl Future<Output = Result<T, SmppFfiError>> + Send + 'static,
+) -> Result<T, SmppFfiError> {
+    runtime()
+        .spawn(future)
+        .await
+        .map_err(|e| SmppFfiError::Generic {
+            msg: format!("Async task failed: {}", e),
+        })?
+}
+
+/// One TCP connection, shared between the session and its reader task.
+struct Link {
+    writer: tokio::sync::Mutex<Box<dyn AsyncWrite + Send + Unpin>>,
+    window: Mutex<Window<oneshot::Sender<Reply>>>,
+    /// One permit per free slot in the window.
+    slots: Semaphore,
+    started: Instant,
+    sequence: Arc<SequenceGenerator>,
+    open: AtomicBool,
+    bound: AtomicBool,
+    response_timeout: Duration,
+    listener: Option<Arc<dyn DeliverSmListener>>,
+    orphan_responses: AtomicU64,
+    reader: Mutex<Option<JoinHandle<()>>>,
+}
+
+impl Link {
+    /// Sends a request built for a fresh sequence number and waits for its response.
+    async fn request(&self, encode: impl FnOnce(u32) -> Vec<u8>) -> Result<Pdu, SmppFfiError> {
+        let (_, response) = self.exchange(encode).await?;
+        reject_generic_nack(response)
+    }
+
+    /// Like `request`, but returns the response with its header, `generic_nack` included.
+    async fn exchange(&self, encode: impl FnOnce(u32) -> Vec<u8>) -> Result<Reply, SmppFfiError> {
+        let _slot = match timeout(self.response_timeout, self.slots.acquire()).await {
+            Ok(Ok(slot)) => slot,
+            Ok(Err(_)) => return Err(closed_error()),
+            Err(_) => {
+                return Err(SmppFfiError::Generic {
+                    msg: "Timed out waiting for a free slot in the request window".to_string(),
+                })
+            }
+        };
+        let sequence_number = self.sequence.next_value();
+        let bytes = encode(sequence_number);
+        let command_id = u32::from_be_bytes([bytes[4], bytes[5], bytes[6], bytes[7]]);
+        let (sender, receiver) = oneshot::channel();
+        {
+            let mut window = self.window.lock().unwrap();
+            // Checked under the lock so that no request slips in after `finish` drains.
+            if !self.open.load(Ordering::SeqCst) {
+                return Err(closed_error());
+            }
+            window.insert(sequence_number, command_id, self.now_ms(), sender)?;
+        }
+
+        if let Err(e) = self.write(&bytes).await {
+            self.window.lock().unwrap().remove(sequence_number);
+            return Err(e);
+        }
+        match timeout(self.response_timeout, receiver).await {
+            Ok(Ok(reply)) => Ok(reply),
+            Ok(Err(_)) => Err(closed_error()),
+            Err(_) => {
+                self.window.lock().unwrap().remove(sequence_number);
+                Err(SmppFfiError::Generic {
+                    msg: format!(
+                        "Timed out waiting for the response to sequence number {}",
+                        sequence_number
+                    ),
+                })
+            }
+        }
+    }
+
+    /// Hands a response to the request waiting for it.
+    fn complete(&self, header: &PduHeader, response: Result<Pdu, SmppFfiError>) {
+        let completed = self.window.lock().unwrap().complete(header);
+        match completed {
+            Some((_, sender)) => {
+                let _ = sender.send((*header, response));
+            }
+            None => {
+                self.orphan_responses.fetch_add(1, Ordering::SeqCst);
+            }
+        }
+    }
+
+    fn now_ms(&self) -> u64 {
+        self.started.elapsed().as_millis() as u64
+    }
+
+    async fn write(&self, bytes: &[u8]) -> Result<(), SmppFfiError> {
+        self.writer
+            .lock()
+            .await
+            .write_all(bytes)
+            .await
+            .map_err(SmppFfiError::from)
+    }
+
+    /// Marks the link closed and fails every request still waiting.
+    fn finish(&self) {
+        self.open.store(false, Ordering::SeqCst);
+        self.bound.store(false, Ordering::SeqCst);
+        // Dropping the senders wakes every waiting request with a closed error.
+        self.window.lock().unwrap().drain();
+        self.slots.close();
+    }
+
+    /// Closes the connection and stops the reader task.
+    async fn close(&self) {
+        self.finish();
+        let _ = self.writer.lock().await.shutdown().await;
+        if let Some(reader) = self.reader.lock().unwrap().take() {
+            reader.abort();
+        }
+    }
+
+    async fn read_loop(self: Arc<Self>, mut reader: Box<dyn AsyncRead + Send + Unpin>) {
+        while let Ok(frame) = read_frame(&mut reader).await {
+            if !self.dispatch(&frame).await {
+                break;
+            }
+        }
+        self.finish();
+        let _ = self.writer.lock().await.shutdown().await;
+    }
+
+    /// Handles one inbound PDU. Returns false when the link must close.
+    async fn dispatch(&self, frame: &[u8]) -> bool {
+        let header = match decode_pdu_header(frame) {
+            Ok(header) => header,
+            Err(_) => return false,
+        };
+        let pdu = match decode_pdu(frame) {
+            Ok(pdu) => pdu,
+            // A body-less response carries only an error status.
+            Err(_) if header.command_id & 0x8000_0000 != 0 => {
+                self.complete(&header, Err(status_error("Request", header.command_status)));
+                return true;
+            }
+            Err(_) if is_supported_command_id(header.command_id) => {
+                let _ = self
+                    .write(&encode_error_response(&header, ESME_RSYSERR))
+                    .await;
+                return true;
+            }
+            Err(_) => {
+                self.reply_generic_nack(header.sequence_number, ESME_RINVCMDID)
+                    .await;
+                return true;
+            }
+        };
+
+        match pdu {
+            Pdu::EnquireLink { pdu } => {
+                let _ = self
+                    .write(&encode_enquire_link_response(&EnquireLinkResponse {
+                        sequence_number: pdu.sequence_number,
+                        command_status: COMMAND_STATUS_OK,
+                    }))
+                    .await;
+                true
+            }
+            Pdu::DeliverSm { pdu } => {
+                let sequence_number = pdu.sequence_number;
+                let command_status = match &self.listener {
+                    // The listener is foreign code that may block, so it runs off the workers.
+                    Some(listener) => {
+                        let listener = listener.clone();
+                        tokio::task::spawn_blocking(move || listener.on_deliver_sm(pdu))
+                            .await
+                            .unwrap_or(ESME_RSYSERR)
+                    }
+                    None => COMMAND_STATUS_OK,
+                };
+                let _ = self
+                    .write(&encode_delivery_sm_response(&DeliverSmResponse {
+                        sequence_number,
+                        command_status,
+                        message_id: String::new(),
+                        status_description: get_status_description(command_status),
+                    }))
+                    .await;
+                true
+            }
+            Pdu::Unbind { pdu } => {
+                self.bound.store(false, Ordering::SeqCst);
+                let _ = self
+                    .write(&encode_unbind_response(&UnbindResponse {
+                        sequence_number: pdu.sequence_number,
+                        command_status: COMMAND_STATUS_OK,
+                    }))
+                    .await;
+                false
+            }
+            response if header.command_id & 0x8000_0000 != 0 => {
+                self.complete(&header, Ok(response));
+                true
+            }
+            _ => {
+                self.reply_generic_nack(header.sequence_number, ESME_RINVBNDSTS)
+                    .await;
+                true
+            }
+        }
+    }
+
+    async fn reply_generic_nack(&self, sequence_number: u32, command_status: u32) {
+        let _ = self
+            .write(&encode_generic_nack(&GenericNack {
+                sequence_number,
+                command_status,
+            }))
+            .await;
+    }
+}
+
+/// Reads one complete PDU, header included, like `framer::read_frame`.
+async fn read_frame(reader: &mut (dyn AsyncRead + Send + Unpin)) -> Result<Vec<u8>, SmppFfiError> {
+    let mut length = [0u8; 4];
+    reader.read_exact(&mut length).await?;
+    let command_length = u32::from_be_bytes(length) as usize;
+    check_command_length(command_length)?;
+
+    let mut frame = vec![0u8; command_length];
+    frame[..4].copy_from_slice(&length);
+    reader.read_exact(&mut frame[4..]).await?;
+    Ok(frame)
+}
@@ -0,0 +1,374 @@
+//! # Bind Authentication
+//!
+//! A ready-made `BindAuthenticator` for `SmscListener`. Binds are checked
+//! against ESME accounts held in memory or looked up through a foreign
+//! callback, each account limiting the bind modes and source addresses it
+//! may use. Repeated credential failures lock the `system_id` out for a while.
+use crate::common::*;
+use crate::session_pdu_ffi::BindRequest;
+use crate::smsc_listener::BindAuthenticator;
+use std::collections::HashMap;
+use std::net::{IpAddr, SocketAddr};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+/// An ESME allowed to bind.
+#[derive(uniffi::Record, Clone, Debug, PartialEq, Eq)]
+pub struct EsmeAccount {
+    /// The system_id the ESME binds with.
+    pub system_id: String,
+    /// The password the ESME binds with.
+    pub password: String,
+    /// The system_type the ESME must bind with. Empty accepts any.
+    #[uniffi(default = "")]
+    pub system_type: String,
+    /// Bind modes the account may use. Empty allows all of them.
+    #[uniffi(default = [])]
+    pub allowed_modes: Vec<BindMode>,
+    /// Source addresses the account may bind from, as IP addresses or CIDR
+    /// ranges such as `10.0.0.0/8`. Empty allows any address.
+    #[uniffi(default = [])]
+    pub allowed_ip_ranges: Vec<String>,
+}
+
+/// Creates an `EsmeAccount` allowed to bind in any mode from anywhere.
+#[uniffi::export]
+pub fn esme_account_new(system_id: String, password: String) -> EsmeAccount {
+    EsmeAccount {
+        system_id,
+        password,
+        system_type: String::new(),
+        allowed_modes: Vec::new(),
+        allowed_ip_ranges: Vec::new(),
+    }
+}
+
+/// Finds ESME accounts in the host's own storage.
+#[uniffi::export(with_foreign)]
+pub trait AccountLookup: Send + Sync {
+    /// Returns the account with `system_id`, or `None` if there is none.
+    fn find_account(&self, system_id: String) -> Option<EsmeAccount>;
+}
+
+/// Lockout settings of an `AccountAuthenticator`.
+#[derive(uniffi::Record, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct BindAuthConfig {
+    /// Failed binds in a row after which a system_id is locked out. 0 never locks.
+    #[uniffi(default = 5)]
+    pub max_failed_attempts: u32,
+    /// How long a lockout lasts, in milliseconds.
+    #[uniffi(default = 300000)]
+    pub lockout_duration_ms: u64,
+}
+
+impl Default for BindAuthConfig {
+    fn default() -> Self {
+        Self {
+            max_failed_attempts: 5,
+            lockout_duration_ms: 300_000,
+        }
+    }
+}
+
+/// Creates a `BindAuthConfig` locking a system_id out for 5 minutes after 5 failed binds.
+#[uniffi::export]
+pub fn bind_auth_config_default() -> BindAuthConfig {
+    BindAuthConfig::default()
+}
+
+/// A system_id that is currently locked out.
+#[derive(uniffi::Record, Clone, Debug, PartialEq, Eq)]
+pub struct BindLockout {
+    /// The locked system_id.
+    pub system_id: String,
+    /// Failed binds that led to the lockout.
+    pub failed_attempts: u32,
+    /// Time until binds are accepted again, in milliseconds.
+    pub remaining_ms: u64,
+}
+
+/// Checks binds against ESME accounts.
+///
+/// Answers `ESME_RINVSYSID` for an unknown system_id, `ESME_RINVPASWD` for
+/// a wrong password and `ESME_RINVSYSTYP` for a wrong system_type. A bind
+/// from a disallowed address or in a disallowed mode, and any bind of a
+/// locked-out system_id, gets `ESME_RBINDFAIL`. Only wrong system_ids and
+/// passwords count towards a lockout.
+#[derive(uniffi::Object)]
+pub struct AccountAuthenticator {
+    config: BindAuthConfig,
+    accounts: Accounts,
+    failures: Mutex<HashMap<String, Failures>>,
+}
+
+#[uniffi::export]
+impl AccountAuthenticator {
+    /// Creates an authenticator holding `accounts` in memory.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if an account has an invalid IP range.
+    #[uniffi::constructor]
+    pub fn with_accounts(
+        config: BindAuthConfig,
+        accounts: Vec<EsmeAccount>,
+    ) -> Result<Arc<Self>, SmppFfiError> {
+        let mut stored = HashMap::new();
+        for account in accounts {
+            check_ip_ranges(&account)?;
+            stored.insert(account.system_id.clone(), account);
+        }
+        Ok(Self::new(config, Accounts::Stored(Mutex::new(stored))))
+    }
+
+    /// Creates an authenticator that finds accounts through `lookup` on every bind.
+    ///
+    /// An account with an invalid IP range cannot bind.
+    #[uniffi::constructor]
+    pub fn with_lookup(config: BindAuthConfig, lookup: Arc<dyn AccountLookup>) -> Arc<Self> {
+        Self::new(config, Accounts::Lookup(lookup))
+    }
+
+    /// Adds an account, replacing any with the same system_id.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the account has an invalid IP range or the
+    /// authenticator uses an `AccountLookup`.
+    pub fn add_account(&self, account: EsmeAccount) -> Result<(), SmppFfiError> {
+        check_ip_ranges(&account)?;
+        match &self.accounts {
+            Accounts::Stored(accounts) => {
+                accounts
+                    .lock()
+                    .unwrap()
+                    .insert(account.system_id.clone(), account);
+                Ok(())
+            }
+            Accounts::Lookup(_) => Err(lookup_error()),
+        }
+    }
+
+    /// Removes the account with `system_id`. Returns true if there was one.
+    ///
+    /// Sessions already bound with it stay bound.
+    pub fn remove_account(&self, system_id: String) -> bool {
+        match &self.accounts {
+            Accounts::Stored(accounts) => accounts.lock().unwrap().remove(&system_id).is_some(),
+            Accounts::Lookup(_) => false,
+        }
+    }
+
+    /// Checks a bind from `peer_addr`, returning the `command_status` for its response.
+    pub fn check(&self, peer_addr: String, request: BindRequest) -> u32 {
+        let now = Instant::now();
+        if self.locked_until(&request.system_id, now).is_some() {
+            return ESME_RBINDFAIL;
+        }
+        let Some(account) = self.find(&request.system_id) else {
+            self.fail(&request.system_id, now);
+            return ESME_RINVSYSID;
+        };
+        if !constant_time_eq(account.password.as_bytes(), request.password.as_bytes()) {
+            self.fail(&request.system_id, now);
+            return ESME_RINVPASWD;
+        }
+        if !account.system_type.is_empty() && account.system_type != request.system_type {
+            return ESME_RINVSYSTYP;
+        }
+        if !account.allowed_modes.is_empty() && !account.allowed_modes.contains(&request.mode) {
+            return ESME_RBINDFAIL;
+        }
+        if !address_allowed(&account, &peer_addr) {
+            return ESME_RBINDFAIL;
+        }
+        self.failures.lock().unwrap().remove(&request.system_id);
+        COMMAND_STATUS_OK
+    }
+
+    /// Returns the system_ids that are currently locked out.
+    pub fn lockouts(&self) -> Vec<BindLockout> {
+        let now = Instant::now();
+        let mut failures = self.failures.lock().unwrap();
+        failures.retain(|_, entry| !entry.expired(now, self.lockout_duration()));
+        failures
+            .iter()
+            .filter_map(|(system_id, entry)| {
+                let until = entry.locked_until?;
+                Some(BindLockout {
+                    system_id: system_id.clone(),
+                    failed_attempts: entry.count,
+                    remaining_ms: until.saturating_duration_since(now).as_millis() as u64,
+                })
+            })
+            .collect()
+    }
+
+    /// Lifts the lockout of `system_id` and forgets its failed binds.
+    pub fn unlock(&self, system_id: String) {
+        self.failures.lock().unwrap().remove(&system_id);
+    }
+
+    /// Returns this authenticator as the `BindAuthenticator` of an `SmscListener`.
+    pub fn as_bind_authenticator(self: Arc<Self>) -> Arc<dyn BindAuthenticator> {
+        self
+    }
+}
+
+impl BindAuthenticator for AccountAuthenticator {
+    fn authenticate(&self, peer_addr: String, request: BindRequest) -> u32 {
+        self.check(peer_addr, request)
+    }
+}
+
+impl AccountAuthenticator {
+    fn new(config: BindAuthConfig, accounts: Accounts) -> Arc<Self> {
+        Arc::new(Self {
+            config,
+            accounts,
+            failures: Mutex::new(HashMap::new()),
+        })
+    }
+
+    fn find(&self, system_id: &str) -> Option<EsmeAccount> {
+        match &self.accounts {
+            Accounts::Stored(accounts) => accounts.lock().unwrap().get(system_id).cloned(),
+            Accounts::Lookup(lookup) => lookup.find_account(system_id.to_string()),
+        }
+    }
+
+    fn lockout_duration(&self) -> Duration {
+        Duration::from_millis(self.config.lockout_duration_ms)
+    }
+
+    fn locked_until(&self, system_id: &str, now: Instant) -> Option<Instant> {
+        let mut failures = self.failures.lock().unwrap();
+        let entry = failures.get(system_id)?;
+        if entry.expired(now, self.lockout_duration()) {
+            failures.remove(system_id);
+            return None;
+        }
+        entry.locked_until
+    }
+
+    /// Records a failed bind, locking the system_id out once it has failed too often.
+    fn fail(&self, system_id: &str, now: Instant) {
+        let mut failures = self.failures.lock().unwrap();
+        // Entries of system_ids that stopped trying would otherwise pile up.
+        failures.retain(|_, entry| !entry.expired(now, self.lockout_duration()));
+        let entry = failures.entry(system_id.to_string()).or_insert(Failures {
+            count: 0,
+            last: now,
+            locked_until: None,
+        });
+        entry.count += 1;
+        entry.last = now;
+        if self.config.max_failed_attempts > 0 && entry.count >= self.config.max_failed_attempts {
+            entry.locked_until = Some(now + self.lockout_duration());
+        }
+    }
+}
+
+enum Accounts {
+    Stored(Mutex<HashMap<String, EsmeAccount>>),
+    Lookup(Arc<dyn AccountLookup>),
+}
+
+/// Failed binds of one system_id.
+struct Failures {
+    count: u32,
+    last: Instant,
+    locked_until: Option<Instant>,
+}
+
+impl Failures {
+    /// True once a lockout has ended, or failures have stopped for a lockout's length.
+    fn expired(&self, now: Instant, lockout: Duration) -> bool {
+        match self.locked_until {
+            Some(until) => now >= until,
+            None => now.duration_since(self.last) >= lockout,
+        }
+    }
+}
+
+/// An IP network in CIDR notation.
+struct IpRange {
+    network: IpAddr,
+    prefix_len: u32,
+}
+
+impl IpRange {
+    fn parse(range: &str) -> Result<Self, SmppFfiError> {
+        let invalid = || SmppFfiError::Generic {
+            msg: format!("Invalid IP range: {}", range),
+        };
+        let (address, prefix_len) = match range.split_once('/') {
+            Some((address, prefix_len)) => (address, Some(prefix_len)),
+            None => (range, None),
+        };
+        let network: IpAddr = address.trim().parse().map_err(|_| invalid())?;
+        let max_len = if network.is_ipv4() { 32 } else { 128 };
+        let prefix_len = match prefix_len {
+            Some(prefix_len) => prefix_len.trim().parse().map_err(|_| invalid())?,
+            None => max_len,
+        };
+        if prefix_len > max_len {
+            return Err(invalid());
+        }
+        Ok(Self {
+            network,
+            prefix_len,
+        })
+    }
+
+    fn contains(&self, address: IpAddr) -> bool {
+        match (self.network, address.to_canonical()) {
+            (IpAddr::V4(network), IpAddr::V4(address)) => {
+                let mask = u32::MAX.checked_shl(32 - self.prefix_len).unwrap_or(0);
+                u32::from(network) & mask == u32::from(address) & mask
+            }
+            (IpAddr::V6(network), IpAddr::V6(address)) => {
+                let mask = u128::MAX.checked_shl(128 - self.prefix_len).unwrap_or(0);
+                u128::from(network) & mask == u128::from(address) & mask
+            }
+            _ => false,
+        }
+    }
+}
+
+fn check_ip_ranges(account: &EsmeAccount) -> Result<(), SmppFfiError> {
+    for range in &account.allowed_ip_ranges {
+        IpRange::parse(range)?;
+    }
+    Ok(())
+}
+
+/// True if the account may bind from `peer_addr`, given as `ip:port` or a bare IP.
+fn address_allowed(account: &EsmeAccount, peer_addr: &str) -> bool {
+    if account.allowed_ip_ranges.is_empty() {
+        return true;
+    }
+    let address = match peer_addr.parse::<SocketAddr>() {
+        Ok(socket) => socket.ip(),
+        Err(_) => match peer_addr.parse::<IpAddr>() {
+            Ok(address) => address,
+            Err(_) => return false,
+        },
+    };
+    account
+        .allowed_ip_ranges
+        .iter()
+        .filter_map(|range| IpRange::parse(range).ok())
+        .any(|range| range.contains(address))
+}
+
+/// Compares secrets without stopping at the first difference.
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0, |diff, (x, y)| diff | (x ^ y)) == 0
+}
+
+fn lookup_error() -> SmppFfiError {
+    SmppFfiError::Generic {
+        msg: "Accounts are provided by an AccountLookup".to_string(),
+    }
+}
@@ -1,9 +1,16 @@
 use crate::common::{Npi, SmppFfiError, Ton};
-use crate::tlv::Tlv;
+use crate::tlv::{Tags, Tlv};
 use smpp_codec::pdus::{
     BroadcastSm as NativeBroadcastSmRequest, BroadcastSmResp as NativeBroadcastSmResponse,
 };
 
+/// Command ID of Broadcast SM Response as defined by SMPP v5.0.
+///
+/// `CMD_BROADCAST_SM_RESP` shares its value with Query Broadcast SM Response,
+/// so `decode_pdu` reads that value as a query_broadcast_sm_resp and
+/// `encode_broadcast_sm_response` writes this one instead.
+pub(crate) const CMD_BROADCAST_SM_RESP_V50: u32 = 0x80000111;
+
 /// Represents a Broadcast SM request.
 #[derive(uniffi::Record, Clone, Debug, PartialEq)]
 pub struct BroadcastSmRequest {
@@ -50,8 +57,34 @@ pub struct BroadcastSmResponse {
     pub optional_params: Vec<Tlv>,
 }
 
+/// TLVs every broadcast_sm must carry.
+pub(crate) const MANDATORY_BROADCAST_TLVS: [(u16, &str); 4] = [
+    (Tags::BROADCAST_AREA_IDENTIFIER, "broadcast_area_identifier"),
+    (Tags::BROADCAST_CONTENT_TYPE, "broadcast_content_type"),
+    (Tags::BROADCAST_REP_NUM, "broadcast_rep_num"),
+    (
+        Tags::BROADCAST_FREQUENCY_INTERVAL,
+        "broadcast_frequency_interval",
+    ),
+];
+
+/// Checks that the mandatory broadcast TLVs are present.
+pub(crate) fn check_mandatory_broadcast_tlvs(tlvs: &[Tlv]) -> Result<(), SmppFfiError> {
+    for (tag, name) in MANDATORY_BROADCAST_TLVS {
+        if !tlvs.iter().any(|t| t.tag == tag) {
+            return Err(SmppFfiError::Generic {
+                msg: format!("Mandatory TLV '{}' is missing", name),
+            });
+        }
+    }
+    Ok(())
+}
+
 /// Encodes a `BroadcastSmRequest` into a byte vector.
 ///
+/// The mandatory broadcast TLVs are not checked; see
+/// `try_encode_broadcast_sm_request`.
+///
 /// # Panics
 ///
 /// Panics if the internal encoding fails.
@@ -83,6 +116,21 @@ pub fn encode_broadcast_sm_request(request: &BroadcastSmRequest) -> Vec<u8> {
     buffer
 }
 
+/// Encodes a `BroadcastSmRequest` into a byte vector, checking that the
+/// mandatory broadcast TLVs are present.
+///
+/// # Errors
+///
+/// Returns an error if `broadcast_area_identifier`, `broadcast_content_type`,
+/// `broadcast_rep_num` or `broadcast_frequency_interval` is missing.
+#[uniffi::export]
+pub fn try_encode_broadcast_sm_request(
+    request: &BroadcastSmRequest,
+) -> Result<Vec<u8>, SmppFfiError> {
+    check_mandatory_broadcast_tlvs(&request.optional_params)?;
+    Ok(encode_broadcast_sm_request(request))
+}
+
 /// Decodes a byte buffer into a `BroadcastSmRequest`.
 ///
 /// # Errors
@@ -124,6 +172,9 @@ pub fn decode_broadcast_sm_request(buffer: &[u8]) -> Result<BroadcastSmRequest,
 
 /// Encodes a `BroadcastSmResponse` into a byte vector.
 ///
+/// The `command_id` is the SMPP v5.0 value 0x80000111, so the bytes decode
+/// as a broadcast_sm_resp rather than a query_broadcast_sm_resp.
+///
 /// # Panics
 ///
 /// Panics if the internal encoding fails.
@@ -144,6 +195,7 @@ pub fn encode_broadcast_sm_response(response: &BroadcastSmResponse) -> Vec<u8> {
     internal_response
         .encode(&mut buffer)
         .expect("Failed to encode BroadcastSmResponse");
+    buffer[4..8].copy_from_slice(&CMD_BROADCAST_SM_RESP_V50.to_be_bytes());
     buffer
 }
 
@@ -0,0 +1,972 @@
+//! # Request Builders
+//!
+//! Chainable builders for the larger request records. Every builder starts
+//! from sensible defaults, so host code only sets the fields it cares about.
+//! Unless set explicitly, the sequence number is drawn from a
+//! `SequenceGenerator` at `build`, the process-wide one by default.
+use crate::address::{address_validate, Address, AddressField};
+use crate::broadcast_pdu_ffi::{check_mandatory_broadcast_tlvs, BroadcastSmRequest};
+use crate::common::{Npi, SmppFfiError, Ton};
+use crate::delivery_pdu_ffi::{DataSm, DeliverSmRequest};
+use crate::sequence::{default_sequence_generator, SequenceGenerator};
+use crate::splitter::EncodingType;
+use crate::submission_pdu_ffi::SubmitSmRequest;
+use crate::tlv::{Tags, Tlv};
+use crate::tlv_v50::{
+    tlv_new_broadcast_area_identifier, tlv_new_broadcast_content_type,
+    tlv_new_broadcast_frequency_interval, tlv_new_broadcast_rep_num, BroadcastAreaIdentifier,
+    BroadcastContentType, BroadcastFrequencyInterval,
+};
+use smpp_codec::encoding;
+use std::sync::{Arc, Mutex};
+
+/// Maximum length of the `short_message` field, in octets.
+const MAX_SHORT_MESSAGE_LEN: usize = 254;
+
+/// Delivery receipt policy, mapped to bits 0-1 of `registered_delivery`.
+#[derive(uniffi::Enum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DeliveryReceiptPolicy {
+    /// No SMSC delivery receipt requested.
+    None,
+    /// Receipt requested on final delivery outcome (success or failure).
+    OnSuccessOrFailure,
+    /// Receipt requested on delivery failure only.
+    OnFailure,
+    /// Receipt requested on delivery success only (SMPP v5.0).
+    OnSuccess,
+}
+
+impl DeliveryReceiptPolicy {
+    /// Returns the `registered_delivery` bits for this policy.
+    pub fn bits(self) -> u8 {
+        match self {
+            DeliveryReceiptPolicy::None => 0x00,
+            DeliveryReceiptPolicy::OnSuccessOrFailure => 0x01,
+            DeliveryReceiptPolicy::OnFailure => 0x02,
+            DeliveryReceiptPolicy::OnSuccess => 0x03,
+        }
+    }
+}
+
+/// Encodes text, returning the encoded bytes and the matching data coding.
+///
+/// GSM 7-bit is used when every character is in the GSM 03.38 alphabet,
+/// otherwise the text falls back to UCS-2.
+fn encode_text_auto(text: &str) -> (Vec<u8>, u8) {
+    match encoding::gsm_7bit_encode(text) {
+        Ok(bytes) => (bytes, 0x00),
+        Err(_) => (encoding::encode_16bit(text), 0x08),
+    }
+}
+
+fn encode_text(text: &str, encoding: EncodingType) -> Result<(Vec<u8>, u8), SmppFfiError> {
+    match encoding {
+        EncodingType::Gsm7Bit => encoding::gsm_7bit_encode(text)
+            .map(|bytes| (bytes, 0x00))
+            .map_err(|msg| SmppFfiError::Generic { msg }),
+        EncodingType::Latin1 => Ok((encoding::encode_8bit(text), 0x03)),
+        EncodingType::Ucs2 => Ok((encoding::encode_16bit(text), 0x08)),
+    }
+}
+
+/// Formats a relative validity period (`YYMMDDhhmmss000R`) from seconds.
+fn relative_time(seconds: u32) -> Result<String, SmppFfiError> {
+    let days = seconds / 86_400;
+    if days > 99 {
+        return Err(SmppFfiError::Generic {
+            msg: format!("Relative time of {} seconds exceeds 99 days", seconds),
+        });
+    }
+    let hours = (seconds % 86_400) / 3_600;
+    let minutes = (seconds % 3_600) / 60;
+    let secs = seconds % 60;
+    Ok(format!(
+        "0000{:02}{:02}{:02}{:02}000R",
+        days, hours, minutes, secs
+    ))
+}
+
+/// Checks a C-Octet String field against its maximum size (including the NULL).
+fn check_c_octet(field: &str, value: &str, max: usize) -> Result<(), SmppFfiError> {
+    if value.len() + 1 > max {
+        return Err(SmppFfiError::Generic {
+            msg: format!(
+                "Field '{}' is {} octets, maximum is {} including NULL",
+                field,
+                value.len(),
+                max - 1
+            ),
+        });
+    }
+    Ok(())
+}
+
+fn check_address(ton: Ton, npi: Npi, value: &str, field: AddressField) -> Result<(), SmppFfiError> {
+    address_validate(
+        &Address {
+            ton,
+            npi,
+            value: value.to_string(),
+        },
+        field,
+    )
+}
+
+/// Checks the body fields shared by the message-carrying requests.
+fn check_message_body(short_message: &[u8], tlvs: &[Tlv]) -> Result<(), SmppFfiError> {
+    if short_message.len() > MAX_SHORT_MESSAGE_LEN {
+        return Err(SmppFfiError::Generic {
+            msg: format!(
+                "short_message is {} octets, maximum is {}",
+                short_message.len(),
+                MAX_SHORT_MESSAGE_LEN
+            ),
+        });
+    }
+    if !short_message.is_empty() && tlvs.iter().any(|t| t.tag == Tags::MESSAGE_PAYLOAD) {
+        return Err(SmppFfiError::Generic {
+            msg: "short_message and message_payload are mutually exclusive".to_string(),
+        });
+    }
+    check_tlv_lengths(tlvs)
+}
+
+/// Checks that every TLV value fits the 16-bit length field.
+fn check_tlv_lengths(tlvs: &[Tlv]) -> Result<(), SmppFfiError> {
+    match tlvs.iter().find(|t| t.value.len() > u16::MAX as usize) {
+        Some(tlv) => Err(SmppFfiError::Generic {
+            msg: format!(
+                "TLV 0x{:04X} is {} octets, maximum is {}",
+                tlv.tag,
+                tlv.value.len(),
+                u16::MAX
+            ),
+        }),
+        None => Ok(()),
+    }
+}
+
+/// Replaces an existing TLV with the same tag, or appends it.
+fn upsert_tlv(tlvs: &mut Vec<Tlv>, tlv: Tlv) {
+    match tlvs.iter_mut().find(|t| t.tag == tlv.tag) {
+        Some(existing) => *existing = tlv,
+        None => tlvs.push(tlv),
+    }
+}
+
+fn payload_tlv(bytes: Vec<u8>) -> Tlv {
+    Tlv {
+        tag: Tags::MESSAGE_PAYLOAD,
+        length: bytes.len() as u16,
+        value: bytes,
+    }
+}
+
+/// Places encoded text in `short_message`, or in `message_payload` if it does not fit.
+fn set_body(short_message: &mut Vec<u8>, tlvs: &mut Vec<Tlv>, bytes: Vec<u8>) {
+    tlvs.retain(|t| t.tag != Tags::MESSAGE_PAYLOAD);
+    if bytes.len() > MAX_SHORT_MESSAGE_LEN {
+        short_message.clear();
+        tlvs.push(payload_tlv(bytes));
+    } else {
+        *short_message = bytes;
+    }
+}
+
+// --- SubmitSm ---
+
+/// Builder for `SubmitSmRequest`.
+#[derive(uniffi::Object)]
+pub struct SubmitSmBuilder {
+    inner: Mutex<SubmitSmRequest>,
+    sequence: Mutex<Arc<SequenceGenerator>>,
+}
+
+impl Default for SubmitSmBuilder {
+    fn default() -> Self {
+        Self {
+            inner: Mutex::new(SubmitSmRequest {
+                sequence_number: 0,
+                service_type: String::new(),
+                source_addr_ton: Ton::Unknown,
+                source_addr_npi: Npi::Unknown,
+                source_addr: String::new(),
+                dest_addr_ton: Ton::Unknown,
+                dest_addr_npi: Npi::Unknown,
+                destination_addr: String::new(),
+                esm_class: 0,
+                protocol_id: 0,
+                priority_flag: 0,
+                schedule_delivery_time: None,
+                validity_period: None,
+                registered_delivery: 0,
+                replace_if_present_flag: 0,
+                data_coding: 0,
+                sm_default_msg_id: 0,
+                short_message: Vec::new(),
+                tlvs: Vec::new(),
+            }),
+            sequence: Mutex::new(default_sequence_generator()),
+        }
+    }
+}
+
+#[uniffi::export]
+impl SubmitSmBuilder {
+    /// Creates a builder with default values.
+    #[uniffi::constructor]
+    pub fn new() -> Arc<Self> {
+        Arc::new(Self::default())
+    }
+
+    /// Sets the sequence number, overriding the generator.
+    pub fn sequence_number(self: Arc<Self>, sequence_number: u32) -> Arc<Self> {
+        self.inner.lock().unwrap().sequence_number = sequence_number;
+        self
+    }
+
+    /// Sets the generator that assigns the sequence number at `build`.
+    pub fn sequence_generator(self: Arc<Self>, generator: Arc<SequenceGenerator>) -> Arc<Self> {
+        *self.sequence.lock().unwrap() = generator;
+        self
+    }
+
+    /// Sets the service type.
+    pub fn service_type(self: Arc<Self>, service_type: String) -> Arc<Self> {
+        self.inner.lock().unwrap().service_type = service_type;
+        self
+    }
+
+    /// Sets the source address.
+    pub fn source(self: Arc<Self>, address: Address) -> Arc<Self> {
+        {
+            let mut inner = self.inner.lock().unwrap();
+            inner.source_addr_ton = address.ton;
+            inner.source_addr_npi = address.npi;
+            inner.source_addr = address.value;
+        }
+        self
+    }
+
+    /// Sets the destination address.
+    pub fn destination(self: Arc<Self>, address: Address) -> Arc<Self> {
+        {
+            let mut inner = self.inner.lock().unwrap();
+            inner.dest_addr_ton = address.ton;
+            inner.dest_addr_npi = address.npi;
+            inner.destination_addr = address.value;
+        }
+        self
+    }
+
+    /// Sets the message text, choosing GSM 7-bit or UCS-2 automatically.
+    ///
+    /// Text longer than 254 octets is carried in the `message_payload` TLV.
+    pub fn text(self: Arc<Self>, text: String) -> Arc<Self> {
+        let (bytes, data_coding) = encode_text_auto(&text);
+        {
+            let mut inner = self.inner.lock().unwrap();
+            let inner = &mut *inner;
+            inner.data_coding = data_coding;
+            set_body(&mut inner.short_message, &mut inner.tlvs, bytes);
+        }
+        self
+    }
+
+    /// Sets the message text using an explicit encoding.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the text cannot be represented in the encoding.
+    pub fn text_with_encoding(
+        self: Arc<Self>,
+        text: String,
+        encoding: EncodingType,
+    ) -> Result<Arc<Self>, SmppFfiError> {
+        let (bytes, data_coding) = encode_text(&text, encoding)?;
+        {
+            let mut inner = self.inner.lock().unwrap();
+            let inner = &mut *inner;
+            inner.data_coding = data_coding;
+            set_body(&mut inner.short_message, &mut inner.tlvs, bytes);
+        }
+        Ok(self)
+    }
+
+    /// Sets raw message bytes and their data coding.
+    pub fn short_message(self: Arc<Self>, short_message: Vec<u8>, data_coding: u8) -> Arc<Self> {
+        {
+            let mut inner = self.inner.lock().unwrap();
+            inner.short_message = short_message;
+            inner.data_coding = data_coding;
+        }
+        self
+    }
+
+    /// Sets the ESM class.
+    pub fn esm_class(self: Arc<Self>, esm_class: u8) -> Arc<Self> {
+        self.inner.lock().unwrap().esm_class = esm_class;
+        self
+    }
+
+    /// Sets the protocol ID.
+    pub fn protocol_id(self: Arc<Self>, protocol_id: u8) -> Arc<Self> {
+        self.inner.lock().unwrap().protocol_id = protocol_id;
+        self
+    }
+
+    /// Sets the priority flag.
+    pub fn priority(self: Arc<Self>, priority_flag: u8) -> Arc<Self> {
+        self.inner.lock().unwrap().priority_flag = priority_flag;
+        self
+    }
+
+    /// Sets the delivery receipt policy, keeping the other `registered_delivery` bits.
+    pub fn delivery_receipt(self: Arc<Self>, policy: DeliveryReceiptPolicy) -> Arc<Self> {
+        {
+            let mut inner = self.inner.lock().unwrap();
+            inner.registered_delivery = (inner.registered_delivery & !0x03) | policy.bits();
+        }
+        self
+    }
+
+    /// Sets the raw `registered_delivery` flag.
+    pub fn registered_delivery(self: Arc<Self>, registered_delivery: u8) -> Arc<Self> {
+        self.inner.lock().unwrap().registered_delivery = registered_delivery;
+        self
+    }
+
+    /// Sets the schedule delivery time (absolute or relative SMPP time).
+    pub fn schedule_delivery_time(self: Arc<Self>, time: String) -> Arc<Self> {
+        self.inner.lock().unwrap().schedule_delivery_time = Some(time);
+        self
+    }
+
+    /// Sets the validity period (absolute or relative SMPP time).
+    pub fn validity_period(self: Arc<Self>, period: String) -> Arc<Self> {
+        self.inner.lock().unwrap().validity_period = Some(period);
+        self
+    }
+
+    /// Sets a relative validity period from a number of seconds.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the period exceeds 99 days.
+    pub fn validity_seconds(self: Arc<Self>, seconds: u32) -> Result<Arc<Self>, SmppFfiError> {
+        let period = relative_time(seconds)?;
+        self.inner.lock().unwrap().validity_period = Some(period);
+        Ok(self)
+    }
+
+    /// Sets the replace if present flag.
+    pub fn replace_if_present(self: Arc<Self>, replace: bool) -> Arc<Self> {
+        self.inner.lock().unwrap().replace_if_present_flag = replace as u8;
+        self
+    }
+
+    /// Adds a TLV, replacing any existing TLV with the same tag.
+    pub fn tlv(self: Arc<Self>, tlv: Tlv) -> Arc<Self> {
+        upsert_tlv(&mut self.inner.lock().unwrap().tlvs, tlv);
+        self
+    }
+
+    /// Validates the fields and returns the request.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a field exceeds its size limit or the destination is missing.
+    pub fn build(&self) -> Result<SubmitSmRequest, SmppFfiError> {
+        let mut request = self.inner.lock().unwrap().clone();
+        check_c_octet("service_type", &request.service_type, 6)?;
+        check_address(
+            request.source_addr_ton,
+            request.source_addr_npi,
+            &request.source_addr,
+            AddressField::ShortMessage,
+        )?;
+        check_address(
+            request.dest_addr_ton,
+            request.dest_addr_npi,
+            &request.destination_addr,
+            AddressField::ShortMessage,
+        )?;
+        if request.destination_addr.is_empty() {
+            return Err(SmppFfiError::Generic {
+                msg: "destination_addr is required".to_string(),
+            });
+        }
+        if let Some(time) = &request.schedule_delivery_time {
+            check_c_octet("schedule_delivery_time", time, 17)?;
+        }
+        if let Some(period) = &request.validity_period {
+            check_c_octet("validity_period", period, 17)?;
+        }
+        check_message_body(&request.short_message, &request.tlvs)?;
+        if request.sequence_number == 0 {
+            request.sequence_number = self.sequence.lock().unwrap().next_value();
+        }
+        Ok(request)
+    }
+}
+
+// --- DeliverSm ---
+
+/// Builder for `DeliverSmRequest`.
+#[derive(uniffi::Object)]
+pub struct DeliverSmBuilder {
+    inner: Mutex<DeliverSmRequest>,
+    sequence: Mutex<Arc<SequenceGenerator>>,
+}
+
+impl Default for DeliverSmBuilder {
+    fn default() -> Self {
+        Self {
+            inner: Mutex::new(DeliverSmRequest {
+                sequence_number: 0,
+                service_type: String::new(),
+                source_addr_ton: Ton::Unknown,
+                source_addr_npi: Npi::Unknown,
+                source_addr: String::new(),
+                dest_addr_ton: Ton::Unknown,
+                dest_addr_npi: Npi::Unknown,
+                dest_addr: String::new(),
+                esm_class: 0,
+                protocol_id: 0,
+                priority_flag: 0,
+                schedule_delivery_time: None,
+                validity_period: None,
+                registered_delivery: 0,
+                replace_if_present_flag: 0,
+                data_coding: 0,
+                sm_default_msg_id: 0,
+                short_message: Vec::new(),
+                optional_params: Vec::new(),
+            }),
+            sequence: Mutex::new(default_sequence_generator()),
+        }
+    }
+}
+
+#[uniffi::export]
+impl DeliverSmBuilder {
+    /// Creates a builder with default values.
+    #[uniffi::constructor]
+    pub fn new() -> Arc<Self> {
+        Arc::new(Self::default())
+    }
+
+    /// Sets the sequence number, overriding the generator.
+    pub fn sequence_number(self: Arc<Self>, sequence_number: u32) -> Arc<Self> {
+        self.inner.lock().unwrap().sequence_number = sequence_number;
+        self
+    }
+
+    /// Sets the generator that assigns the sequence number at `build`.
+    pub fn sequence_generator(self: Arc<Self>, generator: Arc<SequenceGenerator>) -> Arc<Self> {
+        *self.sequence.lock().unwrap() = generator;
+        self
+    }
+
+    /// Sets the service type.
+    pub fn service_type(self: Arc<Self>, service_type: String) -> Arc<Self> {
+        self.inner.lock().unwrap().service_type = service_type;
+        self
+    }
+
+    /// Sets the source address.
+    pub fn source(self: Arc<Self>, address: Address) -> Arc<Self> {
+        {
+            let mut inner = self.inner.lock().unwrap();
+            inner.source_addr_ton = address.ton;
+            inner.source_addr_npi = address.npi;
+            inner.source_addr = address.value;
+        }
+        self
+    }
+
+    /// Sets the destination address.
+    pub fn destination(self: Arc<Self>, address: Address) -> Arc<Self> {
+        {
+            let mut inner = self.inner.lock().unwrap();
+            inner.dest_addr_ton = address.ton;
+            inner.dest_addr_npi = address.npi;
+            inner.dest_addr = address.value;
+        }
+        self
+    }
+
+    /// Sets the message text, choosing GSM 7-bit or UCS-2 automatically.
+    ///
+    /// Text longer than 254 octets is carried in the `message_payload` TLV.
+    pub fn text(self: Arc<Self>, text: String) -> Arc<Self> {
+        let (bytes, data_coding) = encode_text_auto(&text);
+        {
+            let mut inner = self.inner.lock().unwrap();
+            let inner = &mut *inner;
+            inner.data_coding = data_coding;
+            set_body(&mut inner.short_message, &mut inner.optional_params, bytes);
+        }
+        self
+    }
+
+    /// Sets the message text using an explicit encoding.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the text cannot be represented in the encoding.
+    pub fn text_with_encoding(
+        self: Arc<Self>,
+        text: String,
+        encoding: EncodingType,
+    ) -> Result<Arc<Self>, SmppFfiError> {
+        let (bytes, data_coding) = encode_text(&text, encoding)?;
+        {
+            let mut inner = self.inner.lock().unwrap();
+            let inner = &mut *inner;
+            inner.data_coding = data_coding;
+            set_body(&mut inner.short_message, &mut inner.optional_params, bytes);
+        }
+        Ok(self)
+    }
+
+    /// Sets raw message bytes and their data coding.
+    pub fn short_message(self: Arc<Self>, short_message: Vec<u8>, data_coding: u8) -> Arc<Self> {
+        {
+            let mut inner = self.inner.lock().unwrap();
+            inner.short_message = short_message;
+            inner.data_coding = data_coding;
+        }
+        self
+    }
+
+    /// Sets the ESM class.
+    pub fn esm_class(self: Arc<Self>, esm_class: u8) -> Arc<Self> {
+        self.inner.lock().unwrap().esm_class = esm_class;
+        self
+    }
+
+    /// Sets the protocol ID.
+    pub fn protocol_id(self: Arc<Self>, protocol_id: u8) -> Arc<Self> {
+        self.inner.lock().unwrap().protocol_id = protocol_id;
+        self
+    }
+
+    /// Sets the priority flag.
+    pub fn priority(self: Arc<Self>, priority_flag: u8) -> Arc<Self> {
+        self.inner.lock().unwrap().priority_flag = priority_flag;
+        self
+    }
+
+    /// Sets the delivery receipt policy, keeping the other `registered_delivery` bits.
+    pub fn delivery_receipt(self: Arc<Self>, policy: DeliveryReceiptPolicy) -> Arc<Self> {
+        {
+            let mut inner = self.inner.lock().unwrap();
+            inner.registered_delivery = (inner.registered_delivery & !0x03) | policy.bits();
+        }
+        self
+    }
+
+    /// Adds a TLV, replacing any existing TLV with the same tag.
+    pub fn tlv(self: Arc<Self>, tlv: Tlv) -> Arc<Self> {
+        upsert_tlv(&mut self.inner.lock().unwrap().optional_params, tlv);
+        self
+    }
+
+    /// Validates the fields and returns the request.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a field exceeds its size limit or the destination is missing.
+    pub fn build(&self) -> Result<DeliverSmRequest, SmppFfiError> {
+        let mut request = self.inner.lock().unwrap().clone();
+        check_c_octet("service_type", &request.service_type, 6)?;
+        check_address(
+            request.source_addr_ton,
+            request.source_addr_npi,
+            &request.source_addr,
+            AddressField::ShortMessage,
+        )?;
+        check_address(
+            request.dest_addr_ton,
+            request.dest_addr_npi,
+            &request.dest_addr,
+            AddressField::ShortMessage,
+        )?;
+        if request.dest_addr.is_empty() {
+            return Err(SmppFfiError::Generic {
+                msg: "dest_addr is required".to_string(),
+            });
+        }
+        check_message_body(&request.short_message, &request.optional_params)?;
+        if request.sequence_number == 0 {
+            request.sequence_number = self.sequence.lock().unwrap().next_value();
+        }
+        Ok(request)
+    }
+}
+
+// --- DataSm ---
+
+/// Builder for `DataSm`.
+#[derive(uniffi::Object)]
+pub struct DataSmBuilder {
+    inner: Mutex<DataSm>,
+    sequence: Mutex<Arc<SequenceGenerator>>,
+}
+
+impl Default for DataSmBuilder {
+    fn default() -> Self {
+        Self {
+            inner: Mutex::new(DataSm {
+                sequence_number: 0,
+                service_type: String::new(),
+                source_addr_ton: Ton::Unknown,
+                source_addr_npi: Npi::Unknown,
+                source_addr: String::new(),
+                dest_addr_ton: Ton::Unknown,
+                dest_addr_npi: Npi::Unknown,
+                dest_addr: String::new(),
+                esm_class: 0,
+                registered_delivery: 0,
+                data_coding: 0,
+                optional_params: Vec::new(),
+            }),
+            sequence: Mutex::new(default_sequence_generator()),
+        }
+    }
+}
+
+#[uniffi::export]
+impl DataSmBuilder {
+    /// Creates a builder with default values.
+    #[uniffi::constructor]
+    pub fn new() -> Arc<Self> {
+        Arc::new(Self::default())
+    }
+
+    /// Sets the sequence number, overriding the generator.
+    pub fn sequence_number(self: Arc<Self>, sequence_number: u32) -> Arc<Self> {
+        self.inner.lock().unwrap().sequence_number = sequence_number;
+        self
+    }
+
+    /// Sets the generator that assigns the sequence number at `build`.
+    pub fn sequence_generator(self: Arc<Self>, generator: Arc<SequenceGenerator>) -> Arc<Self> {
+        *self.sequence.lock().unwrap() = generator;
+        self
+    }
+
+    /// Sets the service type.
+    pub fn service_type(self: Arc<Self>, service_type: String) -> Arc<Self> {
+        self.inner.lock().unwrap().service_type = service_type;
+        self
+    }
+
+    /// Sets the source address.
+    pub fn source(self: Arc<Self>, address: Address) -> Arc<Self> {
+        {
+            let mut inner = self.inner.lock().unwrap();
+            inner.source_addr_ton = address.ton;
+            inner.source_addr_npi = address.npi;
+            inner.source_addr = address.value;
+        }
+        self
+    }
+
+    /// Sets the destination address.
+    pub fn destination(self: Arc<Self>, address: Address) -> Arc<Self> {
+        {
+            let mut inner = self.inner.lock().unwrap();
+            inner.dest_addr_ton = address.ton;
+            inner.dest_addr_npi = address.npi;
+            inner.dest_addr = address.value;
+        }
+        self
+    }
+
+    /// Sets the message text in the `message_payload` TLV, choosing GSM 7-bit or UCS-2.
+    pub fn text(self: Arc<Self>, text: String) -> Arc<Self> {
+        let (bytes, data_coding) = encode_text_auto(&text);
+        {
+            let mut inner = self.inner.lock().unwrap();
+            inner.data_coding = data_coding;
+            upsert_tlv(&mut inner.optional_params, payload_tlv(bytes));
+        }
+        self
+    }
+
+    /// Sets the message text in the `message_payload` TLV using an explicit encoding.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the text cannot be represented in the encoding.
+    pub fn text_with_encoding(
+        self: Arc<Self>,
+        text: String,
+        encoding: EncodingType,
+    ) -> Result<Arc<Self>, SmppFfiError> {
+        let (bytes, data_coding) = encode_text(&text, encoding)?;
+        {
+            let mut inner = self.inner.lock().unwrap();
+            inner.data_coding = data_coding;
+            upsert_tlv(&mut inner.optional_params, payload_tlv(bytes));
+        }
+        Ok(self)
+    }
+
+    /// Sets the ESM class.
+    pub fn esm_class(self: Arc<Self>, esm_class: u8) -> Arc<Self> {
+        self.inner.lock().unwrap().esm_class = esm_class;
+        self
+    }
+
+    /// Sets the delivery receipt policy, keeping the other `registered_delivery` bits.
+    pub fn delivery_receipt(self: Arc<Self>, policy: DeliveryReceiptPolicy) -> Arc<Self> {
+        {
+            let mut inner = self.inner.lock().unwrap();
+            inner.registered_delivery = (inner.registered_delivery & !0x03) | policy.bits();
+        }
+        self
+    }
+
+    /// Sets the validity period as the `qos_time_to_live` TLV, in seconds.
+    pub fn time_to_live(self: Arc<Self>, seconds: u32) -> Arc<Self> {
+        let value = seconds.to_be_bytes().to_vec();
+        upsert_tlv(
+            &mut self.inner.lock().unwrap().optional_params,
+            Tlv {
+                tag: Tags::QOS_TIME_TO_LIVE,
+                length: value.len() as u16,
+                value,
+            },
+        );
+        self
+    }
+
+    /// Adds a TLV, replacing any existing TLV with the same tag.
+    pub fn tlv(self: Arc<Self>, tlv: Tlv) -> Arc<Self> {
+        upsert_tlv(&mut self.inner.lock().unwrap().optional_params, tlv);
+        self
+    }
+
+    /// Validates the fields and returns the request.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a field exceeds its size limit or the destination is missing.
+    pub fn build(&self) -> Result<DataSm, SmppFfiError> {
+        let mut request = self.inner.lock().unwrap().clone();
+        check_c_octet("service_type", &request.service_type, 6)?;
+        check_address(
+            request.source_addr_ton,
+            request.source_addr_npi,
+            &request.source_addr,
+            AddressField::DataSm,
+        )?;
+        check_address(
+            request.dest_addr_ton,
+            request.dest_addr_npi,
+            &request.dest_addr,
+            AddressField::DataSm,
+        )?;
+        if request.dest_addr.is_empty() {
+            return Err(SmppFfiError::Generic {
+                msg: "dest_addr is required".to_string(),
+            });
+        }
+        check_tlv_lengths(&request.optional_params)?;
+        if request.sequence_number == 0 {
+            request.sequence_number = self.sequence.lock().unwrap().next_value();
+        }
+        Ok(request)
+    }
+}
+
+// --- BroadcastSm ---
+
+/// Builder for `BroadcastSmRequest`.
+#[derive(uniffi::Object)]
+pub struct BroadcastSmBuilder {
+    inner: Mutex<BroadcastSmRequest>,
+    sequence: Mutex<Arc<SequenceGenerator>>,
+}
+
+impl Default for BroadcastSmBuilder {
+    fn default() -> Self {
+        Self {
+            inner: Mutex::new(BroadcastSmRequest {
+                sequence_number: 0,
+                service_type: String::new(),
+                source_addr_ton: Ton::Unknown,
+                source_addr_npi: Npi::Unknown,
+                source_addr: String::new(),
+                message_id: String::new(),
+                priority_flag: 0,
+                schedule_delivery_time: None,
+                validity_period: None,
+                replace_if_present_flag: 0,
+                data_coding: 0,
+                sm_default_msg_id: 0,
+                optional_params: Vec::new(),
+            }),
+            sequence: Mutex::new(default_sequence_generator()),
+        }
+    }
+}
+
+#[uniffi::export]
+impl BroadcastSmBuilder {
+    /// Creates a builder with default values.
+    #[uniffi::constructor]
+    pub fn new() -> Arc<Self> {
+        Arc::new(Self::default())
+    }
+
+    /// Sets the sequence number, overriding the generator.
+    pub fn sequence_number(self: Arc<Self>, sequence_number: u32) -> Arc<Self> {
+        self.inner.lock().unwrap().sequence_number = sequence_number;
+        self
+    }
+
+    /// Sets the generator that assigns the sequence number at `build`.
+    pub fn sequence_generator(self: Arc<Self>, generator: Arc<SequenceGenerator>) -> Arc<Self> {
+        *self.sequence.lock().unwrap() = generator;
+        self
+    }
+
+    /// Sets the service type.
+    pub fn service_type(self: Arc<Self>, service_type: String) -> Arc<Self> {
+        self.inner.lock().unwrap().service_type = service_type;
+        self
+    }
+
+    /// Sets the source address.
+    pub fn source(self: Arc<Self>, address: Address) -> Arc<Self> {
+        {
+            let mut inner = self.inner.lock().unwrap();
+            inner.source_addr_ton = address.ton;
+            inner.source_addr_npi = address.npi;
+            inner.source_addr = address.value;
+        }
+        self
+    }
+
+    /// Sets the message ID of a broadcast to replace.
+    pub fn message_id(self: Arc<Self>, message_id: String) -> Arc<Self> {
+        self.inner.lock().unwrap().message_id = message_id;
+        self
+    }
+
+    /// Sets the broadcast content in the `message_payload` TLV, choosing GSM 7-bit or UCS-2.
+    pub fn text(self: Arc<Self>, text: String) -> Arc<Self> {
+        let (bytes, data_coding) = encode_text_auto(&text);
+        {
+            let mut inner = self.inner.lock().unwrap();
+            inner.data_coding = data_coding;
+            upsert_tlv(&mut inner.optional_params, payload_tlv(bytes));
+        }
+        self
+    }
+
+    /// Sets the priority flag.
+    pub fn priority(self: Arc<Self>, priority_flag: u8) -> Arc<Self> {
+        self.inner.lock().unwrap().priority_flag = priority_flag;
+        self
+    }
+
+    /// Sets the schedule delivery time (absolute or relative SMPP time).
+    pub fn schedule_delivery_time(self: Arc<Self>, time: String) -> Arc<Self> {
+        self.inner.lock().unwrap().schedule_delivery_time = Some(time);
+        self
+    }
+
+    /// Sets the validity period (absolute or relative SMPP time).
+    pub fn validity_period(self: Arc<Self>, period: String) -> Arc<Self> {
+        self.inner.lock().unwrap().validity_period = Some(period);
+        self
+    }
+
+    /// Sets a relative validity period from a number of seconds.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the period exceeds 99 days.
+    pub fn validity_seconds(self: Arc<Self>, seconds: u32) -> Result<Arc<Self>, SmppFfiError> {
+        let period = relative_time(seconds)?;
+        self.inner.lock().unwrap().validity_period = Some(period);
+        Ok(self)
+    }
+
+    /// Adds an area the broadcast is sent to. Call it once per area.
+    pub fn area_identifier(self: Arc<Self>, area: BroadcastAreaIdentifier) -> Arc<Self> {
+        self.inner
+            .lock()
+            .unwrap()
+            .optional_params
+            .push(tlv_new_broadcast_area_identifier(area));
+        self
+    }
+
+    /// Sets the content type of the broadcast.
+    pub fn content_type(self: Arc<Self>, content_type: BroadcastContentType) -> Arc<Self> {
+        upsert_tlv(
+            &mut self.inner.lock().unwrap().optional_params,
+            tlv_new_broadcast_content_type(content_type),
+        );
+        self
+    }
+
+    /// Sets the number of times the broadcast is repeated.
+    pub fn rep_num(self: Arc<Self>, rep_num: u16) -> Arc<Self> {
+        upsert_tlv(
+            &mut self.inner.lock().unwrap().optional_params,
+            tlv_new_broadcast_rep_num(rep_num),
+        );
+        self
+    }
+
+    /// Sets the interval between repetitions of the broadcast.
+    pub fn frequency_interval(self: Arc<Self>, interval: BroadcastFrequencyInterval) -> Arc<Self> {
+        upsert_tlv(
+            &mut self.inner.lock().unwrap().optional_params,
+            tlv_new_broadcast_frequency_interval(interval),
+        );
+        self
+    }
+
+    /// Adds a TLV, replacing any existing TLV with the same tag.
+    pub fn tlv(self: Arc<Self>, tlv: Tlv) -> Arc<Self> {
+        upsert_tlv(&mut self.inner.lock().unwrap().optional_params, tlv);
+        self
+    }
+
+    /// Validates the fields and returns the request.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a field exceeds its size limit or a mandatory broadcast TLV is missing.
+    pub fn build(&self) -> Result<BroadcastSmRequest, SmppFfiError> {
+        let mut request = self.inner.lock().unwrap().clone();
+        check_c_octet("service_type", &request.service_type, 6)?;
+        check_address(
+            request.source_addr_ton,
+            request.source_addr_npi,
+            &request.source_addr,
+            AddressField::ShortMessage,
+        )?;
+        check_c_octet("message_id", &request.message_id, 65)?;
+        if let Some(time) = &request.schedule_delivery_time {
+            check_c_octet("schedule_delivery_time", time, 17)?;
+        }
+        if let Some(period) = &request.validity_period {
+            check_c_octet("validity_period", period, 17)?;
+        }
+        check_mandatory_broadcast_tlvs(&request.optional_params)?;
+        check_tlv_lengths(&request.optional_params)?;
+        if request.sequence_number == 0 {
+            request.sequence_number = self.sequence.lock().unwrap().next_value();
+        }
+        Ok(request)
+    }
+}
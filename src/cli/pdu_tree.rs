@@ -0,0 +1,617 @@
+//! The PDU tree shared by `smpp-decode` and `smpp-encode`: header, fields,
+//! TLVs with their names and values, the decoded text and the parsed UDH,
+//! as ordered JSON that can be printed as is or rendered as an indented tree.
+use serde_json::{json, Map, Value};
+use smpp_codec::common::get_status_description;
+use smpp_codec::encoding::{decode_16bit, decode_8bit, gsm_7bit_decode};
+use smpp_codec_ffi::ancillary_pdu_ffi::MessageState;
+use smpp_codec_ffi::common::*;
+use smpp_codec_ffi::pdu::{decode_pdu, decode_pdu_header, get_command_name, Pdu};
+use smpp_codec_ffi::submission_pdu_ffi::Destination;
+use smpp_codec_ffi::tlv::{get_tag_name, Tags, Tlv};
+
+/// TLVs shown as text.
+pub const TEXT_TLVS: [u16; 5] = [
+    Tags::RECEIPTED_MESSAGE_ID,
+    Tags::ADDITIONAL_STATUS_INFO_TEXT,
+    Tags::SOURCE_NETWORK_ID,
+    Tags::DEST_NETWORK_ID,
+    Tags::BROADCAST_END_TIME,
+];
+
+/// TLVs of 1, 2 or 4 octets that are not numbers.
+const OCTET_TLVS: [u16; 5] = [
+    Tags::SOURCE_SUBADDRESS,
+    Tags::DEST_SUBADDRESS,
+    Tags::CALLBACK_NUM_ATAG,
+    Tags::BROADCAST_AREA_IDENTIFIER,
+    Tags::BILLING_IDENTIFICATION,
+];
+
+/// Parses a hex dump.
+pub fn parse_hex(input: &[u8]) -> Result<Vec<u8>, String> {
+    let text = std::str::from_utf8(input).map_err(|_| "Input is not hex".to_string())?;
+    let mut digits = String::new();
+    for token in text.split(|c: char| c.is_whitespace() || c == ',' || c == ':') {
+        let token = token
+            .strip_prefix("0x")
+            .or_else(|| token.strip_prefix("0X"))
+            .unwrap_or(token);
+        if let Some(c) = token.chars().find(|c| !c.is_ascii_hexdigit()) {
+            return Err(format!("Invalid hex character '{}'", c));
+        }
+        digits.push_str(token);
+    }
+    if digits.is_empty() || !digits.len().is_multiple_of(2) {
+        return Err(format!("Odd or empty hex input ({} digits)", digits.len()));
+    }
+    Ok((0..digits.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(&digits[i..i + 2], 16).unwrap())
+        .collect())
+}
+
+/// Renders the tree of one PDU from `describe` under a `title` line.
+pub fn tree(title: &str, pdu: &Value) -> String {
+    let mut out = format!(
+        "{}: {}\n",
+        title,
+        pdu["header"]["command"].as_str().unwrap_or_default()
+    );
+    for (key, value) in pdu.as_object().unwrap() {
+        if key != "offset" {
+            render(key, value, 1, &mut out);
+        }
+    }
+    out
+}
+
+/// Appends `value` as an indented tree under `key`.
+fn render(key: &str, value: &Value, depth: usize, out: &mut String) {
+    let indent = "  ".repeat(depth);
+    match value {
+        Value::Object(map) => {
+            out.push_str(&format!("{}{}\n", indent, key));
+            for (key, value) in map {
+                render(key, value, depth + 1, out);
+            }
+        }
+        Value::Array(items) => {
+            out.push_str(&format!("{}{}\n", indent, key));
+            for (index, item) in items.iter().enumerate() {
+                render(&format!("[{}]", index), item, depth + 1, out);
+            }
+        }
+        Value::String(text) => {
+            out.push_str(&format!("{}{}: {}\n", indent, key, text.escape_debug()))
+        }
+        _ => out.push_str(&format!("{}{}: {}\n", indent, key, value)),
+    }
+}
+
+/// The tree of one PDU.
+pub fn describe(offset: usize, frame: &[u8]) -> Value {
+    let header = decode_pdu_header(frame).unwrap();
+    let mut pdu = Map::new();
+    pdu.insert("offset".into(), json!(offset));
+    pdu.insert(
+        "header".into(),
+        json!({
+            "command_length": header.command_length,
+            "command_id": format!("0x{:08X}", header.command_id),
+            "command": get_command_name(header.command_id).unwrap_or("unknown"),
+            "command_status": format!("0x{:08X}", header.command_status),
+            "status": get_status_description(header.command_status),
+            "sequence_number": header.sequence_number,
+        }),
+    );
+    match decode_pdu(frame) {
+        Ok(decoded) => {
+            let body = body(&decoded);
+            if !body.is_empty() {
+                pdu.insert("body".into(), Value::Object(body));
+            }
+        }
+        Err(e) => {
+            pdu.insert("error".into(), json!(e.to_string()));
+            pdu.insert("hex".into(), json!(hex(frame)));
+        }
+    }
+    Value::Object(pdu)
+}
+
+/// The body fields of a decoded PDU, in wire order.
+fn body(pdu: &Pdu) -> Map<String, Value> {
+    let mut body = Fields::default();
+    match pdu {
+        Pdu::BindRequest { pdu } => {
+            body.text("system_id", &pdu.system_id);
+            body.text("password", &pdu.password);
+            body.text("system_type", &pdu.system_type);
+            body.put(
+                "interface_version",
+                format!(
+                    "0x{:02X} ({}.{})",
+                    pdu.interface_version,
+                    pdu.interface_version >> 4,
+                    pdu.interface_version & 0x0F
+                ),
+            );
+            body.address("addr", pdu.addr_ton, pdu.addr_npi, &pdu.address_range);
+        }
+        Pdu::BindResponse { pdu } => body.text("system_id", &pdu.system_id),
+        Pdu::Outbind { pdu } => {
+            body.text("system_id", &pdu.system_id);
+            body.text("password", &pdu.password);
+        }
+        Pdu::EnquireLink { .. }
+        | Pdu::EnquireLinkResponse { .. }
+        | Pdu::Unbind { .. }
+        | Pdu::UnbindResponse { .. }
+        | Pdu::GenericNack { .. }
+        | Pdu::CancelSmResponse { .. }
+        | Pdu::ReplaceSmResponse { .. }
+        | Pdu::CancelBroadcastSmResponse { .. } => {}
+        Pdu::SubmitSm { pdu } => {
+            body.text("service_type", &pdu.service_type);
+            body.address(
+                "source",
+                pdu.source_addr_ton,
+                pdu.source_addr_npi,
+                &pdu.source_addr,
+            );
+            body.address(
+                "destination",
+                pdu.dest_addr_ton,
+                pdu.dest_addr_npi,
+                &pdu.destination_addr,
+            );
+            body.esm_class(pdu.esm_class);
+            body.put("protocol_id", pdu.protocol_id);
+            body.put("priority_flag", pdu.priority_flag);
+            body.time("schedule_delivery_time", &pdu.schedule_delivery_time);
+            body.time("validity_period", &pdu.validity_period);
+            body.registered_delivery(pdu.registered_delivery);
+            body.put("replace_if_present_flag", pdu.replace_if_present_flag);
+            body.data_coding(pdu.data_coding);
+            body.put("sm_default_msg_id", pdu.sm_default_msg_id);
+            body.message(&pdu.short_message, pdu.esm_class, pdu.data_coding);
+            body.tlvs(&pdu.tlvs, pdu.esm_class, pdu.data_coding);
+        }
+        Pdu::SubmitSmResponse { pdu } => body.text("message_id", &pdu.message_id),
+        Pdu::SubmitMulti { pdu } => {
+            body.text("service_type", &pdu.service_type);
+            body.address(
+                "source",
+                pdu.source_addr_ton,
+                pdu.source_addr_npi,
+                &pdu.source_addr,
+            );
+            let destinations = pdu
+                .destinations
+                .iter()
+                .map(|destination| match destination {
+                    Destination::SmeAddress { address } => {
+                        address_value(address.ton, address.npi, &address.value)
+                    }
+                    Destination::DistributionList { name } => json!({ "distribution_list": name }),
+                })
+                .collect();
+            body.put("destinations", Value::Array(destinations));
+            body.esm_class(pdu.esm_class);
+            body.put("protocol_id", pdu.protocol_id);
+            body.put("priority_flag", pdu.priority_flag);
+            body.time("schedule_delivery_time", &pdu.schedule_delivery_time);
+            body.time("validity_period", &pdu.validity_period);
+            body.registered_delivery(pdu.registered_delivery);
+            body.put("replace_if_present_flag", pdu.replace_if_present_flag);
+            body.data_coding(pdu.data_coding);
+            body.put("sm_default_msg_id", pdu.sm_default_msg_id);
+            body.message(&pdu.short_message, pdu.esm_class, pdu.data_coding);
+            body.tlvs(&pdu.tlvs, pdu.esm_class, pdu.data_coding);
+        }
+        Pdu::SubmitMultiResponse { pdu } => {
+            body.text("message_id", &pdu.message_id);
+            let unsuccessful = pdu
+                .unsuccess_smes
+                .iter()
+                .map(|sme| {
+                    let mut value = address_value(sme.ton, sme.npi, &sme.address);
+                    value["error_status"] = json!(status(sme.error_status));
+                    value
+                })
+                .collect();
+            body.put("unsuccess_smes", Value::Array(unsuccessful));
+        }
+        Pdu::DeliverSm { pdu } => {
+            body.text("service_type", &pdu.service_type);
+            body.address(
+                "source",
+                pdu.source_addr_ton,
+                pdu.source_addr_npi,
+                &pdu.source_addr,
+            );
+            body.address(
+                "destination",
+                pdu.dest_addr_ton,
+                pdu.dest_addr_npi,
+                &pdu.dest_addr,
+            );
+            body.esm_class(pdu.esm_class);
+            body.put("protocol_id", pdu.protocol_id);
+            body.put("priority_flag", pdu.priority_flag);
+            body.time("schedule_delivery_time", &pdu.schedule_delivery_time);
+            body.time("validity_period", &pdu.validity_period);
+            body.registered_delivery(pdu.registered_delivery);
+            body.put("replace_if_present_flag", pdu.replace_if_present_flag);
+            body.data_coding(pdu.data_coding);
+            body.put("sm_default_msg_id", pdu.sm_default_msg_id);
+            body.message(&pdu.short_message, pdu.esm_class, pdu.data_coding);
+            body.tlvs(&pdu.optional_params, pdu.esm_class, pdu.data_coding);
+        }
+        Pdu::DeliverSmResponse { pdu } => body.text("message_id", &pdu.message_id),
+        Pdu::DataSm { pdu } => {
+            body.text("service_type", &pdu.service_type);
+            body.address(
+                "source",
+                pdu.source_addr_ton,
+                pdu.source_addr_npi,
+                &pdu.source_addr,
+            );
+            body.address(
+                "destination",
+                pdu.dest_addr_ton,
+                pdu.dest_addr_npi,
+                &pdu.dest_addr,
+            );
+            body.esm_class(pdu.esm_class);
+            body.registered_delivery(pdu.registered_delivery);
+            body.data_coding(pdu.data_coding);
+            body.tlvs(&pdu.optional_params, pdu.esm_class, pdu.data_coding);
+        }
+        Pdu::DataSmResponse { pdu } => {
+            body.text("message_id", &pdu.message_id);
+            body.tlvs(&pdu.optional_params, 0, 0);
+        }
+        Pdu::QuerySm { pdu } => {
+            body.text("message_id", &pdu.message_id);
+            body.address(
+                "source",
+                pdu.source_addr_ton,
+                pdu.source_addr_npi,
+                &pdu.source_addr,
+            );
+        }
+        Pdu::QuerySmResponse { pdu } => {
+            body.text("message_id", &pdu.message_id);
+            body.text("final_date", &pdu.final_date);
+            body.put("message_state", message_state(pdu.message_state));
+            body.put("error_code", pdu.error_code);
+        }
+        Pdu::CancelSm { pdu } => {
+            body.text("service_type", &pdu.service_type);
+            body.text("message_id", &pdu.message_id);
+            body.address(
+                "source",
+                pdu.source_addr_ton,
+                pdu.source_addr_npi,
+                &pdu.source_addr,
+            );
+            body.address(
+                "destination",
+                pdu.dest_addr_ton,
+                pdu.dest_addr_npi,
+                &pdu.dest_addr,
+            );
+        }
+        Pdu::ReplaceSm { pdu } => {
+            body.text("message_id", &pdu.message_id);
+            body.address(
+                "source",
+                pdu.source_addr_ton,
+                pdu.source_addr_npi,
+                &pdu.source_addr,
+            );
+            body.text("schedule_delivery_time", &pdu.schedule_delivery_time);
+            body.text("validity_period", &pdu.validity_period);
+            body.registered_delivery(pdu.registered_delivery);
+            body.put("sm_default_msg_id", pdu.sm_default_msg_id);
+            // replace_sm keeps the data_coding of the original message.
+            body.message(&pdu.short_message, 0, 0);
+        }
+        Pdu::BroadcastSm { pdu } => {
+            body.text("service_type", &pdu.service_type);
+            body.address(
+                "source",
+                pdu.source_addr_ton,
+                pdu.source_addr_npi,
+                &pdu.source_addr,
+            );
+            body.text("message_id", &pdu.message_id);
+            body.put("priority_flag", pdu.priority_flag);
+            body.time("schedule_delivery_time", &pdu.schedule_delivery_time);
+            body.time("validity_period", &pdu.validity_period);
+            body.put("replace_if_present_flag", pdu.replace_if_present_flag);
+            body.data_coding(pdu.data_coding);
+            body.put("sm_default_msg_id", pdu.sm_default_msg_id);
+            body.tlvs(&pdu.optional_params, 0, pdu.data_coding);
+        }
+        Pdu::BroadcastSmResponse { pdu } => {
+            body.text("message_id", &pdu.message_id);
+            body.tlvs(&pdu.optional_params, 0, 0);
+        }
+        Pdu::QueryBroadcastSm { pdu } => {
+            body.text("message_id", &pdu.message_id);
+            body.address(
+                "source",
+                pdu.source_addr_ton,
+                pdu.source_addr_npi,
+                &pdu.source_addr,
+            );
+            body.tlvs(&pdu.optional_params, 0, 0);
+        }
+        Pdu::QueryBroadcastSmResponse { pdu } => {
+            body.text("message_id", &pdu.message_id);
+            body.tlvs(&pdu.optional_params, 0, 0);
+        }
+        Pdu::CancelBroadcastSm { pdu } => {
+            body.text("service_type", &pdu.service_type);
+            body.text("message_id", &pdu.message_id);
+            body.address(
+                "source",
+                pdu.source_addr_ton,
+                pdu.source_addr_npi,
+                &pdu.source_addr,
+            );
+            body.tlvs(&pdu.optional_params, 0, 0);
+        }
+    }
+    body.0
+}
+
+/// Body fields in insertion order.
+#[derive(Default)]
+struct Fields(Map<String, Value>);
+
+impl Fields {
+    fn put(&mut self, name: &str, value: impl Into<Value>) {
+        self.0.insert(name.to_string(), value.into());
+    }
+
+    fn text(&mut self, name: &str, value: &str) {
+        self.put(name, value);
+    }
+
+    fn time(&mut self, name: &str, value: &Option<String>) {
+        self.put(name, value.clone().unwrap_or_default());
+    }
+
+    fn address(&mut self, name: &str, ton: Ton, npi: Npi, address: &str) {
+        self.put(name, address_value(ton, npi, address));
+    }
+
+    fn esm_class(&mut self, esm_class: u8) {
+        let mode = match esm_class & 0x03 {
+            0x00 => "default mode",
+            0x01 => "datagram mode",
+            0x02 => "forward mode",
+            _ => "store and forward mode",
+        };
+        let mut flags = vec![mode];
+        match esm_class & 0x3C {
+            0x00 => {}
+            0x04 => flags.push("delivery receipt"),
+            0x08 => flags.push("delivery acknowledgement"),
+            0x10 => flags.push("user acknowledgement"),
+            0x18 => flags.push("conversation abort"),
+            0x20 => flags.push("intermediate delivery notification"),
+            _ => flags.push("unknown message type"),
+        }
+        if esm_class & 0x40 != 0 {
+            flags.push("UDHI");
+        }
+        if esm_class & 0x80 != 0 {
+            flags.push("reply path");
+        }
+        self.put(
+            "esm_class",
+            format!("0x{:02X} ({})", esm_class, flags.join(", ")),
+        );
+    }
+
+    fn registered_delivery(&mut self, registered_delivery: u8) {
+        let receipt = match registered_delivery & 0x03 {
+            0x00 => "no receipt",
+            0x01 => "receipt on success or failure",
+            0x02 => "receipt on failure",
+            _ => "receipt on success",
+        };
+        self.put(
+            "registered_delivery",
+            format!("0x{:02X} ({})", registered_delivery, receipt),
+        );
+    }
+
+    fn data_coding(&mut self, data_coding: u8) {
+        let name = match data_coding {
+            0x00 => "SMSC default alphabet",
+            0x01 => "IA5 (ASCII)",
+            0x02 | 0x04 => "8-bit binary",
+            0x03 => "Latin-1",
+            0x05 => "JIS",
+            0x06 => "Cyrillic",
+            0x07 => "Latin/Hebrew",
+            0x08 => "UCS2",
+            0x09 => "Pictogram",
+            0x0A => "ISO-2022-JP",
+            0x0D => "Extended Kanji JIS",
+            0x0E => "KS C 5601",
+            _ => "other",
+        };
+        self.put("data_coding", format!("0x{:02X} ({})", data_coding, name));
+    }
+
+    fn message(&mut self, content: &[u8], esm_class: u8, data_coding: u8) {
+        self.put(
+            "short_message",
+            message_value(content, esm_class, data_coding),
+        );
+    }
+
+    fn tlvs(&mut self, tlvs: &[Tlv], esm_class: u8, data_coding: u8) {
+        if tlvs.is_empty() {
+            return;
+        }
+        let tlvs = tlvs
+            .iter()
+            .map(|tlv| tlv_value(tlv, esm_class, data_coding))
+            .collect();
+        self.put("tlvs", Value::Array(tlvs));
+    }
+}
+
+fn address_value(ton: Ton, npi: Npi, address: &str) -> Value {
+    json!({
+        "ton": format!("{:?}", ton),
+        "npi": format!("{:?}", npi),
+        "address": address,
+    })
+}
+
+fn status(command_status: u32) -> String {
+    format!(
+        "0x{:08X} ({})",
+        command_status,
+        get_status_description(command_status)
+    )
+}
+
+fn message_state(state: u8) -> String {
+    let name = [
+        MessageState::Scheduled,
+        MessageState::Enroute,
+        MessageState::Delivered,
+        MessageState::Expired,
+        MessageState::Deleted,
+        MessageState::Undeliverable,
+        MessageState::Accepted,
+        MessageState::Unknown,
+        MessageState::Rejected,
+        MessageState::Skipped,
+    ]
+    .into_iter()
+    .find(|candidate| *candidate as u8 == state)
+    .map_or("invalid".to_string(), |state| format!("{:?}", state));
+    format!("{} ({})", state, name)
+}
+
+/// Message content with its UDH and text.
+fn message_value(content: &[u8], esm_class: u8, data_coding: u8) -> Value {
+    let mut message = Map::new();
+    message.insert("length".into(), json!(content.len()));
+    message.insert("hex".into(), json!(hex(content)));
+    let mut text = content;
+    if esm_class & 0x40 != 0 && !content.is_empty() {
+        let header_len = usize::from(content[0]) + 1;
+        match content.get(1..header_len) {
+            Some(header) => {
+                message.insert("udh".into(), udh_value(header));
+                text = &content[header_len..];
+            }
+            None => {
+                message.insert("udh".into(), json!("UDH length exceeds the message"));
+                text = &[];
+            }
+        }
+    }
+    let decoded = match data_coding {
+        0x00 => Some(gsm_7bit_decode(text)),
+        0x01 | 0x03 => Some(decode_8bit(text)),
+        0x08 => Some(decode_16bit(text)),
+        _ => None,
+    };
+    if let Some(decoded) = decoded {
+        message.insert("text".into(), json!(decoded));
+    }
+    Value::Object(message)
+}
+
+/// The information elements of a user data header, without its length octet.
+fn udh_value(mut header: &[u8]) -> Value {
+    let mut elements = Vec::new();
+    while !header.is_empty() {
+        let iei = header[0];
+        let Some(&length) = header.get(1) else {
+            elements.push(json!({ "error": "truncated information element" }));
+            break;
+        };
+        let Some(data) = header.get(2..2 + usize::from(length)) else {
+            elements.push(json!({ "error": "truncated information element" }));
+            break;
+        };
+        let mut element = json!({ "iei": format!("0x{:02X}", iei) });
+        match (iei, data) {
+            (0x00, &[reference, total, sequence]) => {
+                element["name"] = json!("concatenated short message, 8-bit reference");
+                element["reference"] = json!(reference);
+                element["total"] = json!(total);
+                element["sequence"] = json!(sequence);
+            }
+            (0x08, &[hi, lo, total, sequence]) => {
+                element["name"] = json!("concatenated short message, 16-bit reference");
+                element["reference"] = json!(u16::from_be_bytes([hi, lo]));
+                element["total"] = json!(total);
+                element["sequence"] = json!(sequence);
+            }
+            (0x04, &[destination, source]) => {
+                element["name"] = json!("application port, 8-bit");
+                element["destination_port"] = json!(destination);
+                element["source_port"] = json!(source);
+            }
+            (0x05, &[d1, d2, s1, s2]) => {
+                element["name"] = json!("application port, 16-bit");
+                element["destination_port"] = json!(u16::from_be_bytes([d1, d2]));
+                element["source_port"] = json!(u16::from_be_bytes([s1, s2]));
+            }
+            _ => element["data"] = json!(hex(data)),
+        }
+        elements.push(element);
+        header = &header[2 + usize::from(length)..];
+    }
+    Value::Array(elements)
+}
+
+fn tlv_value(tlv: &Tlv, esm_class: u8, data_coding: u8) -> Value {
+    let value = &tlv.value;
+    let interpreted = match (tlv.tag, value.as_slice()) {
+        (Tags::MESSAGE_PAYLOAD, _) => message_value(value, esm_class, data_coding),
+        (Tags::MESSAGE_STATE, &[state]) => json!(message_state(state)),
+        (Tags::NETWORK_ERROR_CODE, &[network_type, hi, lo]) => json!(format!(
+            "type {}, error {}",
+            network_type,
+            u16::from_be_bytes([hi, lo])
+        )),
+        (tag, _) if TEXT_TLVS.contains(&tag) => {
+            json!(String::from_utf8_lossy(value).trim_end_matches('\0'))
+        }
+        (tag, _) if get_tag_name(tag).is_none() || OCTET_TLVS.contains(&tag) => json!(hex(value)),
+        (_, &[a]) => json!(a),
+        (_, &[a, b]) => json!(u16::from_be_bytes([a, b])),
+        (_, &[a, b, c, d]) => json!(u32::from_be_bytes([a, b, c, d])),
+        _ => json!(hex(value)),
+    };
+    json!({
+        "tag": format!("0x{:04X}", tlv.tag),
+        "name": get_tag_name(tlv.tag).unwrap_or("unknown"),
+        "length": tlv.length,
+        "value": interpreted,
+    })
+}
+
+pub fn hex(bytes: &[u8]) -> String {
+    bytes
+        .iter()
+        .map(|byte| format!("{:02X}", byte))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
@@ -14,6 +14,18 @@ pub enum SmppFfiError {
     },
 }
 
+impl From<uniffi::UnexpectedUniFFICallbackError> for SmppFfiError {
+    fn from(e: uniffi::UnexpectedUniFFICallbackError) -> Self {
+        SmppFfiError::Generic { msg: e.reason }
+    }
+}
+
+impl From<std::io::Error> for SmppFfiError {
+    fn from(e: std::io::Error) -> Self {
+        SmppFfiError::Generic { msg: e.to_string() }
+    }
+}
+
 // --- Command IDs ---
 // These constants define the Command ID for each SMPP PDU.
 
@@ -219,6 +231,99 @@ impl From<Npi> for common::Npi {
 
 /// Command Status
 pub const COMMAND_STATUS_OK: u32 = 0x00000000;
+/// Message Length is invalid
+pub const ESME_RINVMSGLEN: u32 = 0x00000001;
+/// Invalid Command ID
+pub const ESME_RINVCMDID: u32 = 0x00000003;
+/// Incorrect BIND Status for given command
+pub const ESME_RINVBNDSTS: u32 = 0x00000004;
+/// ESME Already in Bound State
+pub const ESME_RALYBND: u32 = 0x00000005;
+/// Invalid Priority Flag
+pub const ESME_RINVPRTFLG: u32 = 0x00000006;
+/// Invalid Registered Delivery Flag
+pub const ESME_RINVREGDLVFLG: u32 = 0x00000007;
+/// System Error
+pub const ESME_RSYSERR: u32 = 0x00000008;
+/// Invalid Source Address
+pub const ESME_RINVSRCADR: u32 = 0x0000000A;
+/// Invalid Destination Address
+pub const ESME_RINVDSTADR: u32 = 0x0000000B;
+/// Message ID is invalid
+pub const ESME_RINVMSGID: u32 = 0x0000000C;
+/// Bind Failed
+pub const ESME_RBINDFAIL: u32 = 0x0000000D;
+/// Invalid Password
+pub const ESME_RINVPASWD: u32 = 0x0000000E;
+/// Invalid System ID
+pub const ESME_RINVSYSID: u32 = 0x0000000F;
+/// Cancel SM Failed
+pub const ESME_RCANCELFAIL: u32 = 0x00000011;
+/// Replace SM Failed
+pub const ESME_RREPLACEFAIL: u32 = 0x00000013;
+/// Invalid Service Type
+pub const ESME_RINVSERVICETYPE: u32 = 0x00000015;
+/// Invalid number of destinations
+pub const ESME_RINVNUMDESTS: u32 = 0x00000033;
+/// Invalid Distribution List name
+pub const ESME_RINVDLNAME: u32 = 0x00000034;
+/// Invalid esm_class field data
+pub const ESME_RINVESMCLASS: u32 = 0x00000043;
+/// Invalid system_type field
+pub const ESME_RINVSYSTYP: u32 = 0x00000053;
+/// Invalid replace_if_present flag
+pub const ESME_RINVREPFLAG: u32 = 0x00000054;
+/// Throttling error (ESME has exceeded allowed message limits)
+pub const ESME_RTHROTTLED: u32 = 0x00000058;
+/// query_sm request failed
+pub const ESME_RQUERYFAIL: u32 = 0x00000067;
+/// Invalid Scheduled Delivery Time
+pub const ESME_RINVSCHED: u32 = 0x00000061;
+/// Invalid message validity period (Expiry time)
+pub const ESME_RINVEXPIRY: u32 = 0x00000062;
+/// Optional Parameter not allowed
+pub const ESME_ROPTPARNOTALLWD: u32 = 0x000000C1;
+/// Invalid Parameter Length
+pub const ESME_RINVPARLEN: u32 = 0x000000C2;
+/// Expected Optional Parameter missing
+pub const ESME_RMISSINGOPTPARAM: u32 = 0x000000C3;
+/// Invalid Optional Parameter Value
+pub const ESME_RINVOPTPARAMVAL: u32 = 0x000000C4;
+
+/// SMPP protocol version, as negotiated through the bind `interface_version`.
+#[derive(uniffi::Enum, Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum SmppVersion {
+    /// SMPP v3.3 (no TLVs)
+    V33,
+    /// SMPP v3.4
+    V34,
+    /// SMPP v5.0
+    V50,
+}
+
+impl SmppVersion {
+    /// Returns the `interface_version` value for this version.
+    pub fn interface_version(self) -> u8 {
+        match self {
+            SmppVersion::V33 => 0x33,
+            SmppVersion::V34 => SMPP_INTERFACE_VERSION_34,
+            SmppVersion::V50 => SMPP_INTERFACE_VERSION_50,
+        }
+    }
+}
+
+/// Maps a bind `interface_version` to an SMPP version.
+///
+/// Values up to 0x33 are v3.3, values from 0x50 are v5.0, anything in
+/// between is treated as v3.4.
+#[uniffi::export]
+pub fn smpp_version_from_interface_version(interface_version: u8) -> SmppVersion {
+    match interface_version {
+        0..=0x33 => SmppVersion::V33,
+        0x50.. => SmppVersion::V50,
+        _ => SmppVersion::V34,
+    }
+}
 
 /// Bind Mode (Receiver, Transmitter, Transceiver)
 #[derive(uniffi::Enum, Clone, Copy, Debug, PartialEq, Eq)]
@@ -1,10 +1,14 @@
+use crate::address::Address;
+use crate::ancillary_pdu_ffi::MessageState;
 use crate::common::{Npi, SmppFfiError, Ton};
-use crate::tlv::Tlv;
+use crate::message_store::{message_state_is_final, utc_fields, StoredMessage};
+use crate::tlv::{tlv_new, tlv_new_string, tlv_new_u8, Tags, Tlv};
 use smpp_codec::pdus::{
     DataSm as InternalDataSm, DataSmResp as InternalDataSmResponse,
     DeliverSmRequest as InternalDeliverSmRequest, DeliverSmResponse as InternalDeliverSmResponse,
     DeliveryReceipt as InternalDeliveryReceipt,
 };
+use std::time::{SystemTime, UNIX_EPOCH};
 
 /// Represents a Data SM request.
 #[derive(uniffi::Record, Clone, Debug, PartialEq)]
@@ -289,6 +293,142 @@ pub fn encode_delivery_receipt(receipt: &DeliveryReceipt) -> Vec<u8> {
     internal_receipt.to_string().into_bytes()
 }
 
+/// `esm_class` of a `deliver_sm` carrying an SMSC delivery receipt.
+pub const ESM_CLASS_DELIVERY_RECEIPT: u8 = 0x04;
+
+/// Network type of the `network_error_code` TLV for GSM errors.
+const NETWORK_TYPE_GSM: u8 = 0x03;
+
+/// Builds the `deliver_sm` that reports the final state of a stored message.
+///
+/// The receipt goes from the message's destination back to its source with
+/// `esm_class` 0x04, the `id:... stat:... err:...` text and the
+/// `receipted_message_id` and `message_state` TLVs. A non-zero
+/// `network_error_code` is also sent as a GSM `network_error_code` TLV.
+/// Returns `None` if the message's `registered_delivery` did not ask for a
+/// receipt in this outcome. The sequence number is 0.
+///
+/// # Errors
+///
+/// Returns an error if `final_state` is not a final state.
+#[uniffi::export]
+pub fn build_delivery_receipt_pdu(
+    message: StoredMessage,
+    final_state: MessageState,
+    network_error_code: u16,
+) -> Result<Option<DeliverSmRequest>, SmppFfiError> {
+    if !message_state_is_final(final_state) {
+        return Err(SmppFfiError::Generic {
+            msg: format!("{:?} is not a final message state", final_state),
+        });
+    }
+    let delivered = final_state == MessageState::Delivered;
+    let requested = match message.registered_delivery & 0x03 {
+        0x01 => true,
+        0x02 => !delivered,
+        0x03 => delivered,
+        _ => false,
+    };
+    if !requested {
+        return Ok(None);
+    }
+
+    let done_time_ms = message.done_time_ms.unwrap_or_else(|| {
+        SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |elapsed| elapsed.as_millis() as u64)
+    });
+    let receipt = DeliveryReceipt {
+        message_id: message.message_id.clone(),
+        submitted_count: 1,
+        delivered_count: u32::from(delivered),
+        submit_date: receipt_date(message.submit_time_ms),
+        done_date: receipt_date(done_time_ms),
+        status: receipt_status(final_state).to_string(),
+        error_code: u32::from(network_error_code),
+        text: receipt_text(&message),
+    };
+    let mut optional_params = vec![
+        tlv_new_string(Tags::RECEIPTED_MESSAGE_ID, message.message_id.clone()),
+        tlv_new_u8(Tags::MESSAGE_STATE, final_state as u8),
+    ];
+    if network_error_code != 0 {
+        let [high, low] = network_error_code.to_be_bytes();
+        optional_params.push(tlv_new(
+            Tags::NETWORK_ERROR_CODE,
+            vec![NETWORK_TYPE_GSM, high, low],
+        ));
+    }
+    let Address { ton, npi, value } = message.destination;
+    Ok(Some(DeliverSmRequest {
+        sequence_number: 0,
+        service_type: message.service_type,
+        source_addr_ton: ton,
+        source_addr_npi: npi,
+        source_addr: value,
+        dest_addr_ton: message.source.ton,
+        dest_addr_npi: message.source.npi,
+        dest_addr: message.source.value,
+        esm_class: ESM_CLASS_DELIVERY_RECEIPT,
+        protocol_id: 0,
+        priority_flag: 0,
+        schedule_delivery_time: None,
+        validity_period: None,
+        registered_delivery: 0,
+        replace_if_present_flag: 0,
+        data_coding: 0,
+        sm_default_msg_id: 0,
+        short_message: encode_delivery_receipt(&receipt),
+        optional_params,
+    }))
+}
+
+/// Formats a time as the `YYMMDDhhmm` dates of a receipt, in UTC.
+fn receipt_date(time_ms: u64) -> String {
+    let [year, month, day, hour, minute, _] = utc_fields(time_ms);
+    format!(
+        "{:02}{:02}{:02}{:02}{:02}",
+        year % 100,
+        month,
+        day,
+        hour,
+        minute
+    )
+}
+
+fn receipt_status(state: MessageState) -> &'static str {
+    match state {
+        MessageState::Delivered => "DELIVRD",
+        MessageState::Expired => "EXPIRED",
+        MessageState::Deleted => "DELETED",
+        MessageState::Undeliverable => "UNDELIV",
+        MessageState::Accepted => "ACCEPTD",
+        MessageState::Rejected => "REJECTD",
+        MessageState::Skipped => "SKIPPED",
+        MessageState::Scheduled | MessageState::Enroute | MessageState::Unknown => "UNKNOWN",
+    }
+}
+
+/// The first 20 characters of a text message; empty for binary and UCS2 content.
+fn receipt_text(message: &StoredMessage) -> String {
+    let mut content = message.short_message.as_slice();
+    if message.esm_class & 0x40 != 0 {
+        let header_len = content.first().map_or(0, |&udhl| usize::from(udhl) + 1);
+        content = content.get(header_len..).unwrap_or_default();
+    }
+    if !matches!(message.data_coding, 0x00 | 0x01 | 0x03) {
+        return String::new();
+    }
+    content
+        .iter()
+        .take(20)
+        .map(|&byte| match byte {
+            0x20..=0x7E => byte as char,
+            _ => '?',
+        })
+        .collect()
+}
+
 /// Encodes a `DataSmResponse` into a byte vector.
 ///
 /// # Panics
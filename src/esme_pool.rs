@@ -0,0 +1,341 @@
+//! # ESME Pool
+//!
+//! Several `EsmeSession` binds to one or more SMSC endpoints behind a single
+//! `submit`. Submits are spread across the bound sessions, the pool as a
+//! whole keeps to a TPS cap, and binds that end are replaced in the background.
+use crate::common::*;
+use crate::esme_session::{DeliverSmListener, EsmeSession, EsmeSessionConfig};
+use crate::session_pdu_ffi::BindRequest;
+use crate::submission_pdu_ffi::{SubmitSmRequest, SubmitSmResponse};
+use crate::throttle::{Throttle, ThrottleConfig, ThrottleStats};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError, Sender};
+use std::sync::{Arc, Mutex};
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+
+/// How the pool picks a session for each submit.
+#[derive(uniffi::Enum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LoadBalancing {
+    /// Each bound session in turn.
+    RoundRobin,
+    /// The bound session with the fewest requests awaiting a response.
+    LeastInFlight,
+    /// Each bound session in proportion to the weight of its endpoint.
+    Weighted,
+}
+
+/// An SMSC endpoint and the binds the pool keeps to it.
+#[derive(uniffi::Record, Clone, Debug, PartialEq)]
+pub struct PoolEndpoint {
+    /// Connection settings of each bind.
+    pub config: EsmeSessionConfig,
+    /// The bind request of each bind.
+    pub bind: BindRequest,
+    /// Number of binds to this endpoint.
+    #[uniffi(default = 1)]
+    pub binds: u32,
+    /// Share of the traffic under `LoadBalancing::Weighted`.
+    #[uniffi(default = 1)]
+    pub weight: u32,
+}
+
+/// Settings for an `EsmePool`.
+#[derive(uniffi::Record, Clone, Debug, PartialEq)]
+pub struct EsmePoolConfig {
+    /// The endpoints to bind to.
+    pub endpoints: Vec<PoolEndpoint>,
+    /// How submits are spread across the binds.
+    pub strategy: LoadBalancing,
+    /// Maximum submits per second across the whole pool. 0 is unlimited.
+    #[uniffi(default = 0)]
+    pub max_per_second: u32,
+    /// Time between attempts to replace binds that have ended, in milliseconds.
+    #[uniffi(default = 5000)]
+    pub rebind_interval_ms: u64,
+}
+
+/// Creates an `EsmePoolConfig` without a TPS cap.
+#[uniffi::export]
+pub fn esme_pool_config_new(
+    endpoints: Vec<PoolEndpoint>,
+    strategy: LoadBalancing,
+) -> EsmePoolConfig {
+    EsmePoolConfig {
+        endpoints,
+        strategy,
+        max_per_second: 0,
+        rebind_interval_ms: 5_000,
+    }
+}
+
+/// The state of one bind in an `EsmePool`.
+#[derive(uniffi::Record, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct PoolMemberStatus {
+    /// Index of the bind's endpoint in `EsmePoolConfig::endpoints`.
+    pub endpoint_index: u32,
+    /// True while the bind is usable.
+    pub bound: bool,
+    /// Requests awaiting a response on the bind.
+    pub in_flight: u32,
+}
+
+/// A set of binds used as one.
+#[derive(uniffi::Object)]
+pub struct EsmePool {
+    inner: Arc<PoolInner>,
+    stop: Mutex<Option<Sender<()>>>,
+    rebinder: Mutex<Option<JoinHandle<()>>>,
+}
+
+#[uniffi::export]
+impl EsmePool {
+    /// Binds every member of the pool.
+    ///
+    /// Binds that fail are retried in the background.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the pool has no binds or none of them succeeds.
+    #[uniffi::constructor]
+    pub fn connect(
+        config: EsmePoolConfig,
+        listener: Option<Arc<dyn DeliverSmListener>>,
+    ) -> Result<Arc<Self>, SmppFfiError> {
+        let members: Vec<Member> = config
+            .endpoints
+            .iter()
+            .enumerate()
+            .flat_map(|(endpoint, settings)| {
+                (0..settings.binds).map(move |_| Member {
+                    endpoint,
+                    session: Mutex::new(None),
+                })
+            })
+            .collect();
+        if members.is_empty() {
+            return Err(SmppFfiError::Generic {
+                msg: "The pool has no binds".to_string(),
+            });
+        }
+        let inner = Arc::new(PoolInner {
+            throttle: Throttle::new(ThrottleConfig::new(config.max_per_second)),
+            weights: Mutex::new(vec![0; members.len()]),
+            members,
+            next: AtomicUsize::new(0),
+            listener,
+            config,
+        });
+
+        let mut last_error = None;
+        for member in &inner.members {
+            if let Err(e) = inner.bind(member) {
+                last_error = Some(e);
+            }
+        }
+        if inner.bound_count() == 0 {
+            return Err(last_error.unwrap_or_else(no_bound_session_error));
+        }
+
+        let (stop, stopped) = mpsc::channel();
+        let rebinder = {
+            let inner = inner.clone();
+            thread::Builder::new()
+                .name("smpp-pool-rebinder".to_string())
+                .spawn(move || inner.rebind_loop(stopped))?
+        };
+        Ok(Arc::new(Self {
+            inner,
+            stop: Mutex::new(Some(stop)),
+            rebinder: Mutex::new(Some(rebinder)),
+        }))
+    }
+
+    /// Submits a message on one of the bound sessions.
+    ///
+    /// Waits for the pool's TPS cap. If the chosen bind fails before the
+    /// request is written, the message is submitted on another one. Once
+    /// written, the SMSC may have accepted it, so a failure is returned
+    /// instead of risking a duplicate.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if no bind is usable or the submit fails as in `EsmeSession::submit`.
+    pub fn submit(&self, request: SubmitSmRequest) -> Result<SubmitSmResponse, SmppFfiError> {
+        self.inner.throttle.acquire(CMD_SUBMIT_SM);
+        let mut last_error = None;
+        for _ in 0..self.inner.members.len() {
+            let Some(session) = self.inner.pick() else {
+                break;
+            };
+            match session.try_submit(request.clone()) {
+                Err(failure) if !failure.sent => last_error = Some(failure.error),
+                result => return result.map_err(|failure| failure.error),
+            }
+        }
+        Err(last_error.unwrap_or_else(no_bound_session_error))
+    }
+
+    /// Returns the number of binds in the pool.
+    pub fn size(&self) -> u32 {
+        self.inner.members.len() as u32
+    }
+
+    /// Returns the number of usable binds.
+    pub fn bound_count(&self) -> u32 {
+        self.inner.bound_count() as u32
+    }
+
+    /// Returns the state of every bind.
+    pub fn members(&self) -> Vec<PoolMemberStatus> {
+        self.inner
+            .members
+            .iter()
+            .map(|member| {
+                let session = member.session();
+                PoolMemberStatus {
+                    endpoint_index: member.endpoint as u32,
+                    bound: session.as_ref().is_some_and(|session| session.is_bound()),
+                    in_flight: session.map_or(0, |session| session.in_flight()),
+                }
+            })
+            .collect()
+    }
+
+    /// Returns the pool's send rate and the submits waiting for its TPS cap.
+    pub fn throttle_stats(&self) -> ThrottleStats {
+        self.inner.throttle.stats()
+    }
+
+    /// Unbinds every session and stops replacing them.
+    pub fn close(&self) {
+        self.stop.lock().unwrap().take();
+        if let Some(rebinder) = self.rebinder.lock().unwrap().take() {
+            let _ = rebinder.join();
+        }
+        for member in &self.inner.members {
+            if let Some(session) = member.session.lock().unwrap().take() {
+                let _ = session.unbind();
+            }
+        }
+    }
+}
+
+impl Drop for EsmePool {
+    fn drop(&mut self) {
+        self.close();
+    }
+}
+
+/// One bind slot.
+struct Member {
+    endpoint: usize,
+    session: Mutex<Option<Arc<EsmeSession>>>,
+}
+
+impl Member {
+    fn session(&self) -> Option<Arc<EsmeSession>> {
+        self.session.lock().unwrap().clone()
+    }
+
+    fn bound_session(&self) -> Option<Arc<EsmeSession>> {
+        self.session().filter(|session| session.is_bound())
+    }
+}
+
+/// The pool itself, shared with the rebinder thread.
+struct PoolInner {
+    config: EsmePoolConfig,
+    listener: Option<Arc<dyn DeliverSmListener>>,
+    members: Vec<Member>,
+    /// Where round-robin continues.
+    next: AtomicUsize,
+    /// Running weights for smooth weighted round-robin.
+    weights: Mutex<Vec<i64>>,
+    throttle: Throttle,
+}
+
+impl PoolInner {
+    fn bind(&self, member: &Member) -> Result<(), SmppFfiError> {
+        let endpoint = &self.config.endpoints[member.endpoint];
+        let session = EsmeSession::connect(
+            endpoint.config.clone(),
+            endpoint.bind.clone(),
+            self.listener.clone(),
+        )?;
+        *member.session.lock().unwrap() = Some(session);
+        Ok(())
+    }
+
+    fn bound_count(&self) -> usize {
+        self.members
+            .iter()
+            .filter(|member| member.bound_session().is_some())
+            .count()
+    }
+
+    /// Picks a bound session according to the strategy.
+    fn pick(&self) -> Option<Arc<EsmeSession>> {
+        let start = self.next.fetch_add(1, Ordering::SeqCst);
+        let count = self.members.len();
+        // Bound sessions, in round-robin order from `start`.
+        let bound = (0..count).filter_map(|offset| {
+            let index = (start + offset) % count;
+            self.members[index]
+                .bound_session()
+                .map(|session| (index, session))
+        });
+        match self.config.strategy {
+            LoadBalancing::RoundRobin => bound.map(|(_, session)| session).next(),
+            LoadBalancing::LeastInFlight => bound
+                .min_by_key(|(_, session)| session.in_flight())
+                .map(|(_, session)| session),
+            LoadBalancing::Weighted => {
+                let bound: Vec<_> = bound.collect();
+                let mut weights = self.weights.lock().unwrap();
+                let mut total = 0;
+                let mut best: Option<(usize, Arc<EsmeSession>)> = None;
+                for (index, session) in bound {
+                    let weight =
+                        i64::from(self.config.endpoints[self.members[index].endpoint].weight);
+                    weights[index] += weight;
+                    total += weight;
+                    if best
+                        .as_ref()
+                        .is_none_or(|(chosen, _)| weights[index] > weights[*chosen])
+                    {
+                        best = Some((index, session));
+                    }
+                }
+                let (index, session) = best?;
+                weights[index] -= total;
+                Some(session)
+            }
+        }
+    }
+
+    /// Replaces ended binds every `rebind_interval_ms` until stopped.
+    fn rebind_loop(&self, stop: Receiver<()>) {
+        let interval = Duration::from_millis(self.config.rebind_interval_ms);
+        loop {
+            match stop.recv_timeout(interval) {
+                Err(RecvTimeoutError::Timeout) => {}
+                _ => return,
+            }
+            for member in &self.members {
+                // A session that is reconnecting by itself is left alone.
+                let ended = member.session().is_none_or(|session| session.is_closed());
+                if ended {
+                    member.session.lock().unwrap().take();
+                    let _ = self.bind(member);
+                }
+            }
+        }
+    }
+}
+
+fn no_bound_session_error() -> SmppFfiError {
+    SmppFfiError::Generic {
+        msg: "No bound session in the pool".to_string(),
+    }
+}
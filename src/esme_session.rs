@@ -0,0 +1,1075 @@
+//! # ESME Session
+//!
+//! A blocking client session over TCP. It binds to an SMSC, correlates
+//! responses with requests, answers `enquire_link` and hands inbound
+//! `deliver_sm` to a foreign callback. While bound it sends its own
+//! `enquire_link` when the link goes quiet and drops the link once the SMSC
+//! stops answering them. A `ReconnectPolicy` brings dropped links back and
+//! a `ThrottleConfig` paces outbound requests.
+use crate::common::*;
+use crate::delivery_pdu_ffi::{encode_delivery_sm_response, DeliverSmRequest, DeliverSmResponse};
+use crate::framer::read_frame;
+use crate::pdu::*;
+use crate::reconnect::{reconnect_delay_ms, InFlightPolicy, ReconnectPolicy};
+use crate::request_window::{RequestWindowConfig, Window};
+use crate::sequence::SequenceGenerator;
+use crate::session_pdu_ffi::*;
+use crate::session_state::LinkHealth;
+use crate::submission_pdu_ffi::{encode_submit_sm_request, SubmitSmRequest, SubmitSmResponse};
+use crate::throttle::{Throttle, ThrottleConfig, ThrottleStats};
+use crate::tls::{Stream, TlsClientConfig};
+use smpp_codec::common::get_status_description;
+use std::io::Write;
+use std::net::{TcpStream, ToSocketAddrs};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError, Sender};
+use std::sync::{Arc, Condvar, Mutex};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+
+/// Receives the requests an SMSC sends to a bound ESME.
+#[uniffi::export(with_foreign)]
+pub trait DeliverSmListener: Send + Sync {
+    /// Called for every inbound `deliver_sm`.
+    ///
+    /// Returns the `command_status` of the `deliver_sm_resp`.
+    fn on_deliver_sm(&self, request: DeliverSmRequest) -> u32;
+}
+
+/// Receives changes in the health of an `EsmeSession` link.
+#[uniffi::export(with_foreign)]
+pub trait LinkHealthListener: Send + Sync {
+    /// Called from the keepalive thread whenever the health changes.
+    fn on_link_health(&self, health: LinkHealth);
+}
+
+/// The connection state of an `EsmeSession`, as reported to a `ConnectionStateListener`.
+#[derive(uniffi::Enum, Clone, Debug, PartialEq, Eq)]
+pub enum ConnectionState {
+    /// The session has (re)bound.
+    Bound,
+    /// The link dropped; the session will try to rebind.
+    Disconnected {
+        /// Why the link dropped.
+        reason: String,
+    },
+    /// The session is waiting before its next attempt to reconnect.
+    Reconnecting {
+        /// The number of the attempt, starting at 1.
+        attempt: u32,
+        /// The wait before the attempt, in milliseconds.
+        delay_ms: u64,
+    },
+    /// The session has ended and will not reconnect.
+    Closed {
+        /// Why the session ended.
+        reason: String,
+    },
+}
+
+/// Receives changes in the connection state of an `EsmeSession`.
+#[uniffi::export(with_foreign)]
+pub trait ConnectionStateListener: Send + Sync {
+    /// Called from a session thread whenever the connection state changes.
+    fn on_connection_state(&self, state: ConnectionState);
+}
+
+/// Connection settings for an `EsmeSession`.
+#[derive(uniffi::Record, Clone, Debug, PartialEq, Eq)]
+pub struct EsmeSessionConfig {
+    /// Host name or IP address of the SMSC.
+    pub host: String,
+    /// TCP port of the SMSC.
+    pub port: u16,
+    /// Maximum time to establish the TCP connection, in milliseconds.
+    #[uniffi(default = 10000)]
+    pub connect_timeout_ms: u64,
+    /// Maximum time to wait for a response, in milliseconds.
+    #[uniffi(default = 30000)]
+    pub response_timeout_ms: u64,
+    /// Maximum number of unacknowledged requests; further calls wait for a free slot.
+    #[uniffi(default = 10)]
+    pub max_in_flight: u32,
+    /// Time without inbound traffic before `enquire_link` is sent, in milliseconds. 0 disables it.
+    #[uniffi(default = 30000)]
+    pub enquire_link_interval_ms: u64,
+    /// Unanswered keepalives in a row after which the link is dropped. 0 never gives up.
+    #[uniffi(default = 3)]
+    pub max_missed_enquire_links: u32,
+    /// How to reconnect after a failed bind or a dropped link. `None` never reconnects.
+    #[uniffi(default = None)]
+    pub reconnect: Option<ReconnectPolicy>,
+    /// Outbound rate limits. `None` sends without limit but still retries `ESME_RTHROTTLED`.
+    #[uniffi(default = None)]
+    pub throttle: Option<ThrottleConfig>,
+    /// TLS settings. `None` connects over plain TCP.
+    #[uniffi(default = None)]
+    pub tls: Option<TlsClientConfig>,
+}
+
+impl EsmeSessionConfig {
+    /// Creates a configuration with default timeouts.
+    pub fn new(host: String, port: u16) -> Self {
+        Self {
+            host,
+            port,
+            connect_timeout_ms: 10_000,
+            response_timeout_ms: 30_000,
+            max_in_flight: 10,
+            enquire_link_interval_ms: 30_000,
+            max_missed_enquire_links: 3,
+            reconnect: None,
+            throttle: None,
+            tls: None,
+        }
+    }
+}
+
+/// Creates an `EsmeSessionConfig` with default timeouts.
+#[uniffi::export]
+pub fn esme_session_config_new(host: String, port: u16) -> EsmeSessionConfig {
+    EsmeSessionConfig::new(host, port)
+}
+
+/// A bound ESME connection to an SMSC.
+///
+/// With a `ReconnectPolicy` the session survives dropped links: it
+/// reconnects, rebinds with the same bind request and carries on.
+#[derive(uniffi::Object)]
+pub struct EsmeSession {
+    inner: Arc<Inner>,
+}
+
+#[uniffi::export]
+impl EsmeSession {
+    /// Connects to the SMSC and binds with the given request.
+    ///
+    /// The sequence number of the bind request is assigned by the session.
+    /// With a `ReconnectPolicy`, failed attempts are retried before returning.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the connection fails or the SMSC rejects the bind.
+    #[uniffi::constructor]
+    pub fn connect(
+        config: EsmeSessionConfig,
+        bind: BindRequest,
+        listener: Option<Arc<dyn DeliverSmListener>>,
+    ) -> Result<Arc<Self>, SmppFfiError> {
+        Self::connect_with_sequence_generator(config, bind, listener, SequenceGenerator::new())
+    }
+
+    /// Connects and binds like `connect`, drawing sequence numbers from `sequence`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the connection fails or the SMSC rejects the bind.
+    #[uniffi::constructor]
+    pub fn connect_with_sequence_generator(
+        config: EsmeSessionConfig,
+        bind: BindRequest,
+        listener: Option<Arc<dyn DeliverSmListener>>,
+        sequence: Arc<SequenceGenerator>,
+    ) -> Result<Arc<Self>, SmppFfiError> {
+        let session = Self::unconnected(config, bind, listener, sequence);
+        let connected = session.inner.connect_loop(0);
+        session.finish_connect(connected)
+    }
+
+    /// Submits a message and waits for its `submit_sm_resp`.
+    ///
+    /// The sequence number of the request is assigned by the session.
+    /// A response with a non-zero `command_status` is returned as is.
+    /// Calls from several threads are pipelined up to `max_in_flight`.
+    /// Under `InFlightPolicy::Resubmit` a call waits out reconnection.
+    /// Calls wait for the rate limiter, and a throttled submit is retried
+    /// after a pause before its response is returned.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the session is not bound, the link fails, the
+    /// response times out or the SMSC answers with `generic_nack`.
+    pub fn submit(&self, request: SubmitSmRequest) -> Result<SubmitSmResponse, SmppFfiError> {
+        self.try_submit(request).map_err(|failure| failure.error)
+    }
+
+    /// Unbinds from the SMSC and closes the connection.
+    ///
+    /// Calling it on a session that is no longer bound only closes the connection.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the SMSC does not acknowledge the unbind.
+    pub fn unbind(&self) -> Result<(), SmppFfiError> {
+        self.inner.closed.store(true, Ordering::SeqCst);
+        let result = match self.inner.current_link() {
+            Some(link) if link.bound.swap(false, Ordering::SeqCst) => {
+                match link.request(|sequence_number| encode_unbind(&Unbind { sequence_number })) {
+                    Ok(Pdu::UnbindResponse { .. }) => Ok(()),
+                    Ok(other) => Err(unexpected_response("Unbind", &other)),
+                    Err(e) => Err(e),
+                }
+            }
+            _ => Ok(()),
+        };
+        self.close();
+        result
+    }
+
+    /// Returns true while the session is bound.
+    pub fn is_bound(&self) -> bool {
+        self.inner
+            .current_link()
+            .is_some_and(|link| link.bound.load(Ordering::SeqCst))
+    }
+
+    /// Returns the generator that assigns this session's sequence numbers.
+    pub fn sequence_generator(&self) -> Arc<SequenceGenerator> {
+        self.inner.sequence.clone()
+    }
+
+    /// Returns the number of requests waiting for a response.
+    pub fn in_flight(&self) -> u32 {
+        self.inner
+            .current_link()
+            .map_or(0, |link| link.window.lock().unwrap().len() as u32)
+    }
+
+    /// Returns the number of responses that matched no request in flight.
+    pub fn orphan_responses(&self) -> u64 {
+        self.inner.shared.orphan_responses.load(Ordering::SeqCst)
+    }
+
+    /// Returns the current send rate, the requests queued by the rate limiter
+    /// and the number of `ESME_RTHROTTLED` responses.
+    pub fn throttle_stats(&self) -> ThrottleStats {
+        self.inner.throttle.stats()
+    }
+
+    /// Returns the health of the link as seen by the keepalives.
+    pub fn link_health(&self) -> LinkHealth {
+        *self.inner.shared.health.lock().unwrap()
+    }
+
+    /// Sets the listener told about changes in link health, replacing any previous one.
+    pub fn set_link_health_listener(&self, listener: Option<Arc<dyn LinkHealthListener>>) {
+        *self.inner.shared.health_listener.lock().unwrap() = listener;
+    }
+
+    /// Sets the listener told about disconnects, reconnects and rebinds, replacing any previous one.
+    pub fn set_connection_state_listener(
+        &self,
+        listener: Option<Arc<dyn ConnectionStateListener>>,
+    ) {
+        *self.inner.shared.state_listener.lock().unwrap() = listener;
+    }
+}
+
+impl EsmeSession {
+    /// Like `submit`, but tells whether a failed request was written to the connection.
+    pub(crate) fn try_submit(
+        &self,
+        request: SubmitSmRequest,
+    ) -> Result<SubmitSmResponse, RequestFailure> {
+        let mut request = request;
+        let response = self.inner.request(CMD_SUBMIT_SM, |sequence_number| {
+            request.sequence_number = sequence_number;
+            encode_submit_sm_request(&request)
+        })?;
+        match response {
+            Pdu::SubmitSmResponse { pdu } => Ok(pdu),
+            other => Err(RequestFailure {
+                error: unexpected_response("SubmitSm", &other),
+                sent: true,
+            }),
+        }
+    }
+
+    /// Binds as a receiver over a connection on which the SMSC sent `outbind`.
+    ///
+    /// The SMSC opened the connection, so the session never reconnects.
+    pub(crate) fn accept_outbind(
+        stream: Stream,
+        config: EsmeSessionConfig,
+        bind: BindRequest,
+        listener: Option<Arc<dyn DeliverSmListener>>,
+    ) -> Result<Arc<Self>, SmppFfiError> {
+        let config = EsmeSessionConfig {
+            reconnect: None,
+            ..config
+        };
+        let bind = BindRequest {
+            mode: BindMode::Receiver,
+            ..bind
+        };
+        let session = Self::unconnected(config, bind, listener, SequenceGenerator::new());
+        let connected = session.inner.bind_stream(stream).map_err(|(_, e)| e);
+        session.finish_connect(connected)
+    }
+
+    /// Returns true once the session has ended for good.
+    pub(crate) fn is_closed(&self) -> bool {
+        matches!(*self.inner.connection.lock().unwrap(), Connection::Closed)
+    }
+
+    fn unconnected(
+        config: EsmeSessionConfig,
+        bind: BindRequest,
+        listener: Option<Arc<dyn DeliverSmListener>>,
+        sequence: Arc<SequenceGenerator>,
+    ) -> Arc<Self> {
+        Arc::new(Self {
+            inner: Arc::new(Inner {
+                throttle: Throttle::new(config.throttle.clone().unwrap_or_default()),
+                config,
+                bind,
+                sequence,
+                shared: Arc::new(Shared {
+                    listener,
+                    orphan_responses: AtomicU64::new(0),
+                    health: Mutex::new(LinkHealth::Healthy),
+                    health_listener: Mutex::new(None),
+                    state_listener: Mutex::new(None),
+                }),
+                connection: Mutex::new(Connection::Connecting),
+                changed: Condvar::new(),
+                closed: AtomicBool::new(false),
+                threads: Mutex::new(Vec::new()),
+            }),
+        })
+    }
+
+    fn finish_connect(
+        self: Arc<Self>,
+        connected: Result<Arc<Link>, SmppFfiError>,
+    ) -> Result<Arc<Self>, SmppFfiError> {
+        match connected {
+            Ok(link) => {
+                self.inner.set_up(link);
+                Ok(self)
+            }
+            Err(e) => {
+                self.close();
+                Err(e)
+            }
+        }
+    }
+
+    fn close(&self) {
+        self.inner.shutdown("Closed by the host");
+        let threads = std::mem::take(&mut *self.inner.threads.lock().unwrap());
+        for thread in threads {
+            if thread.thread().id() != thread::current().id() {
+                let _ = thread.join();
+            }
+        }
+    }
+}
+
+impl Drop for EsmeSession {
+    fn drop(&mut self) {
+        self.inner.shutdown("Closed by the host");
+    }
+}
+
+/// A response and the header it arrived with.
+type Reply = (PduHeader, Result<Pdu, SmppFfiError>);
+
+/// A request that got no usable response.
+pub(crate) struct RequestFailure {
+    pub(crate) error: SmppFfiError,
+    /// True if the request was written to a connection, so the SMSC may have acted on it.
+    pub(crate) sent: bool,
+}
+
+impl RequestFailure {
+    fn unsent(error: SmppFfiError) -> Self {
+        Self { error, sent: false }
+    }
+}
+
+/// Where the session is in its connection lifecycle.
+enum Connection {
+    /// The first bind has not completed yet.
+    Connecting,
+    /// Bound over this link.
+    Up(Arc<Link>),
+    /// Waiting for a new link after the previous one dropped.
+    Reconnecting,
+    /// Finished for good.
+    Closed,
+}
+
+/// The session itself, shared with the threads that read and reconnect.
+struct Inner {
+    config: EsmeSessionConfig,
+    bind: BindRequest,
+    sequence: Arc<SequenceGenerator>,
+    shared: Arc<Shared>,
+    throttle: Throttle,
+    connection: Mutex<Connection>,
+    /// Signalled whenever `connection` changes or the session closes.
+    changed: Condvar,
+    /// Set once the host closes the session.
+    closed: AtomicBool,
+    threads: Mutex<Vec<JoinHandle<()>>>,
+}
+
+impl Inner {
+    fn current_link(&self) -> Option<Arc<Link>> {
+        match &*self.connection.lock().unwrap() {
+            Connection::Up(link) => Some(link.clone()),
+            _ => None,
+        }
+    }
+
+    fn resubmits(&self) -> bool {
+        self.config
+            .reconnect
+            .as_ref()
+            .is_some_and(|policy| policy.in_flight == InFlightPolicy::Resubmit)
+    }
+
+    /// Returns the bound link, waiting out reconnection when requests are resubmitted.
+    fn bound_link(&self) -> Result<Arc<Link>, SmppFfiError> {
+        let mut connection = self.connection.lock().unwrap();
+        loop {
+            match &*connection {
+                Connection::Up(link) if link.open.load(Ordering::SeqCst) => {
+                    link.check_bound()?;
+                    return Ok(link.clone());
+                }
+                Connection::Up(_) | Connection::Reconnecting if self.resubmits() => {
+                    connection = self.changed.wait(connection).unwrap();
+                }
+                _ => return Err(not_bound_error()),
+            }
+        }
+    }
+
+    /// Sends a request on the bound link within the rate limits.
+    ///
+    /// The request is sent again on a new link if the policy says so, and
+    /// after a pause when the SMSC answers with `ESME_RTHROTTLED`.
+    fn request(
+        &self,
+        command_id: u32,
+        mut encode: impl FnMut(u32) -> Vec<u8>,
+    ) -> Result<Pdu, RequestFailure> {
+        let mut retry = 0;
+        // Whether an earlier attempt may have reached the SMSC.
+        let mut sent = false;
+        loop {
+            self.throttle.acquire(command_id);
+            let link = self
+                .bound_link()
+                .map_err(|error| RequestFailure { error, sent })?;
+            let (header, response) = match link.exchange(&mut encode) {
+                Ok(reply) => reply,
+                Err(failure)
+                    if self.resubmits()
+                        && !link.open.load(Ordering::SeqCst)
+                        && !self.closed.load(Ordering::SeqCst) =>
+                {
+                    sent |= failure.sent;
+                    continue;
+                }
+                Err(failure) => {
+                    return Err(RequestFailure {
+                        sent: sent || failure.sent,
+                        ..failure
+                    })
+                }
+            };
+            if header.command_status == ESME_RTHROTTLED && self.throttle.throttled(retry) {
+                retry += 1;
+                continue;
+            }
+            return reject_generic_nack(response)
+                .map_err(|error| RequestFailure { error, sent: true });
+        }
+    }
+
+    /// Connects and binds, retrying per the reconnect policy.
+    ///
+    /// `attempt` is the number of the first retry, or 0 to try once without waiting first.
+    fn connect_loop(self: &Arc<Self>, mut attempt: u32) -> Result<Arc<Link>, SmppFfiError> {
+        loop {
+            if attempt > 0 {
+                if let Some(policy) = &self.config.reconnect {
+                    let delay_ms = reconnect_delay_ms(policy, attempt);
+                    self.shared
+                        .notify(ConnectionState::Reconnecting { attempt, delay_ms });
+                    self.wait(Duration::from_millis(delay_ms));
+                }
+            }
+            if self.closed.load(Ordering::SeqCst) {
+                return Err(closed_error());
+            }
+            let (bind_status, error) = match self.establish() {
+                Ok(link) => return Ok(link),
+                Err(failure) => failure,
+            };
+            let retry = self
+                .config
+                .reconnect
+                .as_ref()
+                .is_some_and(|policy| policy.allows_retry(attempt, bind_status));
+            if !retry {
+                return Err(error);
+            }
+            attempt += 1;
+        }
+    }
+
+    /// Sleeps for `delay` unless the session closes first.
+    fn wait(&self, delay: Duration) {
+        let deadline = Instant::now() + delay;
+        let mut connection = self.connection.lock().unwrap();
+        while !self.closed.load(Ordering::SeqCst) {
+            let now = Instant::now();
+            if now >= deadline {
+                return;
+            }
+            connection = self
+                .changed
+                .wait_timeout(connection, deadline - now)
+                .unwrap()
+                .0;
+        }
+    }
+
+    /// Opens a link and binds it.
+    ///
+    /// A failure carries the bind `command_status` when the SMSC rejected the bind.
+    fn establish(self: &Arc<Self>) -> Result<Arc<Link>, (Option<u32>, SmppFfiError)> {
+        let stream = open_stream(&self.config).map_err(|e| (None, e))?;
+        self.bind_stream(stream)
+    }
+
+    /// Binds over an open connection, failing like `establish`.
+    fn bind_stream(
+        self: &Arc<Self>,
+        stream: Stream,
+    ) -> Result<Arc<Link>, (Option<u32>, SmppFfiError)> {
+        let reader_stream = stream.try_clone().map_err(|e| (None, e.into()))?;
+        let link = Arc::new(Link {
+            writer: Mutex::new(stream),
+            window: Mutex::new(Window::new(RequestWindowConfig {
+                max_in_flight: self.config.max_in_flight,
+                response_timeout_ms: self.config.response_timeout_ms,
+            })),
+            window_freed: Condvar::new(),
+            started: Instant::now(),
+            last_inbound_ms: AtomicU64::new(0),
+            sequence: self.sequence.clone(),
+            open: AtomicBool::new(true),
+            bound: AtomicBool::new(false),
+            response_timeout: Duration::from_millis(self.config.response_timeout_ms),
+            shared: self.shared.clone(),
+            keepalive_stop: Mutex::new(None),
+        });
+        let reader = {
+            let link = link.clone();
+            let inner = Arc::downgrade(self);
+            thread::Builder::new()
+                .name("smpp-esme-reader".to_string())
+                .spawn(move || {
+                    link.read_loop(reader_stream);
+                    if let Some(inner) = inner.upgrade() {
+                        inner.link_lost(&link);
+                    }
+                })
+                .map_err(|e| (None, e.into()))?
+        };
+        self.track(reader);
+
+        let mut bind = self.bind.clone();
+        let response = link.request(|sequence_number| {
+            bind.sequence_number = sequence_number;
+            encode_bind_request(&bind)
+        });
+        let failure = match response {
+            Ok(Pdu::BindResponse { pdu }) if pdu.command_status == COMMAND_STATUS_OK => {
+                link.bound.store(true, Ordering::SeqCst);
+                if self.config.enquire_link_interval_ms > 0 {
+                    if let Err(e) = self.start_keepalive(&link) {
+                        link.shutdown();
+                        return Err((None, e));
+                    }
+                }
+                return Ok(link);
+            }
+            Ok(Pdu::BindResponse { pdu }) => (
+                Some(pdu.command_status),
+                status_error("Bind", pdu.command_status),
+            ),
+            Ok(other) => (None, unexpected_response("Bind", &other)),
+            Err(e) => (None, e),
+        };
+        link.shutdown();
+        Err(failure)
+    }
+
+    fn start_keepalive(&self, link: &Arc<Link>) -> Result<(), SmppFfiError> {
+        let (stop, stopped) = mpsc::channel();
+        *link.keepalive_stop.lock().unwrap() = Some(stop);
+        let interval = Duration::from_millis(self.config.enquire_link_interval_ms);
+        let max_missed = self.config.max_missed_enquire_links;
+        let keepalive = {
+            let link = link.clone();
+            thread::Builder::new()
+                .name("smpp-esme-keepalive".to_string())
+                .spawn(move || link.keepalive_loop(stopped, interval, max_missed))?
+        };
+        self.track(keepalive);
+        Ok(())
+    }
+
+    fn track(&self, thread: JoinHandle<()>) {
+        let mut threads = self.threads.lock().unwrap();
+        threads.retain(|thread| !thread.is_finished());
+        threads.push(thread);
+    }
+
+    /// Makes a freshly bound link the current one.
+    fn set_up(&self, link: Arc<Link>) {
+        {
+            let mut connection = self.connection.lock().unwrap();
+            if self.closed.load(Ordering::SeqCst) {
+                link.shutdown();
+                return;
+            }
+            *connection = Connection::Up(link);
+        }
+        self.changed.notify_all();
+        self.shared.set_health(LinkHealth::Healthy);
+        self.shared.notify(ConnectionState::Bound);
+    }
+
+    /// Called on the reader thread once its link has dropped.
+    fn link_lost(self: &Arc<Self>, link: &Arc<Link>) {
+        {
+            let mut connection = self.connection.lock().unwrap();
+            match &*connection {
+                // A host shutdown reports its own reason.
+                Connection::Up(current)
+                    if Arc::ptr_eq(current, link) && !self.closed.load(Ordering::SeqCst) => {}
+                _ => return,
+            }
+            if self.config.reconnect.is_none() {
+                drop(connection);
+                self.finish("Connection lost");
+                return;
+            }
+            *connection = Connection::Reconnecting;
+        }
+        self.changed.notify_all();
+        self.shared.notify(ConnectionState::Disconnected {
+            reason: "Connection lost".to_string(),
+        });
+        match self.connect_loop(1) {
+            Ok(link) => self.set_up(link),
+            Err(e) => self.finish(&e.to_string()),
+        }
+    }
+
+    /// Closes the current link, if any, and ends the session.
+    fn shutdown(&self, reason: &str) {
+        self.closed.store(true, Ordering::SeqCst);
+        if let Some(link) = self.current_link() {
+            link.shutdown();
+        }
+        self.finish(reason);
+    }
+
+    fn finish(&self, reason: &str) {
+        {
+            let mut connection = self.connection.lock().unwrap();
+            if matches!(*connection, Connection::Closed) {
+                return;
+            }
+            *connection = Connection::Closed;
+        }
+        self.changed.notify_all();
+        self.shared.notify(ConnectionState::Closed {
+            reason: reason.to_string(),
+        });
+    }
+}
+
+/// What the links of one session report to.
+struct Shared {
+    listener: Option<Arc<dyn DeliverSmListener>>,
+    orphan_responses: AtomicU64,
+    health: Mutex<LinkHealth>,
+    health_listener: Mutex<Option<Arc<dyn LinkHealthListener>>>,
+    state_listener: Mutex<Option<Arc<dyn ConnectionStateListener>>>,
+}
+
+impl Shared {
+    fn set_health(&self, health: LinkHealth) {
+        {
+            let mut current = self.health.lock().unwrap();
+            if *current == health {
+                return;
+            }
+            *current = health;
+        }
+        let listener = self.health_listener.lock().unwrap().clone();
+        if let Some(listener) = listener {
+            listener.on_link_health(health);
+        }
+    }
+
+    fn notify(&self, state: ConnectionState) {
+        let listener = self.state_listener.lock().unwrap().clone();
+        if let Some(listener) = listener {
+            listener.on_connection_state(state);
+        }
+    }
+}
+
+/// One TCP connection, shared between the session and its reader thread.
+struct Link {
+    writer: Mutex<Stream>,
+    window: Mutex<Window<Sender<Reply>>>,
+    window_freed: Condvar,
+    started: Instant,
+    /// When the last PDU arrived, in milliseconds since `started`.
+    last_inbound_ms: AtomicU64,
+    sequence: Arc<SequenceGenerator>,
+    open: AtomicBool,
+    bound: AtomicBool,
+    response_timeout: Duration,
+    shared: Arc<Shared>,
+    /// Dropping the sender stops the keepalive thread.
+    keepalive_stop: Mutex<Option<Sender<()>>>,
+}
+
+impl Link {
+    fn check_bound(&self) -> Result<(), SmppFfiError> {
+        if !self.bound.load(Ordering::SeqCst) {
+            return Err(not_bound_error());
+        }
+        Ok(())
+    }
+
+    /// Sends a request built for a fresh sequence number and waits for its response.
+    fn request(&self, encode: impl FnOnce(u32) -> Vec<u8>) -> Result<Pdu, SmppFfiError> {
+        let (_, response) = self.exchange(encode).map_err(|failure| failure.error)?;
+        reject_generic_nack(response)
+    }
+
+    /// Like `request`, but returns the response with its header, `generic_nack` included.
+    fn exchange(&self, encode: impl FnOnce(u32) -> Vec<u8>) -> Result<Reply, RequestFailure> {
+        if !self.open.load(Ordering::SeqCst) {
+            return Err(RequestFailure::unsent(closed_error()));
+        }
+        let sequence_number = self.sequence.next_value();
+        let bytes = encode(sequence_number);
+        let command_id = u32::from_be_bytes([bytes[4], bytes[5], bytes[6], bytes[7]]);
+        let (sender, receiver) = mpsc::channel();
+        self.acquire(sequence_number, command_id, sender)
+            .map_err(RequestFailure::unsent)?;
+
+        // A failed write leaves at most part of the PDU, which the SMSC cannot act on.
+        if let Err(e) = self.write(&bytes) {
+            self.release(sequence_number);
+            return Err(RequestFailure::unsent(e));
+        }
+        let error = match receiver.recv_timeout(self.response_timeout) {
+            Ok(reply) => return Ok(reply),
+            Err(RecvTimeoutError::Timeout) => {
+                self.release(sequence_number);
+                SmppFfiError::Generic {
+                    msg: format!(
+                        "Timed out waiting for the response to sequence number {}",
+                        sequence_number
+                    ),
+                }
+            }
+            Err(RecvTimeoutError::Disconnected) => closed_error(),
+        };
+        Err(RequestFailure { error, sent: true })
+    }
+
+    /// Waits for a free slot in the request window and records the request in it.
+    fn acquire(
+        &self,
+        sequence_number: u32,
+        command_id: u32,
+        sender: Sender<Reply>,
+    ) -> Result<(), SmppFfiError> {
+        let deadline = Instant::now() + self.response_timeout;
+        let mut window = self.window.lock().unwrap();
+        loop {
+            // Checked under the lock so that no request slips in after `read_loop` drains.
+            if !self.open.load(Ordering::SeqCst) {
+                return Err(closed_error());
+            }
+            if !window.is_full() {
+                break;
+            }
+            let now = Instant::now();
+            if now >= deadline {
+                return Err(SmppFfiError::Generic {
+                    msg: "Timed out waiting for a free slot in the request window".to_string(),
+                });
+            }
+            window = self
+                .window_freed
+                .wait_timeout(window, deadline - now)
+                .unwrap()
+                .0;
+        }
+        window.insert(sequence_number, command_id, self.now_ms(), sender)
+    }
+
+    /// Drops a request from the window without a response.
+    fn release(&self, sequence_number: u32) {
+        self.window.lock().unwrap().remove(sequence_number);
+        self.window_freed.notify_one();
+    }
+
+    /// Hands a response to the request waiting for it.
+    fn complete(&self, header: &PduHeader, response: Result<Pdu, SmppFfiError>) {
+        let completed = self.window.lock().unwrap().complete(header);
+        match completed {
+            Some((_, sender)) => {
+                self.window_freed.notify_one();
+                let _ = sender.send((*header, response));
+            }
+            None => {
+                self.shared.orphan_responses.fetch_add(1, Ordering::SeqCst);
+            }
+        }
+    }
+
+    fn now_ms(&self) -> u64 {
+        self.started.elapsed().as_millis() as u64
+    }
+
+    fn write(&self, bytes: &[u8]) -> Result<(), SmppFfiError> {
+        self.writer
+            .lock()
+            .unwrap()
+            .write_all(bytes)
+            .map_err(SmppFfiError::from)
+    }
+
+    fn shutdown(&self) {
+        self.open.store(false, Ordering::SeqCst);
+        self.bound.store(false, Ordering::SeqCst);
+        self.keepalive_stop.lock().unwrap().take();
+        self.writer.lock().unwrap().shutdown();
+    }
+
+    /// Sends `enquire_link` whenever nothing has arrived for `interval`, until the link closes.
+    fn keepalive_loop(&self, stop: Receiver<()>, interval: Duration, max_missed: u32) {
+        let interval_ms = interval.as_millis() as u64;
+        let mut missed = 0;
+        loop {
+            let idle_ms = self
+                .now_ms()
+                .saturating_sub(self.last_inbound_ms.load(Ordering::SeqCst));
+            if idle_ms < interval_ms {
+                match stop.recv_timeout(Duration::from_millis(interval_ms - idle_ms)) {
+                    Err(RecvTimeoutError::Timeout) => continue,
+                    _ => return,
+                }
+            }
+            if !self.bound.load(Ordering::SeqCst) {
+                return;
+            }
+            let answered = self
+                .request(|sequence_number| encode_enquire_link(&EnquireLink { sequence_number }))
+                .is_ok();
+            if !self.open.load(Ordering::SeqCst) {
+                return;
+            }
+            if answered {
+                missed = 0;
+                self.shared.set_health(LinkHealth::Healthy);
+                continue;
+            }
+            missed += 1;
+            if max_missed > 0 && missed >= max_missed {
+                self.shared.set_health(LinkHealth::Dead);
+                self.shutdown();
+                return;
+            }
+            self.shared.set_health(LinkHealth::Degraded {
+                missed_responses: missed,
+            });
+        }
+    }
+
+    fn read_loop(&self, mut stream: Stream) {
+        while let Ok(frame) = read_frame(&mut stream) {
+            if !self.dispatch(&frame) {
+                break;
+            }
+        }
+        self.shutdown();
+        // Dropping the senders wakes every waiting request with a closed error.
+        self.window.lock().unwrap().drain();
+        self.window_freed.notify_all();
+    }
+
+    /// Handles one inbound PDU. Returns false when the link must close.
+    fn dispatch(&self, frame: &[u8]) -> bool {
+        let header = match decode_pdu_header(frame) {
+            Ok(header) => header,
+            Err(_) => return false,
+        };
+        self.last_inbound_ms.store(self.now_ms(), Ordering::SeqCst);
+        let pdu = match decode_pdu(frame) {
+            Ok(pdu) => pdu,
+            // A body-less response carries only an error status.
+            Err(_) if header.command_id & 0x8000_0000 != 0 => {
+                self.complete(&header, Err(status_error("Request", header.command_status)));
+                return true;
+            }
+            Err(_) if is_supported_command_id(header.command_id) => {
+                let _ = self.write(&encode_error_response(&header, ESME_RSYSERR));
+                return true;
+            }
+            Err(_) => {
+                self.reply_generic_nack(header.sequence_number, ESME_RINVCMDID);
+                return true;
+            }
+        };
+
+        match pdu {
+            Pdu::EnquireLink { pdu } => {
+                let _ = self.write(&encode_enquire_link_response(&EnquireLinkResponse {
+                    sequence_number: pdu.sequence_number,
+                    command_status: COMMAND_STATUS_OK,
+                }));
+                true
+            }
+            Pdu::DeliverSm { pdu } => {
+                let sequence_number = pdu.sequence_number;
+                let command_status = match &self.shared.listener {
+                    Some(listener) => listener.on_deliver_sm(pdu),
+                    None => COMMAND_STATUS_OK,
+                };
+                let _ = self.write(&encode_delivery_sm_response(&DeliverSmResponse {
+                    sequence_number,
+                    command_status,
+                    message_id: String::new(),
+                    status_description: get_status_description(command_status),
+                }));
+                true
+            }
+            Pdu::Unbind { pdu } => {
+                self.bound.store(false, Ordering::SeqCst);
+                let _ = self.write(&encode_unbind_response(&UnbindResponse {
+                    sequence_number: pdu.sequence_number,
+                    command_status: COMMAND_STATUS_OK,
+                }));
+                false
+            }
+            response if header.command_id & 0x8000_0000 != 0 => {
+                self.complete(&header, Ok(response));
+                true
+            }
+            _ => {
+                self.reply_generic_nack(header.sequence_number, ESME_RINVBNDSTS);
+                true
+            }
+        }
+    }
+
+    fn reply_generic_nack(&self, sequence_number: u32, command_status: u32) {
+        let _ = self.write(&encode_generic_nack(&GenericNack {
+            sequence_number,
+            command_status,
+        }));
+    }
+}
+
+/// Connects to the SMSC, completing the TLS handshake if configured.
+fn open_stream(config: &EsmeSessionConfig) -> Result<Stream, SmppFfiError> {
+    let timeout = Duration::from_millis(config.connect_timeout_ms);
+    connect_stream(&config.host, config.port, timeout, config.tls.as_ref())
+}
+
+/// Connects and completes the TLS handshake, if any, each within `timeout`.
+pub(crate) fn connect_stream(
+    host: &str,
+    port: u16,
+    timeout: Duration,
+    tls: Option<&TlsClientConfig>,
+) -> Result<Stream, SmppFfiError> {
+    let stream = connect_tcp(host, port, timeout)?;
+    stream.set_read_timeout(Some(timeout))?;
+    stream.set_write_timeout(Some(timeout))?;
+    let stream = Stream::connect(stream, tls, host)?;
+    stream.tcp().set_read_timeout(None)?;
+    stream.tcp().set_write_timeout(None)?;
+    Ok(stream)
+}
+
+/// Connects to the first address of `host` that accepts within `timeout`.
+fn connect_tcp(host: &str, port: u16, timeout: Duration) -> Result<TcpStream, SmppFfiError> {
+    let mut last_error = None;
+    for address in (host, port).to_socket_addrs()? {
+        match TcpStream::connect_timeout(&address, timeout) {
+            Ok(stream) => {
+                let _ = stream.set_nodelay(true);
+                return Ok(stream);
+            }
+            Err(e) => last_error = Some(e),
+        }
+    }
+    Err(SmppFfiError::Generic {
+        msg: match last_error {
+            Some(e) => format!("Failed to connect to {}:{}: {}", host, port, e),
+            None => format!("No address found for {}", host),
+        },
+    })
+}
+
+/// Turns a `generic_nack` into an error.
+pub(crate) fn reject_generic_nack(
+    response: Result<Pdu, SmppFfiError>,
+) -> Result<Pdu, SmppFfiError> {
+    match response? {
+        Pdu::GenericNack { pdu } => Err(status_error("Request", pdu.command_status)),
+        response => Ok(response),
+    }
+}
+
+pub(crate) fn not_bound_error() -> SmppFfiError {
+    SmppFfiError::Generic {
+        msg: "Session is not bound".to_string(),
+    }
+}
+
+pub(crate) fn closed_error() -> SmppFfiError {
+    SmppFfiError::Generic {
+        msg: "Connection closed".to_string(),
+    }
+}
+
+pub(crate) fn status_error(operation: &str, command_status: u32) -> SmppFfiError {
+    SmppFfiError::Generic {
+        msg: format!(
+            "{} failed: {} (0x{:08X})",
+            operation,
+            get_status_description(command_status),
+            command_status
+        ),
+    }
+}
+
+pub(crate) fn unexpected_response(operation: &str, response: &Pdu) -> SmppFfiError {
+    SmppFfiError::Generic {
+        msg: format!(
+            "{} received an unexpected response: {:?}",
+            operation, response
+        ),
+    }
+}
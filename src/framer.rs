@@ -0,0 +1,117 @@
+//! # PDU Framing
+//!
+//! Splits a byte stream into complete PDUs using the `command_length` field.
+use crate::common::{SmppFfiError, HEADER_LEN};
+use std::io::{self, Read};
+use std::net::{SocketAddr, TcpStream};
+use std::sync::{Arc, Mutex};
+
+/// Largest `command_length` accepted from a peer.
+///
+/// Large enough for a 64 KiB `message_payload` plus the mandatory fields.
+pub const MAX_COMMAND_LENGTH: usize = 0x20000;
+
+/// Reads one complete PDU, header included, from a blocking reader.
+///
+/// # Errors
+///
+/// Returns an error if the reader fails, the stream ends, or the
+/// `command_length` is outside `16..=MAX_COMMAND_LENGTH`.
+pub fn read_frame<R: Read>(reader: &mut R) -> io::Result<Vec<u8>> {
+    let mut length = [0u8; 4];
+    reader.read_exact(&mut length)?;
+    let command_length = u32::from_be_bytes(length) as usize;
+    check_command_length(command_length)
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e.to_string()))?;
+
+    let mut frame = vec![0u8; command_length];
+    frame[..4].copy_from_slice(&length);
+    reader.read_exact(&mut frame[4..])?;
+    Ok(frame)
+}
+
+/// Checks that a `command_length` is plausible.
+///
+/// # Errors
+///
+/// Returns an error if the length is shorter than the header or longer than
+/// `MAX_COMMAND_LENGTH`.
+pub fn check_command_length(command_length: usize) -> Result<(), SmppFfiError> {
+    if !(HEADER_LEN..=MAX_COMMAND_LENGTH).contains(&command_length) {
+        return Err(SmppFfiError::Generic {
+            msg: format!("Invalid command_length: {}", command_length),
+        });
+    }
+    Ok(())
+}
+
+/// Splits complete PDUs off the front of a buffer, leaving any partial PDU in place.
+///
+/// # Errors
+///
+/// Returns an error if a `command_length` is invalid; the stream cannot be
+/// resynchronised after that.
+pub(crate) fn split_frames(buffer: &mut Vec<u8>) -> Result<Vec<Vec<u8>>, SmppFfiError> {
+    let mut frames = Vec::new();
+    let mut offset = 0;
+    while buffer.len() - offset >= 4 {
+        let command_length = u32::from_be_bytes([
+            buffer[offset],
+            buffer[offset + 1],
+            buffer[offset + 2],
+            buffer[offset + 3],
+        ]) as usize;
+        check_command_length(command_length)?;
+        if buffer.len() - offset < command_length {
+            break;
+        }
+        frames.push(buffer[offset..offset + command_length].to_vec());
+        offset += command_length;
+    }
+    buffer.drain(..offset);
+    Ok(frames)
+}
+
+/// Incremental PDU framer for callers that own the socket.
+#[derive(uniffi::Object, Default)]
+pub struct FrameDecoder {
+    buffer: Mutex<Vec<u8>>,
+}
+
+#[uniffi::export]
+impl FrameDecoder {
+    /// Creates an empty decoder.
+    #[uniffi::constructor]
+    pub fn new() -> Arc<Self> {
+        Arc::new(Self::default())
+    }
+
+    /// Appends received bytes and returns every PDU completed by them.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a `command_length` is invalid.
+    pub fn push(&self, bytes: Vec<u8>) -> Result<Vec<Vec<u8>>, SmppFfiError> {
+        let mut buffer = self.buffer.lock().unwrap();
+        buffer.extend_from_slice(&bytes);
+        split_frames(&mut buffer)
+    }
+
+    /// Returns the number of buffered bytes not yet part of a complete PDU.
+    pub fn pending_len(&self) -> u64 {
+        self.buffer.lock().unwrap().len() as u64
+    }
+}
+
+/// Wakes a thread blocked in `accept` on `local_addr` by connecting to it.
+///
+/// An unspecified address is reached through the loopback interface.
+pub(crate) fn wake_accept(mut local_addr: SocketAddr) {
+    if local_addr.ip().is_unspecified() {
+        local_addr.set_ip(match local_addr {
+            SocketAddr::V4(_) => [127, 0, 0, 1].into(),
+            SocketAddr::V6(_) => std::net::Ipv6Addr::LOCALHOST.into(),
+        });
+    }
+    let _ = TcpStream::connect(local_addr);
+}
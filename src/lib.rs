@@ -2,19 +2,59 @@
 
 #![warn(missing_docs)]
 uniffi::setup_scaffolding!();
+/// Address FFI module.
+pub mod address;
+/// Address range FFI module.
+pub mod address_range;
 /// Ancillary PDU FFI module.
 pub mod ancillary_pdu_ffi;
+/// Async session FFI module.
+pub mod async_session;
+/// Bind authentication FFI module.
+pub mod bind_auth;
 /// Broadcast PDU FFI module.
 pub mod broadcast_pdu_ffi;
+/// Request builders FFI module.
+pub mod builders;
 /// Common types FFI module.
 pub mod common;
 /// Delivery PDU FFI module.
 pub mod delivery_pdu_ffi;
+/// ESME pool FFI module.
+pub mod esme_pool;
+/// ESME session FFI module.
+pub mod esme_session;
+/// PDU framing FFI module.
+pub mod framer;
+/// Message store FFI module.
+pub mod message_store;
+/// Outbind FFI module.
+pub mod outbind;
+/// Generic PDU FFI module.
+pub mod pdu;
+/// Reconnect policy FFI module.
+pub mod reconnect;
+/// Request window FFI module.
+pub mod request_window;
+/// Sequence number FFI module.
+pub mod sequence;
 /// Session PDU FFI module.
 pub mod session_pdu_ffi;
+/// Sans-IO session state FFI module.
+pub mod session_state;
+/// SMSC listener FFI module.
+pub mod smsc_listener;
 /// Splitter FFI module.
 pub mod splitter;
 /// Submission PDU FFI module.
 pub mod submission_pdu_ffi;
+/// Throttle FFI module.
+pub mod throttle;
+/// TLS FFI module.
+pub mod tls;
 /// TLV FFI module.
 pub mod tlv;
+/// SMPP v5.0 TLV FFI module.
+pub mod tlv_v50;
+/// Validation FFI module.
+pub mod validation;
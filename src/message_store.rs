@@ -0,0 +1,706 @@
+//! # Message Store
+//!
+//! Server-side state of submitted messages. A `MessageCenter` issues message
+//! IDs for `submit_sm`, keeps each message in a `MessageStore` and answers
+//! `query_sm`, `cancel_sm` and `replace_sm` from what it stored. Stores are
+//! pluggable: an in-memory map, an append-only log file or a foreign
+//! implementation backed by a database.
+use crate::address::Address;
+use crate::ancillary_pdu_ffi::*;
+use crate::common::*;
+use crate::pdu::Pdu;
+use crate::smsc_listener::SmscSessionInfo;
+use crate::submission_pdu_ffi::{SubmitSmRequest, SubmitSmResponse};
+use crate::tlv::Tags;
+use smpp_codec::common as codec;
+use std::collections::HashMap;
+use std::fs::{File, OpenOptions};
+use std::io::{Read, Write};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Maximum size of a message ID in octets, excluding the NULL.
+pub const MAX_MESSAGE_ID_LEN: u32 = 65;
+
+/// Returns true if a message in this state will not change any more.
+#[uniffi::export]
+pub fn message_state_is_final(state: MessageState) -> bool {
+    !matches!(state, MessageState::Scheduled | MessageState::Enroute)
+}
+
+/// A submitted message as kept by a `MessageStore`.
+#[derive(uniffi::Record, Clone, Debug, PartialEq, Eq)]
+pub struct StoredMessage {
+    /// The message ID returned in `submit_sm_resp`.
+    pub message_id: String,
+    /// The system_id of the ESME that submitted the message.
+    pub system_id: String,
+    /// The service type.
+    pub service_type: String,
+    /// The source address.
+    pub source: Address,
+    /// The destination address.
+    pub destination: Address,
+    /// The ESM class.
+    pub esm_class: u8,
+    /// The `registered_delivery` flags, which decide whether a receipt is due.
+    pub registered_delivery: u8,
+    /// The data coding scheme.
+    pub data_coding: u8,
+    /// The message content.
+    pub short_message: Vec<u8>,
+    /// The current state.
+    pub state: MessageState,
+    /// Network error code of a failed delivery, 0 otherwise.
+    pub error_code: u8,
+    /// Submit time in milliseconds since the UNIX epoch.
+    pub submit_time_ms: u64,
+    /// Time the message reached a final state, in milliseconds since the UNIX epoch.
+    pub done_time_ms: Option<u64>,
+}
+
+/// Persists `StoredMessage`s by message ID.
+#[uniffi::export(with_foreign)]
+pub trait MessageStore: Send + Sync {
+    /// Inserts a message or replaces the one with the same message ID.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the message cannot be stored.
+    fn save(&self, message: StoredMessage) -> Result<(), SmppFfiError>;
+
+    /// Returns the message with this ID, if any.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the store cannot be read.
+    fn load(&self, message_id: String) -> Result<Option<StoredMessage>, SmppFfiError>;
+}
+
+/// A `MessageStore` that keeps messages in memory.
+#[derive(uniffi::Object, Debug, Default)]
+pub struct InMemoryMessageStore {
+    messages: Mutex<HashMap<String, StoredMessage>>,
+}
+
+#[uniffi::export]
+impl InMemoryMessageStore {
+    /// Creates an empty store.
+    #[uniffi::constructor]
+    pub fn new() -> Arc<Self> {
+        Arc::new(Self::default())
+    }
+
+    /// Returns the number of stored messages.
+    pub fn len(&self) -> u64 {
+        self.messages.lock().unwrap().len() as u64
+    }
+
+    /// Returns true if no message is stored.
+    pub fn is_empty(&self) -> bool {
+        self.messages.lock().unwrap().is_empty()
+    }
+
+    /// Returns the store as a `MessageStore`.
+    pub fn as_message_store(self: Arc<Self>) -> Arc<dyn MessageStore> {
+        self
+    }
+}
+
+impl MessageStore for InMemoryMessageStore {
+    fn save(&self, message: StoredMessage) -> Result<(), SmppFfiError> {
+        let mut messages = self.messages.lock().unwrap();
+        messages.insert(message.message_id.clone(), message);
+        Ok(())
+    }
+
+    fn load(&self, message_id: String) -> Result<Option<StoredMessage>, SmppFfiError> {
+        Ok(self.messages.lock().unwrap().get(&message_id).cloned())
+    }
+}
+
+/// A `MessageStore` that appends every save to a log file.
+///
+/// Each line holds one tab-separated message. Opening the file replays the
+/// log, so the latest save of each message ID wins; `compact` rewrites the
+/// file with one line per message.
+#[derive(uniffi::Object, Debug)]
+pub struct FileMessageStore {
+    path: String,
+    state: Mutex<FileState>,
+}
+
+#[derive(Debug)]
+struct FileState {
+    file: File,
+    messages: HashMap<String, StoredMessage>,
+}
+
+#[uniffi::export]
+impl FileMessageStore {
+    /// Opens a log file, creating it if missing.
+    ///
+    /// An incomplete last line, as left by a save cut short by a crash, is
+    /// truncated from the file.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file cannot be opened or holds a malformed line
+    /// before the last one.
+    #[uniffi::constructor]
+    pub fn open(path: String) -> Result<Arc<Self>, SmppFfiError> {
+        let mut file = OpenOptions::new()
+            .create(true)
+            .read(true)
+            .append(true)
+            .open(&path)?;
+        let mut contents = Vec::new();
+        file.read_to_end(&mut contents)?;
+        let lines: Vec<&[u8]> = contents.split_inclusive(|&b| b == b'\n').collect();
+        let mut messages = HashMap::new();
+        // Octets up to the end of the last complete line.
+        let mut complete = 0;
+        for (index, line) in lines.iter().enumerate() {
+            // `None` for a malformed line, `Some(None)` for a blank one.
+            let text = std::str::from_utf8(line).ok();
+            let parsed = match text.map(|text| text.trim_end_matches('\n')) {
+                Some("") => Some(None),
+                Some(text) => log::parse(text).map(Some),
+                None => None,
+            };
+            match parsed {
+                Some(message) if line.ends_with(b"\n") => {
+                    if let Some(message) = message {
+                        messages.insert(message.message_id.clone(), message);
+                    }
+                }
+                _ if index + 1 == lines.len() => break,
+                _ => {
+                    return Err(SmppFfiError::Generic {
+                        msg: format!("Malformed message at {}:{}", path, index + 1),
+                    })
+                }
+            }
+            complete += line.len();
+        }
+        if complete < contents.len() {
+            file.set_len(complete as u64)?;
+        }
+        Ok(Arc::new(Self {
+            path,
+            state: Mutex::new(FileState { file, messages }),
+        }))
+    }
+
+    /// Returns the number of stored messages.
+    pub fn len(&self) -> u64 {
+        self.state.lock().unwrap().messages.len() as u64
+    }
+
+    /// Returns true if no message is stored.
+    pub fn is_empty(&self) -> bool {
+        self.state.lock().unwrap().messages.is_empty()
+    }
+
+    /// Rewrites the log with only the latest save of each message.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file cannot be rewritten.
+    pub fn compact(&self) -> Result<(), SmppFfiError> {
+        let mut state = self.state.lock().unwrap();
+        let temporary = format!("{}.compact", self.path);
+        let mut contents = String::new();
+        for message in state.messages.values() {
+            contents.push_str(&log::format(message));
+            contents.push('\n');
+        }
+        // Synced before the rename so that a crash leaves either log complete.
+        let mut file = File::create(&temporary)?;
+        file.write_all(contents.as_bytes())?;
+        file.sync_all()?;
+        std::fs::rename(&temporary, &self.path)?;
+        state.file = OpenOptions::new().append(true).open(&self.path)?;
+        Ok(())
+    }
+
+    /// Returns the store as a `MessageStore`.
+    pub fn as_message_store(self: Arc<Self>) -> Arc<dyn MessageStore> {
+        self
+    }
+}
+
+impl MessageStore for FileMessageStore {
+    fn save(&self, message: StoredMessage) -> Result<(), SmppFfiError> {
+        let mut state = self.state.lock().unwrap();
+        state
+            .file
+            .write_all(format!("{}\n", log::format(&message)).as_bytes())?;
+        state.messages.insert(message.message_id.clone(), message);
+        Ok(())
+    }
+
+    fn load(&self, message_id: String) -> Result<Option<StoredMessage>, SmppFfiError> {
+        Ok(self
+            .state
+            .lock()
+            .unwrap()
+            .messages
+            .get(&message_id)
+            .cloned())
+    }
+}
+
+/// Issues unique message IDs of at most `MAX_MESSAGE_ID_LEN` octets.
+///
+/// An ID is the prefix followed by 16 hex digits of a counter seeded with the
+/// current time in microseconds, so IDs stay unique across restarts.
+#[derive(uniffi::Object, Debug)]
+pub struct MessageIdGenerator {
+    prefix: String,
+    last: AtomicU64,
+}
+
+#[uniffi::export]
+impl MessageIdGenerator {
+    /// Creates a generator whose IDs start with `prefix`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the prefix leaves no room for the 16 digits or is not ASCII.
+    #[uniffi::constructor]
+    pub fn new(prefix: String) -> Result<Arc<Self>, SmppFfiError> {
+        if prefix.len() + 16 > MAX_MESSAGE_ID_LEN as usize || !prefix.is_ascii() {
+            return Err(SmppFfiError::Generic {
+                msg: format!(
+                    "Message ID prefix '{}' must be ASCII and at most {} octets",
+                    prefix,
+                    MAX_MESSAGE_ID_LEN - 16
+                ),
+            });
+        }
+        Ok(Arc::new(Self {
+            prefix,
+            last: AtomicU64::new(0),
+        }))
+    }
+
+    /// Returns the next message ID.
+    pub fn next_id(&self) -> String {
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |elapsed| elapsed.as_micros() as u64);
+        let previous = self
+            .last
+            .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |last| {
+                Some(now.max(last + 1))
+            })
+            .unwrap();
+        format!("{}{:016x}", self.prefix, now.max(previous + 1))
+    }
+}
+
+/// Answers `submit_sm`, `query_sm`, `cancel_sm` and `replace_sm` from a `MessageStore`.
+///
+/// Requests for a message ID that another system_id submitted, or with a
+/// source address other than the original one, are treated as unknown.
+/// `cancel_sm` without a message ID is not supported and fails with
+/// `ESME_RCANCELFAIL`.
+#[derive(uniffi::Object)]
+pub struct MessageCenter {
+    store: Arc<dyn MessageStore>,
+    ids: Arc<MessageIdGenerator>,
+}
+
+#[uniffi::export]
+impl MessageCenter {
+    /// Creates a message center.
+    #[uniffi::constructor]
+    pub fn new(store: Arc<dyn MessageStore>, ids: Arc<MessageIdGenerator>) -> Arc<Self> {
+        Arc::new(Self { store, ids })
+    }
+
+    /// Answers the requests the message center handles; returns `None` for any other PDU.
+    pub fn handle(&self, session: SmscSessionInfo, request: Pdu) -> Option<Pdu> {
+        Some(match request {
+            Pdu::SubmitSm { pdu } => Pdu::SubmitSmResponse {
+                pdu: self.submit(session, pdu),
+            },
+            Pdu::QuerySm { pdu } => Pdu::QuerySmResponse {
+                pdu: self.query(session, pdu),
+            },
+            Pdu::CancelSm { pdu } => Pdu::CancelSmResponse {
+                pdu: self.cancel(session, pdu),
+            },
+            Pdu::ReplaceSm { pdu } => Pdu::ReplaceSmResponse {
+                pdu: self.replace(session, pdu),
+            },
+            _ => return None,
+        })
+    }
+
+    /// Stores a submitted message and returns its ID.
+    ///
+    /// The message starts `Scheduled` if it has a delivery time and `Enroute` otherwise.
+    pub fn submit(&self, session: SmscSessionInfo, request: SubmitSmRequest) -> SubmitSmResponse {
+        let short_message = if request.short_message.is_empty() {
+            request
+                .tlvs
+                .into_iter()
+                .find(|tlv| tlv.tag == Tags::MESSAGE_PAYLOAD)
+                .map(|tlv| tlv.value)
+                .unwrap_or_default()
+        } else {
+            request.short_message
+        };
+        let scheduled = request
+            .schedule_delivery_time
+            .is_some_and(|time| !time.is_empty());
+        let message = StoredMessage {
+            message_id: self.ids.next_id(),
+            system_id: session.system_id,
+            service_type: request.service_type,
+            source: Address {
+                ton: request.source_addr_ton,
+                npi: request.source_addr_npi,
+                value: request.source_addr,
+            },
+            destination: Address {
+                ton: request.dest_addr_ton,
+                npi: request.dest_addr_npi,
+                value: request.destination_addr,
+            },
+            esm_class: request.esm_class,
+            registered_delivery: request.registered_delivery,
+            data_coding: request.data_coding,
+            short_message,
+            state: if scheduled {
+                MessageState::Scheduled
+            } else {
+                MessageState::Enroute
+            },
+            error_code: 0,
+            submit_time_ms: now_ms(),
+            done_time_ms: None,
+        };
+        let message_id = message.message_id.clone();
+        let command_status = match self.store.save(message) {
+            Ok(()) => COMMAND_STATUS_OK,
+            Err(_) => ESME_RSYSERR,
+        };
+        SubmitSmResponse {
+            sequence_number: request.sequence_number,
+            command_status,
+            message_id: if command_status == COMMAND_STATUS_OK {
+                message_id
+            } else {
+                String::new()
+            },
+            status_description: String::new(),
+        }
+    }
+
+    /// Reports the state of a stored message.
+    pub fn query(&self, session: SmscSessionInfo, request: QuerySmRequest) -> QuerySmResponse {
+        let mut response = QuerySmResponse {
+            sequence_number: request.sequence_number,
+            command_status: COMMAND_STATUS_OK,
+            message_id: request.message_id.clone(),
+            final_date: String::new(),
+            message_state: MessageState::Unknown as u8,
+            error_code: 0,
+            status_description: String::new(),
+        };
+        match self.find(&session, &request.message_id, &request.source_addr) {
+            Ok(message) => {
+                response.final_date = message.done_time_ms.map(smpp_time).unwrap_or_default();
+                response.message_state = message.state as u8;
+                response.error_code = message.error_code;
+            }
+            Err(status) => response.command_status = status.unwrap_or(ESME_RQUERYFAIL),
+        }
+        response
+    }
+
+    /// Cancels a message that has not reached a final state.
+    pub fn cancel(&self, session: SmscSessionInfo, request: CancelSmRequest) -> CancelSmResponse {
+        let command_status = if request.message_id.is_empty() {
+            ESME_RCANCELFAIL
+        } else {
+            self.update(
+                &session,
+                &request.message_id,
+                &request.source_addr,
+                ESME_RCANCELFAIL,
+                |message| {
+                    message.state = MessageState::Deleted;
+                    message.done_time_ms = Some(now_ms());
+                },
+            )
+        };
+        CancelSmResponse {
+            sequence_number: request.sequence_number,
+            command_status,
+            status_description: String::new(),
+        }
+    }
+
+    /// Replaces the content and `registered_delivery` of a message that has not reached a final state.
+    pub fn replace(
+        &self,
+        session: SmscSessionInfo,
+        request: ReplaceSmRequest,
+    ) -> ReplaceSmResponse {
+        let command_status = self.update(
+            &session,
+            &request.message_id,
+            &request.source_addr,
+            ESME_RREPLACEFAIL,
+            |message| {
+                message.short_message = request.short_message.clone();
+                message.registered_delivery = request.registered_delivery;
+            },
+        );
+        ReplaceSmResponse {
+            sequence_number: request.sequence_number,
+            command_status,
+            status_description: String::new(),
+        }
+    }
+
+    /// Moves a message to a new state, e.g. when its delivery finishes.
+    ///
+    /// A final state also sets the done time. A message already in a final
+    /// state, such as a cancelled one, is left alone. Returns the updated
+    /// message, or `None` if the ID is unknown or the message is final.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the store fails.
+    pub fn set_state(
+        &self,
+        message_id: String,
+        state: MessageState,
+        error_code: u8,
+    ) -> Result<Option<StoredMessage>, SmppFfiError> {
+        let Some(mut message) = self.store.load(message_id)? else {
+            return Ok(None);
+        };
+        if message_state_is_final(message.state) {
+            return Ok(None);
+        }
+        message.state = state;
+        message.error_code = error_code;
+        if message_state_is_final(state) {
+            message.done_time_ms.get_or_insert_with(now_ms);
+        }
+        self.store.save(message.clone())?;
+        Ok(Some(message))
+    }
+
+    /// Returns a stored message by ID.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the store fails.
+    pub fn message(&self, message_id: String) -> Result<Option<StoredMessage>, SmppFfiError> {
+        self.store.load(message_id)
+    }
+}
+
+impl MessageCenter {
+    /// Loads a message the session may see. `Err(None)` means the store failed.
+    fn find(
+        &self,
+        session: &SmscSessionInfo,
+        message_id: &str,
+        source_addr: &str,
+    ) -> Result<StoredMessage, Option<u32>> {
+        match self.store.load(message_id.to_string()) {
+            Ok(Some(message))
+                if message.system_id == session.system_id
+                    && (source_addr.is_empty() || message.source.value == source_addr) =>
+            {
+                Ok(message)
+            }
+            Ok(_) => Err(Some(ESME_RINVMSGID)),
+            Err(_) => Err(None),
+        }
+    }
+
+    /// Applies `change` to a message that is not final. Returns the command status.
+    fn update(
+        &self,
+        session: &SmscSessionInfo,
+        message_id: &str,
+        source_addr: &str,
+        failure: u32,
+        change: impl FnOnce(&mut StoredMessage),
+    ) -> u32 {
+        let mut message = match self.find(session, message_id, source_addr) {
+            Ok(message) => message,
+            Err(status) => return status.unwrap_or(failure),
+        };
+        if message_state_is_final(message.state) {
+            return failure;
+        }
+        change(&mut message);
+        match self.store.save(message) {
+            Ok(()) => COMMAND_STATUS_OK,
+            Err(_) => failure,
+        }
+    }
+}
+
+fn now_ms() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |elapsed| elapsed.as_millis() as u64)
+}
+
+/// Splits milliseconds since the UNIX epoch into UTC year, month, day, hour, minute and second.
+pub(crate) fn utc_fields(time_ms: u64) -> [u64; 6] {
+    let seconds = time_ms / 1000;
+    let days = (seconds / 86_400) as i64;
+    let time = seconds % 86_400;
+    // Civil-from-days, after Howard Hinnant's algorithm.
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + i64::from(month <= 2);
+    [
+        year as u64,
+        month as u64,
+        day as u64,
+        time / 3600,
+        time % 3600 / 60,
+        time % 60,
+    ]
+}
+
+/// Formats a time as an SMPP absolute time in UTC, `YYMMDDhhmmsstnnp`.
+fn smpp_time(time_ms: u64) -> String {
+    let [year, month, day, hour, minute, second] = utc_fields(time_ms);
+    format!(
+        "{:02}{:02}{:02}{:02}{:02}{:02}{}00+",
+        year % 100,
+        month,
+        day,
+        hour,
+        minute,
+        second,
+        time_ms % 1000 / 100
+    )
+}
+
+/// The line format of `FileMessageStore`.
+mod log {
+    use super::*;
+
+    pub(super) fn format(message: &StoredMessage) -> String {
+        [
+            escape(&message.message_id),
+            escape(&message.system_id),
+            escape(&message.service_type),
+            (message.source.ton as u8).to_string(),
+            (message.source.npi as u8).to_string(),
+            escape(&message.source.value),
+            (message.destination.ton as u8).to_string(),
+            (message.destination.npi as u8).to_string(),
+            escape(&message.destination.value),
+            message.esm_class.to_string(),
+            message.registered_delivery.to_string(),
+            message.data_coding.to_string(),
+            message
+                .short_message
+                .iter()
+                .map(|byte| format!("{:02x}", byte))
+                .collect(),
+            (message.state as u8).to_string(),
+            message.error_code.to_string(),
+            message.submit_time_ms.to_string(),
+            message
+                .done_time_ms
+                .map(|time| time.to_string())
+                .unwrap_or_default(),
+        ]
+        .join("\t")
+    }
+
+    pub(super) fn parse(line: &str) -> Option<StoredMessage> {
+        let fields: Vec<&str> = line.split('\t').collect();
+        let [message_id, system_id, service_type, source_ton, source_npi, source, destination_ton, destination_npi, destination, esm_class, registered_delivery, data_coding, short_message, state, error_code, submit_time, done_time] =
+            fields.as_slice()
+        else {
+            return None;
+        };
+        let address = |ton: &str, npi: &str, value: &str| {
+            Some(Address {
+                ton: codec::Ton::from(ton.parse::<u8>().ok()?).into(),
+                npi: codec::Npi::from(npi.parse::<u8>().ok()?).into(),
+                value: unescape(value)?,
+            })
+        };
+        Some(StoredMessage {
+            message_id: unescape(message_id)?,
+            system_id: unescape(system_id)?,
+            service_type: unescape(service_type)?,
+            source: address(source_ton, source_npi, source)?,
+            destination: address(destination_ton, destination_npi, destination)?,
+            esm_class: esm_class.parse().ok()?,
+            registered_delivery: registered_delivery.parse().ok()?,
+            data_coding: data_coding.parse().ok()?,
+            short_message: (0..short_message.len())
+                .step_by(2)
+                .map(|i| u8::from_str_radix(short_message.get(i..i + 2)?, 16).ok())
+                .collect::<Option<_>>()?,
+            state: MessageState::from_u8(state.parse().ok()?)?,
+            error_code: error_code.parse().ok()?,
+            submit_time_ms: submit_time.parse().ok()?,
+            done_time_ms: if done_time.is_empty() {
+                None
+            } else {
+                Some(done_time.parse().ok()?)
+            },
+        })
+    }
+
+    fn escape(value: &str) -> String {
+        let mut escaped = String::with_capacity(value.len());
+        for c in value.chars() {
+            match c {
+                '\\' => escaped.push_str("\\\\"),
+                '\t' => escaped.push_str("\\t"),
+                '\n' => escaped.push_str("\\n"),
+                '\r' => escaped.push_str("\\r"),
+                c => escaped.push(c),
+            }
+        }
+        escaped
+    }
+
+    fn unescape(value: &str) -> Option<String> {
+        let mut unescaped = String::with_capacity(value.len());
+        let mut chars = value.chars();
+        while let Some(c) = chars.next() {
+            if c != '\\' {
+                unescaped.push(c);
+                continue;
+            }
+            unescaped.push(match chars.next()? {
+                '\\' => '\\',
+                't' => '\t',
+                'n' => '\n',
+                'r' => '\r',
+                _ => return None,
+            });
+        }
+        Some(unescaped)
+    }
+}
@@ -0,0 +1,227 @@
+//! # Outbind
+//!
+//! The ESME side of the outbind procedure. An SMSC with messages to deliver
+//! connects to the ESME and sends `outbind`; the ESME checks the SMSC's
+//! credentials and binds as a receiver over the same connection.
+use crate::common::*;
+use crate::esme_session::{DeliverSmListener, EsmeSession, EsmeSessionConfig};
+use crate::framer::{read_frame, wake_accept};
+use crate::pdu::*;
+use crate::session_pdu_ffi::*;
+use crate::tls::{server_tls, ServerTls, Stream, TlsServerConfig};
+use std::io::Write;
+use std::net::{SocketAddr, TcpListener, TcpStream};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+
+/// Receives the sessions established through outbind.
+#[uniffi::export(with_foreign)]
+pub trait OutbindHandler: Send + Sync {
+    /// Called with every receiver session bound after an accepted `outbind`.
+    fn on_session(&self, peer_addr: String, session: Arc<EsmeSession>);
+}
+
+/// Settings for an `OutbindListener`.
+#[derive(uniffi::Record, Clone, Debug, PartialEq)]
+pub struct OutbindListenerConfig {
+    /// Local address to listen on.
+    pub host: String,
+    /// Local port to listen on. Use 0 to pick a free port.
+    pub port: u16,
+    /// The system_id the SMSC must send in `outbind`.
+    pub system_id: String,
+    /// The password the SMSC must send in `outbind`.
+    pub password: String,
+    /// The request to bind with; its mode is always `Receiver`.
+    pub bind: BindRequest,
+    /// Timers and limits of the resulting sessions. Host, port, TLS and reconnect policy are ignored.
+    pub session: EsmeSessionConfig,
+    /// TLS settings. `None` accepts plain TCP.
+    #[uniffi(default = None)]
+    pub tls: Option<TlsServerConfig>,
+}
+
+impl OutbindListenerConfig {
+    /// Creates a configuration with default session settings.
+    pub fn new(
+        host: String,
+        port: u16,
+        system_id: String,
+        password: String,
+        bind: BindRequest,
+    ) -> Self {
+        Self {
+            session: EsmeSessionConfig::new(host.clone(), port),
+            host,
+            port,
+            system_id,
+            password,
+            bind,
+            tls: None,
+        }
+    }
+}
+
+/// Creates an `OutbindListenerConfig` with default session settings.
+#[uniffi::export]
+pub fn outbind_listener_config_new(
+    host: String,
+    port: u16,
+    system_id: String,
+    password: String,
+    bind: BindRequest,
+) -> OutbindListenerConfig {
+    OutbindListenerConfig::new(host, port, system_id, password, bind)
+}
+
+/// Accepts SMSC connections carrying `outbind` and turns them into receiver sessions.
+#[derive(uniffi::Object)]
+pub struct OutbindListener {
+    local_addr: SocketAddr,
+    acceptor: Arc<Acceptor>,
+    thread: Mutex<Option<JoinHandle<()>>>,
+}
+
+#[uniffi::export]
+impl OutbindListener {
+    /// Starts listening for `outbind`.
+    ///
+    /// Inbound `deliver_sm` on the resulting sessions goes to `listener`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the address cannot be bound or the TLS settings are unusable.
+    #[uniffi::constructor]
+    pub fn start(
+        config: OutbindListenerConfig,
+        listener: Option<Arc<dyn DeliverSmListener>>,
+        handler: Arc<dyn OutbindHandler>,
+    ) -> Result<Arc<Self>, SmppFfiError> {
+        let tls = config.tls.as_ref().map(server_tls).transpose()?;
+        let tcp = TcpListener::bind((config.host.as_str(), config.port))?;
+        let local_addr = tcp.local_addr()?;
+        let acceptor = Arc::new(Acceptor {
+            config,
+            tls,
+            listener,
+            handler,
+            stopped: AtomicBool::new(false),
+        });
+        let thread = {
+            let acceptor = acceptor.clone();
+            thread::Builder::new()
+                .name("smpp-outbind-acceptor".to_string())
+                .spawn(move || acceptor.accept_loop(tcp))?
+        };
+        Ok(Arc::new(Self {
+            local_addr,
+            acceptor,
+            thread: Mutex::new(Some(thread)),
+        }))
+    }
+
+    /// Returns the port the listener is bound to.
+    pub fn local_port(&self) -> u16 {
+        self.local_addr.port()
+    }
+
+    /// Stops accepting connections. Sessions already handed out stay open.
+    pub fn shutdown(&self) {
+        if self.acceptor.stopped.swap(true, Ordering::SeqCst) {
+            return;
+        }
+        // Wake the blocking accept so the acceptor sees the stop flag.
+        wake_accept(self.local_addr);
+        if let Some(thread) = self.thread.lock().unwrap().take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+impl Drop for OutbindListener {
+    fn drop(&mut self) {
+        self.shutdown();
+    }
+}
+
+/// The settings and handler that the accept loop and each outbind connection use.
+struct Acceptor {
+    config: OutbindListenerConfig,
+    tls: Option<ServerTls>,
+    listener: Option<Arc<dyn DeliverSmListener>>,
+    handler: Arc<dyn OutbindHandler>,
+    stopped: AtomicBool,
+}
+
+impl Acceptor {
+    fn accept_loop(self: Arc<Self>, tcp: TcpListener) {
+        for stream in tcp.incoming() {
+            if self.stopped.load(Ordering::SeqCst) {
+                break;
+            }
+            let Ok(stream) = stream else { continue };
+            let acceptor = self.clone();
+            let _ = thread::Builder::new()
+                .name("smpp-outbind-session".to_string())
+                .spawn(move || acceptor.serve(stream));
+        }
+    }
+
+    /// Completes the TLS handshake if configured, waits for an acceptable
+    /// `outbind`, then binds and hands over the session.
+    fn serve(&self, tcp: TcpStream) {
+        let Ok(peer_addr) = tcp.peer_addr() else {
+            return;
+        };
+        let _ = tcp.set_nodelay(true);
+        let timeout = Duration::from_millis(self.config.session.response_timeout_ms);
+        if tcp.set_read_timeout(Some(timeout)).is_err() {
+            return;
+        }
+        let Ok(mut stream) = Stream::accept(tcp, self.tls.as_ref()) else {
+            return;
+        };
+        if !self.await_outbind(&mut stream) {
+            stream.shutdown();
+            return;
+        }
+        if stream.tcp().set_read_timeout(None).is_err() {
+            return;
+        }
+        if let Ok(session) = EsmeSession::accept_outbind(
+            stream,
+            self.config.session.clone(),
+            self.config.bind.clone(),
+            self.listener.clone(),
+        ) {
+            self.handler.on_session(peer_addr.to_string(), session);
+        }
+    }
+
+    /// Reads until `outbind` arrives, answering `enquire_link` meanwhile.
+    ///
+    /// Returns true if the SMSC sent the expected credentials.
+    fn await_outbind(&self, stream: &mut Stream) -> bool {
+        while let Ok(frame) = read_frame(stream) {
+            match decode_pdu(&frame) {
+                Ok(Pdu::Outbind { pdu }) => {
+                    return pdu.system_id == self.config.system_id
+                        && pdu.password == self.config.password;
+                }
+                Ok(Pdu::EnquireLink { pdu }) => {
+                    let response = encode_enquire_link_response(&EnquireLinkResponse {
+                        sequence_number: pdu.sequence_number,
+                        command_status: COMMAND_STATUS_OK,
+                    });
+                    if stream.write_all(&response).is_err() {
+                        return false;
+                    }
+                }
+                _ => return false,
+            }
+        }
+        false
+    }
+}
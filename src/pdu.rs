@@ -0,0 +1,500 @@
+//! # Generic PDU
+//!
+//! A single enum over every PDU record exposed by this crate, with header
+//! parsing and decoding dispatched on the `command_id`.
+use crate::ancillary_pdu_ffi::*;
+use crate::broadcast_pdu_ffi::*;
+use crate::common::*;
+use crate::delivery_pdu_ffi::*;
+use crate::session_pdu_ffi::*;
+use crate::submission_pdu_ffi::*;
+
+/// The 16 octet header common to every PDU.
+#[derive(uniffi::Record, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct PduHeader {
+    /// The total length of the PDU, header included.
+    pub command_length: u32,
+    /// The command ID.
+    pub command_id: u32,
+    /// The command status.
+    pub command_status: u32,
+    /// The sequence number.
+    pub sequence_number: u32,
+}
+
+/// Any PDU supported by this crate.
+#[derive(uniffi::Enum, Clone, Debug, PartialEq)]
+pub enum Pdu {
+    /// bind_receiver, bind_transmitter or bind_transceiver.
+    BindRequest {
+        /// The PDU.
+        pdu: BindRequest,
+    },
+    /// bind_*_resp.
+    BindResponse {
+        /// The PDU.
+        pdu: BindResponse,
+    },
+    /// outbind.
+    Outbind {
+        /// The PDU.
+        pdu: Outbind,
+    },
+    /// enquire_link.
+    EnquireLink {
+        /// The PDU.
+        pdu: EnquireLink,
+    },
+    /// enquire_link_resp.
+    EnquireLinkResponse {
+        /// The PDU.
+        pdu: EnquireLinkResponse,
+    },
+    /// unbind.
+    Unbind {
+        /// The PDU.
+        pdu: Unbind,
+    },
+    /// unbind_resp.
+    UnbindResponse {
+        /// The PDU.
+        pdu: UnbindResponse,
+    },
+    /// generic_nack.
+    GenericNack {
+        /// The PDU.
+        pdu: GenericNack,
+    },
+    /// submit_sm.
+    SubmitSm {
+        /// The PDU.
+        pdu: SubmitSmRequest,
+    },
+    /// submit_sm_resp.
+    SubmitSmResponse {
+        /// The PDU.
+        pdu: SubmitSmResponse,
+    },
+    /// submit_multi.
+    SubmitMulti {
+        /// The PDU.
+        pdu: SubmitMultiRequest,
+    },
+    /// submit_multi_resp.
+    SubmitMultiResponse {
+        /// The PDU.
+        pdu: SubmitMultiResponse,
+    },
+    /// deliver_sm.
+    DeliverSm {
+        /// The PDU.
+        pdu: DeliverSmRequest,
+    },
+    /// deliver_sm_resp.
+    DeliverSmResponse {
+        /// The PDU.
+        pdu: DeliverSmResponse,
+    },
+    /// data_sm.
+    DataSm {
+        /// The PDU.
+        pdu: DataSm,
+    },
+    /// data_sm_resp.
+    DataSmResponse {
+        /// The PDU.
+        pdu: DataSmResponse,
+    },
+    /// query_sm.
+    QuerySm {
+        /// The PDU.
+        pdu: QuerySmRequest,
+    },
+    /// query_sm_resp.
+    QuerySmResponse {
+        /// The PDU.
+        pdu: QuerySmResponse,
+    },
+    /// cancel_sm.
+    CancelSm {
+        /// The PDU.
+        pdu: CancelSmRequest,
+    },
+    /// cancel_sm_resp.
+    CancelSmResponse {
+        /// The PDU.
+        pdu: CancelSmResponse,
+    },
+    /// replace_sm.
+    ReplaceSm {
+        /// The PDU.
+        pdu: ReplaceSmRequest,
+    },
+    /// replace_sm_resp.
+    ReplaceSmResponse {
+        /// The PDU.
+        pdu: ReplaceSmResponse,
+    },
+    /// broadcast_sm.
+    BroadcastSm {
+        /// The PDU.
+        pdu: BroadcastSmRequest,
+    },
+    /// broadcast_sm_resp.
+    BroadcastSmResponse {
+        /// The PDU.
+        pdu: BroadcastSmResponse,
+    },
+    /// query_broadcast_sm.
+    QueryBroadcastSm {
+        /// The PDU.
+        pdu: QueryBroadcastSmRequest,
+    },
+    /// query_broadcast_sm_resp.
+    QueryBroadcastSmResponse {
+        /// The PDU.
+        pdu: QueryBroadcastSmResponse,
+    },
+    /// cancel_broadcast_sm.
+    CancelBroadcastSm {
+        /// The PDU.
+        pdu: CancelBroadcastSmRequest,
+    },
+    /// cancel_broadcast_sm_resp.
+    CancelBroadcastSmResponse {
+        /// The PDU.
+        pdu: CancelBroadcastSmResponse,
+    },
+}
+
+impl Pdu {
+    /// Returns the sequence number of the PDU.
+    pub fn sequence_number(&self) -> u32 {
+        match self {
+            Pdu::BindRequest { pdu } => pdu.sequence_number,
+            Pdu::BindResponse { pdu } => pdu.sequence_number,
+            Pdu::Outbind { pdu } => pdu.sequence_number,
+            Pdu::EnquireLink { pdu } => pdu.sequence_number,
+            Pdu::EnquireLinkResponse { pdu } => pdu.sequence_number,
+            Pdu::Unbind { pdu } => pdu.sequence_number,
+            Pdu::UnbindResponse { pdu } => pdu.sequence_number,
+            Pdu::GenericNack { pdu } => pdu.sequence_number,
+            Pdu::SubmitSm { pdu } => pdu.sequence_number,
+            Pdu::SubmitSmResponse { pdu } => pdu.sequence_number,
+            Pdu::SubmitMulti { pdu } => pdu.sequence_number,
+            Pdu::SubmitMultiResponse { pdu } => pdu.sequence_number,
+            Pdu::DeliverSm { pdu } => pdu.sequence_number,
+            Pdu::DeliverSmResponse { pdu } => pdu.sequence_number,
+            Pdu::DataSm { pdu } => pdu.sequence_number,
+            Pdu::DataSmResponse { pdu } => pdu.sequence_number,
+            Pdu::QuerySm { pdu } => pdu.sequence_number,
+            Pdu::QuerySmResponse { pdu } => pdu.sequence_number,
+            Pdu::CancelSm { pdu } => pdu.sequence_number,
+            Pdu::CancelSmResponse { pdu } => pdu.sequence_number,
+            Pdu::ReplaceSm { pdu } => pdu.sequence_number,
+            Pdu::ReplaceSmResponse { pdu } => pdu.sequence_number,
+            Pdu::BroadcastSm { pdu } => pdu.sequence_number,
+            Pdu::BroadcastSmResponse { pdu } => pdu.sequence_number,
+            Pdu::QueryBroadcastSm { pdu } => pdu.sequence_number,
+            Pdu::QueryBroadcastSmResponse { pdu } => pdu.sequence_number,
+            Pdu::CancelBroadcastSm { pdu } => pdu.sequence_number,
+            Pdu::CancelBroadcastSmResponse { pdu } => pdu.sequence_number,
+        }
+    }
+
+    /// Replaces the sequence number of the PDU.
+    pub(crate) fn set_sequence_number(&mut self, sequence_number: u32) {
+        match self {
+            Pdu::BindRequest { pdu } => pdu.sequence_number = sequence_number,
+            Pdu::BindResponse { pdu } => pdu.sequence_number = sequence_number,
+            Pdu::Outbind { pdu } => pdu.sequence_number = sequence_number,
+            Pdu::EnquireLink { pdu } => pdu.sequence_number = sequence_number,
+            Pdu::EnquireLinkResponse { pdu } => pdu.sequence_number = sequence_number,
+            Pdu::Unbind { pdu } => pdu.sequence_number = sequence_number,
+            Pdu::UnbindResponse { pdu } => pdu.sequence_number = sequence_number,
+            Pdu::GenericNack { pdu } => pdu.sequence_number = sequence_number,
+            Pdu::SubmitSm { pdu } => pdu.sequence_number = sequence_number,
+            Pdu::SubmitSmResponse { pdu } => pdu.sequence_number = sequence_number,
+            Pdu::SubmitMulti { pdu } => pdu.sequence_number = sequence_number,
+            Pdu::SubmitMultiResponse { pdu } => pdu.sequence_number = sequence_number,
+            Pdu::DeliverSm { pdu } => pdu.sequence_number = sequence_number,
+            Pdu::DeliverSmResponse { pdu } => pdu.sequence_number = sequence_number,
+            Pdu::DataSm { pdu } => pdu.sequence_number = sequence_number,
+            Pdu::DataSmResponse { pdu } => pdu.sequence_number = sequence_number,
+            Pdu::QuerySm { pdu } => pdu.sequence_number = sequence_number,
+            Pdu::QuerySmResponse { pdu } => pdu.sequence_number = sequence_number,
+            Pdu::CancelSm { pdu } => pdu.sequence_number = sequence_number,
+            Pdu::CancelSmResponse { pdu } => pdu.sequence_number = sequence_number,
+            Pdu::ReplaceSm { pdu } => pdu.sequence_number = sequence_number,
+            Pdu::ReplaceSmResponse { pdu } => pdu.sequence_number = sequence_number,
+            Pdu::BroadcastSm { pdu } => pdu.sequence_number = sequence_number,
+            Pdu::BroadcastSmResponse { pdu } => pdu.sequence_number = sequence_number,
+            Pdu::QueryBroadcastSm { pdu } => pdu.sequence_number = sequence_number,
+            Pdu::QueryBroadcastSmResponse { pdu } => pdu.sequence_number = sequence_number,
+            Pdu::CancelBroadcastSm { pdu } => pdu.sequence_number = sequence_number,
+            Pdu::CancelBroadcastSmResponse { pdu } => pdu.sequence_number = sequence_number,
+        }
+    }
+}
+
+/// Decodes the 16 octet header at the start of a buffer.
+///
+/// # Errors
+///
+/// Returns an error if the buffer is shorter than the header.
+#[uniffi::export]
+pub fn decode_pdu_header(buffer: &[u8]) -> Result<PduHeader, SmppFfiError> {
+    if buffer.len() < HEADER_LEN {
+        return Err(SmppFfiError::Generic {
+            msg: "Buffer too short to decode PDU header".to_string(),
+        });
+    }
+    let word =
+        |i: usize| u32::from_be_bytes([buffer[i], buffer[i + 1], buffer[i + 2], buffer[i + 3]]);
+    Ok(PduHeader {
+        command_length: word(0),
+        command_id: word(4),
+        command_status: word(8),
+        sequence_number: word(12),
+    })
+}
+
+/// Decodes a complete PDU, choosing the PDU type from its `command_id`.
+///
+/// # Errors
+///
+/// Returns an error if the command ID is not supported or the decoding fails.
+#[uniffi::export]
+pub fn decode_pdu(buffer: &[u8]) -> Result<Pdu, SmppFfiError> {
+    let header = decode_pdu_header(buffer)?;
+    let pdu = match header.command_id {
+        CMD_BIND_RECEIVER | CMD_BIND_TRANSMITTER | CMD_BIND_TRANSCEIVER => Pdu::BindRequest {
+            pdu: decode_bind_request(buffer)?,
+        },
+        CMD_BIND_RECEIVER_RESP | CMD_BIND_TRANSMITTER_RESP | CMD_BIND_TRANSCEIVER_RESP => {
+            Pdu::BindResponse {
+                pdu: decode_bind_response(buffer)?,
+            }
+        }
+        CMD_OUTBIND => Pdu::Outbind {
+            pdu: decode_outbind(buffer)?,
+        },
+        CMD_ENQUIRE_LINK => Pdu::EnquireLink {
+            pdu: decode_enquire_link(buffer)?,
+        },
+        CMD_ENQUIRE_LINK_RESP => Pdu::EnquireLinkResponse {
+            pdu: decode_enquire_link_response(buffer)?,
+        },
+        CMD_UNBIND => Pdu::Unbind {
+            pdu: decode_unbind(buffer)?,
+        },
+        CMD_UNBIND_RESP => Pdu::UnbindResponse {
+            pdu: decode_unbind_response(buffer)?,
+        },
+        GENERIC_NACK => Pdu::GenericNack {
+            pdu: decode_generic_nack(buffer)?,
+        },
+        CMD_SUBMIT_SM => Pdu::SubmitSm {
+            pdu: decode_submit_sm_request(buffer)?,
+        },
+        CMD_SUBMIT_SM_RESP => Pdu::SubmitSmResponse {
+            pdu: decode_submit_sm_response(buffer)?,
+        },
+        CMD_SUBMIT_MULTI_SM => Pdu::SubmitMulti {
+            pdu: decode_submit_multi_request(buffer)?,
+        },
+        CMD_SUBMIT_MULTI_SM_RESP => Pdu::SubmitMultiResponse {
+            pdu: decode_submit_multi_response(buffer)?,
+        },
+        CMD_DELIVER_SM => Pdu::DeliverSm {
+            pdu: decode_deliver_sm(buffer)?,
+        },
+        CMD_DELIVER_SM_RESP => Pdu::DeliverSmResponse {
+            pdu: decode_delivery_sm_response(buffer)?,
+        },
+        CMD_DATA_SM => Pdu::DataSm {
+            pdu: decode_data_sm(buffer)?,
+        },
+        CMD_DATA_SM_RESP => Pdu::DataSmResponse {
+            pdu: decode_data_sm_response(buffer)?,
+        },
+        CMD_QUERY_SM => Pdu::QuerySm {
+            pdu: decode_query_sm_request(buffer)?,
+        },
+        CMD_QUERY_SM_RESP => Pdu::QuerySmResponse {
+            pdu: decode_query_sm_response(buffer)?,
+        },
+        CMD_CANCEL_SM => Pdu::CancelSm {
+            pdu: decode_cancel_sm_request(buffer)?,
+        },
+        CMD_CANCEL_SM_RESP => Pdu::CancelSmResponse {
+            pdu: decode_cancel_sm_response(buffer)?,
+        },
+        CMD_REPLACE_SM => Pdu::ReplaceSm {
+            pdu: decode_replace_sm_request(buffer)?,
+        },
+        CMD_REPLACE_SM_RESP => Pdu::ReplaceSmResponse {
+            pdu: decode_replace_sm_response(buffer)?,
+        },
+        CMD_BROADCAST_SM => Pdu::BroadcastSm {
+            pdu: decode_broadcast_sm_request(buffer)?,
+        },
+        CMD_BROADCAST_SM_RESP_V50 => Pdu::BroadcastSmResponse {
+            pdu: decode_broadcast_sm_response(buffer)?,
+        },
+        CMD_QUERY_BROADCAST_SM => Pdu::QueryBroadcastSm {
+            pdu: decode_query_broadcast_sm_request(buffer)?,
+        },
+        CMD_QUERY_BROADCAST_SM_RESP => Pdu::QueryBroadcastSmResponse {
+            pdu: decode_query_broadcast_sm_response(buffer)?,
+        },
+        CMD_CANCEL_BROADCAST_SM => Pdu::CancelBroadcastSm {
+            pdu: decode_cancel_broadcast_sm_request(buffer)?,
+        },
+        CMD_CANCEL_BROADCAST_SM_RESP => Pdu::CancelBroadcastSmResponse {
+            pdu: decode_cancel_broadcast_sm_response(buffer)?,
+        },
+        other => {
+            return Err(SmppFfiError::Generic {
+                msg: format!("Unsupported Command ID: 0x{:08X}", other),
+            })
+        }
+    };
+    Ok(pdu)
+}
+
+/// Encodes any PDU into a byte vector.
+///
+/// `BindResponse` carries no bind mode, so its `command_id` is written as
+/// by `encode_bind_response`.
+///
+/// # Panics
+///
+/// Panics if the internal encoding fails.
+#[uniffi::export]
+pub fn encode_pdu(pdu: &Pdu) -> Vec<u8> {
+    match pdu {
+        Pdu::BindRequest { pdu } => encode_bind_request(pdu),
+        Pdu::BindResponse { pdu } => encode_bind_response(pdu),
+        Pdu::Outbind { pdu } => encode_outbind(pdu),
+        Pdu::EnquireLink { pdu } => encode_enquire_link(pdu),
+        Pdu::EnquireLinkResponse { pdu } => encode_enquire_link_response(pdu),
+        Pdu::Unbind { pdu } => encode_unbind(pdu),
+        Pdu::UnbindResponse { pdu } => encode_unbind_response(pdu),
+        Pdu::GenericNack { pdu } => encode_generic_nack(pdu),
+        Pdu::SubmitSm { pdu } => encode_submit_sm_request(pdu),
+        Pdu::SubmitSmResponse { pdu } => encode_submit_sm_response(pdu),
+        Pdu::SubmitMulti { pdu } => encode_submit_multi_request(pdu),
+        Pdu::SubmitMultiResponse { pdu } => encode_submit_multi_response(pdu),
+        Pdu::DeliverSm { pdu } => encode_deliver_sm(pdu),
+        Pdu::DeliverSmResponse { pdu } => encode_delivery_sm_response(pdu),
+        Pdu::DataSm { pdu } => encode_data_sm(pdu),
+        Pdu::DataSmResponse { pdu } => encode_data_sm_response(pdu),
+        Pdu::QuerySm { pdu } => encode_query_sm_request(pdu),
+        Pdu::QuerySmResponse { pdu } => encode_query_sm_response(pdu),
+        Pdu::CancelSm { pdu } => encode_cancel_sm_request(pdu),
+        Pdu::CancelSmResponse { pdu } => encode_cancel_sm_response(pdu),
+        Pdu::ReplaceSm { pdu } => encode_replace_sm_request(pdu),
+        Pdu::ReplaceSmResponse { pdu } => encode_replace_sm_response(pdu),
+        Pdu::BroadcastSm { pdu } => encode_broadcast_sm_request(pdu),
+        Pdu::BroadcastSmResponse { pdu } => encode_broadcast_sm_response(pdu),
+        Pdu::QueryBroadcastSm { pdu } => encode_query_broadcast_sm_request(pdu),
+        Pdu::QueryBroadcastSmResponse { pdu } => encode_query_broadcast_sm_response(pdu),
+        Pdu::CancelBroadcastSm { pdu } => encode_cancel_broadcast_sm_request(pdu),
+        Pdu::CancelBroadcastSmResponse { pdu } => encode_cancel_broadcast_sm_response(pdu),
+    }
+}
+
+/// Gets the SMPP name of a command ID, e.g. `submit_sm_resp`.
+///
+/// Returns `None` for unknown command IDs.
+pub fn get_command_name(command_id: u32) -> Option<&'static str> {
+    let name = match command_id {
+        CMD_BIND_RECEIVER => "bind_receiver",
+        CMD_BIND_RECEIVER_RESP => "bind_receiver_resp",
+        CMD_BIND_TRANSMITTER => "bind_transmitter",
+        CMD_BIND_TRANSMITTER_RESP => "bind_transmitter_resp",
+        CMD_BIND_TRANSCEIVER => "bind_transceiver",
+        CMD_BIND_TRANSCEIVER_RESP => "bind_transceiver_resp",
+        CMD_OUTBIND => "outbind",
+        CMD_ENQUIRE_LINK => "enquire_link",
+        CMD_ENQUIRE_LINK_RESP => "enquire_link_resp",
+        CMD_SUBMIT_SM => "submit_sm",
+        CMD_SUBMIT_SM_RESP => "submit_sm_resp",
+        CMD_DELIVER_SM => "deliver_sm",
+        CMD_DELIVER_SM_RESP => "deliver_sm_resp",
+        CMD_UNBIND => "unbind",
+        CMD_UNBIND_RESP => "unbind_resp",
+        CMD_SUBMIT_MULTI_SM => "submit_multi",
+        CMD_SUBMIT_MULTI_SM_RESP => "submit_multi_resp",
+        CMD_QUERY_SM => "query_sm",
+        CMD_QUERY_SM_RESP => "query_sm_resp",
+        CMD_CANCEL_SM => "cancel_sm",
+        CMD_CANCEL_SM_RESP => "cancel_sm_resp",
+        CMD_REPLACE_SM => "replace_sm",
+        CMD_REPLACE_SM_RESP => "replace_sm_resp",
+        CMD_DATA_SM => "data_sm",
+        CMD_DATA_SM_RESP => "data_sm_resp",
+        CMD_ALERT_NOTIFICATION => "alert_notification",
+        CMD_ALERT_NOTIFICATION_RESP => "alert_notification_resp",
+        GENERIC_NACK => "generic_nack",
+        CMD_BROADCAST_SM => "broadcast_sm",
+        CMD_BROADCAST_SM_RESP_V50 => "broadcast_sm_resp",
+        // broadcast_sm_resp used this value before SMPP v5.0.
+        CMD_QUERY_BROADCAST_SM_RESP => "query_broadcast_sm_resp",
+        CMD_QUERY_BROADCAST_SM => "query_broadcast_sm",
+        CMD_CANCEL_BROADCAST_SM => "cancel_broadcast_sm",
+        CMD_CANCEL_BROADCAST_SM_RESP => "cancel_broadcast_sm_resp",
+        _ => return None,
+    };
+    Some(name)
+}
+
+/// Returns true if `decode_pdu` supports the command ID.
+pub(crate) fn is_supported_command_id(command_id: u32) -> bool {
+    matches!(
+        command_id,
+        CMD_BIND_RECEIVER
+            | CMD_BIND_TRANSMITTER
+            | CMD_BIND_TRANSCEIVER
+            | CMD_BIND_RECEIVER_RESP
+            | CMD_BIND_TRANSMITTER_RESP
+            | CMD_BIND_TRANSCEIVER_RESP
+            | CMD_OUTBIND
+            | CMD_ENQUIRE_LINK
+            | CMD_ENQUIRE_LINK_RESP
+            | CMD_UNBIND
+            | CMD_UNBIND_RESP
+            | GENERIC_NACK
+            | CMD_SUBMIT_SM
+            | CMD_SUBMIT_SM_RESP
+            | CMD_SUBMIT_MULTI_SM
+            | CMD_SUBMIT_MULTI_SM_RESP
+            | CMD_DELIVER_SM
+            | CMD_DELIVER_SM_RESP
+            | CMD_DATA_SM
+            | CMD_DATA_SM_RESP
+            | CMD_QUERY_SM
+            | CMD_QUERY_SM_RESP
+            | CMD_CANCEL_SM
+            | CMD_CANCEL_SM_RESP
+            | CMD_REPLACE_SM
+            | CMD_REPLACE_SM_RESP
+            | CMD_BROADCAST_SM
+            | CMD_BROADCAST_SM_RESP_V50
+            | CMD_QUERY_BROADCAST_SM
+            | CMD_QUERY_BROADCAST_SM_RESP
+            | CMD_CANCEL_BROADCAST_SM
+            | CMD_CANCEL_BROADCAST_SM_RESP
+    )
+}
+
+/// Encodes a body-less response to `header` carrying an error status.
+pub(crate) fn encode_error_response(header: &PduHeader, command_status: u32) -> Vec<u8> {
+    let mut bytes = Vec::with_capacity(HEADER_LEN);
+    bytes.extend_from_slice(&(HEADER_LEN as u32).to_be_bytes());
+    bytes.extend_from_slice(&(header.command_id | 0x8000_0000).to_be_bytes());
+    bytes.extend_from_slice(&command_status.to_be_bytes());
+    bytes.extend_from_slice(&header.sequence_number.to_be_bytes());
+    bytes
+}
@@ -0,0 +1,90 @@
+//! # Reconnect Policy
+//!
+//! How an `EsmeSession` retries after a failed bind or a dropped link:
+//! exponential backoff with jitter, a cap on attempts and the bind
+//! statuses that are not worth retrying.
+use crate::common::*;
+use std::collections::hash_map::RandomState;
+use std::hash::{BuildHasher, Hasher};
+
+/// What happens to requests in flight when the link drops.
+#[derive(uniffi::Enum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum InFlightPolicy {
+    /// Fail them with a connection error.
+    Fail,
+    /// Send them again once the session has rebound.
+    ///
+    /// The SMSC may already have accepted a request whose response was lost,
+    /// so a resubmitted message can be delivered twice.
+    Resubmit,
+}
+
+/// Settings for reconnecting and rebinding.
+#[derive(uniffi::Record, Clone, Debug, PartialEq, Eq)]
+pub struct ReconnectPolicy {
+    /// Delay before the first retry, in milliseconds.
+    #[uniffi(default = 1000)]
+    pub initial_delay_ms: u64,
+    /// Upper bound for the delay between retries, in milliseconds.
+    #[uniffi(default = 60000)]
+    pub max_delay_ms: u64,
+    /// Random variation applied to each delay, as a percentage of it.
+    #[uniffi(default = 20)]
+    pub jitter_percent: u8,
+    /// Maximum number of retries in a row. 0 retries forever.
+    #[uniffi(default = 0)]
+    pub max_attempts: u32,
+    /// Bind `command_status` values that end the session instead of being retried.
+    pub fatal_bind_statuses: Vec<u32>,
+    /// What happens to requests in flight when the link drops.
+    pub in_flight: InFlightPolicy,
+}
+
+impl Default for ReconnectPolicy {
+    fn default() -> Self {
+        Self {
+            initial_delay_ms: 1_000,
+            max_delay_ms: 60_000,
+            jitter_percent: 20,
+            max_attempts: 0,
+            fatal_bind_statuses: vec![ESME_RINVPASWD, ESME_RINVSYSID],
+            in_flight: InFlightPolicy::Fail,
+        }
+    }
+}
+
+impl ReconnectPolicy {
+    /// Returns true if another retry is allowed after `attempt` retries.
+    pub(crate) fn allows_retry(&self, attempt: u32, bind_status: Option<u32>) -> bool {
+        if bind_status.is_some_and(|status| self.fatal_bind_statuses.contains(&status)) {
+            return false;
+        }
+        self.max_attempts == 0 || attempt < self.max_attempts
+    }
+}
+
+/// Creates a `ReconnectPolicy` that retries forever, gives up on bad
+/// credentials and fails requests in flight.
+#[uniffi::export]
+pub fn reconnect_policy_default() -> ReconnectPolicy {
+    ReconnectPolicy::default()
+}
+
+/// Returns the delay before retry number `attempt` (starting at 1), in milliseconds.
+///
+/// The delay doubles with every attempt up to `max_delay_ms`, then varies
+/// randomly by up to `jitter_percent` in either direction.
+#[uniffi::export]
+pub fn reconnect_delay_ms(policy: &ReconnectPolicy, attempt: u32) -> u64 {
+    let doublings = attempt.saturating_sub(1).min(63);
+    let base = policy
+        .initial_delay_ms
+        .saturating_mul(1u64 << doublings)
+        .min(policy.max_delay_ms);
+    let spread = base * u64::from(policy.jitter_percent.min(100)) / 100;
+    if spread == 0 {
+        return base;
+    }
+    let random = RandomState::new().build_hasher().finish();
+    base - spread + random % (2 * spread + 1)
+}
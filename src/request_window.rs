@@ -0,0 +1,250 @@
+//! # Request Window
+//!
+//! Tracks unacknowledged requests, limits how many may be in flight and
+//! matches responses to them by sequence number and command ID.
+use crate::common::*;
+use crate::pdu::PduHeader;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+/// Settings for a `RequestWindow`.
+#[derive(uniffi::Record, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct RequestWindowConfig {
+    /// Maximum number of unacknowledged requests.
+    #[uniffi(default = 10)]
+    pub max_in_flight: u32,
+    /// Time after which an unacknowledged request is stale, in milliseconds.
+    #[uniffi(default = 30000)]
+    pub response_timeout_ms: u64,
+}
+
+impl Default for RequestWindowConfig {
+    fn default() -> Self {
+        Self {
+            max_in_flight: 10,
+            response_timeout_ms: 30_000,
+        }
+    }
+}
+
+/// Creates a `RequestWindowConfig` with the default limits.
+#[uniffi::export]
+pub fn request_window_config_default() -> RequestWindowConfig {
+    RequestWindowConfig::default()
+}
+
+/// A request waiting for its response.
+#[derive(uniffi::Record, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct InFlightRequest {
+    /// The sequence number of the request.
+    pub sequence_number: u32,
+    /// The command ID of the request.
+    pub command_id: u32,
+    /// When the request was sent, in the caller's milliseconds.
+    pub sent_at_ms: u64,
+}
+
+/// The outcome of offering a response to a `RequestWindow`.
+#[derive(uniffi::Enum, Clone, Debug, PartialEq, Eq)]
+pub enum ResponseMatch {
+    /// The response answers this request, which has left the window.
+    Matched {
+        /// The request.
+        request: InFlightRequest,
+    },
+    /// No request in the window is answered by this response.
+    Orphan {
+        /// The header of the response.
+        header: PduHeader,
+    },
+}
+
+/// Returns true if `response_id` answers a request with `request_id`.
+///
+/// `generic_nack` answers any request.
+pub(crate) fn is_response_to(request_id: u32, response_id: u32) -> bool {
+    response_id == request_id | 0x8000_0000 || response_id == GENERIC_NACK
+}
+
+/// The window itself, carrying a payload per request for the session types.
+pub(crate) struct Window<T> {
+    config: RequestWindowConfig,
+    entries: HashMap<u32, (InFlightRequest, T)>,
+}
+
+impl<T> Window<T> {
+    pub(crate) fn new(config: RequestWindowConfig) -> Self {
+        Self {
+            config,
+            entries: HashMap::new(),
+        }
+    }
+
+    pub(crate) fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub(crate) fn is_full(&self) -> bool {
+        self.entries.len() >= self.config.max_in_flight as usize
+    }
+
+    /// Adds a request.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the window is full or the sequence number is already in flight.
+    pub(crate) fn insert(
+        &mut self,
+        sequence_number: u32,
+        command_id: u32,
+        now_ms: u64,
+        payload: T,
+    ) -> Result<(), SmppFfiError> {
+        if self.is_full() {
+            return Err(SmppFfiError::Generic {
+                msg: format!(
+                    "Request window is full ({} in flight)",
+                    self.config.max_in_flight
+                ),
+            });
+        }
+        if self.entries.contains_key(&sequence_number) {
+            return Err(SmppFfiError::Generic {
+                msg: format!("Sequence number {} is already in flight", sequence_number),
+            });
+        }
+        let request = InFlightRequest {
+            sequence_number,
+            command_id,
+            sent_at_ms: now_ms,
+        };
+        self.entries.insert(sequence_number, (request, payload));
+        Ok(())
+    }
+
+    /// Removes and returns the request answered by a response.
+    ///
+    /// A response whose command ID does not pair with the request leaves the request in place.
+    pub(crate) fn complete(&mut self, header: &PduHeader) -> Option<(InFlightRequest, T)> {
+        let (request, _) = self.entries.get(&header.sequence_number)?;
+        if !is_response_to(request.command_id, header.command_id) {
+            return None;
+        }
+        self.entries.remove(&header.sequence_number)
+    }
+
+    pub(crate) fn remove(&mut self, sequence_number: u32) -> Option<(InFlightRequest, T)> {
+        self.entries.remove(&sequence_number)
+    }
+
+    /// Removes and returns the requests older than the response timeout, oldest first.
+    pub(crate) fn expire(&mut self, now_ms: u64) -> Vec<(InFlightRequest, T)> {
+        let timeout = self.config.response_timeout_ms;
+        let stale: Vec<u32> = self
+            .entries
+            .values()
+            .filter(|(request, _)| now_ms.saturating_sub(request.sent_at_ms) >= timeout)
+            .map(|(request, _)| request.sequence_number)
+            .collect();
+        let mut expired: Vec<_> = stale
+            .into_iter()
+            .filter_map(|sequence_number| self.entries.remove(&sequence_number))
+            .collect();
+        expired.sort_by_key(|(request, _)| (request.sent_at_ms, request.sequence_number));
+        expired
+    }
+
+    /// Removes and returns every request, in sequence number order.
+    pub(crate) fn drain(&mut self) -> Vec<(InFlightRequest, T)> {
+        let mut drained: Vec<_> = self.entries.drain().map(|(_, entry)| entry).collect();
+        drained.sort_by_key(|(request, _)| request.sequence_number);
+        drained
+    }
+
+    /// Returns when the oldest request becomes stale, in the caller's milliseconds.
+    pub(crate) fn next_deadline_ms(&self) -> Option<u64> {
+        self.entries
+            .values()
+            .map(|(request, _)| request.sent_at_ms + self.config.response_timeout_ms)
+            .min()
+    }
+}
+
+/// A thread-safe window of in-flight requests.
+///
+/// Times are supplied by the caller in milliseconds from any fixed origin.
+#[derive(uniffi::Object)]
+pub struct RequestWindow {
+    window: Mutex<Window<()>>,
+}
+
+#[uniffi::export]
+impl RequestWindow {
+    /// Creates an empty window.
+    #[uniffi::constructor]
+    pub fn new(config: RequestWindowConfig) -> Arc<Self> {
+        Arc::new(Self {
+            window: Mutex::new(Window::new(config)),
+        })
+    }
+
+    /// Records a sent request.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the window is full or the sequence number is already in flight.
+    pub fn insert(
+        &self,
+        sequence_number: u32,
+        command_id: u32,
+        now_ms: u64,
+    ) -> Result<(), SmppFfiError> {
+        self.window
+            .lock()
+            .unwrap()
+            .insert(sequence_number, command_id, now_ms, ())
+    }
+
+    /// Matches a received response against the window.
+    pub fn complete(&self, header: PduHeader) -> ResponseMatch {
+        match self.window.lock().unwrap().complete(&header) {
+            Some((request, ())) => ResponseMatch::Matched { request },
+            None => ResponseMatch::Orphan { header },
+        }
+    }
+
+    /// Removes a request without a response, for example when the caller gives up on it.
+    pub fn remove(&self, sequence_number: u32) -> Option<InFlightRequest> {
+        self.window
+            .lock()
+            .unwrap()
+            .remove(sequence_number)
+            .map(|(request, ())| request)
+    }
+
+    /// Removes and returns the stale requests, oldest first.
+    pub fn expire(&self, now_ms: u64) -> Vec<InFlightRequest> {
+        self.window
+            .lock()
+            .unwrap()
+            .expire(now_ms)
+            .into_iter()
+            .map(|(request, ())| request)
+            .collect()
+    }
+
+    /// Returns when the oldest request becomes stale, if any request is in flight.
+    pub fn next_deadline_ms(&self) -> Option<u64> {
+        self.window.lock().unwrap().next_deadline_ms()
+    }
+
+    /// Returns the number of requests in flight.
+    pub fn in_flight(&self) -> u32 {
+        self.window.lock().unwrap().len() as u32
+    }
+
+    /// Returns true if no further request may be sent.
+    pub fn is_full(&self) -> bool {
+        self.window.lock().unwrap().is_full()
+    }
+}
@@ -0,0 +1,121 @@
+//! # Sequence Numbers
+//!
+//! A thread-safe source of request sequence numbers. SMPP allows
+//! `0x00000001..=0x7FFFFFFF`; after the last value the generator wraps to 1.
+use crate::common::SmppFfiError;
+use std::fs;
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::{Arc, OnceLock};
+
+/// Smallest valid sequence number.
+pub const MIN_SEQUENCE_NUMBER: u32 = 0x0000_0001;
+/// Largest valid sequence number.
+pub const MAX_SEQUENCE_NUMBER: u32 = 0x7FFF_FFFF;
+
+/// Issues sequence numbers in `MIN_SEQUENCE_NUMBER..=MAX_SEQUENCE_NUMBER`.
+#[derive(uniffi::Object, Debug)]
+pub struct SequenceGenerator {
+    /// The value the next call to `next_value` returns.
+    next: AtomicU32,
+}
+
+#[uniffi::export]
+impl SequenceGenerator {
+    /// Creates a generator starting at 1.
+    #[uniffi::constructor]
+    pub fn new() -> Arc<Self> {
+        Arc::new(Self {
+            next: AtomicU32::new(MIN_SEQUENCE_NUMBER),
+        })
+    }
+
+    /// Creates a generator whose first value is `first`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `first` is not a valid sequence number.
+    #[uniffi::constructor]
+    pub fn with_seed(first: u32) -> Result<Arc<Self>, SmppFfiError> {
+        check_sequence_number(first)?;
+        Ok(Arc::new(Self {
+            next: AtomicU32::new(first),
+        }))
+    }
+
+    /// Creates a generator from a value saved by `save`.
+    ///
+    /// A missing file starts the generator at 1.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file cannot be read or holds an invalid value.
+    #[uniffi::constructor]
+    pub fn load(path: String) -> Result<Arc<Self>, SmppFfiError> {
+        let contents = match fs::read_to_string(&path) {
+            Ok(contents) => contents,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Self::new()),
+            Err(e) => return Err(e.into()),
+        };
+        let first = contents
+            .trim()
+            .parse::<u32>()
+            .map_err(|e| SmppFfiError::Generic {
+                msg: format!("Invalid sequence number in {}: {}", path, e),
+            })?;
+        Self::with_seed(first)
+    }
+
+    /// Returns the next sequence number.
+    pub fn next_value(&self) -> u32 {
+        self.next
+            .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |current| {
+                Some(following(current))
+            })
+            .unwrap()
+    }
+
+    /// Returns the value the next call to `next_value` will return, without consuming it.
+    pub fn peek(&self) -> u32 {
+        self.next.load(Ordering::SeqCst)
+    }
+
+    /// Writes the next value to a file, for `load` to resume from.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file cannot be written.
+    pub fn save(&self, path: String) -> Result<(), SmppFfiError> {
+        fs::write(path, format!("{}\n", self.peek()))?;
+        Ok(())
+    }
+}
+
+/// Returns the process-wide generator used when no other generator is given.
+#[uniffi::export]
+pub fn default_sequence_generator() -> Arc<SequenceGenerator> {
+    static DEFAULT: OnceLock<Arc<SequenceGenerator>> = OnceLock::new();
+    DEFAULT.get_or_init(SequenceGenerator::new).clone()
+}
+
+/// Checks that a value is a valid sequence number.
+///
+/// # Errors
+///
+/// Returns an error if the value is outside `MIN_SEQUENCE_NUMBER..=MAX_SEQUENCE_NUMBER`.
+#[uniffi::export]
+pub fn check_sequence_number(sequence_number: u32) -> Result<(), SmppFfiError> {
+    if !(MIN_SEQUENCE_NUMBER..=MAX_SEQUENCE_NUMBER).contains(&sequence_number) {
+        return Err(SmppFfiError::Generic {
+            msg: format!("Invalid sequence number: 0x{:08X}", sequence_number),
+        });
+    }
+    Ok(())
+}
+
+fn following(current: u32) -> u32 {
+    if current >= MAX_SEQUENCE_NUMBER {
+        MIN_SEQUENCE_NUMBER
+    } else {
+        current + 1
+    }
+}
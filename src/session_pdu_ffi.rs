@@ -10,7 +10,7 @@ use smpp_codec::pdus::{
 
 // -- Request ---
 /// Represents a Bind request (Receiver, Transmitter, or Transceiver).
-#[derive(uniffi::Record)]
+#[derive(uniffi::Record, Clone, Debug, PartialEq)]
 pub struct BindRequest {
     /// The sequence number of the PDU.
     pub sequence_number: u32,
@@ -82,7 +82,7 @@ pub fn decode_bind_request(buffer: &[u8]) -> Result<BindRequest, SmppFfiError> {
 }
 
 /// Represents an Enquire Link request.
-#[derive(uniffi::Record)]
+#[derive(uniffi::Record, Clone, Debug, PartialEq)]
 pub struct EnquireLink {
     /// The sequence number of the PDU.
     pub sequence_number: u32,
@@ -122,12 +122,15 @@ pub fn decode_enquire_link(buffer: &[u8]) -> Result<EnquireLink, SmppFfiError> {
 }
 
 /// Represents an Outbind request.
-#[derive(uniffi::Record)]
+#[derive(uniffi::Record, Clone, Debug, PartialEq)]
 pub struct Outbind {
     /// The sequence number of the PDU.
     pub sequence_number: u32,
     /// The system_id.
     pub system_id: String,
+    /// The password.
+    #[uniffi(default = "")]
+    pub password: String,
 }
 
 /// Encodes an `Outbind` request into a byte vector.
@@ -140,7 +143,7 @@ pub fn encode_outbind(req: &Outbind) -> Vec<u8> {
     let internal_req = InternalOutbindRequest {
         sequence_number: req.sequence_number,
         system_id: req.system_id.clone(),
-        password: String::new(), // FFI struct misses password, defaulting to empty or needs update later
+        password: req.password.clone(),
     };
 
     let mut buffer = Vec::new();
@@ -161,13 +164,14 @@ pub fn decode_outbind(buffer: &[u8]) -> Result<Outbind, SmppFfiError> {
         Ok(internal_resp) => Ok(Outbind {
             sequence_number: internal_resp.sequence_number,
             system_id: internal_resp.system_id,
+            password: internal_resp.password,
         }),
         Err(e) => Err(SmppFfiError::Generic { msg: e.to_string() }),
     }
 }
 
 /// Represents an Unbind request.
-#[derive(uniffi::Record)]
+#[derive(uniffi::Record, Clone, Debug, PartialEq)]
 pub struct Unbind {
     /// The sequence number of the PDU.
     pub sequence_number: u32,
@@ -206,7 +210,7 @@ pub fn decode_unbind(buffer: &[u8]) -> Result<Unbind, SmppFfiError> {
 }
 
 /// Represents a Generic Nack response.
-#[derive(uniffi::Record)]
+#[derive(uniffi::Record, Clone, Debug, PartialEq)]
 pub struct GenericNack {
     /// The sequence number of the PDU.
     pub sequence_number: u32,
@@ -252,7 +256,7 @@ pub fn decode_generic_nack(buffer: &[u8]) -> Result<GenericNack, SmppFfiError> {
 // --- Response ---
 
 /// Represents a Bind response.
-#[derive(uniffi::Record)]
+#[derive(uniffi::Record, Clone, Debug, PartialEq)]
 pub struct BindResponse {
     /// The sequence number of the PDU.
     pub sequence_number: u32,
@@ -302,7 +306,7 @@ pub fn decode_bind_response(buffer: &[u8]) -> Result<BindResponse, SmppFfiError>
 }
 
 /// Represents an Enquire Link response.
-#[derive(uniffi::Record)]
+#[derive(uniffi::Record, Clone, Debug, PartialEq)]
 pub struct EnquireLinkResponse {
     /// The sequence number of the PDU.
     pub sequence_number: u32,
@@ -346,7 +350,7 @@ pub fn decode_enquire_link_response(buffer: &[u8]) -> Result<EnquireLinkResponse
 }
 
 /// Represents an Unbind response.
-#[derive(uniffi::Record)]
+#[derive(uniffi::Record, Clone, Debug, PartialEq)]
 pub struct UnbindResponse {
     /// The sequence number of the PDU.
     pub sequence_number: u32,
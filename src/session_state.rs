@@ -0,0 +1,685 @@
+//! # Session State
+//!
+//! An SMPP session without IO. The caller feeds it received bytes, local
+//! commands and expired timers; it returns the bytes to write, the events to
+//! act on and the timers to start or cancel. It never touches a socket or a
+//! clock, so it can sit under any event loop.
+use crate::common::*;
+use crate::framer::split_frames;
+use crate::pdu::*;
+use crate::request_window::{InFlightRequest, RequestWindowConfig, Window};
+use crate::sequence::SequenceGenerator;
+use crate::session_pdu_ffi::*;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+const RESPONSE_BIT: u32 = 0x8000_0000;
+
+/// The side of the session this state machine plays.
+#[derive(uniffi::Enum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SessionRole {
+    /// The client that binds.
+    Esme,
+    /// The server that accepts binds.
+    Smsc,
+}
+
+/// Session states as defined by the SMPP specification.
+#[derive(uniffi::Enum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SessionStatus {
+    /// Connected but not bound.
+    Open,
+    /// Bound as transmitter.
+    BoundTx,
+    /// Bound as receiver.
+    BoundRx,
+    /// Bound as transceiver.
+    BoundTrx,
+    /// Unbind completed; the transport should be closed.
+    Unbound,
+    /// The transport is closed.
+    Closed,
+}
+
+impl SessionStatus {
+    /// Returns the bound state matching a bind mode.
+    pub fn bound(mode: BindMode) -> Self {
+        match mode {
+            BindMode::Transmitter => SessionStatus::BoundTx,
+            BindMode::Receiver => SessionStatus::BoundRx,
+            BindMode::Transceiver => SessionStatus::BoundTrx,
+        }
+    }
+
+    /// Returns true in any of the bound states.
+    pub fn is_bound(self) -> bool {
+        matches!(
+            self,
+            SessionStatus::BoundTx | SessionStatus::BoundRx | SessionStatus::BoundTrx
+        )
+    }
+}
+
+/// A timer the caller runs on behalf of the session.
+#[derive(uniffi::Enum, Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum SessionTimer {
+    /// The link has been idle long enough to send `enquire_link`.
+    EnquireLink,
+    /// The response to a request is overdue.
+    Response {
+        /// The sequence number of the request.
+        sequence_number: u32,
+    },
+}
+
+/// How well the peer answers our `enquire_link` keepalives.
+#[derive(uniffi::Enum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LinkHealth {
+    /// The last keepalive was answered.
+    Healthy,
+    /// Recent keepalives went unanswered.
+    Degraded {
+        /// The number of keepalives missed in a row.
+        missed_responses: u32,
+    },
+    /// Too many keepalives went unanswered; the peer is considered dead.
+    Dead,
+}
+
+/// Tells the caller to start or cancel a timer.
+#[derive(uniffi::Enum, Clone, Debug, PartialEq, Eq)]
+pub enum TimerInstruction {
+    /// Start the timer, replacing it if it is already running.
+    Start {
+        /// The timer.
+        timer: SessionTimer,
+        /// Delay before `on_timer` should be called, in milliseconds.
+        delay_ms: u64,
+    },
+    /// Cancel the timer if it is running.
+    Cancel {
+        /// The timer.
+        timer: SessionTimer,
+    },
+}
+
+/// Something the caller may need to act on.
+#[derive(uniffi::Enum, Clone, Debug, PartialEq)]
+pub enum SessionEvent {
+    /// The session moved to a new state.
+    StateChanged {
+        /// The new state.
+        state: SessionStatus,
+    },
+    /// The peer sent a request that must be answered with `send_response`.
+    RequestReceived {
+        /// The request.
+        pdu: Pdu,
+    },
+    /// The peer answered one of our requests.
+    ResponseReceived {
+        /// The header of the response.
+        header: PduHeader,
+        /// The decoded response, absent when the body could not be decoded.
+        pdu: Option<Pdu>,
+    },
+    /// The health of the link changed.
+    LinkHealthChanged {
+        /// The new health.
+        health: LinkHealth,
+    },
+    /// A request got no response in time.
+    ///
+    /// Unanswered keepalives are reported as `LinkHealthChanged` instead.
+    RequestTimedOut {
+        /// The sequence number of the request.
+        sequence_number: u32,
+        /// The command ID of the request.
+        command_id: u32,
+    },
+    /// A response matched none of our requests in flight.
+    OrphanResponse {
+        /// The header of the response.
+        header: PduHeader,
+    },
+    /// The peer broke the protocol.
+    ProtocolError {
+        /// What went wrong.
+        description: String,
+    },
+}
+
+/// The result of one call into a `SessionState`.
+#[derive(uniffi::Record, Clone, Debug, Default, PartialEq)]
+pub struct SessionOutput {
+    /// Bytes to write to the transport, in order.
+    pub outbound: Vec<u8>,
+    /// Events, in the order they happened.
+    pub events: Vec<SessionEvent>,
+    /// Timers to start or cancel, in order.
+    pub timers: Vec<TimerInstruction>,
+}
+
+/// The result of sending a request.
+#[derive(uniffi::Record, Clone, Debug, PartialEq)]
+pub struct SentRequest {
+    /// The sequence number assigned to the request.
+    pub sequence_number: u32,
+    /// Bytes, events and timers produced by sending it.
+    pub output: SessionOutput,
+}
+
+/// Settings for a `SessionState`.
+#[derive(uniffi::Record, Clone, Debug, PartialEq, Eq)]
+pub struct SessionStateConfig {
+    /// The side of the session.
+    pub role: SessionRole,
+    /// Idle time before `enquire_link` is due, in milliseconds. 0 disables it.
+    #[uniffi(default = 30000)]
+    pub enquire_link_interval_ms: u64,
+    /// Maximum time to wait for a response, in milliseconds.
+    #[uniffi(default = 30000)]
+    pub response_timeout_ms: u64,
+    /// Maximum number of our requests awaiting a response.
+    #[uniffi(default = 10)]
+    pub max_in_flight: u32,
+    /// Unanswered keepalives in a row after which the peer is dead. 0 never gives up.
+    #[uniffi(default = 3)]
+    pub max_missed_enquire_links: u32,
+}
+
+impl SessionStateConfig {
+    /// Creates a configuration with default timers.
+    pub fn new(role: SessionRole) -> Self {
+        Self {
+            role,
+            enquire_link_interval_ms: 30_000,
+            response_timeout_ms: 30_000,
+            max_in_flight: 10,
+            max_missed_enquire_links: 3,
+        }
+    }
+}
+
+/// Creates a `SessionStateConfig` with default timers.
+#[uniffi::export]
+pub fn session_state_config_new(role: SessionRole) -> SessionStateConfig {
+    SessionStateConfig::new(role)
+}
+
+/// Returns true if `sender` may send the request `command_id` in `status`.
+pub(crate) fn request_allowed(sender: SessionRole, status: SessionStatus, command_id: u32) -> bool {
+    match (sender, status) {
+        (_, SessionStatus::Unbound | SessionStatus::Closed) => false,
+        (SessionRole::Esme, SessionStatus::Open) => matches!(
+            command_id,
+            CMD_BIND_RECEIVER | CMD_BIND_TRANSMITTER | CMD_BIND_TRANSCEIVER | CMD_ENQUIRE_LINK
+        ),
+        (SessionRole::Smsc, SessionStatus::Open) => {
+            matches!(command_id, CMD_OUTBIND | CMD_ENQUIRE_LINK)
+        }
+        _ if matches!(command_id, CMD_ENQUIRE_LINK | CMD_UNBIND) => true,
+        (SessionRole::Esme, SessionStatus::BoundTx | SessionStatus::BoundTrx) => matches!(
+            command_id,
+            CMD_SUBMIT_SM
+                | CMD_SUBMIT_MULTI_SM
+                | CMD_DATA_SM
+                | CMD_QUERY_SM
+                | CMD_CANCEL_SM
+                | CMD_REPLACE_SM
+                | CMD_BROADCAST_SM
+                | CMD_QUERY_BROADCAST_SM
+                | CMD_CANCEL_BROADCAST_SM
+        ),
+        (SessionRole::Smsc, SessionStatus::BoundRx | SessionStatus::BoundTrx) => {
+            matches!(command_id, CMD_DELIVER_SM | CMD_DATA_SM)
+        }
+        _ => false,
+    }
+}
+
+/// A sans-IO SMPP session.
+#[derive(uniffi::Object)]
+pub struct SessionState {
+    machine: Mutex<Machine>,
+}
+
+#[uniffi::export]
+impl SessionState {
+    /// Creates a session in the `Open` state.
+    #[uniffi::constructor]
+    pub fn new(config: SessionStateConfig) -> Arc<Self> {
+        Self::with_sequence_generator(config, SequenceGenerator::new())
+    }
+
+    /// Creates a session in the `Open` state, drawing sequence numbers from `sequence`.
+    #[uniffi::constructor]
+    pub fn with_sequence_generator(
+        config: SessionStateConfig,
+        sequence: Arc<SequenceGenerator>,
+    ) -> Arc<Self> {
+        Arc::new(Self {
+            machine: Mutex::new(Machine {
+                status: SessionStatus::Open,
+                buffer: Vec::new(),
+                sequence,
+                outstanding: Window::new(RequestWindowConfig {
+                    max_in_flight: config.max_in_flight,
+                    response_timeout_ms: config.response_timeout_ms,
+                }),
+                inbound: HashMap::new(),
+                missed_enquire_links: 0,
+                config,
+            }),
+        })
+    }
+
+    /// Returns the current state.
+    pub fn status(&self) -> SessionStatus {
+        self.machine.lock().unwrap().status
+    }
+
+    /// Returns the generator that assigns this session's sequence numbers.
+    pub fn sequence_generator(&self) -> Arc<SequenceGenerator> {
+        self.machine.lock().unwrap().sequence.clone()
+    }
+
+    /// Returns the number of our requests still waiting for a response.
+    pub fn outstanding_requests(&self) -> u32 {
+        self.machine.lock().unwrap().outstanding.len() as u32
+    }
+
+    /// Returns the health of the link as seen by our keepalives.
+    pub fn link_health(&self) -> LinkHealth {
+        self.machine.lock().unwrap().link_health()
+    }
+
+    /// Processes bytes read from the transport.
+    ///
+    /// `enquire_link` and `unbind` from the peer are answered automatically;
+    /// requests illegal in the current state are rejected with `ESME_RINVBNDSTS`.
+    pub fn receive(&self, bytes: Vec<u8>) -> SessionOutput {
+        let mut out = SessionOutput::default();
+        self.machine.lock().unwrap().receive(&bytes, &mut out);
+        out
+    }
+
+    /// Sends a request, assigning its sequence number.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the PDU is not a request or is not allowed in the current state.
+    pub fn send_request(&self, request: Pdu) -> Result<SentRequest, SmppFfiError> {
+        let mut out = SessionOutput::default();
+        let sequence_number = self
+            .machine
+            .lock()
+            .unwrap()
+            .send_request(request, &mut out)?;
+        Ok(SentRequest {
+            sequence_number,
+            output: out,
+        })
+    }
+
+    /// Answers a request reported by `SessionEvent::RequestReceived`.
+    ///
+    /// The response is matched to the request by its sequence number.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if no request with that sequence number is awaiting a response.
+    pub fn send_response(&self, response: Pdu) -> Result<SessionOutput, SmppFfiError> {
+        let mut out = SessionOutput::default();
+        self.machine
+            .lock()
+            .unwrap()
+            .send_response(response, &mut out)?;
+        Ok(out)
+    }
+
+    /// Handles a timer started by a `TimerInstruction::Start`.
+    ///
+    /// When `max_missed_enquire_links` keepalives in a row go unanswered the
+    /// link is reported `Dead` and the session closes.
+    pub fn on_timer(&self, timer: SessionTimer) -> SessionOutput {
+        let mut out = SessionOutput::default();
+        self.machine.lock().unwrap().on_timer(timer, &mut out);
+        out
+    }
+
+    /// Marks the transport as closed.
+    pub fn close(&self) -> SessionOutput {
+        let mut out = SessionOutput::default();
+        self.machine.lock().unwrap().close(&mut out);
+        out
+    }
+}
+
+struct Machine {
+    config: SessionStateConfig,
+    status: SessionStatus,
+    buffer: Vec<u8>,
+    sequence: Arc<SequenceGenerator>,
+    /// Our requests awaiting a response.
+    outstanding: Window<()>,
+    /// Peer requests awaiting our response: sequence number to command ID.
+    inbound: HashMap<u32, u32>,
+    /// Keepalives missed in a row.
+    missed_enquire_links: u32,
+}
+
+impl Machine {
+    fn peer(&self) -> SessionRole {
+        match self.config.role {
+            SessionRole::Esme => SessionRole::Smsc,
+            SessionRole::Smsc => SessionRole::Esme,
+        }
+    }
+
+    fn set_status(&mut self, status: SessionStatus, out: &mut SessionOutput) {
+        if self.status == status {
+            return;
+        }
+        self.status = status;
+        out.events
+            .push(SessionEvent::StateChanged { state: status });
+        if status.is_bound() {
+            self.restart_enquire_link(out);
+        } else {
+            out.timers.push(TimerInstruction::Cancel {
+                timer: SessionTimer::EnquireLink,
+            });
+        }
+    }
+
+    fn restart_enquire_link(&self, out: &mut SessionOutput) {
+        if self.status.is_bound() && self.config.enquire_link_interval_ms > 0 {
+            out.timers.push(TimerInstruction::Start {
+                timer: SessionTimer::EnquireLink,
+                delay_ms: self.config.enquire_link_interval_ms,
+            });
+        }
+    }
+
+    fn link_health(&self) -> LinkHealth {
+        let max = self.config.max_missed_enquire_links;
+        match self.missed_enquire_links {
+            0 => LinkHealth::Healthy,
+            missed if max > 0 && missed >= max => LinkHealth::Dead,
+            missed => LinkHealth::Degraded {
+                missed_responses: missed,
+            },
+        }
+    }
+
+    fn set_missed_enquire_links(&mut self, missed: u32, out: &mut SessionOutput) {
+        if self.missed_enquire_links == missed {
+            return;
+        }
+        self.missed_enquire_links = missed;
+        out.events.push(SessionEvent::LinkHealthChanged {
+            health: self.link_health(),
+        });
+    }
+
+    fn receive(&mut self, bytes: &[u8], out: &mut SessionOutput) {
+        if self.status == SessionStatus::Closed {
+            return;
+        }
+        self.buffer.extend_from_slice(bytes);
+        let frames = match split_frames(&mut self.buffer) {
+            Ok(frames) => frames,
+            Err(e) => {
+                out.events.push(SessionEvent::ProtocolError {
+                    description: e.to_string(),
+                });
+                self.close(out);
+                return;
+            }
+        };
+        for frame in frames {
+            self.receive_frame(&frame, out);
+        }
+    }
+
+    fn receive_frame(&mut self, frame: &[u8], out: &mut SessionOutput) {
+        let Ok(header) = decode_pdu_header(frame) else {
+            return;
+        };
+        self.restart_enquire_link(out);
+
+        if header.command_id & RESPONSE_BIT != 0 || header.command_id == GENERIC_NACK {
+            self.receive_response(header, decode_pdu(frame).ok(), out);
+            return;
+        }
+        let request = match decode_pdu(frame) {
+            Ok(request) => request,
+            Err(e) => {
+                let reply = if is_supported_command_id(header.command_id) {
+                    encode_error_response(&header, ESME_RSYSERR)
+                } else {
+                    encode_generic_nack(&GenericNack {
+                        sequence_number: header.sequence_number,
+                        command_status: ESME_RINVCMDID,
+                    })
+                };
+                out.outbound.extend_from_slice(&reply);
+                out.events.push(SessionEvent::ProtocolError {
+                    description: e.to_string(),
+                });
+                return;
+            }
+        };
+        if !request_allowed(self.peer(), self.status, header.command_id) {
+            let status = match request {
+                Pdu::BindRequest { .. } if self.status.is_bound() => ESME_RALYBND,
+                _ => ESME_RINVBNDSTS,
+            };
+            out.outbound
+                .extend_from_slice(&encode_error_response(&header, status));
+            return;
+        }
+
+        match request {
+            Pdu::EnquireLink { pdu } => {
+                out.outbound
+                    .extend_from_slice(&encode_enquire_link_response(&EnquireLinkResponse {
+                        sequence_number: pdu.sequence_number,
+                        command_status: COMMAND_STATUS_OK,
+                    }));
+            }
+            Pdu::Unbind { pdu } => {
+                out.outbound
+                    .extend_from_slice(&encode_unbind_response(&UnbindResponse {
+                        sequence_number: pdu.sequence_number,
+                        command_status: COMMAND_STATUS_OK,
+                    }));
+                self.set_status(SessionStatus::Unbound, out);
+            }
+            // outbind has no response.
+            request @ Pdu::Outbind { .. } => {
+                out.events
+                    .push(SessionEvent::RequestReceived { pdu: request });
+            }
+            request => {
+                self.inbound
+                    .insert(header.sequence_number, header.command_id);
+                out.events
+                    .push(SessionEvent::RequestReceived { pdu: request });
+            }
+        }
+    }
+
+    fn receive_response(&mut self, header: PduHeader, pdu: Option<Pdu>, out: &mut SessionOutput) {
+        let Some((request, ())) = self.outstanding.complete(&header) else {
+            out.events.push(SessionEvent::OrphanResponse { header });
+            return;
+        };
+        let command_id = request.command_id;
+        out.timers.push(TimerInstruction::Cancel {
+            timer: SessionTimer::Response {
+                sequence_number: header.sequence_number,
+            },
+        });
+
+        if command_id == CMD_ENQUIRE_LINK {
+            self.set_missed_enquire_links(0, out);
+        }
+        let accepted =
+            header.command_status == COMMAND_STATUS_OK && header.command_id != GENERIC_NACK;
+        match command_id {
+            CMD_BIND_RECEIVER | CMD_BIND_TRANSMITTER | CMD_BIND_TRANSCEIVER if accepted => {
+                self.set_status(SessionStatus::bound(bind_mode(command_id)), out);
+            }
+            CMD_UNBIND if accepted => self.set_status(SessionStatus::Unbound, out),
+            _ => {}
+        }
+        // Our own keepalive is not reported.
+        if command_id != CMD_ENQUIRE_LINK || !accepted {
+            out.events
+                .push(SessionEvent::ResponseReceived { header, pdu });
+        }
+    }
+
+    fn send_request(&mut self, request: Pdu, out: &mut SessionOutput) -> Result<u32, SmppFfiError> {
+        let mut bytes = encode_pdu(&request);
+        let command_id = u32::from_be_bytes([bytes[4], bytes[5], bytes[6], bytes[7]]);
+        if command_id & RESPONSE_BIT != 0 || command_id == GENERIC_NACK {
+            return Err(SmppFfiError::Generic {
+                msg: format!("Command 0x{:08X} is not a request", command_id),
+            });
+        }
+        if !request_allowed(self.config.role, self.status, command_id) {
+            return Err(SmppFfiError::Generic {
+                msg: format!(
+                    "Command 0x{:08X} is not allowed in state {:?}",
+                    command_id, self.status
+                ),
+            });
+        }
+
+        // outbind has no response.
+        let tracked = command_id != CMD_OUTBIND;
+        if tracked && self.outstanding.is_full() {
+            return Err(SmppFfiError::Generic {
+                msg: "Request window is full".to_string(),
+            });
+        }
+
+        let sequence_number = self.sequence.next_value();
+        bytes[12..16].copy_from_slice(&sequence_number.to_be_bytes());
+        out.outbound.extend_from_slice(&bytes);
+        if tracked {
+            self.outstanding
+                .insert(sequence_number, command_id, 0, ())?;
+            out.timers.push(TimerInstruction::Start {
+                timer: SessionTimer::Response { sequence_number },
+                delay_ms: self.config.response_timeout_ms,
+            });
+        }
+        Ok(sequence_number)
+    }
+
+    fn send_response(
+        &mut self,
+        mut response: Pdu,
+        out: &mut SessionOutput,
+    ) -> Result<(), SmppFfiError> {
+        let sequence_number = response.sequence_number();
+        let Some(command_id) = self.inbound.remove(&sequence_number) else {
+            return Err(SmppFfiError::Generic {
+                msg: format!(
+                    "No request with sequence number {} awaits a response",
+                    sequence_number
+                ),
+            });
+        };
+        response.set_sequence_number(sequence_number);
+        let mut bytes = encode_pdu(&response);
+        // The response takes its command ID from the request it answers,
+        // unless it is a generic_nack.
+        if !matches!(response, Pdu::GenericNack { .. }) {
+            bytes[4..8].copy_from_slice(&(command_id | RESPONSE_BIT).to_be_bytes());
+        }
+        let command_status = u32::from_be_bytes([bytes[8], bytes[9], bytes[10], bytes[11]]);
+        out.outbound.extend_from_slice(&bytes);
+
+        if matches!(
+            command_id,
+            CMD_BIND_RECEIVER | CMD_BIND_TRANSMITTER | CMD_BIND_TRANSCEIVER
+        ) && command_status == COMMAND_STATUS_OK
+            && self.status == SessionStatus::Open
+        {
+            self.set_status(SessionStatus::bound(bind_mode(command_id)), out);
+        }
+        Ok(())
+    }
+
+    fn on_timer(&mut self, timer: SessionTimer, out: &mut SessionOutput) {
+        match timer {
+            SessionTimer::EnquireLink => {
+                if self.status.is_bound() {
+                    let _ = self.send_request(
+                        Pdu::EnquireLink {
+                            pdu: EnquireLink { sequence_number: 0 },
+                        },
+                        out,
+                    );
+                    self.restart_enquire_link(out);
+                }
+            }
+            SessionTimer::Response { sequence_number } => {
+                match self.outstanding.remove(sequence_number) {
+                    Some((
+                        InFlightRequest {
+                            command_id: CMD_ENQUIRE_LINK,
+                            ..
+                        },
+                        (),
+                    )) => {
+                        self.set_missed_enquire_links(self.missed_enquire_links + 1, out);
+                        if self.link_health() == LinkHealth::Dead {
+                            self.close(out);
+                        }
+                    }
+                    Some((InFlightRequest { command_id, .. }, ())) => {
+                        out.events.push(SessionEvent::RequestTimedOut {
+                            sequence_number,
+                            command_id,
+                        });
+                    }
+                    None => {}
+                }
+            }
+        }
+    }
+
+    fn close(&mut self, out: &mut SessionOutput) {
+        if self.status == SessionStatus::Closed {
+            return;
+        }
+        for (
+            InFlightRequest {
+                sequence_number, ..
+            },
+            (),
+        ) in self.outstanding.drain()
+        {
+            out.timers.push(TimerInstruction::Cancel {
+                timer: SessionTimer::Response { sequence_number },
+            });
+        }
+        self.inbound.clear();
+        self.buffer.clear();
+        self.set_status(SessionStatus::Closed, out);
+    }
+}
+
+fn bind_mode(command_id: u32) -> BindMode {
+    match command_id {
+        CMD_BIND_RECEIVER => BindMode::Receiver,
+        CMD_BIND_TRANSMITTER => BindMode::Transmitter,
+        _ => BindMode::Transceiver,
+    }
+}
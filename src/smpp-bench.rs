@@ -0,0 +1,415 @@
+//! # smpp-bench
+//!
+//! A load-generating ESME for benchmarking SMSCs and gateways. It opens
+//! several binds, submits at a target rate with up to `--window` requests
+//! in flight per bind, and reports throughput, submit latency and the
+//! count of responses per command status.
+//!
+//! Usage: `smpp-bench [OPTIONS]`; see `smpp-bench --help`. Every multipart
+//! message counts as one message and one `submit_sm` per part.
+use smpp_codec_ffi::address::address_new;
+use smpp_codec_ffi::builders::SubmitSmBuilder;
+use smpp_codec_ffi::common::*;
+use smpp_codec_ffi::esme_session::*;
+use smpp_codec_ffi::session_pdu_ffi::BindRequest;
+use smpp_codec_ffi::splitter::{split_message, EncodingType, SplitMode};
+use smpp_codec_ffi::submission_pdu_ffi::SubmitSmRequest;
+use smpp_codec_ffi::throttle::ThrottleConfig;
+use smpp_codec_ffi::tlv::{tlv_new, tlv_new_u16, tlv_new_u8, Tags};
+use std::collections::BTreeMap;
+use std::process::ExitCode;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+const USAGE: &str = "Usage: smpp-bench [OPTIONS]
+
+Options:
+  --host HOST            SMSC host [127.0.0.1]
+  --port PORT            SMSC port [2775]
+  --system-id ID         system_id of every bind [bench]
+  --password PASSWORD    password of every bind []
+  --binds N              transmitter binds to open [1]
+  --window N             requests in flight per bind [10]
+  --tps N                target messages per second over all binds; 0 for no limit [100]
+  --messages N           messages to submit [1000]
+  --duration-secs N      stop submitting after N seconds; 0 for no limit [0]
+  --size N               characters per message [160]
+  --encoding ENCODING    gsm7, latin1 or ucs2 [gsm7]
+  --split MODE           udh, sar or payload [udh]
+  --source ADDR          source address [BENCH]
+  --destination ADDR     destination address [447700900000]
+  --timeout-ms N         response timeout [30000]";
+
+/// `esm_class` with the UDH indicator set.
+const ESM_CLASS_UDHI: u8 = 0x40;
+/// Maximum length of the `short_message` field, in octets.
+const MAX_SHORT_MESSAGE_LEN: usize = 254;
+/// Upper bounds of the latency histogram buckets, in milliseconds.
+const BUCKETS_MS: [u64; 10] = [1, 2, 5, 10, 20, 50, 100, 200, 500, 1000];
+
+#[derive(Clone)]
+struct Options {
+    host: String,
+    port: u16,
+    system_id: String,
+    password: String,
+    binds: u32,
+    window: u32,
+    tps: u32,
+    messages: u64,
+    duration_secs: u64,
+    size: usize,
+    encoding: EncodingType,
+    split: SplitMode,
+    source: String,
+    destination: String,
+    timeout_ms: u64,
+}
+
+impl Default for Options {
+    fn default() -> Self {
+        Self {
+            host: "127.0.0.1".to_string(),
+            port: 2775,
+            system_id: "bench".to_string(),
+            password: String::new(),
+            binds: 1,
+            window: 10,
+            tps: 100,
+            messages: 1000,
+            duration_secs: 0,
+            size: 160,
+            encoding: EncodingType::Gsm7Bit,
+            split: SplitMode::Udh,
+            source: "BENCH".to_string(),
+            destination: "447700900000".to_string(),
+            timeout_ms: 30_000,
+        }
+    }
+}
+
+impl Options {
+    fn parse(args: &[String]) -> Result<Self, String> {
+        let mut options = Self::default();
+        let mut args = args.iter();
+        while let Some(flag) = args.next() {
+            let value = args
+                .next()
+                .ok_or_else(|| format!("{} needs a value", flag))?;
+            match flag.as_str() {
+                "--host" => options.host = value.clone(),
+                "--port" => options.port = number(flag, value)?,
+                "--system-id" => options.system_id = value.clone(),
+                "--password" => options.password = value.clone(),
+                "--binds" => options.binds = number(flag, value)?,
+                "--window" => options.window = number(flag, value)?,
+                "--tps" => options.tps = number(flag, value)?,
+                "--messages" => options.messages = number(flag, value)?,
+                "--duration-secs" => options.duration_secs = number(flag, value)?,
+                "--size" => options.size = number(flag, value)?,
+                "--encoding" => {
+                    options.encoding = match value.as_str() {
+                        "gsm7" => EncodingType::Gsm7Bit,
+                        "latin1" => EncodingType::Latin1,
+                        "ucs2" => EncodingType::Ucs2,
+                        _ => return Err(format!("Unknown encoding '{}'", value)),
+                    }
+                }
+                "--split" => {
+                    options.split = match value.as_str() {
+                        "udh" => SplitMode::Udh,
+                        "sar" => SplitMode::Sar,
+                        "payload" => SplitMode::Payload,
+                        _ => return Err(format!("Unknown split mode '{}'", value)),
+                    }
+                }
+                "--source" => options.source = value.clone(),
+                "--destination" => options.destination = value.clone(),
+                "--timeout-ms" => options.timeout_ms = number(flag, value)?,
+                _ => return Err(format!("Unknown option '{}'", flag)),
+            }
+        }
+        if options.binds == 0 || options.window == 0 {
+            return Err("--binds and --window must be at least 1".to_string());
+        }
+        Ok(options)
+    }
+}
+
+fn number<T: std::str::FromStr>(flag: &str, value: &str) -> Result<T, String> {
+    value
+        .parse()
+        .map_err(|_| format!("{} expects a number, got '{}'", flag, value))
+}
+
+fn main() -> ExitCode {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if args.iter().any(|arg| arg == "-h" || arg == "--help") {
+        println!("{}", USAGE);
+        return ExitCode::SUCCESS;
+    }
+    let result = Options::parse(&args).and_then(|options| run(&options));
+    match result {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("smpp-bench: {}", e);
+            ExitCode::FAILURE
+        }
+    }
+}
+
+/// What the workers share: the message counter, the pacing and the results.
+struct Bench {
+    options: Options,
+    next_message: AtomicU64,
+    started: Instant,
+    results: Mutex<Results>,
+}
+
+#[derive(Default)]
+struct Results {
+    messages: u64,
+    /// Submit latency of every answered `submit_sm`, in microseconds.
+    latencies_us: Vec<u64>,
+    /// Responses per command status, with the status description.
+    statuses: BTreeMap<u32, (String, u64)>,
+    /// Submits without a response, per error.
+    errors: BTreeMap<String, u64>,
+}
+
+impl Bench {
+    /// Claims the next message and waits for its slot, or returns `None` when done.
+    fn next(&self) -> Option<u64> {
+        let index = self.next_message.fetch_add(1, Ordering::Relaxed);
+        if index >= self.options.messages {
+            return None;
+        }
+        let mut due = Duration::ZERO;
+        if self.options.tps > 0 {
+            due = Duration::from_secs_f64(index as f64 / self.options.tps as f64);
+        }
+        if self.options.duration_secs > 0 && due >= Duration::from_secs(self.options.duration_secs)
+        {
+            return None;
+        }
+        if let Some(wait) = due.checked_sub(self.started.elapsed()) {
+            thread::sleep(wait);
+        }
+        if self.options.duration_secs > 0
+            && self.started.elapsed() >= Duration::from_secs(self.options.duration_secs)
+        {
+            return None;
+        }
+        Some(index)
+    }
+
+    fn work(&self, session: &EsmeSession) {
+        while let Some(index) = self.next() {
+            let parts = match self.message(index) {
+                Ok(parts) => parts,
+                Err(e) => {
+                    self.record_error(e.to_string());
+                    continue;
+                }
+            };
+            for part in parts {
+                let sent = Instant::now();
+                match session.submit(part) {
+                    Ok(response) => {
+                        let latency_us = sent.elapsed().as_micros() as u64;
+                        let mut results = self.results.lock().unwrap();
+                        results.latencies_us.push(latency_us);
+                        results
+                            .statuses
+                            .entry(response.command_status)
+                            .or_insert_with(|| (response.status_description.clone(), 0))
+                            .1 += 1;
+                    }
+                    Err(e) => self.record_error(e.to_string()),
+                }
+            }
+            self.results.lock().unwrap().messages += 1;
+        }
+    }
+
+    fn record_error(&self, error: String) {
+        *self
+            .results
+            .lock()
+            .unwrap()
+            .errors
+            .entry(error)
+            .or_default() += 1;
+    }
+
+    /// The `submit_sm` requests of message `index`, one per part.
+    fn message(&self, index: u64) -> Result<Vec<SubmitSmRequest>, SmppFfiError> {
+        let options = &self.options;
+        let split = split_message(text(options.size), options.encoding, options.split)?;
+        let total = split.parts.len();
+        let mut requests = Vec::with_capacity(total);
+        for (i, part) in split.parts.into_iter().enumerate() {
+            let mut builder = SubmitSmBuilder::new()
+                .source(address_new(
+                    Ton::Unknown,
+                    Npi::Unknown,
+                    options.source.clone(),
+                ))
+                .destination(address_new(
+                    Ton::International,
+                    Npi::Isdn,
+                    options.destination.clone(),
+                ));
+            builder = match options.split {
+                SplitMode::Udh if total > 1 => builder
+                    .short_message(part, split.data_coding)
+                    .esm_class(ESM_CLASS_UDHI),
+                SplitMode::Sar if total > 1 => builder
+                    .short_message(part, split.data_coding)
+                    .tlv(tlv_new_u16(Tags::SAR_MSG_REF_NUM, index as u16))
+                    .tlv(tlv_new_u8(Tags::SAR_TOTAL_SEGMENTS, total as u8))
+                    .tlv(tlv_new_u8(Tags::SAR_SEGMENT_SEQNUM, i as u8 + 1)),
+                _ if part.len() > MAX_SHORT_MESSAGE_LEN => builder
+                    .short_message(Vec::new(), split.data_coding)
+                    .tlv(tlv_new(Tags::MESSAGE_PAYLOAD, part)),
+                _ => builder.short_message(part, split.data_coding),
+            };
+            requests.push(builder.build()?);
+        }
+        Ok(requests)
+    }
+}
+
+/// A message of `size` characters from the GSM 7-bit basic set.
+fn text(size: usize) -> String {
+    "The quick brown fox jumps over the lazy dog 0123456789 "
+        .chars()
+        .cycle()
+        .take(size)
+        .collect()
+}
+
+fn run(options: &Options) -> Result<(), String> {
+    let config = EsmeSessionConfig {
+        response_timeout_ms: options.timeout_ms,
+        max_in_flight: options.window,
+        // Throttling is part of what is measured, so it is reported rather than retried.
+        throttle: Some(ThrottleConfig {
+            max_throttled_retries: 0,
+            ..ThrottleConfig::default()
+        }),
+        ..esme_session_config_new(options.host.clone(), options.port)
+    };
+    let bind = BindRequest {
+        sequence_number: 0,
+        mode: BindMode::Transmitter,
+        system_id: options.system_id.clone(),
+        password: options.password.clone(),
+        system_type: String::new(),
+        interface_version: SMPP_INTERFACE_VERSION_34,
+        addr_ton: Ton::Unknown,
+        addr_npi: Npi::Unknown,
+        address_range: String::new(),
+    };
+    let mut sessions = Vec::new();
+    for _ in 0..options.binds {
+        let session = EsmeSession::connect(config.clone(), bind.clone(), None)
+            .map_err(|e| format!("bind failed: {}", e))?;
+        sessions.push(session);
+    }
+
+    let bench = Arc::new(Bench {
+        options: options.clone(),
+        next_message: AtomicU64::new(0),
+        started: Instant::now(),
+        results: Mutex::new(Results::default()),
+    });
+    let mut workers = Vec::new();
+    for session in &sessions {
+        for _ in 0..options.window {
+            let bench = bench.clone();
+            let session = session.clone();
+            workers.push(thread::spawn(move || bench.work(&session)));
+        }
+    }
+    for worker in workers {
+        let _ = worker.join();
+    }
+    let elapsed = bench.started.elapsed();
+    for session in sessions {
+        let _ = session.unbind();
+    }
+
+    let mut results = bench.results.lock().unwrap();
+    report(options, elapsed, &mut results);
+    Ok(())
+}
+
+fn report(options: &Options, elapsed: Duration, results: &mut Results) {
+    let secs = elapsed.as_secs_f64().max(f64::EPSILON);
+    let answered = results.latencies_us.len() as u64;
+    let failed: u64 = results.errors.values().sum();
+    println!(
+        "binds {} window {} target {} tps",
+        options.binds, options.window, options.tps
+    );
+    println!(
+        "messages {} submits {} in {:.3} s",
+        results.messages,
+        answered + failed,
+        secs
+    );
+    println!(
+        "throughput {:.1} msg/s {:.1} submit/s",
+        results.messages as f64 / secs,
+        (answered + failed) as f64 / secs
+    );
+
+    let latencies = &mut results.latencies_us;
+    latencies.sort_unstable();
+    if !latencies.is_empty() {
+        println!(
+            "latency ms min {} p50 {} p95 {} p99 {} max {}",
+            ms(latencies[0]),
+            ms(percentile(latencies, 50)),
+            ms(percentile(latencies, 95)),
+            ms(percentile(latencies, 99)),
+            ms(latencies[latencies.len() - 1])
+        );
+        println!("histogram");
+        let mut from = 0;
+        for bound in BUCKETS_MS {
+            let to = latencies.partition_point(|&us| us < bound * 1000);
+            println!("  < {:>4} ms {}", bound, to - from);
+            from = to;
+        }
+        println!(
+            "  >= {:>3} ms {}",
+            BUCKETS_MS[BUCKETS_MS.len() - 1],
+            latencies.len() - from
+        );
+    }
+
+    println!("statuses");
+    for (status, (description, count)) in &results.statuses {
+        println!("  0x{:08X} {} {}", status, description, count);
+    }
+    if !results.errors.is_empty() {
+        println!("errors");
+        for (error, count) in &results.errors {
+            println!("  {} {}", error, count);
+        }
+    }
+}
+
+/// The nearest-rank percentile of sorted `values`.
+fn percentile(values: &[u64], percent: usize) -> u64 {
+    let rank = (values.len() * percent).div_ceil(100).max(1);
+    values[rank - 1]
+}
+
+fn ms(us: u64) -> String {
+    format!("{:.3}", us as f64 / 1000.0)
+}
@@ -0,0 +1,122 @@
+//! # smpp-decode
+//!
+//! Decodes SMPP PDUs from a hex dump or a binary capture and prints them as
+//! a tree: header, fields, TLVs with their names and values, the decoded
+//! message text and the parsed UDH.
+//!
+//! Usage: `smpp-decode [--json] [--hex | --binary] [FILE]`. Without a file,
+//! or with `-`, input is read from stdin. Hex may contain whitespace, `,`,
+//! `:` and `0x` prefixes; input of other characters is read as binary.
+use pdu_tree::{describe, parse_hex, tree};
+use smpp_codec_ffi::framer::read_frame;
+use std::io::{Cursor, ErrorKind, Read};
+use std::process::ExitCode;
+
+#[path = "cli/pdu_tree.rs"]
+mod pdu_tree;
+
+const USAGE: &str = "Usage: smpp-decode [--json] [--hex | --binary] [FILE]
+
+Decodes every PDU in FILE, or stdin if FILE is missing or -.
+
+Options:
+  --json     print a JSON array instead of a tree
+  --hex      read the input as hex
+  --binary   read the input as raw bytes";
+
+#[derive(Clone, Copy, PartialEq)]
+enum Format {
+    Auto,
+    Hex,
+    Binary,
+}
+
+fn main() -> ExitCode {
+    let mut json = false;
+    let mut format = Format::Auto;
+    let mut path = None;
+    for arg in std::env::args().skip(1) {
+        match arg.as_str() {
+            "-h" | "--help" => {
+                println!("{}", USAGE);
+                return ExitCode::SUCCESS;
+            }
+            "--json" => json = true,
+            "--hex" => format = Format::Hex,
+            "--binary" => format = Format::Binary,
+            _ if path.is_none() && (arg == "-" || !arg.starts_with('-')) => path = Some(arg),
+            _ => {
+                eprintln!("{}", USAGE);
+                return ExitCode::from(2);
+            }
+        }
+    }
+    match run(path.as_deref(), format, json) {
+        Ok(true) => ExitCode::SUCCESS,
+        Ok(false) => ExitCode::FAILURE,
+        Err(e) => {
+            eprintln!("smpp-decode: {}", e);
+            ExitCode::FAILURE
+        }
+    }
+}
+
+/// Decodes and prints the input, returning whether every byte was a valid PDU.
+fn run(path: Option<&str>, format: Format, json: bool) -> Result<bool, String> {
+    let mut input = Vec::new();
+    match path {
+        Some(path) if path != "-" => {
+            input = std::fs::read(path).map_err(|e| format!("{}: {}", path, e))?;
+        }
+        _ => {
+            std::io::stdin()
+                .read_to_end(&mut input)
+                .map_err(|e| e.to_string())?;
+        }
+    }
+    let bytes = match format {
+        Format::Hex => parse_hex(&input)?,
+        Format::Binary => input,
+        Format::Auto => parse_hex(&input).unwrap_or(input),
+    };
+
+    let mut complete = true;
+    let mut pdus = Vec::new();
+    let mut cursor = Cursor::new(bytes.as_slice());
+    while (cursor.position() as usize) < bytes.len() {
+        let offset = cursor.position() as usize;
+        match read_frame(&mut cursor) {
+            Ok(frame) => {
+                let pdu = describe(offset, &frame);
+                complete &= pdu.get("error").is_none();
+                pdus.push(pdu);
+            }
+            Err(e) => {
+                complete = false;
+                if e.kind() == ErrorKind::UnexpectedEof {
+                    eprintln!(
+                        "smpp-decode: {} trailing octets at offset {} are not a complete PDU",
+                        bytes.len() - offset,
+                        offset
+                    );
+                } else {
+                    eprintln!("smpp-decode: at offset {}: {}", offset, e);
+                }
+                break;
+            }
+        }
+    }
+
+    if json {
+        println!("{}", serde_json::to_string_pretty(&pdus).unwrap());
+    } else {
+        for (index, pdu) in pdus.iter().enumerate() {
+            if index > 0 {
+                println!();
+            }
+            let title = format!("PDU {} at offset {}", index + 1, pdu["offset"]);
+            print!("{}", tree(&title, pdu));
+        }
+    }
+    Ok(complete)
+}
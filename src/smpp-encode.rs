@@ -0,0 +1,940 @@
+//! # smpp-encode
+//!
+//! Builds PDUs from a JSON or TOML description and writes them as hex or
+//! binary, or sends them to an SMSC and prints what comes back.
+//!
+//! Usage: `smpp-encode [--json] [--binary] [--send HOST:PORT] [FILE]`; see
+//! `smpp-encode --help`. The description uses the layout printed by
+//! `smpp-decode --json`, so a decoded capture can be edited and replayed.
+//! `examples/smpp-encode.toml` shows every kind of entry.
+use pdu_tree::{describe, hex, parse_hex, tree, TEXT_TLVS};
+use serde_json::{Map, Value};
+use smpp_codec::encoding::{encode_16bit, encode_8bit, gsm_7bit_encode};
+use smpp_codec_ffi::address::Address;
+use smpp_codec_ffi::ancillary_pdu_ffi::*;
+use smpp_codec_ffi::broadcast_pdu_ffi::*;
+use smpp_codec_ffi::common::*;
+use smpp_codec_ffi::delivery_pdu_ffi::*;
+use smpp_codec_ffi::framer::read_frame;
+use smpp_codec_ffi::pdu::{decode_pdu_header, encode_pdu, get_command_name, Pdu};
+use smpp_codec_ffi::session_pdu_ffi::*;
+use smpp_codec_ffi::submission_pdu_ffi::*;
+use smpp_codec_ffi::tlv::{get_tag_by_name, get_tag_value_length, Tags, Tlv};
+use std::io::{ErrorKind, Read, Write};
+use std::net::TcpStream;
+use std::process::ExitCode;
+use std::time::Duration;
+
+#[path = "cli/pdu_tree.rs"]
+mod pdu_tree;
+
+const USAGE: &str =
+    "Usage: smpp-encode [--json] [--binary] [--send HOST:PORT] [--timeout-ms N] [FILE]
+
+Encodes the PDUs described in FILE, or stdin if FILE is missing or -.
+The description is JSON, or TOML with one [[pdu]] table per PDU.
+
+Options:
+  --binary            write raw bytes instead of one hex line per PDU
+  --send HOST:PORT    send the PDUs, waiting for the response to each request,
+                      and print every PDU received
+  --json              print received PDUs as a JSON array
+  --timeout-ms N      time to wait for each response [5000]";
+
+const TONS: [Ton; 7] = [
+    Ton::Unknown,
+    Ton::International,
+    Ton::National,
+    Ton::NetworkSpecific,
+    Ton::SubscriberNumber,
+    Ton::Alphanumeric,
+    Ton::Abbreviated,
+];
+
+const NPIS: [Npi; 10] = [
+    Npi::Unknown,
+    Npi::Isdn,
+    Npi::Data,
+    Npi::Telex,
+    Npi::LandMobile,
+    Npi::National,
+    Npi::Private,
+    Npi::Ermes,
+    Npi::Internet,
+    Npi::Wap,
+];
+
+/// Header fields of `smpp-decode` output that are derived when encoding.
+const DERIVED_HEADER_FIELDS: [&str; 3] = ["command_length", "command_id", "status"];
+
+struct Options {
+    path: Option<String>,
+    binary: bool,
+    json: bool,
+    send: Option<String>,
+    timeout_ms: u64,
+}
+
+fn main() -> ExitCode {
+    let mut options = Options {
+        path: None,
+        binary: false,
+        json: false,
+        send: None,
+        timeout_ms: 5000,
+    };
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "-h" | "--help" => {
+                println!("{}", USAGE);
+                return ExitCode::SUCCESS;
+            }
+            "--binary" => options.binary = true,
+            "--json" => options.json = true,
+            "--send" => options.send = args.next(),
+            "--timeout-ms" => match args.next().and_then(|value| value.parse().ok()) {
+                Some(timeout_ms) => options.timeout_ms = timeout_ms,
+                None => {
+                    eprintln!("{}", USAGE);
+                    return ExitCode::from(2);
+                }
+            },
+            _ if options.path.is_none() && (arg == "-" || !arg.starts_with('-')) => {
+                options.path = Some(arg)
+            }
+            _ => {
+                eprintln!("{}", USAGE);
+                return ExitCode::from(2);
+            }
+        }
+    }
+    match run(&options) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("smpp-encode: {}", e);
+            ExitCode::FAILURE
+        }
+    }
+}
+
+fn run(options: &Options) -> Result<(), String> {
+    let mut input = String::new();
+    match options.path.as_deref() {
+        Some(path) if path != "-" => {
+            input = std::fs::read_to_string(path).map_err(|e| format!("{}: {}", path, e))?;
+        }
+        _ => {
+            std::io::stdin()
+                .read_to_string(&mut input)
+                .map_err(|e| e.to_string())?;
+        }
+    }
+    let toml_path = options
+        .path
+        .as_deref()
+        .is_some_and(|path| path.ends_with(".toml"));
+    let description: Value = match serde_json::from_str(&input) {
+        Ok(description) if !toml_path => description,
+        _ => toml::from_str(&input).map_err(|e| format!("Neither JSON nor TOML: {}", e))?,
+    };
+    let entries = match description {
+        Value::Array(entries) => entries,
+        Value::Object(mut entry) => match entry.remove("pdu") {
+            Some(Value::Array(entries)) => entries,
+            Some(single) => vec![single],
+            None => vec![Value::Object(entry)],
+        },
+        _ => return Err("Expected a PDU or a list of PDUs".to_string()),
+    };
+    let mut frames = Vec::new();
+    for (index, entry) in entries.into_iter().enumerate() {
+        let frame = encode_entry(entry).map_err(|e| format!("PDU {}: {}", index + 1, e))?;
+        frames.push(frame);
+    }
+
+    match &options.send {
+        Some(address) => send(address, &frames, options),
+        None if options.binary => std::io::stdout()
+            .write_all(&frames.concat())
+            .map_err(|e| e.to_string()),
+        None => {
+            for frame in &frames {
+                println!("{}", hex(frame));
+            }
+            Ok(())
+        }
+    }
+}
+
+/// Sends every frame, waiting for the response to each request.
+fn send(address: &str, frames: &[Vec<u8>], options: &Options) -> Result<(), String> {
+    let mut stream = TcpStream::connect(address).map_err(|e| format!("{}: {}", address, e))?;
+    stream
+        .set_read_timeout(Some(Duration::from_millis(options.timeout_ms.max(1))))
+        .map_err(|e| e.to_string())?;
+    let mut received = Vec::new();
+    let mut offset = 0;
+    let mut result = Ok(());
+    for frame in frames {
+        stream.write_all(frame).map_err(|e| e.to_string())?;
+        let Ok(header) = decode_pdu_header(frame) else {
+            continue;
+        };
+        let command = get_command_name(header.command_id).unwrap_or("unknown");
+        if !options.json {
+            println!(
+                "Sent {}, sequence {}, {} octets",
+                command,
+                header.sequence_number,
+                frame.len()
+            );
+        }
+        if header.command_id & 0x8000_0000 != 0 {
+            continue;
+        }
+        // Print everything up to the response to this request.
+        loop {
+            let response = match read_frame(&mut stream) {
+                Ok(response) => response,
+                Err(e) => {
+                    let reason = match e.kind() {
+                        ErrorKind::WouldBlock | ErrorKind::TimedOut => {
+                            format!("no response within {} ms", options.timeout_ms)
+                        }
+                        ErrorKind::UnexpectedEof => "connection closed".to_string(),
+                        _ => e.to_string(),
+                    };
+                    result = Err(format!(
+                        "{} (sequence {}): {}",
+                        command, header.sequence_number, reason
+                    ));
+                    break;
+                }
+            };
+            let pdu = describe(offset, &response);
+            offset += response.len();
+            if !options.json {
+                println!("{}", tree("Received", &pdu));
+            }
+            received.push(pdu);
+            let response_header = decode_pdu_header(&response).unwrap();
+            if response_header.sequence_number == header.sequence_number
+                && (response_header.command_id == header.command_id | 0x8000_0000
+                    || response_header.command_id == GENERIC_NACK)
+            {
+                break;
+            }
+        }
+        if result.is_err() {
+            break;
+        }
+    }
+    if options.json {
+        println!("{}", serde_json::to_string_pretty(&received).unwrap());
+    }
+    result
+}
+
+/// Encodes one entry: `{ hex }`, or `{ header, body, override }`.
+fn encode_entry(entry: Value) -> Result<Vec<u8>, String> {
+    let Value::Object(mut entry) = entry else {
+        return Err("Expected a table".to_string());
+    };
+    if let Some(raw) = entry.remove("hex") {
+        return parse_hex(raw.as_str().unwrap_or_default().as_bytes());
+    }
+    let mut header = Fields::take(&mut entry, "header")?;
+    let mut body = Fields::take(&mut entry, "body")?;
+    let mut overrides = Fields::take(&mut entry, "override")?;
+    // Everything else in the output of smpp-decode is derived from the body.
+    for key in ["offset", "error"] {
+        entry.remove(key);
+    }
+    if let Some(key) = entry.keys().next() {
+        return Err(format!("Unknown field '{}'", key));
+    }
+
+    let command = header.string("command")?;
+    let sequence_number = match header.0.contains_key("sequence_number") {
+        true => header.int("sequence_number")?,
+        false => 1,
+    };
+    let command_status = header.int("command_status")?;
+    for key in DERIVED_HEADER_FIELDS {
+        header.0.remove(key);
+    }
+    header.finish("header")?;
+
+    let pdu = build_pdu(&command, sequence_number, command_status, &mut body)?;
+    body.finish("body")?;
+    let mut frame = encode_pdu(&pdu);
+    frame[8..12].copy_from_slice(&command_status.to_be_bytes());
+    if overrides.0.contains_key("command_length") {
+        frame[0..4].copy_from_slice(&overrides.int::<u32>("command_length")?.to_be_bytes());
+    }
+    if overrides.0.contains_key("command_id") {
+        frame[4..8].copy_from_slice(&overrides.int::<u32>("command_id")?.to_be_bytes());
+    }
+    overrides.finish("override")?;
+    Ok(frame)
+}
+
+fn build_pdu(
+    command: &str,
+    sequence_number: u32,
+    command_status: u32,
+    body: &mut Fields,
+) -> Result<Pdu, String> {
+    let pdu = match command {
+        "bind_receiver" | "bind_transmitter" | "bind_transceiver" => {
+            let mode = match command {
+                "bind_receiver" => BindMode::Receiver,
+                "bind_transmitter" => BindMode::Transmitter,
+                _ => BindMode::Transceiver,
+            };
+            let (addr_ton, addr_npi, address_range) = body.address("addr")?;
+            Pdu::BindRequest {
+                pdu: BindRequest {
+                    sequence_number,
+                    mode,
+                    system_id: body.string("system_id")?,
+                    password: body.string("password")?,
+                    system_type: body.string("system_type")?,
+                    interface_version: match body.0.contains_key("interface_version") {
+                        true => body.int("interface_version")?,
+                        false => SMPP_INTERFACE_VERSION_34,
+                    },
+                    addr_ton,
+                    addr_npi,
+                    address_range,
+                },
+            }
+        }
+        "bind_receiver_resp" | "bind_transmitter_resp" | "bind_transceiver_resp" => {
+            Pdu::BindResponse {
+                pdu: BindResponse {
+                    sequence_number,
+                    command_status,
+                    system_id: body.string("system_id")?,
+                },
+            }
+        }
+        "outbind" => Pdu::Outbind {
+            pdu: Outbind {
+                sequence_number,
+                system_id: body.string("system_id")?,
+                password: body.string("password")?,
+            },
+        },
+        "enquire_link" => Pdu::EnquireLink {
+            pdu: EnquireLink { sequence_number },
+        },
+        "enquire_link_resp" => Pdu::EnquireLinkResponse {
+            pdu: EnquireLinkResponse {
+                sequence_number,
+                command_status,
+            },
+        },
+        "unbind" => Pdu::Unbind {
+            pdu: Unbind { sequence_number },
+        },
+        "unbind_resp" => Pdu::UnbindResponse {
+            pdu: UnbindResponse {
+                sequence_number,
+                command_status,
+            },
+        },
+        "generic_nack" => Pdu::GenericNack {
+            pdu: GenericNack {
+                sequence_number,
+                command_status,
+            },
+        },
+        "submit_sm" => {
+            let service_type = body.string("service_type")?;
+            let (source_addr_ton, source_addr_npi, source_addr) = body.address("source")?;
+            let (dest_addr_ton, dest_addr_npi, destination_addr) = body.address("destination")?;
+            let esm_class = body.int("esm_class")?;
+            let data_coding = body.int("data_coding")?;
+            Pdu::SubmitSm {
+                pdu: SubmitSmRequest {
+                    sequence_number,
+                    service_type,
+                    source_addr_ton,
+                    source_addr_npi,
+                    source_addr,
+                    dest_addr_ton,
+                    dest_addr_npi,
+                    destination_addr,
+                    esm_class,
+                    protocol_id: body.int("protocol_id")?,
+                    priority_flag: body.int("priority_flag")?,
+                    schedule_delivery_time: body.time("schedule_delivery_time")?,
+                    validity_period: body.time("validity_period")?,
+                    registered_delivery: body.int("registered_delivery")?,
+                    replace_if_present_flag: body.int("replace_if_present_flag")?,
+                    data_coding,
+                    sm_default_msg_id: body.int("sm_default_msg_id")?,
+                    short_message: body.message("short_message", data_coding)?,
+                    tlvs: body.tlvs(data_coding)?,
+                },
+            }
+        }
+        "submit_sm_resp" => Pdu::SubmitSmResponse {
+            pdu: SubmitSmResponse {
+                sequence_number,
+                command_status,
+                message_id: body.string("message_id")?,
+                status_description: String::new(),
+            },
+        },
+        "submit_multi" => {
+            let service_type = body.string("service_type")?;
+            let (source_addr_ton, source_addr_npi, source_addr) = body.address("source")?;
+            let destinations = body.destinations()?;
+            let esm_class = body.int("esm_class")?;
+            let data_coding = body.int("data_coding")?;
+            Pdu::SubmitMulti {
+                pdu: SubmitMultiRequest {
+                    sequence_number,
+                    service_type,
+                    source_addr_ton,
+                    source_addr_npi,
+                    source_addr,
+                    destinations,
+                    esm_class,
+                    protocol_id: body.int("protocol_id")?,
+                    priority_flag: body.int("priority_flag")?,
+                    schedule_delivery_time: body.time("schedule_delivery_time")?,
+                    validity_period: body.time("validity_period")?,
+                    registered_delivery: body.int("registered_delivery")?,
+                    replace_if_present_flag: body.int("replace_if_present_flag")?,
+                    data_coding,
+                    sm_default_msg_id: body.int("sm_default_msg_id")?,
+                    short_message: body.message("short_message", data_coding)?,
+                    tlvs: body.tlvs(data_coding)?,
+                },
+            }
+        }
+        "submit_multi_resp" => Pdu::SubmitMultiResponse {
+            pdu: SubmitMultiResponse {
+                sequence_number,
+                command_status,
+                message_id: body.string("message_id")?,
+                status_description: String::new(),
+                unsuccess_smes: body.unsuccess_smes()?,
+            },
+        },
+        "deliver_sm" => {
+            let service_type = body.string("service_type")?;
+            let (source_addr_ton, source_addr_npi, source_addr) = body.address("source")?;
+            let (dest_addr_ton, dest_addr_npi, dest_addr) = body.address("destination")?;
+            let esm_class = body.int("esm_class")?;
+            let data_coding = body.int("data_coding")?;
+            Pdu::DeliverSm {
+                pdu: DeliverSmRequest {
+                    sequence_number,
+                    service_type,
+                    source_addr_ton,
+                    source_addr_npi,
+                    source_addr,
+                    dest_addr_ton,
+                    dest_addr_npi,
+                    dest_addr,
+                    esm_class,
+                    protocol_id: body.int("protocol_id")?,
+                    priority_flag: body.int("priority_flag")?,
+                    schedule_delivery_time: body.time("schedule_delivery_time")?,
+                    validity_period: body.time("validity_period")?,
+                    registered_delivery: body.int("registered_delivery")?,
+                    replace_if_present_flag: body.int("replace_if_present_flag")?,
+                    data_coding,
+                    sm_default_msg_id: body.int("sm_default_msg_id")?,
+                    short_message: body.message("short_message", data_coding)?,
+                    optional_params: body.tlvs(data_coding)?,
+                },
+            }
+        }
+        "deliver_sm_resp" => Pdu::DeliverSmResponse {
+            pdu: DeliverSmResponse {
+                sequence_number,
+                command_status,
+                message_id: body.string("message_id")?,
+                status_description: String::new(),
+            },
+        },
+        "data_sm" => {
+            let service_type = body.string("service_type")?;
+            let (source_addr_ton, source_addr_npi, source_addr) = body.address("source")?;
+            let (dest_addr_ton, dest_addr_npi, dest_addr) = body.address("destination")?;
+            let data_coding = body.int("data_coding")?;
+            Pdu::DataSm {
+                pdu: DataSm {
+                    sequence_number,
+                    service_type,
+                    source_addr_ton,
+                    source_addr_npi,
+                    source_addr,
+                    dest_addr_ton,
+                    dest_addr_npi,
+                    dest_addr,
+                    esm_class: body.int("esm_class")?,
+                    registered_delivery: body.int("registered_delivery")?,
+                    data_coding,
+                    optional_params: body.tlvs(data_coding)?,
+                },
+            }
+        }
+        "data_sm_resp" => Pdu::DataSmResponse {
+            pdu: DataSmResponse {
+                sequence_number,
+                command_status,
+                message_id: body.string("message_id")?,
+                status_description: String::new(),
+                optional_params: body.tlvs(0)?,
+            },
+        },
+        "query_sm" => {
+            let message_id = body.string("message_id")?;
+            let (source_addr_ton, source_addr_npi, source_addr) = body.address("source")?;
+            Pdu::QuerySm {
+                pdu: QuerySmRequest {
+                    sequence_number,
+                    message_id,
+                    source_addr_ton,
+                    source_addr_npi,
+                    source_addr,
+                },
+            }
+        }
+        "query_sm_resp" => Pdu::QuerySmResponse {
+            pdu: QuerySmResponse {
+                sequence_number,
+                command_status,
+                message_id: body.string("message_id")?,
+                final_date: body.string("final_date")?,
+                message_state: body.int("message_state")?,
+                error_code: body.int("error_code")?,
+                status_description: String::new(),
+            },
+        },
+        "cancel_sm" => {
+            let service_type = body.string("service_type")?;
+            let message_id = body.string("message_id")?;
+            let (source_addr_ton, source_addr_npi, source_addr) = body.address("source")?;
+            let (dest_addr_ton, dest_addr_npi, dest_addr) = body.address("destination")?;
+            Pdu::CancelSm {
+                pdu: CancelSmRequest {
+                    sequence_number,
+                    service_type,
+                    message_id,
+                    source_addr_ton,
+                    source_addr_npi,
+                    source_addr,
+                    dest_addr_ton,
+                    dest_addr_npi,
+                    dest_addr,
+                },
+            }
+        }
+        "cancel_sm_resp" => Pdu::CancelSmResponse {
+            pdu: CancelSmResponse {
+                sequence_number,
+                command_status,
+                status_description: String::new(),
+            },
+        },
+        "replace_sm" => {
+            let message_id = body.string("message_id")?;
+            let (source_addr_ton, source_addr_npi, source_addr) = body.address("source")?;
+            Pdu::ReplaceSm {
+                pdu: ReplaceSmRequest {
+                    sequence_number,
+                    message_id,
+                    source_addr_ton,
+                    source_addr_npi,
+                    source_addr,
+                    schedule_delivery_time: body.string("schedule_delivery_time")?,
+                    validity_period: body.string("validity_period")?,
+                    registered_delivery: body.int("registered_delivery")?,
+                    sm_default_msg_id: body.int("sm_default_msg_id")?,
+                    // replace_sm keeps the data_coding of the original message.
+                    short_message: body.message("short_message", 0)?,
+                },
+            }
+        }
+        "replace_sm_resp" => Pdu::ReplaceSmResponse {
+            pdu: ReplaceSmResponse {
+                sequence_number,
+                command_status,
+                status_description: String::new(),
+            },
+        },
+        "broadcast_sm" => {
+            let service_type = body.string("service_type")?;
+            let (source_addr_ton, source_addr_npi, source_addr) = body.address("source")?;
+            let data_coding = body.int("data_coding")?;
+            Pdu::BroadcastSm {
+                pdu: BroadcastSmRequest {
+                    sequence_number,
+                    service_type,
+                    source_addr_ton,
+                    source_addr_npi,
+                    source_addr,
+                    message_id: body.string("message_id")?,
+                    priority_flag: body.int("priority_flag")?,
+                    schedule_delivery_time: body.time("schedule_delivery_time")?,
+                    validity_period: body.time("validity_period")?,
+                    replace_if_present_flag: body.int("replace_if_present_flag")?,
+                    data_coding,
+                    sm_default_msg_id: body.int("sm_default_msg_id")?,
+                    optional_params: body.tlvs(data_coding)?,
+                },
+            }
+        }
+        "broadcast_sm_resp" => Pdu::BroadcastSmResponse {
+            pdu: BroadcastSmResponse {
+                sequence_number,
+                command_status,
+                status_description: String::new(),
+                message_id: body.string("message_id")?,
+                optional_params: body.tlvs(0)?,
+            },
+        },
+        "query_broadcast_sm" => {
+            let message_id = body.string("message_id")?;
+            let (source_addr_ton, source_addr_npi, source_addr) = body.address("source")?;
+            Pdu::QueryBroadcastSm {
+                pdu: QueryBroadcastSmRequest {
+                    sequence_number,
+                    message_id,
+                    source_addr_ton,
+                    source_addr_npi,
+                    source_addr,
+                    optional_params: body.tlvs(0)?,
+                },
+            }
+        }
+        "query_broadcast_sm_resp" => Pdu::QueryBroadcastSmResponse {
+            pdu: QueryBroadcastSmResponse {
+                sequence_number,
+                command_status,
+                status_description: String::new(),
+                message_id: body.string("message_id")?,
+                optional_params: body.tlvs(0)?,
+            },
+        },
+        "cancel_broadcast_sm" => {
+            let service_type = body.string("service_type")?;
+            let message_id = body.string("message_id")?;
+            let (source_addr_ton, source_addr_npi, source_addr) = body.address("source")?;
+            Pdu::CancelBroadcastSm {
+                pdu: CancelBroadcastSmRequest {
+                    sequence_number,
+                    service_type,
+                    message_id,
+                    source_addr_ton,
+                    source_addr_npi,
+                    source_addr,
+                    optional_params: body.tlvs(0)?,
+                },
+            }
+        }
+        "cancel_broadcast_sm_resp" => Pdu::CancelBroadcastSmResponse {
+            pdu: CancelBroadcastSmResponse {
+                sequence_number,
+                command_status,
+                status_description: String::new(),
+            },
+        },
+        _ => return Err(format!("Unsupported command '{}'", command)),
+    };
+    Ok(pdu)
+}
+
+/// Fields of one table, removed as they are read so that leftovers can be reported.
+struct Fields(Map<String, Value>);
+
+impl Fields {
+    /// Takes the table `name` out of `entry`; a missing table is empty.
+    fn take(entry: &mut Map<String, Value>, name: &str) -> Result<Self, String> {
+        match entry.remove(name) {
+            None => Ok(Self(Map::new())),
+            Some(Value::Object(fields)) => Ok(Self(fields)),
+            Some(_) => Err(format!("'{}' must be a table", name)),
+        }
+    }
+
+    /// Fails on fields that were never read.
+    fn finish(self, table: &str) -> Result<(), String> {
+        match self.0.keys().next() {
+            Some(key) => Err(format!("Unknown field '{}' in {}", key, table)),
+            None => Ok(()),
+        }
+    }
+
+    /// A string; missing is empty.
+    fn string(&mut self, name: &str) -> Result<String, String> {
+        match self.0.remove(name) {
+            None => Ok(String::new()),
+            Some(Value::String(value)) => Ok(value),
+            Some(_) => Err(format!("'{}' must be a string", name)),
+        }
+    }
+
+    /// An optional time; missing or empty is `None`.
+    fn time(&mut self, name: &str) -> Result<Option<String>, String> {
+        let value = self.string(name)?;
+        Ok(Some(value).filter(|value| !value.is_empty()))
+    }
+
+    /// An integer; missing is 0.
+    fn int<T: TryFrom<u64>>(&mut self, name: &str) -> Result<T, String> {
+        let value = self.0.remove(name).unwrap_or(Value::from(0));
+        let number = int_value(&value).ok_or_else(|| format!("'{}' must be a number", name))?;
+        T::try_from(number).map_err(|_| format!("'{}' is out of range: {}", name, number))
+    }
+
+    /// A `{ ton, npi, address }` table; missing is an empty address.
+    fn address(&mut self, name: &str) -> Result<(Ton, Npi, String), String> {
+        match self.0.remove(name) {
+            None => Ok((Ton::Unknown, Npi::Unknown, String::new())),
+            Some(Value::Object(fields)) => {
+                let mut fields = Self(fields);
+                let ton = fields.enumerated("ton", &TONS, |ton| ton as u8)?;
+                let npi = fields.enumerated("npi", &NPIS, |npi| npi as u8)?;
+                let address = fields.string("address")?;
+                fields.finish(name)?;
+                Ok((ton, npi, address))
+            }
+            Some(_) => Err(format!("'{}' must be a table", name)),
+        }
+    }
+
+    /// A `Ton` or `Npi` given by name or value; missing is `Unknown`.
+    fn enumerated<T: Copy + std::fmt::Debug>(
+        &mut self,
+        name: &str,
+        values: &[T],
+        value_of: fn(T) -> u8,
+    ) -> Result<T, String> {
+        let value = self.0.remove(name).unwrap_or(Value::from(0));
+        values
+            .iter()
+            .copied()
+            .find(|candidate| match &value {
+                Value::String(text) if int_value(&value).is_none() => {
+                    format!("{:?}", candidate).eq_ignore_ascii_case(text)
+                }
+                _ => int_value(&value) == Some(u64::from(value_of(*candidate))),
+            })
+            .ok_or_else(|| format!("Unknown {} {}", name, value))
+    }
+
+    fn destinations(&mut self) -> Result<Vec<Destination>, String> {
+        let mut destinations = Vec::new();
+        for item in self.list("destinations")? {
+            let mut item = Fields::wrap(item, "destinations")?;
+            let destination = match item.0.contains_key("distribution_list") {
+                true => Destination::DistributionList {
+                    name: item.string("distribution_list")?,
+                },
+                false => {
+                    let ton = item.enumerated("ton", &TONS, |ton| ton as u8)?;
+                    let npi = item.enumerated("npi", &NPIS, |npi| npi as u8)?;
+                    let value = item.string("address")?;
+                    Destination::SmeAddress {
+                        address: Address { ton, npi, value },
+                    }
+                }
+            };
+            item.finish("destinations")?;
+            destinations.push(destination);
+        }
+        Ok(destinations)
+    }
+
+    fn unsuccess_smes(&mut self) -> Result<Vec<UnsuccessfulDelivery>, String> {
+        let mut smes = Vec::new();
+        for item in self.list("unsuccess_smes")? {
+            let mut item = Fields::wrap(item, "unsuccess_smes")?;
+            smes.push(UnsuccessfulDelivery {
+                ton: item.enumerated("ton", &TONS, |ton| ton as u8)?,
+                npi: item.enumerated("npi", &NPIS, |npi| npi as u8)?,
+                address: item.string("address")?,
+                error_status: item.int("error_status")?,
+            });
+            item.finish("unsuccess_smes")?;
+        }
+        Ok(smes)
+    }
+
+    fn list(&mut self, name: &str) -> Result<Vec<Value>, String> {
+        match self.0.remove(name) {
+            None => Ok(Vec::new()),
+            Some(Value::Array(items)) => Ok(items),
+            Some(_) => Err(format!("'{}' must be a list", name)),
+        }
+    }
+
+    fn wrap(value: Value, list: &str) -> Result<Self, String> {
+        match value {
+            Value::Object(fields) => Ok(Self(fields)),
+            _ => Err(format!("Items of '{}' must be tables", list)),
+        }
+    }
+
+    /// Message content: text, or a `{ hex }` or `{ text, udh }` table.
+    fn message(&mut self, name: &str, data_coding: u8) -> Result<Vec<u8>, String> {
+        match self.0.remove(name) {
+            None => Ok(Vec::new()),
+            Some(value) => content(value, data_coding).map_err(|e| format!("{}: {}", name, e)),
+        }
+    }
+
+    /// The `tlvs` list: `{ tag or name, value, override_length }` tables.
+    fn tlvs(&mut self, data_coding: u8) -> Result<Vec<Tlv>, String> {
+        let mut tlvs = Vec::new();
+        for item in self.list("tlvs")? {
+            let mut item = Fields::wrap(item, "tlvs")?;
+            let tlv = item.tlv(data_coding)?;
+            item.finish("tlvs")?;
+            tlvs.push(tlv);
+        }
+        Ok(tlvs)
+    }
+
+    fn tlv(&mut self, data_coding: u8) -> Result<Tlv, String> {
+        let name = self.string("name")?;
+        let tag = match self.0.contains_key("tag") {
+            true => self.int("tag")?,
+            false => match get_tag_by_name(&name) {
+                0 => return Err(format!("Unknown TLV '{}'", name)),
+                tag => tag,
+            },
+        };
+        // The length written by smpp-decode is derived from the value.
+        self.0.remove("length");
+        let value = self.0.remove("value").unwrap_or(Value::from(""));
+        let value =
+            tlv_value(tag, value, data_coding).map_err(|e| format!("TLV 0x{:04X}: {}", tag, e))?;
+        let length = match self.0.contains_key("override_length") {
+            true => self.int("override_length")?,
+            false => {
+                u16::try_from(value.len()).map_err(|_| format!("TLV 0x{:04X} is too long", tag))?
+            }
+        };
+        Ok(Tlv { tag, length, value })
+    }
+}
+
+/// A number, or a string starting with a decimal or `0x` number, as `smpp-decode` writes it.
+fn int_value(value: &Value) -> Option<u64> {
+    match value {
+        Value::Number(number) => number.as_u64(),
+        Value::String(text) => {
+            let token = text.split_whitespace().next()?.trim_end_matches(',');
+            match token
+                .strip_prefix("0x")
+                .or_else(|| token.strip_prefix("0X"))
+            {
+                Some(digits) => u64::from_str_radix(digits, 16).ok(),
+                None => token.parse().ok(),
+            }
+        }
+        _ => None,
+    }
+}
+
+/// Parses the hex of a field value, where an empty string is zero octets.
+fn field_hex(text: &str) -> Result<Vec<u8>, String> {
+    if text.trim().is_empty() {
+        return Ok(Vec::new());
+    }
+    parse_hex(text.as_bytes())
+}
+
+/// Encodes message content given as text, or a `{ hex }` or `{ text, udh }` table.
+fn content(value: Value, data_coding: u8) -> Result<Vec<u8>, String> {
+    match value {
+        Value::String(text) => encode_text(&text, data_coding),
+        Value::Object(fields) => {
+            let mut fields = Fields(fields);
+            let bytes = match fields.0.remove("hex") {
+                Some(raw) => field_hex(raw.as_str().unwrap_or_default())?,
+                None => {
+                    let mut bytes = match fields.0.remove("udh") {
+                        Some(Value::String(udh)) => field_hex(&udh)?,
+                        Some(_) => return Err("'udh' must be hex when 'hex' is missing".into()),
+                        None => Vec::new(),
+                    };
+                    bytes.extend(encode_text(&fields.string("text")?, data_coding)?);
+                    bytes
+                }
+            };
+            // The rest of the output of smpp-decode is derived from the hex.
+            for key in ["length", "udh", "text"] {
+                fields.0.remove(key);
+            }
+            fields.finish("message")?;
+            Ok(bytes)
+        }
+        _ => Err("must be text or a table".to_string()),
+    }
+}
+
+fn encode_text(text: &str, data_coding: u8) -> Result<Vec<u8>, String> {
+    match data_coding {
+        0x00 => gsm_7bit_encode(text),
+        0x01 | 0x03 => Ok(encode_8bit(text)),
+        0x08 => Ok(encode_16bit(text)),
+        _ => Err(format!(
+            "text needs data_coding 0, 1, 3 or 8, not {}; give hex instead",
+            data_coding
+        )),
+    }
+}
+
+/// Encodes a TLV value: a number, text, hex or message content, depending on the tag.
+fn tlv_value(tag: u16, value: Value, data_coding: u8) -> Result<Vec<u8>, String> {
+    let fixed = match get_tag_value_length(tag) {
+        Some((min, max)) if min == max && matches!(min, 1 | 2 | 4) => Some(min),
+        _ => None,
+    };
+    match (tag, value) {
+        (Tags::MESSAGE_PAYLOAD, value) => content(value, data_coding),
+        (tag, Value::String(text)) if TEXT_TLVS.contains(&tag) => {
+            let mut bytes = text.into_bytes();
+            bytes.push(0);
+            Ok(bytes)
+        }
+        (Tags::NETWORK_ERROR_CODE, Value::String(text)) if text.starts_with("type ") => {
+            // "type 3, error 1", as written by smpp-decode.
+            let numbers: Vec<u64> = text
+                .split(|c: char| !c.is_ascii_digit())
+                .filter_map(|part| part.parse().ok())
+                .collect();
+            match numbers[..] {
+                [network_type, error] if network_type <= 0xFF && error <= 0xFFFF => {
+                    let [hi, lo] = (error as u16).to_be_bytes();
+                    Ok(vec![network_type as u8, hi, lo])
+                }
+                _ => Err(format!("Invalid network_error_code '{}'", text)),
+            }
+        }
+        (_, value) => match (fixed, int_value(&value)) {
+            (Some(width), Some(number)) if width == 8 || number >> (width * 8) == 0 => {
+                Ok(number.to_be_bytes()[8 - width..].to_vec())
+            }
+            (Some(width), Some(number)) => {
+                Err(format!("{} does not fit in {} octets", number, width))
+            }
+            _ => match value {
+                Value::String(text) => field_hex(&text),
+                Value::Number(_) => Err("a number needs a fixed-length tag; give hex".into()),
+                _ => Err("must be a number, text or hex".to_string()),
+            },
+        },
+    }
+}
@@ -0,0 +1,492 @@
+//! # smpp-sim
+//!
+//! A local SMSC for integration tests, built on `SmscListener`. It answers
+//! submits with generated message IDs, sends delivery receipts after a
+//! delay, injects mobile-originated `deliver_sm` and, on request, misbehaves:
+//! throttling, `generic_nack`, dropped responses and disconnects.
+//!
+//! Usage: `smpp-sim [SCENARIO.toml]`. Without a scenario the simulator
+//! listens on 127.0.0.1:2775, accepts any bind and delivers every message
+//! after one second. The first line on stdout is `listening on HOST:PORT`.
+use serde::Deserialize;
+use smpp_codec_ffi::ancillary_pdu_ffi::MessageState;
+use smpp_codec_ffi::bind_auth::{bind_auth_config_default, esme_account_new, AccountAuthenticator};
+use smpp_codec_ffi::common::*;
+use smpp_codec_ffi::delivery_pdu_ffi::*;
+use smpp_codec_ffi::message_store::*;
+use smpp_codec_ffi::pdu::Pdu;
+use smpp_codec_ffi::session_pdu_ffi::{BindRequest, GenericNack};
+use smpp_codec_ffi::smsc_listener::*;
+use smpp_codec_ffi::submission_pdu_ffi::SubmitMultiResponse;
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashMap};
+use std::io::Write;
+use std::process::ExitCode;
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError, Sender};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+/// The scenario file. Every section and field is optional.
+#[derive(Deserialize, Default)]
+#[serde(default, deny_unknown_fields)]
+struct Scenario {
+    /// Seed of the fault and receipt dice; the clock is used if unset.
+    seed: Option<u64>,
+    listener: ListenerSection,
+    /// Accepted accounts. Without any, every bind is accepted.
+    accounts: Vec<AccountSection>,
+    messages: MessagesSection,
+    receipts: ReceiptsSection,
+    faults: FaultsSection,
+    /// Mobile-originated messages to inject.
+    mo: Vec<MoSection>,
+}
+
+#[derive(Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct ListenerSection {
+    host: String,
+    port: u16,
+    system_id: String,
+}
+
+impl Default for ListenerSection {
+    fn default() -> Self {
+        Self {
+            host: "127.0.0.1".to_string(),
+            port: 2775,
+            system_id: "SMPPSIM".to_string(),
+        }
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct AccountSection {
+    system_id: String,
+    password: String,
+}
+
+#[derive(Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct MessagesSection {
+    /// Prefix of generated message IDs.
+    id_prefix: String,
+    /// Append-only log to keep messages in; memory if unset.
+    store: Option<String>,
+}
+
+impl Default for MessagesSection {
+    fn default() -> Self {
+        Self {
+            id_prefix: "SIM".to_string(),
+            store: None,
+        }
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct ReceiptsSection {
+    /// Time from submit to the final state.
+    delay_ms: u64,
+    /// Share of messages that end `Delivered`; the rest end in `failure_state`.
+    success_ratio: f64,
+    failure_state: FailureState,
+    /// Network error code reported for failed messages.
+    failure_error_code: u16,
+}
+
+impl Default for ReceiptsSection {
+    fn default() -> Self {
+        Self {
+            delay_ms: 1000,
+            success_ratio: 1.0,
+            failure_state: FailureState::Undeliverable,
+            failure_error_code: 1,
+        }
+    }
+}
+
+#[derive(Deserialize, Clone, Copy)]
+#[serde(rename_all = "snake_case")]
+enum FailureState {
+    Undeliverable,
+    Expired,
+    Rejected,
+}
+
+impl From<FailureState> for MessageState {
+    fn from(state: FailureState) -> Self {
+        match state {
+            FailureState::Undeliverable => MessageState::Undeliverable,
+            FailureState::Expired => MessageState::Expired,
+            FailureState::Rejected => MessageState::Rejected,
+        }
+    }
+}
+
+/// Faults applied to requests after bind, other than `enquire_link` and `unbind`.
+///
+/// Each ratio is the probability of the fault per request, checked in the order of the fields.
+#[derive(Deserialize, Default)]
+#[serde(default, deny_unknown_fields)]
+struct FaultsSection {
+    /// Close the connection instead of answering.
+    disconnect_ratio: f64,
+    /// Never answer.
+    drop_response_ratio: f64,
+    /// Answer with `generic_nack` and `ESME_RSYSERR`.
+    generic_nack_ratio: f64,
+    /// Answer with `ESME_RTHROTTLED`.
+    throttle_ratio: f64,
+    /// Answer with `ESME_RTHROTTLED` above this many requests per second and bind; 0 for no limit.
+    max_tps: u32,
+}
+
+#[derive(Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct MoSection {
+    source: String,
+    destination: String,
+    text: String,
+    /// Time before the first and between further messages.
+    interval_ms: u64,
+    /// Number of messages to send; 0 sends until the simulator stops.
+    count: u64,
+}
+
+impl Default for MoSection {
+    fn default() -> Self {
+        Self {
+            source: "447700900000".to_string(),
+            destination: String::new(),
+            text: "Hello from smpp-sim".to_string(),
+            interval_ms: 1000,
+            count: 1,
+        }
+    }
+}
+
+fn main() -> ExitCode {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if args.iter().any(|arg| arg == "-h" || arg == "--help") || args.len() > 1 {
+        eprintln!("Usage: smpp-sim [SCENARIO.toml]");
+        return ExitCode::from(2);
+    }
+    match run(args.first()) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("smpp-sim: {}", e);
+            ExitCode::FAILURE
+        }
+    }
+}
+
+fn run(path: Option<&String>) -> Result<(), String> {
+    let scenario: Scenario = match path {
+        Some(path) => {
+            let contents = std::fs::read_to_string(path).map_err(|e| format!("{}: {}", path, e))?;
+            toml::from_str(&contents).map_err(|e| format!("{}: {}", path, e))?
+        }
+        None => Scenario::default(),
+    };
+    let (receipts, due) = mpsc::channel();
+    let sim = Sim::new(scenario, receipts).map_err(|e| e.to_string())?;
+    let settings = &sim.scenario.listener;
+    let config = SmscListenerConfig {
+        system_id: settings.system_id.clone(),
+        ..smsc_listener_config_new(settings.host.clone(), settings.port)
+    };
+    let listener =
+        SmscListener::start(config, Arc::new(Accounts::new(&sim.scenario)?), sim.clone())
+            .map_err(|e| e.to_string())?;
+    println!("listening on {}:{}", settings.host, listener.local_port());
+    let _ = std::io::stdout().flush();
+
+    {
+        let (sim, listener) = (sim.clone(), listener.clone());
+        thread::spawn(move || sim.send_receipts(&listener, due));
+    }
+    for index in 0..sim.scenario.mo.len() {
+        let (sim, listener) = (sim.clone(), listener.clone());
+        thread::spawn(move || sim.inject_mo(&listener, index));
+    }
+    loop {
+        thread::park();
+    }
+}
+
+/// Checks binds against the scenario accounts, or accepts every bind without any.
+struct Accounts(Option<Arc<AccountAuthenticator>>);
+
+impl Accounts {
+    fn new(scenario: &Scenario) -> Result<Self, String> {
+        if scenario.accounts.is_empty() {
+            return Ok(Self(None));
+        }
+        let accounts = scenario
+            .accounts
+            .iter()
+            .map(|a| esme_account_new(a.system_id.clone(), a.password.clone()))
+            .collect();
+        AccountAuthenticator::with_accounts(bind_auth_config_default(), accounts)
+            .map(|authenticator| Self(Some(authenticator)))
+            .map_err(|e| e.to_string())
+    }
+}
+
+impl BindAuthenticator for Accounts {
+    fn authenticate(&self, peer_addr: String, request: BindRequest) -> u32 {
+        let (mode, system_id) = (request.mode, request.system_id.clone());
+        let command_status = match &self.0 {
+            Some(authenticator) => authenticator.check(peer_addr.clone(), request),
+            None => COMMAND_STATUS_OK,
+        };
+        eprintln!(
+            "bind from {}: {:?} as '{}' -> 0x{:08X}",
+            peer_addr, mode, system_id, command_status
+        );
+        command_status
+    }
+}
+
+/// A receipt due at a point in time. Receipts order by due time.
+#[derive(PartialEq, Eq, PartialOrd, Ord)]
+struct DueReceipt {
+    due: Instant,
+    session_id: u64,
+    message_id: String,
+}
+
+/// Answers the requests of bound ESMEs, injecting the scenario faults.
+struct Sim {
+    scenario: Scenario,
+    ids: Arc<MessageIdGenerator>,
+    center: Arc<MessageCenter>,
+    receipts: Mutex<Sender<DueReceipt>>,
+    /// Start and request count of the current one-second throttling window of each bind.
+    windows: Mutex<HashMap<u64, (Instant, u32)>>,
+    dice: Mutex<u64>,
+}
+
+impl Sim {
+    fn new(scenario: Scenario, receipts: Sender<DueReceipt>) -> Result<Arc<Self>, SmppFfiError> {
+        let store = match &scenario.messages.store {
+            Some(path) => FileMessageStore::open(path.clone())?.as_message_store(),
+            None => InMemoryMessageStore::new().as_message_store(),
+        };
+        let ids = MessageIdGenerator::new(scenario.messages.id_prefix.clone())?;
+        let seed = scenario.seed.unwrap_or_else(|| {
+            SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map_or(1, |elapsed| elapsed.as_nanos() as u64)
+        });
+        Ok(Arc::new(Self {
+            scenario,
+            center: MessageCenter::new(store, ids.clone()),
+            ids,
+            receipts: Mutex::new(receipts),
+            windows: Mutex::new(HashMap::new()),
+            // xorshift state must not be zero.
+            dice: Mutex::new(seed | 1),
+        }))
+    }
+
+    /// Returns true with probability `ratio`.
+    fn roll(&self, ratio: f64) -> bool {
+        if ratio <= 0.0 {
+            return false;
+        }
+        let mut state = self.dice.lock().unwrap();
+        *state ^= *state << 13;
+        *state ^= *state >> 7;
+        *state ^= *state << 17;
+        ((*state >> 11) as f64 / (1u64 << 53) as f64) < ratio
+    }
+
+    /// Counts a request of a bind against `max_tps`. Returns true if it is over the limit.
+    fn over_max_tps(&self, session_id: u64) -> bool {
+        let max_tps = self.scenario.faults.max_tps;
+        if max_tps == 0 {
+            return false;
+        }
+        let mut windows = self.windows.lock().unwrap();
+        let (started, count) = windows
+            .entry(session_id)
+            .or_insert_with(|| (Instant::now(), 0));
+        if started.elapsed() >= Duration::from_secs(1) {
+            *started = Instant::now();
+            *count = 0;
+        }
+        *count += 1;
+        *count > max_tps
+    }
+
+    /// Finishes messages when their delay is up and sends the receipts.
+    fn send_receipts(self: Arc<Self>, listener: &SmscListener, due: Receiver<DueReceipt>) {
+        let mut pending: BinaryHeap<Reverse<DueReceipt>> = BinaryHeap::new();
+        loop {
+            let timeout = pending.peek().map_or(Duration::from_secs(3600), |next| {
+                next.0.due.saturating_duration_since(Instant::now())
+            });
+            match due.recv_timeout(timeout) {
+                Ok(receipt) => {
+                    pending.push(Reverse(receipt));
+                    continue;
+                }
+                Err(RecvTimeoutError::Timeout) => {}
+                Err(RecvTimeoutError::Disconnected) if pending.is_empty() => return,
+                Err(RecvTimeoutError::Disconnected) => {}
+            }
+            while pending
+                .peek()
+                .is_some_and(|next| next.0.due <= Instant::now())
+            {
+                let Reverse(receipt) = pending.pop().unwrap();
+                self.finish(listener, receipt);
+            }
+        }
+    }
+
+    fn finish(&self, listener: &SmscListener, receipt: DueReceipt) {
+        let settings = &self.scenario.receipts;
+        let (state, error_code) = if self.roll(settings.success_ratio) {
+            (MessageState::Delivered, 0)
+        } else {
+            (settings.failure_state.into(), settings.failure_error_code)
+        };
+        let Ok(Some(message)) = self.center.set_state(
+            receipt.message_id,
+            state,
+            error_code.min(u8::MAX as u16) as u8,
+        ) else {
+            return;
+        };
+        if let Ok(Some(pdu)) = build_delivery_receipt_pdu(message.clone(), state, error_code) {
+            let sent = receipt_session(listener, receipt.session_id, &message.system_id)
+                .is_some_and(|session_id| listener.deliver_to(session_id, pdu).is_ok());
+            if !sent {
+                eprintln!("no receiver bind for the receipt of {}", message.message_id);
+            }
+        }
+    }
+
+    /// Sends the mobile-originated messages of one `[[mo]]` entry.
+    fn inject_mo(self: Arc<Self>, listener: &SmscListener, index: usize) {
+        let mo = &self.scenario.mo[index];
+        let mut sent = 0;
+        while mo.count == 0 || sent < mo.count {
+            thread::sleep(Duration::from_millis(mo.interval_ms));
+            let pdu = DeliverSmRequest {
+                sequence_number: 0,
+                service_type: String::new(),
+                source_addr_ton: Ton::International,
+                source_addr_npi: Npi::Isdn,
+                source_addr: mo.source.clone(),
+                dest_addr_ton: Ton::Unknown,
+                dest_addr_npi: Npi::Unknown,
+                dest_addr: mo.destination.clone(),
+                esm_class: 0,
+                protocol_id: 0,
+                priority_flag: 0,
+                schedule_delivery_time: None,
+                validity_period: None,
+                registered_delivery: 0,
+                replace_if_present_flag: 0,
+                data_coding: 0,
+                sm_default_msg_id: 0,
+                short_message: mo.text.as_bytes().to_vec(),
+                optional_params: Vec::new(),
+            };
+            match listener.deliver(pdu) {
+                Ok(_) => sent += 1,
+                Err(_) => eprintln!(
+                    "no receiver bind covers MO destination '{}'",
+                    mo.destination
+                ),
+            }
+        }
+    }
+}
+
+impl SessionHandler for Sim {
+    fn on_request(&self, session: SmscSessionInfo, request: Pdu) -> SessionReply {
+        let faults = &self.scenario.faults;
+        if self.roll(faults.disconnect_ratio) {
+            return SessionReply::Disconnect;
+        }
+        if self.roll(faults.drop_response_ratio) {
+            return SessionReply::NoResponse;
+        }
+        if self.roll(faults.generic_nack_ratio) {
+            return Pdu::GenericNack {
+                pdu: GenericNack {
+                    sequence_number: 0,
+                    command_status: ESME_RSYSERR,
+                },
+            }
+            .into();
+        }
+        if self.roll(faults.throttle_ratio) || self.over_max_tps(session.session_id) {
+            return SessionReply::Reject {
+                command_status: ESME_RTHROTTLED,
+            };
+        }
+
+        let session_id = session.session_id;
+        let response = match request {
+            Pdu::DataSm { pdu } => Pdu::DataSmResponse {
+                pdu: DataSmResponse {
+                    sequence_number: pdu.sequence_number,
+                    command_status: COMMAND_STATUS_OK,
+                    message_id: self.ids.next_id(),
+                    status_description: String::new(),
+                    optional_params: Vec::new(),
+                },
+            },
+            Pdu::SubmitMulti { pdu } => Pdu::SubmitMultiResponse {
+                pdu: SubmitMultiResponse {
+                    sequence_number: pdu.sequence_number,
+                    command_status: COMMAND_STATUS_OK,
+                    message_id: self.ids.next_id(),
+                    status_description: String::new(),
+                    unsuccess_smes: Vec::new(),
+                },
+            },
+            request => match self.center.handle(session, request) {
+                Some(response) => response,
+                None => {
+                    return SessionReply::Reject {
+                        command_status: ESME_RINVCMDID,
+                    }
+                }
+            },
+        };
+        if let Pdu::SubmitSmResponse { pdu } = &response {
+            if pdu.command_status == COMMAND_STATUS_OK {
+                let _ = self.receipts.lock().unwrap().send(DueReceipt {
+                    due: Instant::now() + Duration::from_millis(self.scenario.receipts.delay_ms),
+                    session_id,
+                    message_id: pdu.message_id.clone(),
+                });
+            }
+        }
+        response.into()
+    }
+}
+
+/// Picks the submitting bind if it receives, else another receiving bind of the same system_id.
+fn receipt_session(listener: &SmscListener, session_id: u64, system_id: &str) -> Option<u64> {
+    let receivers = listener.receivers();
+    receivers
+        .iter()
+        .find(|session| session.session_id == session_id)
+        .or_else(|| {
+            receivers
+                .iter()
+                .find(|session| session.system_id == system_id)
+        })
+        .map(|session| session.session_id)
+}
@@ -0,0 +1,603 @@
+//! # SMSC Listener
+//!
+//! A blocking TCP acceptor for the SMSC side of a session. Binds are
+//! authenticated by a foreign callback, requests are checked against the
+//! bind mode and everything else is handed to a `SessionHandler`. The
+//! listener can also open connections itself to ask an ESME to bind through
+//! `outbind`, and routes mobile-originated `deliver_sm` to the receiver binds
+//! whose `address_range` covers the destination.
+use crate::address_range::AddressRange;
+use crate::common::*;
+use crate::delivery_pdu_ffi::{encode_deliver_sm, DeliverSmRequest};
+use crate::esme_session::connect_stream;
+use crate::framer::{read_frame, wake_accept};
+use crate::pdu::*;
+use crate::sequence::default_sequence_generator;
+use crate::session_pdu_ffi::*;
+use crate::session_state::{request_allowed, SessionRole, SessionStatus};
+use crate::tls::{server_tls, ServerTls, Stream, TlsClientConfig, TlsServerConfig};
+use std::collections::HashMap;
+use std::io::Write;
+use std::net::{Shutdown, SocketAddr, TcpListener, TcpStream};
+use std::sync::atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+
+/// Maximum time an accepted connection may take to complete the TLS handshake.
+const TLS_HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Decides whether a bind request is accepted.
+#[uniffi::export(with_foreign)]
+pub trait BindAuthenticator: Send + Sync {
+    /// Called for every bind request received on an unbound connection.
+    ///
+    /// Returns the `command_status` of the `bind_resp`; `COMMAND_STATUS_OK` accepts the bind.
+    fn authenticate(&self, peer_addr: String, request: BindRequest) -> u32;
+}
+
+/// Answers the requests of bound ESMEs.
+#[uniffi::export(with_foreign)]
+pub trait SessionHandler: Send + Sync {
+    /// Called for every request the bind mode allows, except `enquire_link` and `unbind`.
+    ///
+    /// Returns what the listener does with the request.
+    fn on_request(&self, session: SmscSessionInfo, request: Pdu) -> SessionReply;
+}
+
+/// What the listener does with a request after the `SessionHandler` has seen it.
+#[derive(uniffi::Enum, Clone, Debug, PartialEq)]
+pub enum SessionReply {
+    /// Sends a response.
+    Respond {
+        /// The matching response or a `generic_nack`. Its sequence number is set by the listener.
+        pdu: Pdu,
+    },
+    /// Sends a body-less response carrying an error status.
+    Reject {
+        /// The `command_status` of the response.
+        command_status: u32,
+    },
+    /// Sends nothing, as if the response were lost.
+    NoResponse,
+    /// Closes the connection without answering.
+    Disconnect,
+}
+
+impl From<Pdu> for SessionReply {
+    fn from(pdu: Pdu) -> Self {
+        SessionReply::Respond { pdu }
+    }
+}
+
+/// Settings for an `SmscListener`.
+#[derive(uniffi::Record, Clone, Debug, PartialEq, Eq)]
+pub struct SmscListenerConfig {
+    /// Local address to listen on.
+    pub host: String,
+    /// Local port to listen on. Use 0 to pick a free port.
+    pub port: u16,
+    /// The system_id returned in `bind_resp`.
+    #[uniffi(default = "SMSC")]
+    pub system_id: String,
+    /// TLS settings. `None` accepts plain TCP.
+    #[uniffi(default = None)]
+    pub tls: Option<TlsServerConfig>,
+    /// Maximum time `initiate_outbind` waits for the TCP connection, in milliseconds.
+    #[uniffi(default = 10000)]
+    pub outbind_connect_timeout_ms: u64,
+}
+
+impl SmscListenerConfig {
+    /// Creates a configuration with the default system_id.
+    pub fn new(host: String, port: u16) -> Self {
+        Self {
+            host,
+            port,
+            system_id: "SMSC".to_string(),
+            tls: None,
+            outbind_connect_timeout_ms: 10_000,
+        }
+    }
+}
+
+/// Creates an `SmscListenerConfig` with the default system_id.
+#[uniffi::export]
+pub fn smsc_listener_config_new(host: String, port: u16) -> SmscListenerConfig {
+    SmscListenerConfig::new(host, port)
+}
+
+/// Describes a bound ESME connection.
+#[derive(uniffi::Record, Clone, Debug, PartialEq, Eq)]
+pub struct SmscSessionInfo {
+    /// Identifier of the connection, unique per listener.
+    pub session_id: u64,
+    /// Remote address of the ESME.
+    pub peer_addr: String,
+    /// The system_id the ESME bound with.
+    pub system_id: String,
+    /// The bind mode of the session.
+    pub mode: BindMode,
+}
+
+/// Accepts ESME connections and serves them on background threads.
+#[derive(uniffi::Object)]
+pub struct SmscListener {
+    local_addr: SocketAddr,
+    server: Arc<Server>,
+    acceptor: Mutex<Option<JoinHandle<()>>>,
+}
+
+#[uniffi::export]
+impl SmscListener {
+    /// Starts listening and accepting connections.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the address cannot be bound or the TLS settings are unusable.
+    #[uniffi::constructor]
+    pub fn start(
+        config: SmscListenerConfig,
+        authenticator: Arc<dyn BindAuthenticator>,
+        handler: Arc<dyn SessionHandler>,
+    ) -> Result<Arc<Self>, SmppFfiError> {
+        let tls = config.tls.as_ref().map(server_tls).transpose()?;
+        let listener = TcpListener::bind((config.host.as_str(), config.port))?;
+        let local_addr = listener.local_addr()?;
+        let server = Arc::new(Server {
+            system_id: config.system_id,
+            tls,
+            outbind_connect_timeout: Duration::from_millis(config.outbind_connect_timeout_ms),
+            authenticator,
+            handler,
+            stopped: AtomicBool::new(false),
+            next_session_id: AtomicU64::new(1),
+            connections: Mutex::new(HashMap::new()),
+            receivers: Mutex::new(Vec::new()),
+            next_receiver: AtomicUsize::new(0),
+        });
+        let acceptor = {
+            let server = server.clone();
+            thread::Builder::new()
+                .name("smpp-smsc-acceptor".to_string())
+                .spawn(move || server.accept_loop(listener))?
+        };
+        Ok(Arc::new(Self {
+            local_addr,
+            server,
+            acceptor: Mutex::new(Some(acceptor)),
+        }))
+    }
+
+    /// Returns the port the listener is bound to.
+    pub fn local_port(&self) -> u16 {
+        self.local_addr.port()
+    }
+
+    /// Returns the number of open connections.
+    pub fn connection_count(&self) -> u32 {
+        self.server.connections.lock().unwrap().len() as u32
+    }
+
+    /// Connects to an ESME and sends `outbind`, asking it to bind as a receiver.
+    ///
+    /// The connection is then served like an accepted one: the ESME's
+    /// `bind_receiver` goes to the authenticator. Returns the session ID of
+    /// the connection. With `tls` set, the TLS handshake completes before
+    /// `outbind` is sent; a listener that uses TLS requires it.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the listener is shut down, uses TLS while `tls` is
+    /// `None`, the ESME cannot be reached within `outbind_connect_timeout_ms`
+    /// or `outbind` cannot be sent.
+    #[uniffi::method(default(tls = None))]
+    pub fn initiate_outbind(
+        &self,
+        host: String,
+        port: u16,
+        system_id: String,
+        password: String,
+        tls: Option<TlsClientConfig>,
+    ) -> Result<u64, SmppFfiError> {
+        if self.server.stopped.load(Ordering::SeqCst) {
+            return Err(SmppFfiError::Generic {
+                msg: "Listener is shut down".to_string(),
+            });
+        }
+        if self.server.tls.is_some() && tls.is_none() {
+            return Err(SmppFfiError::Generic {
+                msg: "The listener uses TLS, so outbind needs TLS settings".to_string(),
+            });
+        }
+        let timeout = self.server.outbind_connect_timeout;
+        let mut stream = connect_stream(&host, port, timeout, tls.as_ref())?;
+        stream.write_all(&encode_outbind(&Outbind {
+            sequence_number: default_sequence_generator().next_value(),
+            system_id,
+            password,
+        }))?;
+        self.server.serve(Opening::Connected(stream))
+    }
+
+    /// Sends a `deliver_sm` to a receiver or transceiver bind whose address range
+    /// covers `dest_addr_ton`, `dest_addr_npi` and `dest_addr`.
+    ///
+    /// Binds with matching ranges take turns. A sequence number of 0 is replaced
+    /// by one from the default generator. Returns the session ID the message was
+    /// sent to without waiting for its `deliver_sm_resp`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if no bind covers the destination or the PDU cannot be sent.
+    pub fn deliver(&self, mut request: DeliverSmRequest) -> Result<u64, SmppFfiError> {
+        let (session_id, writer) = self.server.route(&request)?;
+        if request.sequence_number == 0 {
+            request.sequence_number = default_sequence_generator().next_value();
+        }
+        writer
+            .lock()
+            .unwrap()
+            .write_all(&encode_deliver_sm(&request))?;
+        Ok(session_id)
+    }
+
+    /// Sends a `deliver_sm`, such as a delivery receipt, to the receiver or
+    /// transceiver bind with this session ID.
+    ///
+    /// A sequence number of 0 is replaced by one from the default generator.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if no such bind is open or the PDU cannot be sent.
+    pub fn deliver_to(
+        &self,
+        session_id: u64,
+        mut request: DeliverSmRequest,
+    ) -> Result<(), SmppFfiError> {
+        let writer = {
+            let receivers = self.server.receivers.lock().unwrap();
+            let receiver = receivers
+                .iter()
+                .find(|r| r.session.session_id == session_id);
+            receiver
+                .map(|r| r.writer.clone())
+                .ok_or_else(|| SmppFfiError::Generic {
+                    msg: format!("No receiver bind with session ID {}", session_id),
+                })?
+        };
+        if request.sequence_number == 0 {
+            request.sequence_number = default_sequence_generator().next_value();
+        }
+        writer
+            .lock()
+            .unwrap()
+            .write_all(&encode_deliver_sm(&request))?;
+        Ok(())
+    }
+
+    /// Returns the receiver and transceiver binds that mobile-originated messages can be routed to.
+    pub fn receivers(&self) -> Vec<SmscSessionInfo> {
+        let receivers = self.server.receivers.lock().unwrap();
+        receivers.iter().map(|r| r.session.clone()).collect()
+    }
+
+    /// Stops accepting connections and closes the open ones.
+    pub fn shutdown(&self) {
+        if self.server.stopped.swap(true, Ordering::SeqCst) {
+            return;
+        }
+        // Wake the blocking accept so the acceptor sees the stop flag.
+        wake_accept(self.local_addr);
+        if let Some(acceptor) = self.acceptor.lock().unwrap().take() {
+            let _ = acceptor.join();
+        }
+        for stream in self.server.connections.lock().unwrap().values() {
+            let _ = stream.shutdown(Shutdown::Both);
+        }
+    }
+}
+
+impl Drop for SmscListener {
+    fn drop(&mut self) {
+        self.shutdown();
+    }
+}
+
+/// State shared between the listener and its connection threads.
+struct Server {
+    system_id: String,
+    tls: Option<ServerTls>,
+    outbind_connect_timeout: Duration,
+    authenticator: Arc<dyn BindAuthenticator>,
+    handler: Arc<dyn SessionHandler>,
+    stopped: AtomicBool,
+    next_session_id: AtomicU64,
+    connections: Mutex<HashMap<u64, TcpStream>>,
+    receivers: Mutex<Vec<Receiver>>,
+    next_receiver: AtomicUsize,
+}
+
+/// A connection to serve: accepted by the listener, or opened by `initiate_outbind`.
+enum Opening {
+    Accepted(TcpStream),
+    Connected(Stream),
+}
+
+/// A bind that receives mobile-originated messages.
+struct Receiver {
+    session: SmscSessionInfo,
+    range: AddressRange,
+    writer: Arc<Mutex<Stream>>,
+}
+
+impl Server {
+    fn accept_loop(self: Arc<Self>, listener: TcpListener) {
+        for stream in listener.incoming() {
+            if self.stopped.load(Ordering::SeqCst) {
+                break;
+            }
+            let Ok(stream) = stream else { continue };
+            let _ = self.serve(Opening::Accepted(stream));
+        }
+    }
+
+    /// Serves a connection on its own thread. Returns its session ID.
+    ///
+    /// An accepted connection first completes the TLS handshake if the listener uses TLS.
+    fn serve(self: &Arc<Self>, opening: Opening) -> Result<u64, SmppFfiError> {
+        let tcp = match &opening {
+            Opening::Accepted(tcp) => tcp,
+            Opening::Connected(stream) => stream.tcp(),
+        };
+        let peer_addr = tcp.peer_addr()?;
+        let registered = tcp.try_clone()?;
+        let _ = tcp.set_nodelay(true);
+        let session_id = self.next_session_id.fetch_add(1, Ordering::SeqCst);
+        self.connections
+            .lock()
+            .unwrap()
+            .insert(session_id, registered);
+
+        let server = self.clone();
+        let spawned = thread::Builder::new()
+            .name(format!("smpp-smsc-session-{}", session_id))
+            .spawn(move || {
+                let stream = match opening {
+                    Opening::Accepted(tcp) => accept(tcp, server.tls.as_ref()),
+                    Opening::Connected(stream) => Ok(stream),
+                };
+                let accepted = stream.and_then(|stream| Ok((stream.try_clone()?, stream)));
+                if let Ok((writer, stream)) = accepted {
+                    Connection {
+                        server: &server,
+                        stream,
+                        writer: Arc::new(Mutex::new(writer)),
+                        session_id,
+                        peer_addr: peer_addr.to_string(),
+                        bound: None,
+                    }
+                    .run();
+                }
+                server.remove_receiver(session_id);
+                server.connections.lock().unwrap().remove(&session_id);
+            });
+        if let Err(e) = spawned {
+            self.connections.lock().unwrap().remove(&session_id);
+            return Err(e.into());
+        }
+        Ok(session_id)
+    }
+
+    /// Picks the next receiver bind covering the destination of a `deliver_sm`.
+    fn route(&self, request: &DeliverSmRequest) -> Result<(u64, Arc<Mutex<Stream>>), SmppFfiError> {
+        let receivers = self.receivers.lock().unwrap();
+        let matching: Vec<&Receiver> = receivers
+            .iter()
+            .filter(|r| {
+                r.range.covers(
+                    request.dest_addr_ton,
+                    request.dest_addr_npi,
+                    &request.dest_addr,
+                )
+            })
+            .collect();
+        if matching.is_empty() {
+            return Err(SmppFfiError::Generic {
+                msg: format!("No receiver bind covers address '{}'", request.dest_addr),
+            });
+        }
+        let receiver =
+            matching[self.next_receiver.fetch_add(1, Ordering::Relaxed) % matching.len()];
+        Ok((receiver.session.session_id, receiver.writer.clone()))
+    }
+
+    fn remove_receiver(&self, session_id: u64) {
+        let mut receivers = self.receivers.lock().unwrap();
+        receivers.retain(|r| r.session.session_id != session_id);
+    }
+}
+
+/// Completes the TLS handshake, if any, within `TLS_HANDSHAKE_TIMEOUT`.
+fn accept(stream: TcpStream, tls: Option<&ServerTls>) -> Result<Stream, SmppFfiError> {
+    if tls.is_some() {
+        stream.set_read_timeout(Some(TLS_HANDSHAKE_TIMEOUT))?;
+    }
+    let stream = Stream::accept(stream, tls)?;
+    stream.tcp().set_read_timeout(None)?;
+    Ok(stream)
+}
+
+/// One ESME connection, served on its own thread.
+///
+/// `stream` is only read; writes go through `writer`, which `deliver` shares.
+struct Connection<'a> {
+    server: &'a Server,
+    stream: Stream,
+    writer: Arc<Mutex<Stream>>,
+    session_id: u64,
+    peer_addr: String,
+    bound: Option<SmscSessionInfo>,
+}
+
+impl Connection<'_> {
+    fn run(&mut self) {
+        while let Ok(frame) = read_frame(&mut self.stream) {
+            if !self.dispatch(&frame) {
+                break;
+            }
+        }
+        self.stream.shutdown();
+    }
+
+    /// Handles one inbound PDU. Returns false when the connection must close.
+    fn dispatch(&mut self, frame: &[u8]) -> bool {
+        let Ok(header) = decode_pdu_header(frame) else {
+            return false;
+        };
+        let request = match decode_pdu(frame) {
+            Ok(request) => request,
+            Err(_) if header.command_id & 0x8000_0000 != 0 => return true,
+            Err(_) if is_supported_command_id(header.command_id) => {
+                return self.reply_header(&header, ESME_RSYSERR);
+            }
+            Err(_) => {
+                return self.write(&encode_generic_nack(&GenericNack {
+                    sequence_number: header.sequence_number,
+                    command_status: ESME_RINVCMDID,
+                }));
+            }
+        };
+
+        match (request, &self.bound) {
+            (Pdu::EnquireLink { pdu }, _) => {
+                self.write(&encode_enquire_link_response(&EnquireLinkResponse {
+                    sequence_number: pdu.sequence_number,
+                    command_status: COMMAND_STATUS_OK,
+                }))
+            }
+            (Pdu::BindRequest { pdu }, None) => self.bind(&header, pdu),
+            (Pdu::BindRequest { .. }, Some(_)) => self.reply_header(&header, ESME_RALYBND),
+            (Pdu::Unbind { pdu }, Some(_)) => {
+                self.server.remove_receiver(self.session_id);
+                self.write(&encode_unbind_response(&UnbindResponse {
+                    sequence_number: pdu.sequence_number,
+                    command_status: COMMAND_STATUS_OK,
+                }));
+                false
+            }
+            // Responses and generic_nack are never answered.
+            _ if header.command_id & 0x8000_0000 != 0 || header.command_id == GENERIC_NACK => true,
+            (request, Some(session))
+                if request_allowed(
+                    SessionRole::Esme,
+                    SessionStatus::bound(session.mode),
+                    header.command_id,
+                ) =>
+            {
+                let session = session.clone();
+                match self.server.handler.on_request(session, request.clone()) {
+                    SessionReply::Respond { pdu } if responds_to(&request, &pdu) => {
+                        self.reply(&header, pdu)
+                    }
+                    SessionReply::Respond { .. } => self.reply_header(&header, ESME_RSYSERR),
+                    SessionReply::Reject { command_status } => {
+                        self.reply_header(&header, command_status)
+                    }
+                    SessionReply::NoResponse => true,
+                    SessionReply::Disconnect => false,
+                }
+            }
+            _ => self.reply_header(&header, ESME_RINVBNDSTS),
+        }
+    }
+
+    fn bind(&mut self, header: &PduHeader, request: BindRequest) -> bool {
+        let session = SmscSessionInfo {
+            session_id: self.session_id,
+            peer_addr: self.peer_addr.clone(),
+            system_id: request.system_id.clone(),
+            mode: request.mode,
+        };
+        let receives = matches!(request.mode, BindMode::Receiver | BindMode::Transceiver);
+        let range = AddressRange::compile(
+            request.addr_ton,
+            request.addr_npi,
+            request.address_range.clone(),
+        );
+        let mut command_status = self
+            .server
+            .authenticator
+            .authenticate(self.peer_addr.clone(), request);
+        if command_status == COMMAND_STATUS_OK && receives && range.is_err() {
+            command_status = ESME_RBINDFAIL;
+        }
+        let mut bytes = encode_pdu(&Pdu::BindResponse {
+            pdu: BindResponse {
+                sequence_number: header.sequence_number,
+                command_status,
+                system_id: self.server.system_id.clone(),
+            },
+        });
+        bytes[4..8].copy_from_slice(&(header.command_id | 0x8000_0000).to_be_bytes());
+
+        // Registering under the writer lock keeps `deliver` from overtaking the bind_resp.
+        let mut writer = self.writer.lock().unwrap();
+        if writer.write_all(&bytes).is_err() {
+            return false;
+        }
+        if command_status == COMMAND_STATUS_OK {
+            if let (true, Ok(range)) = (receives, range) {
+                self.server.receivers.lock().unwrap().push(Receiver {
+                    session: session.clone(),
+                    range,
+                    writer: self.writer.clone(),
+                });
+            }
+            self.bound = Some(session);
+        }
+        true
+    }
+
+    /// Sends a response, taking its sequence number and, unless it is a
+    /// `generic_nack`, its `command_id` from the request.
+    fn reply(&mut self, header: &PduHeader, mut response: Pdu) -> bool {
+        response.set_sequence_number(header.sequence_number);
+        let mut bytes = encode_pdu(&response);
+        if !matches!(response, Pdu::GenericNack { .. }) {
+            bytes[4..8].copy_from_slice(&(header.command_id | 0x8000_0000).to_be_bytes());
+        }
+        self.write(&bytes)
+    }
+
+    /// Sends a body-less response carrying an error status.
+    fn reply_header(&mut self, header: &PduHeader, command_status: u32) -> bool {
+        self.write(&encode_error_response(header, command_status))
+    }
+
+    fn write(&mut self, bytes: &[u8]) -> bool {
+        self.writer.lock().unwrap().write_all(bytes).is_ok()
+    }
+}
+
+fn responds_to(request: &Pdu, response: &Pdu) -> bool {
+    matches!(
+        (request, response),
+        (_, Pdu::GenericNack { .. })
+            | (Pdu::SubmitSm { .. }, Pdu::SubmitSmResponse { .. })
+            | (Pdu::SubmitMulti { .. }, Pdu::SubmitMultiResponse { .. })
+            | (Pdu::DataSm { .. }, Pdu::DataSmResponse { .. })
+            | (Pdu::QuerySm { .. }, Pdu::QuerySmResponse { .. })
+            | (Pdu::CancelSm { .. }, Pdu::CancelSmResponse { .. })
+            | (Pdu::ReplaceSm { .. }, Pdu::ReplaceSmResponse { .. })
+            | (Pdu::BroadcastSm { .. }, Pdu::BroadcastSmResponse { .. })
+            | (
+                Pdu::QueryBroadcastSm { .. },
+                Pdu::QueryBroadcastSmResponse { .. }
+            )
+            | (
+                Pdu::CancelBroadcastSm { .. },
+                Pdu::CancelBroadcastSmResponse { .. }
+            )
+    )
+}
@@ -1,3 +1,4 @@
+use crate::address::Address;
 use crate::common::{Npi, SmppFfiError, Ton};
 use crate::tlv::Tlv;
 use smpp_codec::pdus::{
@@ -68,12 +69,8 @@ pub struct SubmitSmResponse {
 pub enum Destination {
     /// SME Address
     SmeAddress {
-        /// Type of Number.
-        ton: Ton,
-        /// Numbering Plan Indicator.
-        npi: Npi,
-        /// The address.
-        address: String,
+        /// The SME address.
+        address: Address,
     },
     /// Distribution List
     DistributionList {
@@ -86,9 +83,11 @@ impl From<NativeDestination> for Destination {
     fn from(native: NativeDestination) -> Self {
         match native {
             NativeDestination::SmeAddress { ton, npi, address } => Destination::SmeAddress {
-                ton: ton.into(),
-                npi: npi.into(),
-                address,
+                address: Address {
+                    ton: ton.into(),
+                    npi: npi.into(),
+                    value: address,
+                },
             },
             NativeDestination::DistributionList(name) => Destination::DistributionList { name },
         }
@@ -98,10 +97,10 @@ impl From<NativeDestination> for Destination {
 impl From<Destination> for NativeDestination {
     fn from(ffi: Destination) -> Self {
         match ffi {
-            Destination::SmeAddress { ton, npi, address } => NativeDestination::SmeAddress {
-                ton: ton.into(),
-                npi: npi.into(),
-                address,
+            Destination::SmeAddress { address } => NativeDestination::SmeAddress {
+                ton: address.ton.into(),
+                npi: address.npi.into(),
+                address: address.value,
             },
             Destination::DistributionList { name } => NativeDestination::DistributionList(name),
         }
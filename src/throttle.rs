@@ -0,0 +1,216 @@
+//! # Throttle
+//!
+//! Token-bucket rate limiting for outbound requests, per bind and per
+//! command type, plus the back-off applied when the SMSC answers with
+//! `ESME_RTHROTTLED`.
+use std::collections::{HashMap, VecDeque};
+use std::sync::atomic::{AtomicU32, AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// A rate limit for one command type.
+#[derive(uniffi::Record, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct CommandRateLimit {
+    /// The command ID of the request, e.g. `CMD_SUBMIT_SM`.
+    pub command_id: u32,
+    /// Maximum requests of this type per second.
+    pub max_per_second: u32,
+}
+
+/// Outbound rate limits and `ESME_RTHROTTLED` handling.
+#[derive(uniffi::Record, Clone, Debug, PartialEq, Eq)]
+pub struct ThrottleConfig {
+    /// Maximum requests per second over the bind. 0 is unlimited.
+    #[uniffi(default = 0)]
+    pub max_per_second: u32,
+    /// Further limits for individual command types.
+    pub command_limits: Vec<CommandRateLimit>,
+    /// Pause after the first `ESME_RTHROTTLED`, in milliseconds; doubles for each retry of a request.
+    #[uniffi(default = 1000)]
+    pub throttled_retry_delay_ms: u64,
+    /// Times a throttled request is retried before its response is returned as is.
+    #[uniffi(default = 3)]
+    pub max_throttled_retries: u32,
+}
+
+impl ThrottleConfig {
+    /// Creates a configuration limiting the bind to `max_per_second`.
+    pub fn new(max_per_second: u32) -> Self {
+        Self {
+            max_per_second,
+            command_limits: Vec::new(),
+            throttled_retry_delay_ms: 1_000,
+            max_throttled_retries: 3,
+        }
+    }
+}
+
+impl Default for ThrottleConfig {
+    fn default() -> Self {
+        Self::new(0)
+    }
+}
+
+/// Creates a `ThrottleConfig` limiting the bind to `max_per_second`, with default retries.
+#[uniffi::export]
+pub fn throttle_config_new(max_per_second: u32) -> ThrottleConfig {
+    ThrottleConfig::new(max_per_second)
+}
+
+/// A snapshot of a session's outbound traffic.
+#[derive(uniffi::Record, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ThrottleStats {
+    /// Requests sent during the last second.
+    pub send_rate: u32,
+    /// Requests waiting for the rate limiter.
+    pub queue_depth: u32,
+    /// `ESME_RTHROTTLED` responses received so far.
+    pub throttled_responses: u64,
+}
+
+/// A bucket holding up to one second's worth of tokens.
+struct Bucket {
+    per_second: f64,
+    tokens: f64,
+    refilled: Instant,
+}
+
+impl Bucket {
+    fn new(max_per_second: u32, now: Instant) -> Self {
+        Self {
+            per_second: f64::from(max_per_second),
+            tokens: f64::from(max_per_second),
+            refilled: now,
+        }
+    }
+
+    /// Returns how long until a token is available.
+    fn wait(&mut self, now: Instant) -> Duration {
+        let elapsed = now.duration_since(self.refilled).as_secs_f64();
+        self.tokens = (self.tokens + elapsed * self.per_second).min(self.per_second);
+        self.refilled = now;
+        if self.tokens >= 1.0 {
+            Duration::ZERO
+        } else {
+            Duration::from_secs_f64((1.0 - self.tokens) / self.per_second)
+        }
+    }
+}
+
+struct State {
+    bind: Option<Bucket>,
+    commands: HashMap<u32, Bucket>,
+    paused_until: Option<Instant>,
+    /// When each request of the last second was sent.
+    sent: VecDeque<Instant>,
+}
+
+impl State {
+    fn forget_old_sends(&mut self, now: Instant) {
+        while self
+            .sent
+            .front()
+            .is_some_and(|sent| now.duration_since(*sent) >= Duration::from_secs(1))
+        {
+            self.sent.pop_front();
+        }
+    }
+}
+
+/// The rate limiter of one session.
+pub(crate) struct Throttle {
+    config: ThrottleConfig,
+    state: Mutex<State>,
+    waiting: AtomicU32,
+    throttled: AtomicU64,
+}
+
+impl Throttle {
+    pub(crate) fn new(config: ThrottleConfig) -> Self {
+        let now = Instant::now();
+        let bind = (config.max_per_second > 0).then(|| Bucket::new(config.max_per_second, now));
+        let commands = config
+            .command_limits
+            .iter()
+            .filter(|limit| limit.max_per_second > 0)
+            .map(|limit| (limit.command_id, Bucket::new(limit.max_per_second, now)))
+            .collect();
+        Self {
+            config,
+            state: Mutex::new(State {
+                bind,
+                commands,
+                paused_until: None,
+                sent: VecDeque::new(),
+            }),
+            waiting: AtomicU32::new(0),
+            throttled: AtomicU64::new(0),
+        }
+    }
+
+    /// Blocks until a request with `command_id` may be sent, then counts it as sent.
+    pub(crate) fn acquire(&self, command_id: u32) {
+        self.waiting.fetch_add(1, Ordering::SeqCst);
+        loop {
+            let wait = {
+                let mut state = self.state.lock().unwrap();
+                let now = Instant::now();
+                let paused = state
+                    .paused_until
+                    .map_or(Duration::ZERO, |until| until.saturating_duration_since(now));
+                let bind = state
+                    .bind
+                    .as_mut()
+                    .map_or(Duration::ZERO, |bucket| bucket.wait(now));
+                let command = state
+                    .commands
+                    .get_mut(&command_id)
+                    .map_or(Duration::ZERO, |bucket| bucket.wait(now));
+                let wait = paused.max(bind).max(command);
+                if wait.is_zero() {
+                    if let Some(bucket) = state.bind.as_mut() {
+                        bucket.tokens -= 1.0;
+                    }
+                    if let Some(bucket) = state.commands.get_mut(&command_id) {
+                        bucket.tokens -= 1.0;
+                    }
+                    state.forget_old_sends(now);
+                    state.sent.push_back(now);
+                    break;
+                }
+                wait
+            };
+            thread::sleep(wait);
+        }
+        self.waiting.fetch_sub(1, Ordering::SeqCst);
+    }
+
+    /// Records an `ESME_RTHROTTLED` response to the `retry`th attempt of a request.
+    ///
+    /// Pauses all sending and returns true if the request should be retried.
+    pub(crate) fn throttled(&self, retry: u32) -> bool {
+        self.throttled.fetch_add(1, Ordering::SeqCst);
+        if retry >= self.config.max_throttled_retries {
+            return false;
+        }
+        let delay = self
+            .config
+            .throttled_retry_delay_ms
+            .saturating_mul(1u64 << retry.min(63));
+        let until = Instant::now() + Duration::from_millis(delay);
+        let mut state = self.state.lock().unwrap();
+        state.paused_until = Some(state.paused_until.map_or(until, |paused| paused.max(until)));
+        true
+    }
+
+    pub(crate) fn stats(&self) -> ThrottleStats {
+        let mut state = self.state.lock().unwrap();
+        state.forget_old_sends(Instant::now());
+        ThrottleStats {
+            send_rate: state.sent.len() as u32,
+            queue_depth: self.waiting.load(Ordering::SeqCst),
+            throttled_responses: self.throttled.load(Ordering::SeqCst),
+        }
+    }
+}
@@ -0,0 +1,504 @@
+//! # TLS Transport
+//!
+//! SMPP over TLS for the client and server session types. The settings are
+//! always part of the interface; the transport itself is compiled in with
+//! the `tls` cargo feature, and sessions asked to use TLS without it fail to
+//! connect.
+use crate::common::SmppFfiError;
+use std::io::{self, Read, Write};
+use std::net::{Shutdown, TcpStream};
+#[cfg(feature = "tls")]
+use std::sync::{Arc, Mutex};
+use tokio::io::{AsyncRead, AsyncWrite};
+
+/// TLS settings of a client session.
+#[derive(uniffi::Record, Clone, Debug, PartialEq, Eq)]
+pub struct TlsClientConfig {
+    /// PEM certificates of the CAs trusted to sign the SMSC's certificate. Empty trusts the Mozilla root bundle.
+    #[uniffi(default = "")]
+    pub ca_certificates_pem: String,
+    /// PEM certificate chain presented to the SMSC. Empty presents none.
+    #[uniffi(default = "")]
+    pub client_certificate_pem: String,
+    /// PEM private key of the client certificate.
+    #[uniffi(default = "")]
+    pub client_key_pem: String,
+    /// Name sent in SNI and checked against the SMSC's certificate. Empty uses the host.
+    #[uniffi(default = "")]
+    pub server_name: String,
+    /// Whether the SMSC's certificate must be issued for the server name.
+    #[uniffi(default = true)]
+    pub verify_hostname: bool,
+}
+
+impl Default for TlsClientConfig {
+    fn default() -> Self {
+        Self {
+            ca_certificates_pem: String::new(),
+            client_certificate_pem: String::new(),
+            client_key_pem: String::new(),
+            server_name: String::new(),
+            verify_hostname: true,
+        }
+    }
+}
+
+/// Creates a `TlsClientConfig` trusting `ca_certificates_pem`, or the Mozilla roots if it is empty.
+#[uniffi::export]
+pub fn tls_client_config_new(ca_certificates_pem: String) -> TlsClientConfig {
+    TlsClientConfig {
+        ca_certificates_pem,
+        ..TlsClientConfig::default()
+    }
+}
+
+/// TLS settings of a listener.
+#[derive(uniffi::Record, Clone, Debug, PartialEq, Eq)]
+pub struct TlsServerConfig {
+    /// PEM certificate chain presented to clients, leaf first.
+    pub certificate_chain_pem: String,
+    /// PEM private key of the certificate.
+    pub private_key_pem: String,
+    /// PEM certificates of the CAs that sign accepted client certificates.
+    /// Empty does not ask clients for a certificate.
+    #[uniffi(default = "")]
+    pub client_ca_certificates_pem: String,
+}
+
+/// Creates a `TlsServerConfig` that does not ask clients for a certificate.
+#[uniffi::export]
+pub fn tls_server_config_new(
+    certificate_chain_pem: String,
+    private_key_pem: String,
+) -> TlsServerConfig {
+    TlsServerConfig {
+        certificate_chain_pem,
+        private_key_pem,
+        client_ca_certificates_pem: String::new(),
+    }
+}
+
+/// A listener's prepared TLS settings.
+#[cfg(feature = "tls")]
+pub(crate) type ServerTls = Arc<rustls::ServerConfig>;
+
+/// A listener's prepared TLS settings; none exist without the `tls` feature.
+#[cfg(not(feature = "tls"))]
+pub(crate) enum ServerTls {}
+
+/// Checks a listener's TLS settings and prepares them for accepting connections.
+///
+/// # Errors
+///
+/// Returns an error if the certificates or key cannot be used, or the crate
+/// is built without the `tls` feature.
+pub(crate) fn server_tls(config: &TlsServerConfig) -> Result<ServerTls, SmppFfiError> {
+    #[cfg(feature = "tls")]
+    {
+        rustls_config::server(config)
+    }
+    #[cfg(not(feature = "tls"))]
+    {
+        let _ = config;
+        Err(tls_unavailable())
+    }
+}
+
+/// A connected socket, plain or wrapped in TLS.
+///
+/// Clones share the TLS session, so one thread can read while another writes.
+pub(crate) struct Stream {
+    tcp: TcpStream,
+    #[cfg(feature = "tls")]
+    tls: Option<Arc<Mutex<rustls::Connection>>>,
+}
+
+impl Stream {
+    pub(crate) fn plain(tcp: TcpStream) -> Self {
+        Self {
+            tcp,
+            #[cfg(feature = "tls")]
+            tls: None,
+        }
+    }
+
+    /// Completes the client handshake with `host` if `tls` is set.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the settings are unusable, the handshake fails or
+    /// the crate is built without the `tls` feature.
+    pub(crate) fn connect(
+        tcp: TcpStream,
+        tls: Option<&TlsClientConfig>,
+        host: &str,
+    ) -> Result<Self, SmppFfiError> {
+        let Some(tls) = tls else {
+            return Ok(Self::plain(tcp));
+        };
+        #[cfg(feature = "tls")]
+        {
+            let config = rustls_config::client(tls)?;
+            let name = rustls_config::server_name(tls, host)?;
+            let connection = rustls::ClientConnection::new(config, name).map_err(tls_error)?;
+            Self::handshake(tcp, connection.into())
+        }
+        #[cfg(not(feature = "tls"))]
+        {
+            let _ = (tcp, tls, host);
+            Err(tls_unavailable())
+        }
+    }
+
+    /// Completes the server handshake if `tls` is set.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the handshake fails.
+    pub(crate) fn accept(tcp: TcpStream, tls: Option<&ServerTls>) -> Result<Self, SmppFfiError> {
+        let Some(tls) = tls else {
+            return Ok(Self::plain(tcp));
+        };
+        #[cfg(feature = "tls")]
+        {
+            let connection = rustls::ServerConnection::new(tls.clone()).map_err(tls_error)?;
+            Self::handshake(tcp, connection.into())
+        }
+        #[cfg(not(feature = "tls"))]
+        match *tls {}
+    }
+
+    #[cfg(feature = "tls")]
+    fn handshake(
+        mut tcp: TcpStream,
+        mut connection: rustls::Connection,
+    ) -> Result<Self, SmppFfiError> {
+        while connection.is_handshaking() {
+            connection
+                .complete_io(&mut tcp)
+                .map_err(|e| SmppFfiError::Generic {
+                    msg: format!("TLS handshake failed: {}", e),
+                })?;
+        }
+        Ok(Self {
+            tcp,
+            tls: Some(Arc::new(Mutex::new(connection))),
+        })
+    }
+
+    pub(crate) fn try_clone(&self) -> io::Result<Self> {
+        Ok(Self {
+            tcp: self.tcp.try_clone()?,
+            #[cfg(feature = "tls")]
+            tls: self.tls.clone(),
+        })
+    }
+
+    /// The underlying socket, for timeouts and addresses.
+    pub(crate) fn tcp(&self) -> &TcpStream {
+        &self.tcp
+    }
+
+    /// Closes both directions, telling a TLS peer first if no write is under way.
+    pub(crate) fn shutdown(&self) {
+        #[cfg(feature = "tls")]
+        if let Some(Ok(mut connection)) = self.tls.as_ref().map(|tls| tls.try_lock()) {
+            connection.send_close_notify();
+            let _ = connection.write_tls(&mut &self.tcp);
+        }
+        let _ = self.tcp.shutdown(Shutdown::Both);
+    }
+}
+
+impl Read for Stream {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        #[cfg(feature = "tls")]
+        if let Some(tls) = &self.tls {
+            return read_tls(&self.tcp, tls, buf);
+        }
+        self.tcp.read(buf)
+    }
+}
+
+impl Write for Stream {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        #[cfg(feature = "tls")]
+        if let Some(tls) = &self.tls {
+            let mut connection = tls.lock().unwrap();
+            let written = connection.writer().write(buf)?;
+            while connection.wants_write() {
+                connection.write_tls(&mut &self.tcp)?;
+            }
+            return Ok(written);
+        }
+        self.tcp.write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.tcp.flush()
+    }
+}
+
+/// The reading and writing halves of an async connection.
+pub(crate) type AsyncHalves = (
+    Box<dyn AsyncRead + Send + Unpin>,
+    Box<dyn AsyncWrite + Send + Unpin>,
+);
+
+/// Splits an async connection, completing the client handshake with `host` first if `tls` is set.
+///
+/// # Errors
+///
+/// Returns an error like `Stream::connect`.
+pub(crate) async fn connect_async(
+    tcp: tokio::net::TcpStream,
+    tls: Option<&TlsClientConfig>,
+    host: &str,
+) -> Result<AsyncHalves, SmppFfiError> {
+    let Some(tls) = tls else {
+        let (reader, writer) = tcp.into_split();
+        return Ok((Box::new(reader), Box::new(writer)));
+    };
+    #[cfg(feature = "tls")]
+    {
+        let connector = tokio_rustls::TlsConnector::from(rustls_config::client(tls)?);
+        let name = rustls_config::server_name(tls, host)?;
+        let stream = connector
+            .connect(name, tcp)
+            .await
+            .map_err(|e| SmppFfiError::Generic {
+                msg: format!("TLS handshake failed: {}", e),
+            })?;
+        let (reader, writer) = tokio::io::split(stream);
+        Ok((Box::new(reader), Box::new(writer)))
+    }
+    #[cfg(not(feature = "tls"))]
+    {
+        let _ = (tls, host);
+        Err(tls_unavailable())
+    }
+}
+
+/// Reads plaintext, pulling records off the socket without holding the session lock.
+#[cfg(feature = "tls")]
+fn read_tls(
+    mut tcp: &TcpStream,
+    tls: &Mutex<rustls::Connection>,
+    buf: &mut [u8],
+) -> io::Result<usize> {
+    // Small enough that one read never overflows rustls' plaintext buffer.
+    let mut incoming = [0u8; 4096];
+    loop {
+        match tls.lock().unwrap().reader().read(buf) {
+            Err(e) if e.kind() == io::ErrorKind::WouldBlock => {}
+            result => return result,
+        }
+        let read = tcp.read(&mut incoming)?;
+        if read == 0 {
+            return Ok(0);
+        }
+        let mut connection = tls.lock().unwrap();
+        let mut pending = &incoming[..read];
+        while !pending.is_empty() {
+            connection.read_tls(&mut pending)?;
+            connection
+                .process_new_packets()
+                .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+        }
+        while connection.wants_write() {
+            connection.write_tls(&mut tcp)?;
+        }
+    }
+}
+
+#[cfg(feature = "tls")]
+pub(crate) fn tls_error(e: rustls::Error) -> SmppFfiError {
+    SmppFfiError::Generic {
+        msg: format!("TLS error: {}", e),
+    }
+}
+
+#[cfg(not(feature = "tls"))]
+pub(crate) fn tls_unavailable() -> SmppFfiError {
+    SmppFfiError::Generic {
+        msg: "TLS support is not compiled in; build with the `tls` feature".to_string(),
+    }
+}
+
+/// Builds rustls configurations from the FFI settings.
+#[cfg(feature = "tls")]
+pub(crate) mod rustls_config {
+    use super::{tls_error, TlsClientConfig, TlsServerConfig};
+    use crate::common::SmppFfiError;
+    use rustls::client::danger::{HandshakeSignatureValid, ServerCertVerified, ServerCertVerifier};
+    use rustls::client::WebPkiServerVerifier;
+    use rustls::crypto::{ring, CryptoProvider};
+    use rustls::pki_types::pem::PemObject;
+    use rustls::pki_types::{CertificateDer, PrivateKeyDer, ServerName, UnixTime};
+    use rustls::server::WebPkiClientVerifier;
+    use rustls::{
+        CertificateError, ClientConfig, DigitallySignedStruct, Error, RootCertStore, ServerConfig,
+        SignatureScheme,
+    };
+    use std::sync::Arc;
+
+    fn provider() -> Arc<CryptoProvider> {
+        Arc::new(ring::default_provider())
+    }
+
+    pub(crate) fn client(config: &TlsClientConfig) -> Result<Arc<ClientConfig>, SmppFfiError> {
+        let provider = provider();
+        let mut roots = RootCertStore::empty();
+        if config.ca_certificates_pem.is_empty() {
+            roots.extend(webpki_roots::TLS_SERVER_ROOTS.iter().cloned());
+        } else {
+            for certificate in certificates(&config.ca_certificates_pem, "the CA bundle")? {
+                roots.add(certificate).map_err(tls_error)?;
+            }
+        }
+        let verifier =
+            WebPkiServerVerifier::builder_with_provider(Arc::new(roots), provider.clone())
+                .build()
+                .map_err(|e| SmppFfiError::Generic {
+                    msg: format!("TLS error: {}", e),
+                })?;
+        let builder = ClientConfig::builder_with_provider(provider)
+            .with_safe_default_protocol_versions()
+            .map_err(tls_error)?;
+        let builder = if config.verify_hostname {
+            builder.with_webpki_verifier(verifier)
+        } else {
+            builder
+                .dangerous()
+                .with_custom_certificate_verifier(Arc::new(AnyServerName(verifier)))
+        };
+        let client = if config.client_certificate_pem.is_empty() {
+            builder.with_no_client_auth()
+        } else {
+            builder
+                .with_client_auth_cert(
+                    certificates(&config.client_certificate_pem, "the client certificate")?,
+                    private_key(&config.client_key_pem)?,
+                )
+                .map_err(tls_error)?
+        };
+        Ok(Arc::new(client))
+    }
+
+    /// The name for SNI and certificate checks: `server_name`, or else the host.
+    pub(crate) fn server_name(
+        config: &TlsClientConfig,
+        host: &str,
+    ) -> Result<ServerName<'static>, SmppFfiError> {
+        let name = if config.server_name.is_empty() {
+            host
+        } else {
+            config.server_name.as_str()
+        };
+        ServerName::try_from(name.to_string()).map_err(|_| SmppFfiError::Generic {
+            msg: format!("Invalid TLS server name: {}", name),
+        })
+    }
+
+    pub(crate) fn server(config: &TlsServerConfig) -> Result<Arc<ServerConfig>, SmppFfiError> {
+        let provider = provider();
+        let builder = ServerConfig::builder_with_provider(provider.clone())
+            .with_safe_default_protocol_versions()
+            .map_err(tls_error)?;
+        let builder = if config.client_ca_certificates_pem.is_empty() {
+            builder.with_no_client_auth()
+        } else {
+            let mut roots = RootCertStore::empty();
+            for certificate in
+                certificates(&config.client_ca_certificates_pem, "the client CA bundle")?
+            {
+                roots.add(certificate).map_err(tls_error)?;
+            }
+            let verifier = WebPkiClientVerifier::builder_with_provider(Arc::new(roots), provider)
+                .build()
+                .map_err(|e| SmppFfiError::Generic {
+                    msg: format!("TLS error: {}", e),
+                })?;
+            builder.with_client_cert_verifier(verifier)
+        };
+        let server = builder
+            .with_single_cert(
+                certificates(&config.certificate_chain_pem, "the certificate chain")?,
+                private_key(&config.private_key_pem)?,
+            )
+            .map_err(tls_error)?;
+        Ok(Arc::new(server))
+    }
+
+    fn certificates(pem: &str, what: &str) -> Result<Vec<CertificateDer<'static>>, SmppFfiError> {
+        let certificates = CertificateDer::pem_slice_iter(pem.as_bytes())
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| SmppFfiError::Generic {
+                msg: format!("Invalid PEM in {}: {}", what, e),
+            })?;
+        if certificates.is_empty() {
+            return Err(SmppFfiError::Generic {
+                msg: format!("No certificate found in {}", what),
+            });
+        }
+        Ok(certificates)
+    }
+
+    fn private_key(pem: &str) -> Result<PrivateKeyDer<'static>, SmppFfiError> {
+        PrivateKeyDer::from_pem_slice(pem.as_bytes()).map_err(|e| SmppFfiError::Generic {
+            msg: format!("Invalid PEM private key: {}", e),
+        })
+    }
+
+    /// Checks the certificate chain like the WebPKI verifier, whatever name it is issued for.
+    #[derive(Debug)]
+    struct AnyServerName(Arc<WebPkiServerVerifier>);
+
+    impl ServerCertVerifier for AnyServerName {
+        fn verify_server_cert(
+            &self,
+            end_entity: &CertificateDer<'_>,
+            intermediates: &[CertificateDer<'_>],
+            server_name: &ServerName<'_>,
+            ocsp_response: &[u8],
+            now: UnixTime,
+        ) -> Result<ServerCertVerified, Error> {
+            // The name is checked last, so the chain has been verified when it fails.
+            match self.0.verify_server_cert(
+                end_entity,
+                intermediates,
+                server_name,
+                ocsp_response,
+                now,
+            ) {
+                Err(Error::InvalidCertificate(
+                    CertificateError::NotValidForName
+                    | CertificateError::NotValidForNameContext { .. },
+                )) => Ok(ServerCertVerified::assertion()),
+                result => result,
+            }
+        }
+
+        fn verify_tls12_signature(
+            &self,
+            message: &[u8],
+            cert: &CertificateDer<'_>,
+            dss: &DigitallySignedStruct,
+        ) -> Result<HandshakeSignatureValid, Error> {
+            self.0.verify_tls12_signature(message, cert, dss)
+        }
+
+        fn verify_tls13_signature(
+            &self,
+            message: &[u8],
+            cert: &CertificateDer<'_>,
+            dss: &DigitallySignedStruct,
+        ) -> Result<HandshakeSignatureValid, Error> {
+            self.0.verify_tls13_signature(message, cert, dss)
+        }
+
+        fn supported_verify_schemes(&self) -> Vec<SignatureScheme> {
+            self.0.supported_verify_schemes()
+        }
+    }
+}
@@ -102,9 +102,15 @@ impl Tags {
     /// Broadcast content type.
     pub const BROADCAST_CONTENT_TYPE: u16 = native_tags::BROADCAST_CONTENT_TYPE;
     /// Broadcast repetition number.
-    pub const BROADCAST_REP_NUM: u16 = native_tags::BROADCAST_REP_NUM;
+    ///
+    /// SMPP v5.0 assigns 0x0604; `smpp_codec` uses 0x0602, which is
+    /// `broadcast_content_type_info`.
+    pub const BROADCAST_REP_NUM: u16 = 0x0604;
     /// Broadcast frequency interval.
-    pub const BROADCAST_FREQUENCY_INTERVAL: u16 = native_tags::BROADCAST_FREQUENCY_INTERVAL;
+    ///
+    /// SMPP v5.0 assigns 0x0605; `smpp_codec` uses 0x0603, which is
+    /// `broadcast_message_class`.
+    pub const BROADCAST_FREQUENCY_INTERVAL: u16 = 0x0605;
     /// Broadcast area success.
     pub const BROADCAST_AREA_SUCCESS: u16 = native_tags::BROADCAST_AREA_SUCCESS;
     /// Broadcast end time.
@@ -113,11 +119,64 @@ impl Tags {
     pub const BROADCAST_SERVICE_GROUP: u16 = native_tags::BROADCAST_SERVICE_GROUP;
     /// Broadcast channel indicator.
     pub const BROADCAST_CHANNEL_INDICATOR: u16 = native_tags::BROADCAST_CHANNEL_INDICATOR;
+    /// Broadcast content type info.
+    pub const BROADCAST_CONTENT_TYPE_INFO: u16 = 0x0602;
+    /// Broadcast message class.
+    pub const BROADCAST_MESSAGE_CLASS: u16 = 0x0603;
+    /// Broadcast error status.
+    pub const BROADCAST_ERROR_STATUS: u16 = 0x0607;
+    /// Billing identification.
+    pub const BILLING_IDENTIFICATION: u16 = 0x060B;
+    /// Source network ID.
+    pub const SOURCE_NETWORK_ID: u16 = 0x060D;
+    /// Destination network ID.
+    pub const DEST_NETWORK_ID: u16 = 0x060E;
+    /// Source node ID.
+    pub const SOURCE_NODE_ID: u16 = 0x060F;
+    /// Destination node ID.
+    pub const DEST_NODE_ID: u16 = 0x0610;
+    /// Destination address number portability resolution.
+    pub const DEST_ADDR_NP_RESOLUTION: u16 = 0x0611;
+    /// Destination address number portability information.
+    pub const DEST_ADDR_NP_INFORMATION: u16 = 0x0612;
+    /// Destination address number portability country.
+    pub const DEST_ADDR_NP_COUNTRY: u16 = 0x0613;
 }
 
 /// Gets the tag value by its name.
+///
+/// Returns 0 for unknown names.
 pub fn get_tag_by_name(name: &str) -> u16 {
-    smpp_codec::tlv::get_tag_by_name(name)
+    match name.to_ascii_lowercase().as_str() {
+        "broadcast_rep_num" => Tags::BROADCAST_REP_NUM,
+        "broadcast_frequency_interval" => Tags::BROADCAST_FREQUENCY_INTERVAL,
+        "broadcast_content_type_info" => Tags::BROADCAST_CONTENT_TYPE_INFO,
+        "broadcast_message_class" => Tags::BROADCAST_MESSAGE_CLASS,
+        "broadcast_error_status" => Tags::BROADCAST_ERROR_STATUS,
+        "billing_identification" => Tags::BILLING_IDENTIFICATION,
+        "source_network_id" => Tags::SOURCE_NETWORK_ID,
+        "dest_network_id" => Tags::DEST_NETWORK_ID,
+        "source_node_id" => Tags::SOURCE_NODE_ID,
+        "dest_node_id" => Tags::DEST_NODE_ID,
+        "dest_addr_np_resolution" => Tags::DEST_ADDR_NP_RESOLUTION,
+        "dest_addr_np_information" => Tags::DEST_ADDR_NP_INFORMATION,
+        "dest_addr_np_country" => Tags::DEST_ADDR_NP_COUNTRY,
+        _ => smpp_codec::tlv::get_tag_by_name(name),
+    }
+}
+
+/// Gets the name of a standard tag, e.g. `sar_msg_ref_num`.
+///
+/// Returns `None` for unknown and vendor-specific tags.
+pub fn get_tag_name(tag: u16) -> Option<&'static str> {
+    crate::validation::tlv_rule(tag).map(|(name, _, _)| name)
+}
+
+/// Gets the allowed value length of a standard tag as `(min, max)` octets.
+///
+/// Returns `None` for unknown and vendor-specific tags.
+pub fn get_tag_value_length(tag: u16) -> Option<(usize, usize)> {
+    crate::validation::tlv_rule(tag).map(|(_, min, max)| (min, max))
 }
 
 /// Tag-Length-Value (TLV) Parameter
@@ -160,7 +219,7 @@ pub fn tlv_new(tag: u16, value: Vec<u8>) -> Tlv {
 /// Creates a new TLV with a tag name and value.
 #[uniffi::export]
 pub fn tlv_new_from_name(name: &str, value: Vec<u8>) -> Tlv {
-    NativeTlv::new_from_name(name, value).into()
+    NativeTlv::new(get_tag_by_name(name), value).into()
 }
 
 /// Creates a new TLV with a u8 value.
@@ -172,7 +231,7 @@ pub fn tlv_new_u8(tag: u16, value: u8) -> Tlv {
 /// Creates a new TLV with a tag name and u8 value.
 #[uniffi::export]
 pub fn tlv_new_u8_from_name(name: &str, value: u8) -> Tlv {
-    NativeTlv::new_u8_from_name(name, value).into()
+    NativeTlv::new_u8(get_tag_by_name(name), value).into()
 }
 
 /// Creates a new TLV with a u16 value.
@@ -184,7 +243,13 @@ pub fn tlv_new_u16(tag: u16, value: u16) -> Tlv {
 /// Creates a new TLV with a tag name and u16 value.
 #[uniffi::export]
 pub fn tlv_new_u16_from_name(name: &str, value: u16) -> Tlv {
-    NativeTlv::new_u16_from_name(name, value).into()
+    NativeTlv::new_u16(get_tag_by_name(name), value).into()
+}
+
+/// Creates a new TLV with a u32 value.
+#[uniffi::export]
+pub fn tlv_new_u32(tag: u16, value: u32) -> Tlv {
+    NativeTlv::new(tag, value.to_be_bytes().to_vec()).into()
 }
 
 /// Creates a new TLV with a string value.
@@ -262,6 +327,23 @@ pub fn tlv_value_as_u16(tlv: &Tlv) -> Result<u16, SmppFfiError> {
         .map_err(|e| SmppFfiError::Generic { msg: e.to_string() })
 }
 
+/// Extracts the value of the TLV as a u32.
+///
+/// # Errors
+///
+/// Returns an error if the value is not 4 octets long.
+#[uniffi::export]
+pub fn tlv_value_as_u32(tlv: &Tlv) -> Result<u32, SmppFfiError> {
+    let bytes: [u8; 4] = tlv
+        .value
+        .as_slice()
+        .try_into()
+        .map_err(|_| SmppFfiError::Generic {
+            msg: format!("Expected 4 octets, got {}", tlv.value.len()),
+        })?;
+    Ok(u32::from_be_bytes(bytes))
+}
+
 /// Extracts the value of the TLV as a String.
 ///
 /// # Errors
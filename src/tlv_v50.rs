@@ -0,0 +1,772 @@
+//! # SMPP v5.0 TLVs
+//!
+//! Typed constructors and accessors for the TLVs introduced by SMPP v5.0,
+//! so host code never has to lay out their value bytes by hand.
+use crate::common::SmppFfiError;
+use crate::tlv::{tlv_new, tlv_new_u16, tlv_new_u32, tlv_new_u8, tlv_value_as_u32, Tags, Tlv};
+
+/// Billing information passed from the ESME to the MC.
+#[derive(uniffi::Record, Clone, Debug, PartialEq, Eq)]
+pub struct BillingIdentification {
+    /// Format of the billing information: 0x01 internal, 0x80-0xFF vendor specific.
+    pub format: u8,
+    /// The billing information.
+    pub value: Vec<u8>,
+}
+
+/// Result of a number portability query (`dest_addr_np_resolution`).
+#[derive(uniffi::Enum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum NumberPortabilityResolution {
+    /// Query not performed
+    QueryNotPerformed = 0,
+    /// Query performed, number not ported
+    NotPorted = 1,
+    /// Query performed, number ported
+    Ported = 2,
+}
+
+/// USSD service operation (`ussd_service_op`).
+#[derive(uniffi::Enum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum UssdServiceOp {
+    /// PSSD indication
+    PssdIndication,
+    /// PSSR indication
+    PssrIndication,
+    /// USSR request
+    UssrRequest,
+    /// USSN request
+    UssnRequest,
+    /// PSSD response
+    PssdResponse,
+    /// PSSR response
+    PssrResponse,
+    /// USSR confirm
+    UssrConfirm,
+    /// USSN confirm
+    UssnConfirm,
+    /// Reserved or vendor specific operation
+    Other {
+        /// The raw operation value.
+        value: u8,
+    },
+}
+
+impl UssdServiceOp {
+    /// Returns the octet value of the operation.
+    pub fn value(self) -> u8 {
+        match self {
+            UssdServiceOp::PssdIndication => 0,
+            UssdServiceOp::PssrIndication => 1,
+            UssdServiceOp::UssrRequest => 2,
+            UssdServiceOp::UssnRequest => 3,
+            UssdServiceOp::PssdResponse => 16,
+            UssdServiceOp::PssrResponse => 17,
+            UssdServiceOp::UssrConfirm => 18,
+            UssdServiceOp::UssnConfirm => 19,
+            UssdServiceOp::Other { value } => value,
+        }
+    }
+}
+
+impl From<u8> for UssdServiceOp {
+    fn from(value: u8) -> Self {
+        match value {
+            0 => UssdServiceOp::PssdIndication,
+            1 => UssdServiceOp::PssrIndication,
+            2 => UssdServiceOp::UssrRequest,
+            3 => UssdServiceOp::UssnRequest,
+            16 => UssdServiceOp::PssdResponse,
+            17 => UssdServiceOp::PssrResponse,
+            18 => UssdServiceOp::UssrConfirm,
+            19 => UssdServiceOp::UssnConfirm,
+            value => UssdServiceOp::Other { value },
+        }
+    }
+}
+
+/// Broadcast channel (`broadcast_channel_indicator`).
+#[derive(uniffi::Enum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BroadcastChannelIndicator {
+    /// Basic broadcast channel
+    Basic = 0,
+    /// Extended broadcast channel
+    Extended = 1,
+}
+
+/// Broadcast message class (`broadcast_message_class`).
+#[derive(uniffi::Enum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BroadcastMessageClass {
+    /// No class specified
+    NoClass = 0,
+    /// Class 1 (User defined)
+    Class1 = 1,
+    /// Class 2 (User defined)
+    Class2 = 2,
+    /// Class 3 (Terminal equipment)
+    Class3 = 3,
+}
+
+/// Format of a broadcast area (first octet of `broadcast_area_identifier`).
+#[derive(uniffi::Enum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BroadcastAreaFormat {
+    /// Alias, name or identifier of the area
+    AliasName = 0x00,
+    /// Ellipsoid arc
+    EllipsoidArc = 0x01,
+    /// Polygon
+    Polygon = 0x02,
+}
+
+/// Area targeted by a broadcast (`broadcast_area_identifier`).
+#[derive(uniffi::Record, Clone, Debug, PartialEq, Eq)]
+pub struct BroadcastAreaIdentifier {
+    /// Format of the area.
+    pub format: BroadcastAreaFormat,
+    /// The area, encoded as required by the format.
+    pub area: Vec<u8>,
+}
+
+/// Network type of a broadcast content type.
+#[derive(uniffi::Enum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BroadcastNetworkType {
+    /// Generic
+    Generic = 0x00,
+    /// GSM (3GPP 23.041 Cell Broadcast)
+    Gsm = 0x01,
+    /// TDMA (IS-824)
+    Tdma = 0x02,
+    /// CDMA (IS-637)
+    Cdma = 0x03,
+}
+
+/// Content of a broadcast (`broadcast_content_type`).
+#[derive(uniffi::Record, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct BroadcastContentType {
+    /// The network the service applies to.
+    pub network_type: BroadcastNetworkType,
+    /// The broadcast service, e.g. 0x0001 for Emergency Broadcasts.
+    pub service: u16,
+}
+
+/// Time unit of a broadcast frequency interval.
+#[derive(uniffi::Enum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BroadcastFrequencyUnit {
+    /// As frequently as possible
+    AsFrequentlyAsPossible = 0x00,
+    /// Seconds
+    Seconds = 0x08,
+    /// Minutes
+    Minutes = 0x09,
+    /// Hours
+    Hours = 0x0A,
+    /// Days
+    Days = 0x0B,
+    /// Weeks
+    Weeks = 0x0C,
+    /// Months
+    Months = 0x0D,
+    /// Years
+    Years = 0x0E,
+}
+
+/// Interval between broadcast repetitions (`broadcast_frequency_interval`).
+#[derive(uniffi::Record, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct BroadcastFrequencyInterval {
+    /// Time unit of the interval.
+    pub unit: BroadcastFrequencyUnit,
+    /// Number of units between repetitions.
+    pub value: u16,
+}
+
+/// Maximum size of a network ID, NULL included.
+const NETWORK_ID_MAX_LEN: usize = 65;
+/// Number of decimal digits in a node ID.
+const NODE_ID_LEN: usize = 6;
+/// Number of octets in `dest_addr_np_information`.
+const NP_INFORMATION_LEN: usize = 10;
+/// Number of octets in `dest_addr_np_country`.
+const NP_COUNTRY_LEN: usize = 5;
+/// Value of `broadcast_area_success` when the rate is not available.
+const AREA_SUCCESS_NOT_AVAILABLE: u8 = 255;
+
+/// Creates a `billing_identification` TLV.
+#[uniffi::export]
+pub fn tlv_new_billing_identification(billing: BillingIdentification) -> Tlv {
+    let mut value = Vec::with_capacity(billing.value.len() + 1);
+    value.push(billing.format);
+    value.extend_from_slice(&billing.value);
+    tlv_new(Tags::BILLING_IDENTIFICATION, value)
+}
+
+/// Extracts a `billing_identification` TLV.
+///
+/// # Errors
+///
+/// Returns an error if the TLV has another tag or an empty value.
+#[uniffi::export]
+pub fn tlv_value_as_billing_identification(
+    tlv: &Tlv,
+) -> Result<BillingIdentification, SmppFfiError> {
+    check_tag(tlv, &[Tags::BILLING_IDENTIFICATION])?;
+    match tlv.value.split_first() {
+        Some((format, value)) => Ok(BillingIdentification {
+            format: *format,
+            value: value.to_vec(),
+        }),
+        None => Err(SmppFfiError::Generic {
+            msg: "billing_identification is empty".to_string(),
+        }),
+    }
+}
+
+/// Creates a `source_network_id` TLV.
+///
+/// # Errors
+///
+/// Returns an error if the ID is longer than 64 characters.
+#[uniffi::export]
+pub fn tlv_new_source_network_id(network_id: String) -> Result<Tlv, SmppFfiError> {
+    network_id_tlv(Tags::SOURCE_NETWORK_ID, network_id)
+}
+
+/// Creates a `dest_network_id` TLV.
+///
+/// # Errors
+///
+/// Returns an error if the ID is longer than 64 characters.
+#[uniffi::export]
+pub fn tlv_new_dest_network_id(network_id: String) -> Result<Tlv, SmppFfiError> {
+    network_id_tlv(Tags::DEST_NETWORK_ID, network_id)
+}
+
+/// Extracts a `source_network_id` or `dest_network_id` TLV.
+///
+/// # Errors
+///
+/// Returns an error if the TLV has another tag or is not a C-Octet String.
+#[uniffi::export]
+pub fn tlv_value_as_network_id(tlv: &Tlv) -> Result<String, SmppFfiError> {
+    check_tag(tlv, &[Tags::SOURCE_NETWORK_ID, Tags::DEST_NETWORK_ID])?;
+    c_octet_value(tlv)
+}
+
+/// Creates a `source_node_id` TLV from a 6 digit node ID.
+///
+/// # Errors
+///
+/// Returns an error if the ID is not exactly 6 decimal digits.
+#[uniffi::export]
+pub fn tlv_new_source_node_id(node_id: String) -> Result<Tlv, SmppFfiError> {
+    node_id_tlv(Tags::SOURCE_NODE_ID, node_id)
+}
+
+/// Creates a `dest_node_id` TLV from a 6 digit node ID.
+///
+/// # Errors
+///
+/// Returns an error if the ID is not exactly 6 decimal digits.
+#[uniffi::export]
+pub fn tlv_new_dest_node_id(node_id: String) -> Result<Tlv, SmppFfiError> {
+    node_id_tlv(Tags::DEST_NODE_ID, node_id)
+}
+
+/// Extracts a `source_node_id` or `dest_node_id` TLV.
+///
+/// # Errors
+///
+/// Returns an error if the TLV has another tag or is not 6 decimal digits.
+#[uniffi::export]
+pub fn tlv_value_as_node_id(tlv: &Tlv) -> Result<String, SmppFfiError> {
+    check_tag(tlv, &[Tags::SOURCE_NODE_ID, Tags::DEST_NODE_ID])?;
+    let node_id = String::from_utf8(tlv.value.clone()).unwrap_or_default();
+    check_node_id(&node_id)?;
+    Ok(node_id)
+}
+
+/// Creates a `dest_addr_np_resolution` TLV.
+#[uniffi::export]
+pub fn tlv_new_dest_addr_np_resolution(resolution: NumberPortabilityResolution) -> Tlv {
+    tlv_new_u8(Tags::DEST_ADDR_NP_RESOLUTION, resolution as u8)
+}
+
+/// Extracts a `dest_addr_np_resolution` TLV.
+///
+/// # Errors
+///
+/// Returns an error if the TLV has another tag or an unknown value.
+#[uniffi::export]
+pub fn tlv_value_as_dest_addr_np_resolution(
+    tlv: &Tlv,
+) -> Result<NumberPortabilityResolution, SmppFfiError> {
+    check_tag(tlv, &[Tags::DEST_ADDR_NP_RESOLUTION])?;
+    match single_octet(tlv)? {
+        0 => Ok(NumberPortabilityResolution::QueryNotPerformed),
+        1 => Ok(NumberPortabilityResolution::NotPorted),
+        2 => Ok(NumberPortabilityResolution::Ported),
+        other => Err(SmppFfiError::Generic {
+            msg: format!("Unknown dest_addr_np_resolution: {}", other),
+        }),
+    }
+}
+
+/// Creates a `dest_addr_np_information` TLV.
+///
+/// # Errors
+///
+/// Returns an error if the information is not exactly 10 octets.
+#[uniffi::export]
+pub fn tlv_new_dest_addr_np_information(information: Vec<u8>) -> Result<Tlv, SmppFfiError> {
+    if information.len() != NP_INFORMATION_LEN {
+        return Err(SmppFfiError::Generic {
+            msg: format!(
+                "dest_addr_np_information must be {} octets, got {}",
+                NP_INFORMATION_LEN,
+                information.len()
+            ),
+        });
+    }
+    Ok(tlv_new(Tags::DEST_ADDR_NP_INFORMATION, information))
+}
+
+/// Extracts a `dest_addr_np_information` TLV.
+///
+/// # Errors
+///
+/// Returns an error if the TLV has another tag or is not 10 octets.
+#[uniffi::export]
+pub fn tlv_value_as_dest_addr_np_information(tlv: &Tlv) -> Result<Vec<u8>, SmppFfiError> {
+    check_tag(tlv, &[Tags::DEST_ADDR_NP_INFORMATION])?;
+    if tlv.value.len() != NP_INFORMATION_LEN {
+        return Err(SmppFfiError::Generic {
+            msg: format!(
+                "dest_addr_np_information must be {} octets, got {}",
+                NP_INFORMATION_LEN,
+                tlv.value.len()
+            ),
+        });
+    }
+    Ok(tlv.value.clone())
+}
+
+/// Creates a `dest_addr_np_country` TLV from an E.164 country code.
+///
+/// # Errors
+///
+/// Returns an error if the country code is 0 or longer than 3 digits.
+#[uniffi::export]
+pub fn tlv_new_dest_addr_np_country(country_code: u32) -> Result<Tlv, SmppFfiError> {
+    if country_code == 0 || country_code > 999 {
+        return Err(SmppFfiError::Generic {
+            msg: format!("Invalid E.164 country code: {}", country_code),
+        });
+    }
+    let mut value = vec![0u8; NP_COUNTRY_LEN - 4];
+    value.extend_from_slice(&country_code.to_be_bytes());
+    Ok(tlv_new(Tags::DEST_ADDR_NP_COUNTRY, value))
+}
+
+/// Extracts the E.164 country code of a `dest_addr_np_country` TLV.
+///
+/// # Errors
+///
+/// Returns an error if the TLV has another tag or is not 5 octets.
+#[uniffi::export]
+pub fn tlv_value_as_dest_addr_np_country(tlv: &Tlv) -> Result<u32, SmppFfiError> {
+    check_tag(tlv, &[Tags::DEST_ADDR_NP_COUNTRY])?;
+    if tlv.value.len() != NP_COUNTRY_LEN || tlv.value[0] != 0 {
+        return Err(SmppFfiError::Generic {
+            msg: "dest_addr_np_country must be a 5 octet country code".to_string(),
+        });
+    }
+    Ok(u32::from_be_bytes([
+        tlv.value[1],
+        tlv.value[2],
+        tlv.value[3],
+        tlv.value[4],
+    ]))
+}
+
+/// Creates a `congestion_state` TLV.
+///
+/// # Errors
+///
+/// Returns an error if the state is above 100.
+#[uniffi::export]
+pub fn tlv_new_congestion_state(state: u8) -> Result<Tlv, SmppFfiError> {
+    check_percentage("congestion_state", state)?;
+    Ok(tlv_new_u8(Tags::CONGESTION_STATE, state))
+}
+
+/// Extracts a `congestion_state` TLV.
+///
+/// # Errors
+///
+/// Returns an error if the TLV has another tag or a value above 100.
+#[uniffi::export]
+pub fn tlv_value_as_congestion_state(tlv: &Tlv) -> Result<u8, SmppFfiError> {
+    check_tag(tlv, &[Tags::CONGESTION_STATE])?;
+    let state = single_octet(tlv)?;
+    check_percentage("congestion_state", state)?;
+    Ok(state)
+}
+
+/// Creates a `ussd_service_op` TLV.
+#[uniffi::export]
+pub fn tlv_new_ussd_service_op(op: UssdServiceOp) -> Tlv {
+    tlv_new_u8(Tags::USSD_SERVICE_OP, op.value())
+}
+
+/// Extracts a `ussd_service_op` TLV.
+///
+/// # Errors
+///
+/// Returns an error if the TLV has another tag or is not 1 octet.
+#[uniffi::export]
+pub fn tlv_value_as_ussd_service_op(tlv: &Tlv) -> Result<UssdServiceOp, SmppFfiError> {
+    check_tag(tlv, &[Tags::USSD_SERVICE_OP])?;
+    Ok(single_octet(tlv)?.into())
+}
+
+/// Creates a `broadcast_channel_indicator` TLV.
+#[uniffi::export]
+pub fn tlv_new_broadcast_channel_indicator(channel: BroadcastChannelIndicator) -> Tlv {
+    tlv_new_u8(Tags::BROADCAST_CHANNEL_INDICATOR, channel as u8)
+}
+
+/// Extracts a `broadcast_channel_indicator` TLV.
+///
+/// # Errors
+///
+/// Returns an error if the TLV has another tag or an unknown value.
+#[uniffi::export]
+pub fn tlv_value_as_broadcast_channel_indicator(
+    tlv: &Tlv,
+) -> Result<BroadcastChannelIndicator, SmppFfiError> {
+    check_tag(tlv, &[Tags::BROADCAST_CHANNEL_INDICATOR])?;
+    match single_octet(tlv)? {
+        0 => Ok(BroadcastChannelIndicator::Basic),
+        1 => Ok(BroadcastChannelIndicator::Extended),
+        other => Err(SmppFfiError::Generic {
+            msg: format!("Unknown broadcast_channel_indicator: {}", other),
+        }),
+    }
+}
+
+/// Creates a `broadcast_message_class` TLV.
+#[uniffi::export]
+pub fn tlv_new_broadcast_message_class(class: BroadcastMessageClass) -> Tlv {
+    tlv_new_u8(Tags::BROADCAST_MESSAGE_CLASS, class as u8)
+}
+
+/// Extracts a `broadcast_message_class` TLV.
+///
+/// # Errors
+///
+/// Returns an error if the TLV has another tag or an unknown value.
+#[uniffi::export]
+pub fn tlv_value_as_broadcast_message_class(
+    tlv: &Tlv,
+) -> Result<BroadcastMessageClass, SmppFfiError> {
+    check_tag(tlv, &[Tags::BROADCAST_MESSAGE_CLASS])?;
+    match single_octet(tlv)? {
+        0 => Ok(BroadcastMessageClass::NoClass),
+        1 => Ok(BroadcastMessageClass::Class1),
+        2 => Ok(BroadcastMessageClass::Class2),
+        3 => Ok(BroadcastMessageClass::Class3),
+        other => Err(SmppFfiError::Generic {
+            msg: format!("Unknown broadcast_message_class: {}", other),
+        }),
+    }
+}
+
+/// Creates a `broadcast_area_identifier` TLV.
+#[uniffi::export]
+pub fn tlv_new_broadcast_area_identifier(area: BroadcastAreaIdentifier) -> Tlv {
+    let mut value = Vec::with_capacity(area.area.len() + 1);
+    value.push(area.format as u8);
+    value.extend_from_slice(&area.area);
+    tlv_new(Tags::BROADCAST_AREA_IDENTIFIER, value)
+}
+
+/// Extracts a `broadcast_area_identifier` TLV.
+///
+/// # Errors
+///
+/// Returns an error if the TLV has another tag, is empty or has an unknown format.
+#[uniffi::export]
+pub fn tlv_value_as_broadcast_area_identifier(
+    tlv: &Tlv,
+) -> Result<BroadcastAreaIdentifier, SmppFfiError> {
+    check_tag(tlv, &[Tags::BROADCAST_AREA_IDENTIFIER])?;
+    let (format, area) = tlv.value.split_first().ok_or(SmppFfiError::Generic {
+        msg: "broadcast_area_identifier is empty".to_string(),
+    })?;
+    let format = match format {
+        0x00 => BroadcastAreaFormat::AliasName,
+        0x01 => BroadcastAreaFormat::EllipsoidArc,
+        0x02 => BroadcastAreaFormat::Polygon,
+        other => {
+            return Err(SmppFfiError::Generic {
+                msg: format!("Unknown broadcast area format: 0x{:02X}", other),
+            })
+        }
+    };
+    Ok(BroadcastAreaIdentifier {
+        format,
+        area: area.to_vec(),
+    })
+}
+
+/// Creates a `broadcast_content_type` TLV.
+#[uniffi::export]
+pub fn tlv_new_broadcast_content_type(content_type: BroadcastContentType) -> Tlv {
+    let mut value = vec![content_type.network_type as u8];
+    value.extend_from_slice(&content_type.service.to_be_bytes());
+    tlv_new(Tags::BROADCAST_CONTENT_TYPE, value)
+}
+
+/// Extracts a `broadcast_content_type` TLV.
+///
+/// # Errors
+///
+/// Returns an error if the TLV has another tag, is not 3 octets or has an
+/// unknown network type.
+#[uniffi::export]
+pub fn tlv_value_as_broadcast_content_type(
+    tlv: &Tlv,
+) -> Result<BroadcastContentType, SmppFfiError> {
+    check_tag(tlv, &[Tags::BROADCAST_CONTENT_TYPE])?;
+    let [network_type, high, low] = three_octets(tlv)?;
+    let network_type = match network_type {
+        0x00 => BroadcastNetworkType::Generic,
+        0x01 => BroadcastNetworkType::Gsm,
+        0x02 => BroadcastNetworkType::Tdma,
+        0x03 => BroadcastNetworkType::Cdma,
+        other => {
+            return Err(SmppFfiError::Generic {
+                msg: format!("Unknown broadcast network type: 0x{:02X}", other),
+            })
+        }
+    };
+    Ok(BroadcastContentType {
+        network_type,
+        service: u16::from_be_bytes([high, low]),
+    })
+}
+
+/// Creates a `broadcast_frequency_interval` TLV.
+#[uniffi::export]
+pub fn tlv_new_broadcast_frequency_interval(interval: BroadcastFrequencyInterval) -> Tlv {
+    let mut value = vec![interval.unit as u8];
+    value.extend_from_slice(&interval.value.to_be_bytes());
+    tlv_new(Tags::BROADCAST_FREQUENCY_INTERVAL, value)
+}
+
+/// Extracts a `broadcast_frequency_interval` TLV.
+///
+/// # Errors
+///
+/// Returns an error if the TLV has another tag, is not 3 octets or has an
+/// unknown time unit.
+#[uniffi::export]
+pub fn tlv_value_as_broadcast_frequency_interval(
+    tlv: &Tlv,
+) -> Result<BroadcastFrequencyInterval, SmppFfiError> {
+    check_tag(tlv, &[Tags::BROADCAST_FREQUENCY_INTERVAL])?;
+    let [unit, high, low] = three_octets(tlv)?;
+    let unit = match unit {
+        0x00 => BroadcastFrequencyUnit::AsFrequentlyAsPossible,
+        0x08 => BroadcastFrequencyUnit::Seconds,
+        0x09 => BroadcastFrequencyUnit::Minutes,
+        0x0A => BroadcastFrequencyUnit::Hours,
+        0x0B => BroadcastFrequencyUnit::Days,
+        0x0C => BroadcastFrequencyUnit::Weeks,
+        0x0D => BroadcastFrequencyUnit::Months,
+        0x0E => BroadcastFrequencyUnit::Years,
+        other => {
+            return Err(SmppFfiError::Generic {
+                msg: format!("Unknown broadcast frequency unit: 0x{:02X}", other),
+            })
+        }
+    };
+    Ok(BroadcastFrequencyInterval {
+        unit,
+        value: u16::from_be_bytes([high, low]),
+    })
+}
+
+/// Creates a `broadcast_rep_num` TLV.
+#[uniffi::export]
+pub fn tlv_new_broadcast_rep_num(rep_num: u16) -> Tlv {
+    tlv_new_u16(Tags::BROADCAST_REP_NUM, rep_num)
+}
+
+/// Creates a `broadcast_area_success` TLV.
+///
+/// `None` means the success rate is not available.
+///
+/// # Errors
+///
+/// Returns an error if the rate is above 100.
+#[uniffi::export]
+pub fn tlv_new_broadcast_area_success(rate: Option<u8>) -> Result<Tlv, SmppFfiError> {
+    let value = match rate {
+        Some(rate) => {
+            check_percentage("broadcast_area_success", rate)?;
+            rate
+        }
+        None => AREA_SUCCESS_NOT_AVAILABLE,
+    };
+    Ok(tlv_new_u8(Tags::BROADCAST_AREA_SUCCESS, value))
+}
+
+/// Extracts a `broadcast_area_success` TLV.
+///
+/// Returns `None` when the success rate is not available.
+///
+/// # Errors
+///
+/// Returns an error if the TLV has another tag or a reserved value.
+#[uniffi::export]
+pub fn tlv_value_as_broadcast_area_success(tlv: &Tlv) -> Result<Option<u8>, SmppFfiError> {
+    check_tag(tlv, &[Tags::BROADCAST_AREA_SUCCESS])?;
+    match single_octet(tlv)? {
+        AREA_SUCCESS_NOT_AVAILABLE => Ok(None),
+        rate => {
+            check_percentage("broadcast_area_success", rate)?;
+            Ok(Some(rate))
+        }
+    }
+}
+
+/// Creates a `broadcast_end_time` TLV from an SMPP absolute time.
+///
+/// # Errors
+///
+/// Returns an error if the time is not a 16 character absolute time.
+#[uniffi::export]
+pub fn tlv_new_broadcast_end_time(end_time: String) -> Result<Tlv, SmppFfiError> {
+    check_absolute_time(&end_time)?;
+    let mut value = end_time.into_bytes();
+    value.push(0);
+    Ok(tlv_new(Tags::BROADCAST_END_TIME, value))
+}
+
+/// Extracts a `broadcast_end_time` TLV.
+///
+/// # Errors
+///
+/// Returns an error if the TLV has another tag or is not an absolute time.
+#[uniffi::export]
+pub fn tlv_value_as_broadcast_end_time(tlv: &Tlv) -> Result<String, SmppFfiError> {
+    check_tag(tlv, &[Tags::BROADCAST_END_TIME])?;
+    let end_time = c_octet_value(tlv)?;
+    check_absolute_time(&end_time)?;
+    Ok(end_time)
+}
+
+/// Creates a `broadcast_error_status` TLV.
+#[uniffi::export]
+pub fn tlv_new_broadcast_error_status(command_status: u32) -> Tlv {
+    tlv_new_u32(Tags::BROADCAST_ERROR_STATUS, command_status)
+}
+
+/// Extracts a `broadcast_error_status` TLV.
+///
+/// # Errors
+///
+/// Returns an error if the TLV has another tag or is not 4 octets.
+#[uniffi::export]
+pub fn tlv_value_as_broadcast_error_status(tlv: &Tlv) -> Result<u32, SmppFfiError> {
+    check_tag(tlv, &[Tags::BROADCAST_ERROR_STATUS])?;
+    tlv_value_as_u32(tlv)
+}
+
+fn check_tag(tlv: &Tlv, expected: &[u16]) -> Result<(), SmppFfiError> {
+    if !expected.contains(&tlv.tag) {
+        return Err(SmppFfiError::Generic {
+            msg: format!("Unexpected TLV tag 0x{:04X}", tlv.tag),
+        });
+    }
+    Ok(())
+}
+
+fn single_octet(tlv: &Tlv) -> Result<u8, SmppFfiError> {
+    match tlv.value.as_slice() {
+        [value] => Ok(*value),
+        value => Err(SmppFfiError::Generic {
+            msg: format!("Expected 1 octet, got {}", value.len()),
+        }),
+    }
+}
+
+fn three_octets(tlv: &Tlv) -> Result<[u8; 3], SmppFfiError> {
+    tlv.value
+        .as_slice()
+        .try_into()
+        .map_err(|_| SmppFfiError::Generic {
+            msg: format!("Expected 3 octets, got {}", tlv.value.len()),
+        })
+}
+
+fn c_octet_value(tlv: &Tlv) -> Result<String, SmppFfiError> {
+    let value = match tlv.value.split_last() {
+        Some((0, value)) => value,
+        _ => tlv.value.as_slice(),
+    };
+    String::from_utf8(value.to_vec()).map_err(|e| SmppFfiError::Generic { msg: e.to_string() })
+}
+
+fn check_percentage(field: &str, value: u8) -> Result<(), SmppFfiError> {
+    if value > 100 {
+        return Err(SmppFfiError::Generic {
+            msg: format!("{} must be between 0 and 100, got {}", field, value),
+        });
+    }
+    Ok(())
+}
+
+fn check_node_id(node_id: &str) -> Result<(), SmppFfiError> {
+    if node_id.len() != NODE_ID_LEN || !node_id.chars().all(|c| c.is_ascii_digit()) {
+        return Err(SmppFfiError::Generic {
+            msg: format!(
+                "Node ID '{}' must be {} decimal digits",
+                node_id, NODE_ID_LEN
+            ),
+        });
+    }
+    Ok(())
+}
+
+fn check_absolute_time(time: &str) -> Result<(), SmppFfiError> {
+    let bytes = time.as_bytes();
+    let valid = bytes.len() == 16
+        && bytes[..15].iter().all(u8::is_ascii_digit)
+        && matches!(bytes[15], b'+' | b'-');
+    if !valid {
+        return Err(SmppFfiError::Generic {
+            msg: format!("'{}' is not an SMPP absolute time", time),
+        });
+    }
+    Ok(())
+}
+
+fn network_id_tlv(tag: u16, network_id: String) -> Result<Tlv, SmppFfiError> {
+    if network_id.len() + 1 > NETWORK_ID_MAX_LEN {
+        return Err(SmppFfiError::Generic {
+            msg: format!(
+                "Network ID is {} octets, maximum is {}",
+                network_id.len(),
+                NETWORK_ID_MAX_LEN - 1
+            ),
+        });
+    }
+    let mut value = network_id.into_bytes();
+    value.push(0);
+    Ok(tlv_new(tag, value))
+}
+
+fn node_id_tlv(tag: u16, node_id: String) -> Result<Tlv, SmppFfiError> {
+    check_node_id(&node_id)?;
+    Ok(tlv_new(tag, node_id.into_bytes()))
+}
@@ -0,0 +1,711 @@
+//! # Strict Validation
+//!
+//! Checks a PDU against the limits of the SMPP specification, beyond what the
+//! codec itself enforces. Each issue carries the `command_status` an SMSC
+//! would use to reject the PDU.
+use crate::broadcast_pdu_ffi::MANDATORY_BROADCAST_TLVS;
+use crate::common::*;
+use crate::pdu::Pdu;
+use crate::submission_pdu_ffi::Destination;
+use crate::tlv::{Tags, Tlv};
+
+/// A single rule broken by a PDU.
+#[derive(uniffi::Record, Clone, Debug, PartialEq, Eq)]
+pub struct ValidationIssue {
+    /// The offending field, or the TLV name prefixed with `tlv:`.
+    pub field: String,
+    /// A human readable description of the problem.
+    pub description: String,
+    /// The suggested `command_status` for rejecting the PDU.
+    pub command_status: u32,
+}
+
+/// Validates a PDU against the SMPP specification for the given version.
+///
+/// Returns every issue found; an empty list means the PDU is valid.
+/// Vendor specific and unknown TLVs are not reported.
+#[uniffi::export]
+pub fn validate_pdu(pdu: &Pdu, version: SmppVersion) -> Vec<ValidationIssue> {
+    let mut checker = Checker {
+        version,
+        issues: Vec::new(),
+    };
+    let c = &mut checker;
+    match pdu {
+        Pdu::BindRequest { pdu } => {
+            c.c_octet("system_id", &pdu.system_id, 16, ESME_RINVSYSID);
+            c.c_octet("password", &pdu.password, 9, ESME_RINVPASWD);
+            c.c_octet("system_type", &pdu.system_type, 13, ESME_RINVSYSTYP);
+            c.c_octet("address_range", &pdu.address_range, 41, ESME_RBINDFAIL);
+        }
+        Pdu::BindResponse { pdu } => {
+            c.c_octet("system_id", &pdu.system_id, 16, ESME_RINVSYSID);
+        }
+        Pdu::Outbind { pdu } => {
+            c.c_octet("system_id", &pdu.system_id, 16, ESME_RINVSYSID);
+            c.c_octet("password", &pdu.password, 9, ESME_RINVPASWD);
+        }
+        Pdu::SubmitSm { pdu } => {
+            c.c_octet("service_type", &pdu.service_type, 6, ESME_RINVSERVICETYPE);
+            c.c_octet("source_addr", &pdu.source_addr, 21, ESME_RINVSRCADR);
+            c.c_octet(
+                "destination_addr",
+                &pdu.destination_addr,
+                21,
+                ESME_RINVDSTADR,
+            );
+            c.esm_class(pdu.esm_class, SUBMIT_MESSAGE_TYPES);
+            c.priority_flag(pdu.priority_flag);
+            c.time(
+                "schedule_delivery_time",
+                pdu.schedule_delivery_time.as_deref(),
+                ESME_RINVSCHED,
+            );
+            c.time(
+                "validity_period",
+                pdu.validity_period.as_deref(),
+                ESME_RINVEXPIRY,
+            );
+            c.registered_delivery(pdu.registered_delivery);
+            c.replace_if_present_flag(pdu.replace_if_present_flag);
+            c.short_message(&pdu.short_message, &pdu.tlvs);
+            c.tlvs(&pdu.tlvs, SUBMIT_SM_TLVS);
+        }
+        Pdu::SubmitSmResponse { pdu } => {
+            c.c_octet("message_id", &pdu.message_id, 65, ESME_RINVMSGID);
+        }
+        Pdu::SubmitMulti { pdu } => {
+            c.c_octet("service_type", &pdu.service_type, 6, ESME_RINVSERVICETYPE);
+            c.c_octet("source_addr", &pdu.source_addr, 21, ESME_RINVSRCADR);
+            if pdu.destinations.is_empty() || pdu.destinations.len() > 255 {
+                c.issue(
+                    "number_of_dests",
+                    format!(
+                        "{} destinations, must be between 1 and 255",
+                        pdu.destinations.len()
+                    ),
+                    ESME_RINVNUMDESTS,
+                );
+            }
+            for destination in &pdu.destinations {
+                match destination {
+                    Destination::SmeAddress { address } => {
+                        c.c_octet("destination_addr", &address.value, 21, ESME_RINVDSTADR)
+                    }
+                    Destination::DistributionList { name } => {
+                        c.c_octet("dl_name", name, 21, ESME_RINVDLNAME)
+                    }
+                }
+            }
+            c.esm_class(pdu.esm_class, SUBMIT_MESSAGE_TYPES);
+            c.priority_flag(pdu.priority_flag);
+            c.time(
+                "schedule_delivery_time",
+                pdu.schedule_delivery_time.as_deref(),
+                ESME_RINVSCHED,
+            );
+            c.time(
+                "validity_period",
+                pdu.validity_period.as_deref(),
+                ESME_RINVEXPIRY,
+            );
+            c.registered_delivery(pdu.registered_delivery);
+            c.replace_if_present_flag(pdu.replace_if_present_flag);
+            c.short_message(&pdu.short_message, &pdu.tlvs);
+            c.tlvs(&pdu.tlvs, SUBMIT_MULTI_TLVS);
+        }
+        Pdu::SubmitMultiResponse { pdu } => {
+            c.c_octet("message_id", &pdu.message_id, 65, ESME_RINVMSGID);
+            for sme in &pdu.unsuccess_smes {
+                c.c_octet("unsuccess_sme", &sme.address, 21, ESME_RINVDSTADR);
+            }
+        }
+        Pdu::DeliverSm { pdu } => {
+            c.c_octet("service_type", &pdu.service_type, 6, ESME_RINVSERVICETYPE);
+            c.c_octet("source_addr", &pdu.source_addr, 21, ESME_RINVSRCADR);
+            c.c_octet("dest_addr", &pdu.dest_addr, 21, ESME_RINVDSTADR);
+            c.esm_class(pdu.esm_class, DELIVER_MESSAGE_TYPES);
+            c.priority_flag(pdu.priority_flag);
+            c.registered_delivery(pdu.registered_delivery);
+            c.short_message(&pdu.short_message, &pdu.optional_params);
+            c.tlvs(&pdu.optional_params, DELIVER_SM_TLVS);
+        }
+        Pdu::DeliverSmResponse { pdu } => {
+            c.c_octet("message_id", &pdu.message_id, 65, ESME_RINVMSGID);
+        }
+        Pdu::DataSm { pdu } => {
+            c.c_octet("service_type", &pdu.service_type, 6, ESME_RINVSERVICETYPE);
+            c.c_octet("source_addr", &pdu.source_addr, 65, ESME_RINVSRCADR);
+            c.c_octet("dest_addr", &pdu.dest_addr, 65, ESME_RINVDSTADR);
+            c.esm_class(pdu.esm_class, DATA_SM_MESSAGE_TYPES);
+            c.registered_delivery(pdu.registered_delivery);
+            c.tlvs(&pdu.optional_params, DATA_SM_TLVS);
+        }
+        Pdu::DataSmResponse { pdu } => {
+            c.c_octet("message_id", &pdu.message_id, 65, ESME_RINVMSGID);
+            c.tlvs(&pdu.optional_params, DATA_SM_RESP_TLVS);
+        }
+        Pdu::QuerySm { pdu } => {
+            c.c_octet("message_id", &pdu.message_id, 65, ESME_RINVMSGID);
+            c.c_octet("source_addr", &pdu.source_addr, 21, ESME_RINVSRCADR);
+        }
+        Pdu::QuerySmResponse { pdu } => {
+            c.c_octet("message_id", &pdu.message_id, 65, ESME_RINVMSGID);
+        }
+        Pdu::CancelSm { pdu } => {
+            c.c_octet("service_type", &pdu.service_type, 6, ESME_RINVSERVICETYPE);
+            c.c_octet("message_id", &pdu.message_id, 65, ESME_RINVMSGID);
+            c.c_octet("source_addr", &pdu.source_addr, 21, ESME_RINVSRCADR);
+            c.c_octet("dest_addr", &pdu.dest_addr, 21, ESME_RINVDSTADR);
+        }
+        Pdu::ReplaceSm { pdu } => {
+            c.c_octet("message_id", &pdu.message_id, 65, ESME_RINVMSGID);
+            c.c_octet("source_addr", &pdu.source_addr, 21, ESME_RINVSRCADR);
+            c.time(
+                "schedule_delivery_time",
+                Some(&pdu.schedule_delivery_time),
+                ESME_RINVSCHED,
+            );
+            c.time(
+                "validity_period",
+                Some(&pdu.validity_period),
+                ESME_RINVEXPIRY,
+            );
+            c.registered_delivery(pdu.registered_delivery);
+            c.short_message(&pdu.short_message, &[]);
+        }
+        Pdu::BroadcastSm { pdu } => {
+            c.broadcast_version();
+            c.c_octet("service_type", &pdu.service_type, 6, ESME_RINVSERVICETYPE);
+            c.c_octet("source_addr", &pdu.source_addr, 21, ESME_RINVSRCADR);
+            c.c_octet("message_id", &pdu.message_id, 65, ESME_RINVMSGID);
+            c.priority_flag(pdu.priority_flag);
+            c.time(
+                "schedule_delivery_time",
+                pdu.schedule_delivery_time.as_deref(),
+                ESME_RINVSCHED,
+            );
+            c.time(
+                "validity_period",
+                pdu.validity_period.as_deref(),
+                ESME_RINVEXPIRY,
+            );
+            c.replace_if_present_flag(pdu.replace_if_present_flag);
+            c.tlvs(&pdu.optional_params, BROADCAST_SM_TLVS);
+            for (tag, name) in MANDATORY_BROADCAST_TLVS {
+                if !pdu.optional_params.iter().any(|t| t.tag == tag) {
+                    c.issue(
+                        &format!("tlv:{}", name),
+                        "mandatory TLV is missing".to_string(),
+                        ESME_RMISSINGOPTPARAM,
+                    );
+                }
+            }
+        }
+        Pdu::BroadcastSmResponse { pdu } => {
+            c.broadcast_version();
+            c.c_octet("message_id", &pdu.message_id, 65, ESME_RINVMSGID);
+            c.tlvs(&pdu.optional_params, BROADCAST_SM_RESP_TLVS);
+        }
+        Pdu::QueryBroadcastSm { pdu } => {
+            c.broadcast_version();
+            c.c_octet("message_id", &pdu.message_id, 65, ESME_RINVMSGID);
+            c.c_octet("source_addr", &pdu.source_addr, 21, ESME_RINVSRCADR);
+            c.tlvs(&pdu.optional_params, QUERY_BROADCAST_SM_TLVS);
+        }
+        Pdu::QueryBroadcastSmResponse { pdu } => {
+            c.broadcast_version();
+            c.c_octet("message_id", &pdu.message_id, 65, ESME_RINVMSGID);
+            c.tlvs(&pdu.optional_params, QUERY_BROADCAST_SM_RESP_TLVS);
+        }
+        Pdu::CancelBroadcastSm { pdu } => {
+            c.broadcast_version();
+            c.c_octet("service_type", &pdu.service_type, 6, ESME_RINVSERVICETYPE);
+            c.c_octet("message_id", &pdu.message_id, 65, ESME_RINVMSGID);
+            c.c_octet("source_addr", &pdu.source_addr, 21, ESME_RINVSRCADR);
+            c.tlvs(&pdu.optional_params, CANCEL_BROADCAST_SM_TLVS);
+        }
+        Pdu::CancelBroadcastSmResponse { .. } => c.broadcast_version(),
+        Pdu::EnquireLink { .. }
+        | Pdu::EnquireLinkResponse { .. }
+        | Pdu::Unbind { .. }
+        | Pdu::UnbindResponse { .. }
+        | Pdu::GenericNack { .. }
+        | Pdu::CancelSmResponse { .. }
+        | Pdu::ReplaceSmResponse { .. } => {}
+    }
+    checker.issues
+}
+
+/// Message type bits (2-5) of `esm_class` allowed in submit_sm and submit_multi.
+const SUBMIT_MESSAGE_TYPES: &[u8] = &[0x00, 0x08, 0x10];
+/// Message type bits (2-5) of `esm_class` allowed in deliver_sm.
+const DELIVER_MESSAGE_TYPES: &[u8] = &[0x00, 0x04, 0x08, 0x10, 0x18, 0x20];
+/// Message type bits (2-5) of `esm_class` allowed in data_sm, in either direction.
+const DATA_SM_MESSAGE_TYPES: &[u8] = DELIVER_MESSAGE_TYPES;
+
+const SUBMIT_SM_TLVS: &[u16] = &[
+    Tags::USER_MESSAGE_REFERENCE,
+    Tags::SOURCE_PORT,
+    Tags::SOURCE_ADDR_SUBUNIT,
+    Tags::DESTINATION_PORT,
+    Tags::DEST_ADDR_SUBUNIT,
+    Tags::SAR_MSG_REF_NUM,
+    Tags::SAR_TOTAL_SEGMENTS,
+    Tags::SAR_SEGMENT_SEQNUM,
+    Tags::MORE_MESSAGES_TO_SEND,
+    Tags::PAYLOAD_TYPE,
+    Tags::MESSAGE_PAYLOAD,
+    Tags::PRIVACY_INDICATOR,
+    Tags::CALLBACK_NUM,
+    Tags::CALLBACK_NUM_PRES_IND,
+    Tags::CALLBACK_NUM_ATAG,
+    Tags::SOURCE_SUBADDRESS,
+    Tags::DEST_SUBADDRESS,
+    Tags::USER_RESPONSE_CODE,
+    Tags::DISPLAY_TIME,
+    Tags::SMS_SIGNAL,
+    Tags::MS_VALIDITY,
+    Tags::MS_MSG_WAIT_FACILITIES,
+    Tags::NUMBER_OF_MESSAGES,
+    Tags::ALERT_ON_MESSAGE_DELIVERY,
+    Tags::LANGUAGE_INDICATOR,
+    Tags::ITS_REPLY_TYPE,
+    Tags::ITS_SESSION_INFO,
+    Tags::USSD_SERVICE_OP,
+    Tags::BILLING_IDENTIFICATION,
+    Tags::SOURCE_NETWORK_ID,
+    Tags::DEST_NETWORK_ID,
+    Tags::SOURCE_NODE_ID,
+    Tags::DEST_NODE_ID,
+    Tags::DEST_ADDR_NP_RESOLUTION,
+    Tags::DEST_ADDR_NP_INFORMATION,
+    Tags::DEST_ADDR_NP_COUNTRY,
+];
+
+const SUBMIT_MULTI_TLVS: &[u16] = &[
+    Tags::USER_MESSAGE_REFERENCE,
+    Tags::SOURCE_PORT,
+    Tags::SOURCE_ADDR_SUBUNIT,
+    Tags::DESTINATION_PORT,
+    Tags::DEST_ADDR_SUBUNIT,
+    Tags::SAR_MSG_REF_NUM,
+    Tags::SAR_TOTAL_SEGMENTS,
+    Tags::SAR_SEGMENT_SEQNUM,
+    Tags::PAYLOAD_TYPE,
+    Tags::MESSAGE_PAYLOAD,
+    Tags::PRIVACY_INDICATOR,
+    Tags::CALLBACK_NUM,
+    Tags::CALLBACK_NUM_PRES_IND,
+    Tags::CALLBACK_NUM_ATAG,
+    Tags::SOURCE_SUBADDRESS,
+    Tags::DEST_SUBADDRESS,
+    Tags::DISPLAY_TIME,
+    Tags::SMS_SIGNAL,
+    Tags::MS_VALIDITY,
+    Tags::MS_MSG_WAIT_FACILITIES,
+    Tags::ALERT_ON_MESSAGE_DELIVERY,
+    Tags::LANGUAGE_INDICATOR,
+    Tags::BILLING_IDENTIFICATION,
+    Tags::SOURCE_NETWORK_ID,
+    Tags::DEST_NETWORK_ID,
+    Tags::SOURCE_NODE_ID,
+    Tags::DEST_NODE_ID,
+    Tags::DEST_ADDR_NP_RESOLUTION,
+    Tags::DEST_ADDR_NP_INFORMATION,
+    Tags::DEST_ADDR_NP_COUNTRY,
+];
+
+const DELIVER_SM_TLVS: &[u16] = &[
+    Tags::USER_MESSAGE_REFERENCE,
+    Tags::SOURCE_PORT,
+    Tags::DESTINATION_PORT,
+    Tags::SAR_MSG_REF_NUM,
+    Tags::SAR_TOTAL_SEGMENTS,
+    Tags::SAR_SEGMENT_SEQNUM,
+    Tags::USER_RESPONSE_CODE,
+    Tags::PRIVACY_INDICATOR,
+    Tags::PAYLOAD_TYPE,
+    Tags::MESSAGE_PAYLOAD,
+    Tags::CALLBACK_NUM,
+    Tags::SOURCE_SUBADDRESS,
+    Tags::DEST_SUBADDRESS,
+    Tags::LANGUAGE_INDICATOR,
+    Tags::ITS_SESSION_INFO,
+    Tags::NETWORK_ERROR_CODE,
+    Tags::MESSAGE_STATE,
+    Tags::RECEIPTED_MESSAGE_ID,
+    Tags::USSD_SERVICE_OP,
+    Tags::BILLING_IDENTIFICATION,
+    Tags::SOURCE_NETWORK_ID,
+    Tags::DEST_NETWORK_ID,
+    Tags::SOURCE_NODE_ID,
+    Tags::DEST_NODE_ID,
+    Tags::DEST_ADDR_NP_RESOLUTION,
+    Tags::DEST_ADDR_NP_INFORMATION,
+    Tags::DEST_ADDR_NP_COUNTRY,
+];
+
+const DATA_SM_TLVS: &[u16] = &[
+    Tags::SOURCE_PORT,
+    Tags::SOURCE_ADDR_SUBUNIT,
+    Tags::SOURCE_NETWORK_TYPE,
+    Tags::SOURCE_BEARER_TYPE,
+    Tags::SOURCE_TELEMATICS_ID,
+    Tags::DESTINATION_PORT,
+    Tags::DEST_ADDR_SUBUNIT,
+    Tags::DEST_NETWORK_TYPE,
+    Tags::DEST_BEARER_TYPE,
+    Tags::DEST_TELEMATICS_ID,
+    Tags::SAR_MSG_REF_NUM,
+    Tags::SAR_TOTAL_SEGMENTS,
+    Tags::SAR_SEGMENT_SEQNUM,
+    Tags::MORE_MESSAGES_TO_SEND,
+    Tags::QOS_TIME_TO_LIVE,
+    Tags::PAYLOAD_TYPE,
+    Tags::MESSAGE_PAYLOAD,
+    Tags::SET_DPF,
+    Tags::RECEIPTED_MESSAGE_ID,
+    Tags::MESSAGE_STATE,
+    Tags::NETWORK_ERROR_CODE,
+    Tags::USER_MESSAGE_REFERENCE,
+    Tags::PRIVACY_INDICATOR,
+    Tags::CALLBACK_NUM,
+    Tags::CALLBACK_NUM_PRES_IND,
+    Tags::CALLBACK_NUM_ATAG,
+    Tags::SOURCE_SUBADDRESS,
+    Tags::DEST_SUBADDRESS,
+    Tags::USER_RESPONSE_CODE,
+    Tags::DISPLAY_TIME,
+    Tags::SMS_SIGNAL,
+    Tags::MS_VALIDITY,
+    Tags::MS_MSG_WAIT_FACILITIES,
+    Tags::NUMBER_OF_MESSAGES,
+    Tags::ALERT_ON_MESSAGE_DELIVERY,
+    Tags::LANGUAGE_INDICATOR,
+    Tags::ITS_REPLY_TYPE,
+    Tags::ITS_SESSION_INFO,
+    Tags::BILLING_IDENTIFICATION,
+    Tags::SOURCE_NETWORK_ID,
+    Tags::DEST_NETWORK_ID,
+    Tags::SOURCE_NODE_ID,
+    Tags::DEST_NODE_ID,
+    Tags::DEST_ADDR_NP_RESOLUTION,
+    Tags::DEST_ADDR_NP_INFORMATION,
+    Tags::DEST_ADDR_NP_COUNTRY,
+];
+
+const DATA_SM_RESP_TLVS: &[u16] = &[
+    Tags::DELIVERY_FAILURE_REASON,
+    Tags::NETWORK_ERROR_CODE,
+    Tags::ADDITIONAL_STATUS_INFO_TEXT,
+    Tags::DPF_RESULT,
+    Tags::CONGESTION_STATE,
+];
+
+const BROADCAST_SM_TLVS: &[u16] = &[
+    Tags::BROADCAST_AREA_IDENTIFIER,
+    Tags::BROADCAST_CONTENT_TYPE,
+    Tags::BROADCAST_REP_NUM,
+    Tags::BROADCAST_FREQUENCY_INTERVAL,
+    Tags::ALERT_ON_MESSAGE_DELIVERY,
+    Tags::BROADCAST_CHANNEL_INDICATOR,
+    Tags::BROADCAST_CONTENT_TYPE_INFO,
+    Tags::BROADCAST_MESSAGE_CLASS,
+    Tags::BROADCAST_SERVICE_GROUP,
+    Tags::CALLBACK_NUM,
+    Tags::CALLBACK_NUM_ATAG,
+    Tags::CALLBACK_NUM_PRES_IND,
+    Tags::DEST_ADDR_SUBUNIT,
+    Tags::DEST_SUBADDRESS,
+    Tags::DESTINATION_PORT,
+    Tags::DISPLAY_TIME,
+    Tags::LANGUAGE_INDICATOR,
+    Tags::MESSAGE_PAYLOAD,
+    Tags::MS_VALIDITY,
+    Tags::PAYLOAD_TYPE,
+    Tags::PRIVACY_INDICATOR,
+    Tags::SMS_SIGNAL,
+    Tags::SOURCE_ADDR_SUBUNIT,
+    Tags::SOURCE_PORT,
+    Tags::SOURCE_SUBADDRESS,
+    Tags::USER_MESSAGE_REFERENCE,
+];
+
+const BROADCAST_SM_RESP_TLVS: &[u16] = &[
+    Tags::BROADCAST_ERROR_STATUS,
+    Tags::BROADCAST_AREA_IDENTIFIER,
+    Tags::CONGESTION_STATE,
+];
+
+const QUERY_BROADCAST_SM_TLVS: &[u16] = &[Tags::USER_MESSAGE_REFERENCE];
+
+const QUERY_BROADCAST_SM_RESP_TLVS: &[u16] = &[
+    Tags::MESSAGE_STATE,
+    Tags::BROADCAST_AREA_IDENTIFIER,
+    Tags::BROADCAST_AREA_SUCCESS,
+    Tags::BROADCAST_END_TIME,
+    Tags::USER_MESSAGE_REFERENCE,
+    Tags::CONGESTION_STATE,
+];
+
+const CANCEL_BROADCAST_SM_TLVS: &[u16] =
+    &[Tags::BROADCAST_CONTENT_TYPE, Tags::USER_MESSAGE_REFERENCE];
+
+/// TLVs introduced by SMPP v5.0.
+const V50_TLVS: &[u16] = &[
+    Tags::BROADCAST_AREA_IDENTIFIER,
+    Tags::BROADCAST_CONTENT_TYPE,
+    Tags::BROADCAST_REP_NUM,
+    Tags::BROADCAST_FREQUENCY_INTERVAL,
+    Tags::BROADCAST_AREA_SUCCESS,
+    Tags::BROADCAST_END_TIME,
+    Tags::BROADCAST_SERVICE_GROUP,
+    Tags::BROADCAST_CHANNEL_INDICATOR,
+    Tags::BROADCAST_CONTENT_TYPE_INFO,
+    Tags::BROADCAST_MESSAGE_CLASS,
+    Tags::BROADCAST_ERROR_STATUS,
+    Tags::BILLING_IDENTIFICATION,
+    Tags::SOURCE_NETWORK_ID,
+    Tags::DEST_NETWORK_ID,
+    Tags::SOURCE_NODE_ID,
+    Tags::DEST_NODE_ID,
+    Tags::DEST_ADDR_NP_RESOLUTION,
+    Tags::DEST_ADDR_NP_INFORMATION,
+    Tags::DEST_ADDR_NP_COUNTRY,
+    Tags::CONGESTION_STATE,
+];
+
+/// Returns the name and allowed value length range of a standard TLV.
+pub(crate) fn tlv_rule(tag: u16) -> Option<(&'static str, usize, usize)> {
+    let rule = match tag {
+        Tags::DEST_ADDR_SUBUNIT => ("dest_addr_subunit", 1, 1),
+        Tags::DEST_NETWORK_TYPE => ("dest_network_type", 1, 1),
+        Tags::DEST_BEARER_TYPE => ("dest_bearer_type", 1, 1),
+        Tags::DEST_TELEMATICS_ID => ("dest_telematics_id", 2, 2),
+        Tags::SOURCE_ADDR_SUBUNIT => ("source_addr_subunit", 1, 1),
+        Tags::SOURCE_NETWORK_TYPE => ("source_network_type", 1, 1),
+        Tags::SOURCE_BEARER_TYPE => ("source_bearer_type", 1, 1),
+        Tags::SOURCE_TELEMATICS_ID => ("source_telematics_id", 1, 1),
+        Tags::QOS_TIME_TO_LIVE => ("qos_time_to_live", 4, 4),
+        Tags::PAYLOAD_TYPE => ("payload_type", 1, 1),
+        Tags::ADDITIONAL_STATUS_INFO_TEXT => ("additional_status_info_text", 1, 256),
+        Tags::RECEIPTED_MESSAGE_ID => ("receipted_message_id", 1, 65),
+        Tags::MS_MSG_WAIT_FACILITIES => ("ms_msg_wait_facilities", 1, 1),
+        Tags::PRIVACY_INDICATOR => ("privacy_indicator", 1, 1),
+        Tags::SOURCE_SUBADDRESS => ("source_subaddress", 2, 23),
+        Tags::DEST_SUBADDRESS => ("dest_subaddress", 2, 23),
+        Tags::USER_MESSAGE_REFERENCE => ("user_message_reference", 2, 2),
+        Tags::USER_RESPONSE_CODE => ("user_response_code", 1, 1),
+        Tags::SOURCE_PORT => ("source_port", 2, 2),
+        Tags::DESTINATION_PORT => ("destination_port", 2, 2),
+        Tags::SAR_MSG_REF_NUM => ("sar_msg_ref_num", 2, 2),
+        Tags::LANGUAGE_INDICATOR => ("language_indicator", 1, 1),
+        Tags::SAR_TOTAL_SEGMENTS => ("sar_total_segments", 1, 1),
+        Tags::SAR_SEGMENT_SEQNUM => ("sar_segment_seqnum", 1, 1),
+        Tags::SC_INTERFACE_VERSION => ("sc_interface_version", 1, 1),
+        Tags::CALLBACK_NUM_PRES_IND => ("callback_num_pres_ind", 1, 1),
+        Tags::CALLBACK_NUM_ATAG => ("callback_num_atag", 0, 65),
+        Tags::NUMBER_OF_MESSAGES => ("number_of_messages", 1, 1),
+        Tags::CALLBACK_NUM => ("callback_num", 4, 19),
+        Tags::DPF_RESULT => ("dpf_result", 1, 1),
+        Tags::SET_DPF => ("set_dpf", 1, 1),
+        Tags::MS_AVAILABILITY_STATUS => ("ms_availability_status", 1, 1),
+        Tags::NETWORK_ERROR_CODE => ("network_error_code", 3, 3),
+        Tags::MESSAGE_PAYLOAD => ("message_payload", 0, 65535),
+        Tags::DELIVERY_FAILURE_REASON => ("delivery_failure_reason", 1, 1),
+        Tags::MORE_MESSAGES_TO_SEND => ("more_messages_to_send", 1, 1),
+        Tags::MESSAGE_STATE => ("message_state", 1, 1),
+        Tags::CONGESTION_STATE => ("congestion_state", 1, 1),
+        Tags::USSD_SERVICE_OP => ("ussd_service_op", 1, 1),
+        Tags::DISPLAY_TIME => ("display_time", 1, 1),
+        Tags::SMS_SIGNAL => ("sms_signal", 2, 2),
+        Tags::MS_VALIDITY => ("ms_validity", 1, 4),
+        Tags::ALERT_ON_MESSAGE_DELIVERY => ("alert_on_message_delivery", 0, 1),
+        Tags::ITS_REPLY_TYPE => ("its_reply_type", 1, 1),
+        Tags::ITS_SESSION_INFO => ("its_session_info", 2, 2),
+        Tags::BROADCAST_AREA_IDENTIFIER => ("broadcast_area_identifier", 1, 100),
+        Tags::BROADCAST_CONTENT_TYPE => ("broadcast_content_type", 3, 3),
+        Tags::BROADCAST_REP_NUM => ("broadcast_rep_num", 2, 2),
+        Tags::BROADCAST_FREQUENCY_INTERVAL => ("broadcast_frequency_interval", 3, 3),
+        Tags::BROADCAST_AREA_SUCCESS => ("broadcast_area_success", 1, 1),
+        Tags::BROADCAST_END_TIME => ("broadcast_end_time", 17, 17),
+        Tags::BROADCAST_SERVICE_GROUP => ("broadcast_service_group", 1, 255),
+        Tags::BROADCAST_CHANNEL_INDICATOR => ("broadcast_channel_indicator", 1, 1),
+        Tags::BROADCAST_CONTENT_TYPE_INFO => ("broadcast_content_type_info", 1, 255),
+        Tags::BROADCAST_MESSAGE_CLASS => ("broadcast_message_class", 1, 1),
+        Tags::BROADCAST_ERROR_STATUS => ("broadcast_error_status", 4, 4),
+        Tags::BILLING_IDENTIFICATION => ("billing_identification", 1, 1024),
+        Tags::SOURCE_NETWORK_ID => ("source_network_id", 1, 65),
+        Tags::DEST_NETWORK_ID => ("dest_network_id", 1, 65),
+        Tags::SOURCE_NODE_ID => ("source_node_id", 6, 6),
+        Tags::DEST_NODE_ID => ("dest_node_id", 6, 6),
+        Tags::DEST_ADDR_NP_RESOLUTION => ("dest_addr_np_resolution", 1, 1),
+        Tags::DEST_ADDR_NP_INFORMATION => ("dest_addr_np_information", 10, 10),
+        Tags::DEST_ADDR_NP_COUNTRY => ("dest_addr_np_country", 5, 5),
+        _ => return None,
+    };
+    Some(rule)
+}
+
+struct Checker {
+    version: SmppVersion,
+    issues: Vec<ValidationIssue>,
+}
+
+impl Checker {
+    fn issue(&mut self, field: &str, description: String, command_status: u32) {
+        self.issues.push(ValidationIssue {
+            field: field.to_string(),
+            description,
+            command_status,
+        });
+    }
+
+    /// Checks a C-Octet String against its maximum size, NULL included.
+    fn c_octet(&mut self, field: &str, value: &str, max: usize, command_status: u32) {
+        if value.len() + 1 > max {
+            self.issue(
+                field,
+                format!("{} octets, maximum is {}", value.len(), max - 1),
+                command_status,
+            );
+        }
+    }
+
+    /// Checks an absolute (`YYMMDDhhmmsstnn+`/`-`) or relative (`...R`) time.
+    fn time(&mut self, field: &str, value: Option<&str>, command_status: u32) {
+        let value = match value {
+            Some(value) if !value.is_empty() => value,
+            _ => return,
+        };
+        let bytes = value.as_bytes();
+        let valid = bytes.len() == 16
+            && bytes[..15].iter().all(u8::is_ascii_digit)
+            && matches!(bytes[15], b'+' | b'-' | b'R');
+        if !valid {
+            self.issue(
+                field,
+                format!("'{}' is not an SMPP absolute or relative time", value),
+                command_status,
+            );
+        }
+    }
+
+    fn short_message(&mut self, short_message: &[u8], tlvs: &[Tlv]) {
+        if short_message.len() > 254 {
+            self.issue(
+                "sm_length",
+                format!("{} octets, maximum is 254", short_message.len()),
+                ESME_RINVMSGLEN,
+            );
+        }
+        if !short_message.is_empty() && tlvs.iter().any(|t| t.tag == Tags::MESSAGE_PAYLOAD) {
+            self.issue(
+                "tlv:message_payload",
+                "message_payload is not allowed with a non-empty short_message".to_string(),
+                ESME_ROPTPARNOTALLWD,
+            );
+        }
+    }
+
+    fn tlvs(&mut self, tlvs: &[Tlv], allowed: &[u16]) {
+        for tlv in tlvs {
+            let (name, min, max) = match tlv_rule(tlv.tag) {
+                Some(rule) => rule,
+                None => continue,
+            };
+            let field = format!("tlv:{}", name);
+            if self.version == SmppVersion::V33 {
+                self.issue(
+                    &field,
+                    "TLVs are not supported by SMPP v3.3".to_string(),
+                    ESME_ROPTPARNOTALLWD,
+                );
+                continue;
+            }
+            if self.version < SmppVersion::V50 && V50_TLVS.contains(&tlv.tag) {
+                self.issue(
+                    &field,
+                    "TLV requires SMPP v5.0".to_string(),
+                    ESME_ROPTPARNOTALLWD,
+                );
+                continue;
+            }
+            if !allowed.contains(&tlv.tag) {
+                self.issue(
+                    &field,
+                    "TLV is not allowed on this PDU".to_string(),
+                    ESME_ROPTPARNOTALLWD,
+                );
+                continue;
+            }
+            if tlv.value.len() < min || tlv.value.len() > max {
+                self.issue(
+                    &field,
+                    format!("length {} is outside {}..={}", tlv.value.len(), min, max),
+                    ESME_RINVPARLEN,
+                );
+            }
+        }
+    }
+
+    fn esm_class(&mut self, esm_class: u8, message_types: &[u8]) {
+        let message_type = esm_class & 0x3C;
+        if !message_types.contains(&message_type) {
+            self.issue(
+                "esm_class",
+                format!(
+                    "message type 0x{:02X} is not allowed on this PDU",
+                    message_type
+                ),
+                ESME_RINVESMCLASS,
+            );
+        }
+    }
+
+    fn priority_flag(&mut self, priority_flag: u8) {
+        let max = if self.version == SmppVersion::V50 {
+            4
+        } else {
+            3
+        };
+        if priority_flag > max {
+            self.issue(
+                "priority_flag",
+                format!("{} is above the maximum of {}", priority_flag, max),
+                ESME_RINVPRTFLG,
+            );
+        }
+    }
+
+    fn registered_delivery(&mut self, registered_delivery: u8) {
+        if registered_delivery & 0xE0 != 0 {
+            self.issue(
+                "registered_delivery",
+                format!("reserved bits set in 0x{:02X}", registered_delivery),
+                ESME_RINVREGDLVFLG,
+            );
+        }
+    }
+
+    fn replace_if_present_flag(&mut self, flag: u8) {
+        if flag > 1 {
+            self.issue(
+                "replace_if_present_flag",
+                format!("{} is reserved", flag),
+                ESME_RINVREPFLAG,
+            );
+        }
+    }
+
+    fn broadcast_version(&mut self) {
+        if self.version < SmppVersion::V50 {
+            self.issue(
+                "command_id",
+                "broadcast operations require SMPP v5.0".to_string(),
+                ESME_RINVCMDID,
+            );
+        }
+    }
+}
@@ -0,0 +1,80 @@
+use smpp_codec_ffi::address::*;
+use smpp_codec_ffi::common::{Npi, Ton};
+
+#[test]
+fn test_address_from_e164() {
+    let address = address_from_e164("+44 7700-900.123".to_string()).unwrap();
+    assert_eq!(address.ton, Ton::International);
+    assert_eq!(address.npi, Npi::Isdn);
+    assert_eq!(address.value, "447700900123");
+
+    let address = address_from_e164("0044 7700 900123".to_string()).unwrap();
+    assert_eq!(address.value, "447700900123");
+
+    assert!(address_from_e164("+44 7700 ABC".to_string()).is_err());
+    assert!(address_from_e164("+1234567890123456".to_string()).is_err());
+}
+
+#[test]
+fn test_address_alphanumeric() {
+    let address = address_alphanumeric("MyBrand".to_string()).unwrap();
+    assert_eq!(address.ton, Ton::Alphanumeric);
+    assert_eq!(address.npi, Npi::Unknown);
+
+    // 12 characters
+    assert!(address_alphanumeric("MyBrandIsLong".to_string()).is_err());
+    // Extension characters take two septets
+    assert!(address_alphanumeric("Brand{}{}{}".to_string()).is_err());
+    // Not in the GSM alphabet
+    assert!(address_alphanumeric("Brand🚀".to_string()).is_err());
+}
+
+#[test]
+fn test_address_normalize() {
+    let address = address_new(
+        Ton::Unknown,
+        Npi::Unknown,
+        " +1 (555) 010-0200 ".to_string(),
+    );
+    let normalized = address_normalize(&address);
+    assert_eq!(normalized.ton, Ton::International);
+    assert_eq!(normalized.npi, Npi::Isdn);
+    assert_eq!(normalized.value, "15550100200");
+
+    let address = address_new(Ton::National, Npi::Isdn, "020 7946 0000".to_string());
+    let normalized = address_normalize(&address);
+    assert_eq!(normalized.ton, Ton::National);
+    assert_eq!(normalized.value, "02079460000");
+
+    let address = address_new(Ton::Alphanumeric, Npi::Unknown, "My Brand".to_string());
+    assert_eq!(address_normalize(&address), address);
+}
+
+#[test]
+fn test_address_validate_field_lengths() {
+    let address = address_new(Ton::Unknown, Npi::Internet, "a".repeat(30));
+    assert!(address_validate(&address, AddressField::ShortMessage).is_err());
+    assert!(address_validate(&address, AddressField::DataSm).is_ok());
+    assert!(address_validate(&address, AddressField::AlertNotification).is_ok());
+
+    let range = address_new(Ton::Unknown, Npi::Unknown, "^447700[0-9]+$".to_string());
+    assert!(address_validate(&range, AddressField::AddressRange).is_ok());
+
+    assert_eq!(AddressField::ShortMessage.max_len(), 21);
+    assert_eq!(AddressField::AddressRange.max_len(), 41);
+}
+
+#[test]
+fn test_address_validate_rules() {
+    let empty = address_new(Ton::International, Npi::Isdn, String::new());
+    assert!(address_validate(&empty, AddressField::ShortMessage).is_ok());
+
+    let national = address_new(Ton::National, Npi::Isdn, "0207946".to_string());
+    assert!(address_validate(&national, AddressField::ShortMessage).is_ok());
+
+    let bad_isdn = address_new(Ton::Unknown, Npi::Isdn, "12AB".to_string());
+    assert!(address_validate(&bad_isdn, AddressField::ShortMessage).is_err());
+
+    let short_code = address_new(Ton::Abbreviated, Npi::Unknown, "SHORT".to_string());
+    assert!(address_validate(&short_code, AddressField::ShortMessage).is_ok());
+}
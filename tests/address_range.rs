@@ -0,0 +1,147 @@
+mod fixtures;
+
+use fixtures::{AcceptAll, NoRequests};
+use smpp_codec_ffi::address::address_new;
+use smpp_codec_ffi::address_range::*;
+use smpp_codec_ffi::common::*;
+use smpp_codec_ffi::delivery_pdu_ffi::DeliverSmRequest;
+use smpp_codec_ffi::esme_session::*;
+use smpp_codec_ffi::session_pdu_ffi::BindRequest;
+use smpp_codec_ffi::smsc_listener::*;
+use std::sync::mpsc::{self, Sender};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+fn bind(mode: BindMode, ton: Ton, address_range: &str) -> BindRequest {
+    BindRequest {
+        sequence_number: 0,
+        mode,
+        system_id: "esme".to_string(),
+        password: "secret".to_string(),
+        system_type: String::new(),
+        interface_version: SMPP_INTERFACE_VERSION_34,
+        addr_ton: ton,
+        addr_npi: Npi::Unknown,
+        address_range: address_range.to_string(),
+    }
+}
+
+#[test]
+fn test_address_range_matches() {
+    let range = AddressRange::new(Ton::International, Npi::Isdn, "^4477".to_string()).unwrap();
+    assert_eq!(range.pattern(), "^4477");
+    assert!(range.matches(address_new(
+        Ton::International,
+        Npi::Isdn,
+        "447700900123".to_string()
+    )));
+    assert!(!range.matches(address_new(
+        Ton::International,
+        Npi::Isdn,
+        "3377009001".to_string()
+    )));
+    assert!(!range.matches(address_new(
+        Ton::National,
+        Npi::Isdn,
+        "447700900123".to_string()
+    )));
+
+    // Unanchored expressions match anywhere; unknown TON and NPI match any.
+    let range = AddressRange::from_bind(bind(BindMode::Receiver, Ton::Unknown, "77")).unwrap();
+    assert!(range.matches(address_new(Ton::National, Npi::Data, "0177123".to_string())));
+
+    let everything = AddressRange::new(Ton::Unknown, Npi::Unknown, String::new()).unwrap();
+    assert!(everything.matches(address_new(
+        Ton::Alphanumeric,
+        Npi::Unknown,
+        "SHOP".to_string()
+    )));
+}
+
+#[test]
+fn test_address_range_validate() {
+    assert!(address_range_validate("^(1234|5678)[0-9]*$".to_string()).is_ok());
+    assert!(address_range_validate(String::new()).is_ok());
+    assert!(address_range_validate("[0-9".to_string()).is_err());
+    assert!(address_range_validate("1".repeat(41)).is_err());
+    assert!(AddressRange::new(Ton::Unknown, Npi::Unknown, "(".to_string()).is_err());
+}
+
+struct Recorder(&'static str, Mutex<Sender<(&'static str, String)>>);
+
+impl DeliverSmListener for Recorder {
+    fn on_deliver_sm(&self, request: DeliverSmRequest) -> u32 {
+        let _ = self.1.lock().unwrap().send((self.0, request.dest_addr));
+        COMMAND_STATUS_OK
+    }
+}
+
+fn deliver_sm(dest_addr: &str) -> DeliverSmRequest {
+    DeliverSmRequest {
+        sequence_number: 0,
+        service_type: String::new(),
+        source_addr_ton: Ton::International,
+        source_addr_npi: Npi::Isdn,
+        source_addr: "447700900123".to_string(),
+        dest_addr_ton: Ton::International,
+        dest_addr_npi: Npi::Isdn,
+        dest_addr: dest_addr.to_string(),
+        esm_class: 0,
+        protocol_id: 0,
+        priority_flag: 0,
+        schedule_delivery_time: None,
+        validity_period: None,
+        registered_delivery: 0,
+        replace_if_present_flag: 0,
+        data_coding: 0,
+        sm_default_msg_id: 0,
+        short_message: b"Hi".to_vec(),
+        optional_params: Vec::new(),
+    }
+}
+
+#[test]
+fn test_address_range_routing() {
+    let listener = SmscListener::start(
+        smsc_listener_config_new("127.0.0.1".to_string(), 0),
+        Arc::new(AcceptAll),
+        Arc::new(NoRequests),
+    )
+    .unwrap();
+    let config = esme_session_config_new("127.0.0.1".to_string(), listener.local_port());
+    let (sender, received) = mpsc::channel();
+    let connect = |name: &'static str, request: BindRequest| {
+        let recorder = Recorder(name, Mutex::new(sender.clone()));
+        EsmeSession::connect(config.clone(), request, Some(Arc::new(recorder)))
+    };
+
+    let first = connect(
+        "first",
+        bind(BindMode::Receiver, Ton::International, "^1234"),
+    )
+    .unwrap();
+    let second = connect("second", bind(BindMode::Transceiver, Ton::Unknown, "^5678")).unwrap();
+    let _sender = connect("transmitter", bind(BindMode::Transmitter, Ton::Unknown, "")).unwrap();
+    assert_eq!(listener.receivers().len(), 2);
+
+    let timeout = Duration::from_secs(5);
+    listener.deliver(deliver_sm("1234000")).unwrap();
+    assert_eq!(
+        received.recv_timeout(timeout).unwrap(),
+        ("first", "1234000".to_string())
+    );
+    listener.deliver(deliver_sm("5678000")).unwrap();
+    assert_eq!(
+        received.recv_timeout(timeout).unwrap(),
+        ("second", "5678000".to_string())
+    );
+    assert!(listener.deliver(deliver_sm("9999000")).is_err());
+
+    // An invalid range fails a receiver bind.
+    assert!(connect("bad", bind(BindMode::Receiver, Ton::Unknown, "[0-9")).is_err());
+
+    first.unbind().unwrap();
+    assert!(listener.deliver(deliver_sm("1234000")).is_err());
+    assert_eq!(listener.receivers().len(), 1);
+    second.unbind().unwrap();
+}
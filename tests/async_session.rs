@@ -0,0 +1,143 @@
+mod fixtures;
+
+use fixtures::{PasswordCheck, Responder};
+use smpp_codec_ffi::address::address_new;
+use smpp_codec_ffi::ancillary_pdu_ffi::QuerySmRequest;
+use smpp_codec_ffi::async_session::AsyncEsmeSession;
+use smpp_codec_ffi::builders::SubmitSmBuilder;
+use smpp_codec_ffi::common::*;
+use smpp_codec_ffi::esme_session::*;
+use smpp_codec_ffi::session_pdu_ffi::*;
+use smpp_codec_ffi::smsc_listener::*;
+use smpp_codec_ffi::submission_pdu_ffi::SubmitSmRequest;
+use std::sync::Arc;
+
+fn start_listener() -> Arc<SmscListener> {
+    SmscListener::start(
+        smsc_listener_config_new("127.0.0.1".to_string(), 0),
+        Arc::new(PasswordCheck),
+        Arc::new(Responder),
+    )
+    .unwrap()
+}
+
+fn bind_request(password: &str) -> BindRequest {
+    BindRequest {
+        sequence_number: 0,
+        mode: BindMode::Transceiver,
+        system_id: "esme".to_string(),
+        password: password.to_string(),
+        system_type: String::new(),
+        interface_version: SMPP_INTERFACE_VERSION_34,
+        addr_ton: Ton::Unknown,
+        addr_npi: Npi::Unknown,
+        address_range: String::new(),
+    }
+}
+
+fn submit_request(destination: &str) -> SubmitSmRequest {
+    SubmitSmBuilder::new()
+        .destination(address_new(
+            Ton::Unknown,
+            Npi::Unknown,
+            destination.to_string(),
+        ))
+        .text("Hello".to_string())
+        .build()
+        .unwrap()
+}
+
+fn session(listener: &SmscListener, max_in_flight: u32) -> Arc<AsyncEsmeSession> {
+    let config = EsmeSessionConfig {
+        max_in_flight,
+        ..esme_session_config_new("127.0.0.1".to_string(), listener.local_port())
+    };
+    AsyncEsmeSession::new(config, None)
+}
+
+#[tokio::test]
+async fn test_async_session_bind_submit_query_unbind() {
+    let listener = start_listener();
+    let session = session(&listener, 10);
+    assert!(session
+        .clone()
+        .submit(submit_request("1234"))
+        .await
+        .is_err());
+
+    session.clone().bind(bind_request("secret")).await.unwrap();
+    assert!(session.is_bound());
+    assert!(session.clone().bind(bind_request("secret")).await.is_err());
+
+    let response = session
+        .clone()
+        .submit(submit_request("1234"))
+        .await
+        .unwrap();
+    assert_eq!(response.command_status, COMMAND_STATUS_OK);
+    assert_eq!(response.message_id, "esme-1234");
+
+    let response = session
+        .clone()
+        .query(QuerySmRequest {
+            sequence_number: 0,
+            message_id: "esme-1234".to_string(),
+            source_addr_ton: Ton::Unknown,
+            source_addr_npi: Npi::Unknown,
+            source_addr: String::new(),
+        })
+        .await
+        .unwrap();
+    assert_eq!(response.message_id, "esme-1234");
+    assert_eq!(response.message_state, 2);
+
+    session.clone().unbind().await.unwrap();
+    assert!(!session.is_bound());
+    assert!(session
+        .clone()
+        .submit(submit_request("1234"))
+        .await
+        .is_err());
+
+    session.clone().bind(bind_request("secret")).await.unwrap();
+    assert!(session.is_bound());
+    session.unbind().await.unwrap();
+}
+
+#[tokio::test]
+async fn test_async_session_bind_rejected() {
+    let listener = start_listener();
+    let session = session(&listener, 10);
+    let error = session
+        .clone()
+        .bind(bind_request("wrong"))
+        .await
+        .unwrap_err();
+    assert!(error.to_string().contains("0x0000000E"), "{}", error);
+    assert!(!session.is_bound());
+
+    let closed = AsyncEsmeSession::new(esme_session_config_new("127.0.0.1".to_string(), 1), None);
+    assert!(closed.bind(bind_request("secret")).await.is_err());
+}
+
+#[tokio::test(flavor = "current_thread")]
+async fn test_async_session_concurrent_submits() {
+    let listener = start_listener();
+    let session = session(&listener, 50);
+    session.clone().bind(bind_request("secret")).await.unwrap();
+
+    // Far more submits than the window holds, all awaited from a single thread.
+    let submits: Vec<_> = (0..2_000)
+        .map(|i| {
+            let session = session.clone();
+            tokio::spawn(async move { session.submit(submit_request(&i.to_string())).await })
+        })
+        .collect();
+    for (i, submit) in submits.into_iter().enumerate() {
+        let response = submit.await.unwrap().unwrap();
+        assert_eq!(response.message_id, format!("esme-{}", i));
+    }
+    assert_eq!(session.in_flight(), 0);
+    assert_eq!(session.orphan_responses(), 0);
+    session.unbind().await.unwrap();
+}
@@ -0,0 +1,214 @@
+mod fixtures;
+
+use fixtures::NoRequests;
+use smpp_codec_ffi::bind_auth::*;
+use smpp_codec_ffi::common::*;
+use smpp_codec_ffi::esme_session::*;
+use smpp_codec_ffi::session_pdu_ffi::BindRequest;
+use smpp_codec_ffi::smsc_listener::*;
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+
+const PEER: &str = "192.0.2.10:40000";
+
+fn bind(system_id: &str, password: &str, mode: BindMode) -> BindRequest {
+    BindRequest {
+        sequence_number: 1,
+        mode,
+        system_id: system_id.to_string(),
+        password: password.to_string(),
+        system_type: String::new(),
+        interface_version: SMPP_INTERFACE_VERSION_34,
+        addr_ton: Ton::Unknown,
+        addr_npi: Npi::Unknown,
+        address_range: String::new(),
+    }
+}
+
+fn restricted_account() -> EsmeAccount {
+    EsmeAccount {
+        system_type: "VMS".to_string(),
+        allowed_modes: vec![BindMode::Transmitter, BindMode::Transceiver],
+        allowed_ip_ranges: vec!["192.0.2.0/24".to_string(), "2001:db8::/32".to_string()],
+        ..esme_account_new("esme".to_string(), "secret".to_string())
+    }
+}
+
+#[test]
+fn test_bind_auth_statuses() {
+    let auth =
+        AccountAuthenticator::with_accounts(bind_auth_config_default(), vec![restricted_account()])
+            .unwrap();
+    let vms = |mut request: BindRequest| {
+        request.system_type = "VMS".to_string();
+        request
+    };
+
+    let ok = vms(bind("esme", "secret", BindMode::Transceiver));
+    assert_eq!(auth.check(PEER.to_string(), ok.clone()), COMMAND_STATUS_OK);
+    assert_eq!(
+        auth.check("[2001:db8::1]:2775".to_string(), ok.clone()),
+        COMMAND_STATUS_OK
+    );
+    assert_eq!(
+        auth.check("[::ffff:192.0.2.99]:2775".to_string(), ok.clone()),
+        COMMAND_STATUS_OK
+    );
+
+    assert_eq!(
+        auth.check(
+            PEER.to_string(),
+            vms(bind("other", "secret", BindMode::Transceiver))
+        ),
+        ESME_RINVSYSID
+    );
+    assert_eq!(
+        auth.check(
+            PEER.to_string(),
+            vms(bind("esme", "wrong", BindMode::Transceiver))
+        ),
+        ESME_RINVPASWD
+    );
+    assert_eq!(
+        auth.check(
+            PEER.to_string(),
+            bind("esme", "secret", BindMode::Transceiver)
+        ),
+        ESME_RINVSYSTYP
+    );
+    assert_eq!(
+        auth.check(
+            PEER.to_string(),
+            vms(bind("esme", "secret", BindMode::Receiver))
+        ),
+        ESME_RBINDFAIL
+    );
+    assert_eq!(
+        auth.check("198.51.100.1:40000".to_string(), ok.clone()),
+        ESME_RBINDFAIL
+    );
+
+    assert!(auth.remove_account("esme".to_string()));
+    assert_eq!(auth.check(PEER.to_string(), ok), ESME_RINVSYSID);
+
+    let invalid = EsmeAccount {
+        allowed_ip_ranges: vec!["10.0.0.0/33".to_string()],
+        ..esme_account_new("esme".to_string(), "secret".to_string())
+    };
+    assert!(auth.add_account(invalid.clone()).is_err());
+    assert!(
+        AccountAuthenticator::with_accounts(bind_auth_config_default(), vec![invalid]).is_err()
+    );
+}
+
+#[test]
+fn test_bind_auth_lockout() {
+    let config = BindAuthConfig {
+        max_failed_attempts: 3,
+        lockout_duration_ms: 200,
+    };
+    let account = esme_account_new("esme".to_string(), "secret".to_string());
+    let auth = AccountAuthenticator::with_accounts(config, vec![account]).unwrap();
+    let good = bind("esme", "secret", BindMode::Transmitter);
+    let bad = bind("esme", "guess", BindMode::Transmitter);
+
+    // A successful bind resets the count.
+    auth.check(PEER.to_string(), bad.clone());
+    auth.check(PEER.to_string(), bad.clone());
+    assert_eq!(
+        auth.check(PEER.to_string(), good.clone()),
+        COMMAND_STATUS_OK
+    );
+    assert!(auth.lockouts().is_empty());
+
+    for _ in 0..3 {
+        assert_eq!(auth.check(PEER.to_string(), bad.clone()), ESME_RINVPASWD);
+    }
+    assert_eq!(auth.check(PEER.to_string(), good.clone()), ESME_RBINDFAIL);
+    let lockouts = auth.lockouts();
+    assert_eq!(lockouts.len(), 1);
+    assert_eq!(lockouts[0].system_id, "esme");
+    assert_eq!(lockouts[0].failed_attempts, 3);
+    assert!(lockouts[0].remaining_ms <= 200);
+
+    auth.unlock("esme".to_string());
+    assert_eq!(
+        auth.check(PEER.to_string(), good.clone()),
+        COMMAND_STATUS_OK
+    );
+
+    for _ in 0..3 {
+        auth.check(PEER.to_string(), bad.clone());
+    }
+    assert_eq!(auth.check(PEER.to_string(), good.clone()), ESME_RBINDFAIL);
+    thread::sleep(Duration::from_millis(250));
+    assert!(auth.lockouts().is_empty());
+    assert_eq!(auth.check(PEER.to_string(), good), COMMAND_STATUS_OK);
+
+    // Unknown system_ids are locked out too.
+    for _ in 0..3 {
+        assert_eq!(
+            auth.check(PEER.to_string(), bind("ghost", "x", BindMode::Transmitter)),
+            ESME_RINVSYSID
+        );
+    }
+    assert_eq!(auth.lockouts()[0].system_id, "ghost");
+}
+
+struct Directory;
+
+impl AccountLookup for Directory {
+    fn find_account(&self, system_id: String) -> Option<EsmeAccount> {
+        (system_id == "esme").then(restricted_account)
+    }
+}
+
+#[test]
+fn test_bind_auth_lookup() {
+    let auth = AccountAuthenticator::with_lookup(bind_auth_config_default(), Arc::new(Directory));
+    let mut request = bind("esme", "secret", BindMode::Transmitter);
+    request.system_type = "VMS".to_string();
+    assert_eq!(
+        auth.check(PEER.to_string(), request.clone()),
+        COMMAND_STATUS_OK
+    );
+    request.system_id = "other".to_string();
+    assert_eq!(auth.check(PEER.to_string(), request), ESME_RINVSYSID);
+
+    assert!(auth
+        .add_account(esme_account_new("x".to_string(), "y".to_string()))
+        .is_err());
+    assert!(!auth.remove_account("esme".to_string()));
+}
+
+#[test]
+fn test_bind_auth_listener() {
+    let account = EsmeAccount {
+        allowed_ip_ranges: vec!["127.0.0.0/8".to_string()],
+        ..esme_account_new("esme".to_string(), "secret".to_string())
+    };
+    let auth =
+        AccountAuthenticator::with_accounts(bind_auth_config_default(), vec![account]).unwrap();
+    let listener = SmscListener::start(
+        smsc_listener_config_new("127.0.0.1".to_string(), 0),
+        auth.clone().as_bind_authenticator(),
+        Arc::new(NoRequests),
+    )
+    .unwrap();
+    let config = esme_session_config_new("127.0.0.1".to_string(), listener.local_port());
+
+    let Err(error) = EsmeSession::connect(
+        config.clone(),
+        bind("esme", "wrong", BindMode::Transceiver),
+        None,
+    ) else {
+        panic!("Bound with a wrong password");
+    };
+    assert!(error.to_string().contains("0x0000000E"), "{}", error);
+
+    let session =
+        EsmeSession::connect(config, bind("esme", "secret", BindMode::Transceiver), None).unwrap();
+    assert!(session.is_bound());
+    session.unbind().unwrap();
+}
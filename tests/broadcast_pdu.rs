@@ -1,5 +1,26 @@
 use smpp_codec_ffi::broadcast_pdu_ffi::*;
 use smpp_codec_ffi::common::{Npi, Ton};
+use smpp_codec_ffi::pdu::{encode_pdu, Pdu};
+use smpp_codec_ffi::tlv::Tlv;
+use smpp_codec_ffi::tlv_v50::*;
+
+fn mandatory_tlvs() -> Vec<Tlv> {
+    vec![
+        tlv_new_broadcast_area_identifier(BroadcastAreaIdentifier {
+            format: BroadcastAreaFormat::AliasName,
+            area: b"AREA1".to_vec(),
+        }),
+        tlv_new_broadcast_content_type(BroadcastContentType {
+            network_type: BroadcastNetworkType::Gsm,
+            service: 0x0001,
+        }),
+        tlv_new_broadcast_rep_num(3),
+        tlv_new_broadcast_frequency_interval(BroadcastFrequencyInterval {
+            unit: BroadcastFrequencyUnit::Minutes,
+            value: 5,
+        }),
+    ]
+}
 
 #[test]
 fn test_broadcast_sm() {
@@ -16,10 +37,11 @@ fn test_broadcast_sm() {
         replace_if_present_flag: 0,
         data_coding: 0,
         sm_default_msg_id: 0,
-        optional_params: vec![],
+        optional_params: mandatory_tlvs(),
     };
 
     let encoded = encode_broadcast_sm_request(&req);
+    assert_eq!(try_encode_broadcast_sm_request(&req).unwrap(), encoded);
     let decoded =
         decode_broadcast_sm_request(&encoded).expect("Failed to decode BroadcastSmRequest");
     assert_eq!(req, decoded);
@@ -32,8 +54,38 @@ fn test_broadcast_sm() {
         optional_params: vec![],
     };
     let encoded_resp = encode_broadcast_sm_response(&resp);
+    assert_eq!(encoded_resp[4..8], 0x80000111u32.to_be_bytes());
+    assert_eq!(
+        encode_pdu(&Pdu::BroadcastSmResponse { pdu: resp.clone() }),
+        encoded_resp
+    );
     let decoded_resp =
         decode_broadcast_sm_response(&encoded_resp).expect("Failed to decode BroadcastSmResponse");
     println!("Decoded BroadcastSmResponse: {:?}", decoded_resp);
     assert_eq!(resp, decoded_resp);
 }
+
+#[test]
+fn test_broadcast_sm_missing_mandatory_tlv() {
+    let mut optional_params = mandatory_tlvs();
+    optional_params.pop();
+    let req = BroadcastSmRequest {
+        sequence_number: 1,
+        service_type: String::new(),
+        source_addr_ton: Ton::Unknown,
+        source_addr_npi: Npi::Unknown,
+        source_addr: String::new(),
+        message_id: String::new(),
+        priority_flag: 0,
+        schedule_delivery_time: None,
+        validity_period: None,
+        replace_if_present_flag: 0,
+        data_coding: 0,
+        sm_default_msg_id: 0,
+        optional_params,
+    };
+    let err = try_encode_broadcast_sm_request(&req).unwrap_err();
+    assert!(err.to_string().contains("broadcast_frequency_interval"));
+    // The plain encoder leaves the check to the builder and validate_pdu.
+    assert!(!encode_broadcast_sm_request(&req).is_empty());
+}
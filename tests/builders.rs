@@ -0,0 +1,258 @@
+use smpp_codec_ffi::address::{address_alphanumeric, address_from_e164, address_new};
+use smpp_codec_ffi::broadcast_pdu_ffi::{decode_broadcast_sm_request, encode_broadcast_sm_request};
+use smpp_codec_ffi::builders::*;
+use smpp_codec_ffi::common::{Npi, Ton};
+use smpp_codec_ffi::sequence::{check_sequence_number, SequenceGenerator};
+use smpp_codec_ffi::splitter::EncodingType;
+use smpp_codec_ffi::submission_pdu_ffi::{decode_submit_sm_request, encode_submit_sm_request};
+use smpp_codec_ffi::tlv::{tlv_new, tlv_new_u16, Tags};
+use smpp_codec_ffi::tlv_v50::*;
+
+#[test]
+fn test_submit_sm_builder_defaults() {
+    let request = SubmitSmBuilder::new()
+        .source(address_alphanumeric("MyBrand".to_string()).unwrap())
+        .destination(address_from_e164("+44 7700 900123".to_string()).unwrap())
+        .text("Hello".to_string())
+        .delivery_receipt(DeliveryReceiptPolicy::OnSuccessOrFailure)
+        .build()
+        .expect("Failed to build SubmitSmRequest");
+
+    assert_eq!(request.dest_addr_ton, Ton::International);
+    assert_eq!(request.destination_addr, "447700900123");
+    assert!(check_sequence_number(request.sequence_number).is_ok());
+    assert_eq!(request.data_coding, 0x00);
+    assert_eq!(request.short_message.len(), 5);
+    assert_eq!(request.registered_delivery, 0x01);
+    assert!(request.tlvs.is_empty());
+
+    let decoded = decode_submit_sm_request(&encode_submit_sm_request(&request))
+        .expect("Failed to decode SubmitSmRequest");
+    assert_eq!(request, decoded);
+}
+
+#[test]
+fn test_submit_sm_builder_text_encoding() {
+    let request = SubmitSmBuilder::new()
+        .destination(address_new(Ton::Unknown, Npi::Unknown, "123".to_string()))
+        .text("Привет".to_string())
+        .build()
+        .unwrap();
+    assert_eq!(request.data_coding, 0x08);
+    assert_eq!(request.short_message.len(), 12);
+
+    let request = SubmitSmBuilder::new()
+        .destination(address_new(Ton::Unknown, Npi::Unknown, "123".to_string()))
+        .text_with_encoding("café".to_string(), EncodingType::Latin1)
+        .unwrap()
+        .build()
+        .unwrap();
+    assert_eq!(request.data_coding, 0x03);
+    assert_eq!(request.short_message, vec![b'c', b'a', b'f', 0xE9]);
+
+    let result = SubmitSmBuilder::new().text_with_encoding("🚀".to_string(), EncodingType::Gsm7Bit);
+    assert!(result.is_err());
+}
+
+#[test]
+fn test_submit_sm_builder_long_text_uses_payload() {
+    let request = SubmitSmBuilder::new()
+        .destination(address_new(Ton::Unknown, Npi::Unknown, "123".to_string()))
+        .text("A".repeat(300))
+        .build()
+        .unwrap();
+
+    assert!(request.short_message.is_empty());
+    assert_eq!(request.tlvs.len(), 1);
+    assert_eq!(request.tlvs[0].tag, Tags::MESSAGE_PAYLOAD);
+    assert_eq!(request.tlvs[0].value.len(), 300);
+}
+
+#[test]
+fn test_builder_rejects_oversized_payload() {
+    let text = "A".repeat(u16::MAX as usize + 1);
+    let error = SubmitSmBuilder::new()
+        .destination(address_new(Ton::Unknown, Npi::Unknown, "123".to_string()))
+        .text(text.clone())
+        .build()
+        .unwrap_err();
+    assert!(error.to_string().contains("maximum is 65535"));
+
+    let result = DataSmBuilder::new()
+        .destination(address_new(Ton::Unknown, Npi::Unknown, "123".to_string()))
+        .text(text)
+        .build();
+    assert!(result.is_err());
+
+    let request = SubmitSmBuilder::new()
+        .destination(address_new(Ton::Unknown, Npi::Unknown, "123".to_string()))
+        .text("A".repeat(u16::MAX as usize))
+        .build()
+        .unwrap();
+    assert_eq!(request.tlvs[0].length, u16::MAX);
+}
+
+#[test]
+fn test_submit_sm_builder_validity() {
+    let request = SubmitSmBuilder::new()
+        .destination(address_new(Ton::Unknown, Npi::Unknown, "123".to_string()))
+        .validity_seconds(90_061)
+        .unwrap()
+        .build()
+        .unwrap();
+    assert_eq!(request.validity_period.as_deref(), Some("000001010101000R"));
+
+    assert!(SubmitSmBuilder::new()
+        .validity_seconds(100 * 86_400)
+        .is_err());
+}
+
+#[test]
+fn test_submit_sm_builder_validation() {
+    // Missing destination
+    assert!(SubmitSmBuilder::new().build().is_err());
+
+    // Service type too long
+    let result = SubmitSmBuilder::new()
+        .destination(address_new(Ton::Unknown, Npi::Unknown, "123".to_string()))
+        .service_type("TOOLONG".to_string())
+        .build();
+    assert!(result.is_err());
+
+    // short_message together with message_payload
+    let result = SubmitSmBuilder::new()
+        .destination(address_new(Ton::Unknown, Npi::Unknown, "123".to_string()))
+        .short_message(b"Hi".to_vec(), 0)
+        .tlv(tlv_new(Tags::MESSAGE_PAYLOAD, b"Hi".to_vec()))
+        .build();
+    assert!(result.is_err());
+}
+
+#[test]
+fn test_deliver_sm_builder() {
+    let request = DeliverSmBuilder::new()
+        .sequence_number(7)
+        .source(address_from_e164("+447700900123".to_string()).unwrap())
+        .destination(address_new(Ton::Unknown, Npi::Unknown, "1234".to_string()))
+        .text("Reply".to_string())
+        .build()
+        .unwrap();
+
+    assert_eq!(request.sequence_number, 7);
+    assert_eq!(request.dest_addr, "1234");
+    assert_eq!(request.short_message.len(), 5);
+}
+
+#[test]
+fn test_data_sm_builder() {
+    let request = DataSmBuilder::new()
+        .destination(address_new(Ton::Unknown, Npi::Unknown, "1234".to_string()))
+        .text("Hello".to_string())
+        .time_to_live(60)
+        .build()
+        .unwrap();
+
+    assert_eq!(request.optional_params.len(), 2);
+    assert_eq!(request.optional_params[0].tag, Tags::MESSAGE_PAYLOAD);
+    assert_eq!(request.optional_params[1].value, vec![0, 0, 0, 60]);
+}
+
+#[test]
+fn test_broadcast_sm_builder() {
+    // Mandatory broadcast TLVs missing
+    assert!(BroadcastSmBuilder::new().build().is_err());
+
+    let request = BroadcastSmBuilder::new()
+        .source(address_new(
+            Ton::International,
+            Npi::Isdn,
+            "12345".to_string(),
+        ))
+        .text("Alert".to_string())
+        .rep_num(2)
+        .area_identifier(BroadcastAreaIdentifier {
+            format: BroadcastAreaFormat::AliasName,
+            area: vec![0x00, 0x01],
+        })
+        .content_type(BroadcastContentType {
+            network_type: BroadcastNetworkType::Gsm,
+            service: 0x0001,
+        })
+        .frequency_interval(BroadcastFrequencyInterval {
+            unit: BroadcastFrequencyUnit::Minutes,
+            value: 1,
+        })
+        .tlv(tlv_new_u16(Tags::BROADCAST_REP_NUM, 3))
+        .build()
+        .unwrap();
+
+    assert_eq!(request.optional_params.len(), 5);
+    let rep_num = request
+        .optional_params
+        .iter()
+        .find(|t| t.tag == Tags::BROADCAST_REP_NUM)
+        .unwrap();
+    assert_eq!(rep_num.value, vec![0, 3]);
+    assert!(!encode_broadcast_sm_request(&request).is_empty());
+}
+
+#[test]
+fn test_broadcast_sm_builder_multiple_areas() {
+    let request = BroadcastSmBuilder::new()
+        .area_identifier(BroadcastAreaIdentifier {
+            format: BroadcastAreaFormat::AliasName,
+            area: vec![0x00, 0x01],
+        })
+        .area_identifier(BroadcastAreaIdentifier {
+            format: BroadcastAreaFormat::AliasName,
+            area: vec![0x00, 0x02],
+        })
+        .content_type(BroadcastContentType {
+            network_type: BroadcastNetworkType::Gsm,
+            service: 0x0001,
+        })
+        .rep_num(1)
+        .frequency_interval(BroadcastFrequencyInterval {
+            unit: BroadcastFrequencyUnit::Minutes,
+            value: 1,
+        })
+        .build()
+        .unwrap();
+
+    let areas: Vec<Vec<u8>> = request
+        .optional_params
+        .iter()
+        .filter(|t| t.tag == Tags::BROADCAST_AREA_IDENTIFIER)
+        .map(|t| t.value.clone())
+        .collect();
+    assert_eq!(areas, vec![vec![0x00, 0x00, 0x01], vec![0x00, 0x00, 0x02]]);
+    let decoded = decode_broadcast_sm_request(&encode_broadcast_sm_request(&request)).unwrap();
+    assert_eq!(decoded, request);
+}
+
+#[test]
+fn test_builder_rejects_invalid_address() {
+    let result = SubmitSmBuilder::new()
+        .destination(address_new(
+            Ton::International,
+            Npi::Isdn,
+            "44-7700".to_string(),
+        ))
+        .build();
+    assert!(result.is_err());
+}
+
+#[test]
+fn test_builder_sequence_generator() {
+    let generator = SequenceGenerator::with_seed(500).unwrap();
+    let builder = SubmitSmBuilder::new()
+        .sequence_generator(generator.clone())
+        .destination(address_new(Ton::Unknown, Npi::Unknown, "123".to_string()))
+        .text("Hello".to_string());
+    assert_eq!(builder.build().unwrap().sequence_number, 500);
+    assert_eq!(builder.build().unwrap().sequence_number, 501);
+
+    let request = builder.sequence_number(7).build().unwrap();
+    assert_eq!(request.sequence_number, 7);
+    assert_eq!(generator.peek(), 502);
+}
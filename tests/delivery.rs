@@ -1,6 +1,9 @@
+use smpp_codec_ffi::address::address_new;
+use smpp_codec_ffi::ancillary_pdu_ffi::MessageState;
 use smpp_codec_ffi::common::{Npi, Ton};
 use smpp_codec_ffi::delivery_pdu_ffi::*;
-use smpp_codec_ffi::tlv::tlv_new_u8;
+use smpp_codec_ffi::message_store::StoredMessage;
+use smpp_codec_ffi::tlv::{tlv_new_u8, Tags};
 
 #[test]
 fn test_deliver_sm_encoding_decoding() {
@@ -108,3 +111,87 @@ fn test_data_sm_response() {
 
     assert_eq!(response, decoded);
 }
+
+fn stored_message(registered_delivery: u8) -> StoredMessage {
+    StoredMessage {
+        message_id: "SMSC0001".to_string(),
+        system_id: "esme".to_string(),
+        service_type: String::new(),
+        source: address_new(Ton::Alphanumeric, Npi::Unknown, "SHOP".to_string()),
+        destination: address_new(Ton::International, Npi::Isdn, "447700900123".to_string()),
+        esm_class: 0,
+        registered_delivery,
+        data_coding: 0,
+        short_message: b"Your order has been dispatched".to_vec(),
+        state: MessageState::Enroute,
+        error_code: 0,
+        // 2024-02-29 12:34:56 UTC
+        submit_time_ms: 1_709_210_096_000,
+        done_time_ms: Some(1_709_210_160_000),
+    }
+}
+
+#[test]
+fn test_build_delivery_receipt_pdu() {
+    let pdu = build_delivery_receipt_pdu(stored_message(1), MessageState::Delivered, 0)
+        .unwrap()
+        .unwrap();
+    assert_eq!(pdu.esm_class, ESM_CLASS_DELIVERY_RECEIPT);
+    assert_eq!(pdu.source_addr, "447700900123");
+    assert_eq!(pdu.dest_addr_ton, Ton::Alphanumeric);
+    assert_eq!(pdu.dest_addr, "SHOP");
+    assert_eq!(
+        String::from_utf8(pdu.short_message.clone()).unwrap(),
+        "id:SMSC0001 sub:001 dlvrd:001 submit date:2402291234 done date:2402291236 \
+         stat:DELIVRD err:000 text:Your order has been "
+    );
+    assert_eq!(pdu.optional_params.len(), 2);
+    assert_eq!(pdu.optional_params[0].tag, Tags::RECEIPTED_MESSAGE_ID);
+    assert_eq!(pdu.optional_params[0].value, b"SMSC0001\0");
+    assert_eq!(pdu.optional_params[1].tag, Tags::MESSAGE_STATE);
+    assert_eq!(pdu.optional_params[1].value, vec![2]);
+
+    let decoded = decode_deliver_sm(&encode_deliver_sm(&pdu)).unwrap();
+    assert_eq!(decoded.short_message, pdu.short_message);
+    assert_eq!(decoded.optional_params, pdu.optional_params);
+
+    let failed = build_delivery_receipt_pdu(stored_message(1), MessageState::Undeliverable, 34)
+        .unwrap()
+        .unwrap();
+    let text = String::from_utf8(failed.short_message).unwrap();
+    assert!(text.contains("dlvrd:000"), "{}", text);
+    assert!(text.contains("stat:UNDELIV err:034"), "{}", text);
+    assert_eq!(failed.optional_params[2].tag, Tags::NETWORK_ERROR_CODE);
+    assert_eq!(failed.optional_params[2].value, vec![3, 0, 34]);
+
+    // Binary content is left out of the text.
+    let mut binary = stored_message(1);
+    binary.data_coding = 0x04;
+    let pdu = build_delivery_receipt_pdu(binary, MessageState::Delivered, 0)
+        .unwrap()
+        .unwrap();
+    assert!(String::from_utf8(pdu.short_message)
+        .unwrap()
+        .ends_with("text:"));
+
+    assert!(build_delivery_receipt_pdu(stored_message(1), MessageState::Enroute, 0).is_err());
+}
+
+#[test]
+fn test_build_delivery_receipt_pdu_policy() {
+    let receipt = |registered_delivery: u8, state: MessageState| {
+        build_delivery_receipt_pdu(stored_message(registered_delivery), state, 0)
+            .unwrap()
+            .is_some()
+    };
+    for state in [MessageState::Delivered, MessageState::Expired] {
+        assert!(!receipt(0x00, state));
+        assert!(receipt(0x01, state));
+        // Only the low two bits select the receipt; the rest are other notifications.
+        assert!(receipt(0x11, state));
+    }
+    assert!(!receipt(0x02, MessageState::Delivered));
+    assert!(receipt(0x02, MessageState::Rejected));
+    assert!(receipt(0x03, MessageState::Delivered));
+    assert!(!receipt(0x03, MessageState::Deleted));
+}
@@ -0,0 +1,213 @@
+mod fixtures;
+
+use fixtures::AcceptAll;
+use smpp_codec_ffi::address::address_new;
+use smpp_codec_ffi::builders::SubmitSmBuilder;
+use smpp_codec_ffi::common::*;
+use smpp_codec_ffi::esme_pool::*;
+use smpp_codec_ffi::esme_session::esme_session_config_new;
+use smpp_codec_ffi::framer::read_frame;
+use smpp_codec_ffi::pdu::{decode_pdu_header, Pdu};
+use smpp_codec_ffi::session_pdu_ffi::*;
+use smpp_codec_ffi::smsc_listener::*;
+use smpp_codec_ffi::submission_pdu_ffi::{SubmitSmRequest, SubmitSmResponse};
+use std::collections::HashMap;
+use std::io::Write;
+use std::net::TcpListener;
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// Answers every submit with a message ID naming the bind that carried it.
+struct NameBind;
+
+impl SessionHandler for NameBind {
+    fn on_request(&self, session: SmscSessionInfo, _request: Pdu) -> SessionReply {
+        Pdu::SubmitSmResponse {
+            pdu: SubmitSmResponse {
+                sequence_number: 0,
+                command_status: COMMAND_STATUS_OK,
+                message_id: format!("{}-{}", session.system_id, session.session_id),
+                status_description: String::new(),
+            },
+        }
+        .into()
+    }
+}
+
+fn start_smsc(port: u16) -> Arc<SmscListener> {
+    SmscListener::start(
+        smsc_listener_config_new("127.0.0.1".to_string(), port),
+        Arc::new(AcceptAll),
+        Arc::new(NameBind),
+    )
+    .unwrap()
+}
+
+fn endpoint(smsc: &SmscListener, system_id: &str, binds: u32, weight: u32) -> PoolEndpoint {
+    let mut config = esme_session_config_new("127.0.0.1".to_string(), smsc.local_port());
+    config.response_timeout_ms = 5_000;
+    PoolEndpoint {
+        config,
+        bind: BindRequest {
+            sequence_number: 0,
+            mode: BindMode::Transmitter,
+            system_id: system_id.to_string(),
+            password: "secret".to_string(),
+            system_type: String::new(),
+            interface_version: SMPP_INTERFACE_VERSION_34,
+            addr_ton: Ton::Unknown,
+            addr_npi: Npi::Unknown,
+            address_range: String::new(),
+        },
+        binds,
+        weight,
+    }
+}
+
+fn submit_request() -> SubmitSmRequest {
+    SubmitSmBuilder::new()
+        .destination(address_new(Ton::Unknown, Npi::Unknown, "1234".to_string()))
+        .text("Hello".to_string())
+        .build()
+        .unwrap()
+}
+
+/// Accepts one transmitter bind, then closes the connection on the first request.
+fn start_dropping_smsc() -> u16 {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let port = listener.local_addr().unwrap().port();
+    thread::spawn(move || {
+        let (mut stream, _) = listener.accept().unwrap();
+        let bind = decode_pdu_header(&read_frame(&mut stream).unwrap()).unwrap();
+        let mut response = encode_bind_response(&BindResponse {
+            sequence_number: bind.sequence_number,
+            command_status: COMMAND_STATUS_OK,
+            system_id: "SMSC".to_string(),
+        });
+        response[4..8].copy_from_slice(&(CMD_BIND_TRANSMITTER | 0x8000_0000).to_be_bytes());
+        stream.write_all(&response).unwrap();
+        let _ = read_frame(&mut stream);
+    });
+    port
+}
+
+/// Submits `count` messages and counts them per message ID.
+fn spread(pool: &EsmePool, count: usize) -> HashMap<String, usize> {
+    let mut counts = HashMap::new();
+    for _ in 0..count {
+        let response = pool.submit(submit_request()).unwrap();
+        *counts.entry(response.message_id).or_default() += 1;
+    }
+    counts
+}
+
+#[test]
+fn test_esme_pool_round_robin() {
+    let smsc = start_smsc(0);
+    let config = esme_pool_config_new(vec![endpoint(&smsc, "a", 3, 1)], LoadBalancing::RoundRobin);
+    let pool = EsmePool::connect(config, None).unwrap();
+    assert_eq!(pool.size(), 3);
+    assert_eq!(pool.bound_count(), 3);
+
+    let counts = spread(&pool, 6);
+    assert_eq!(counts.len(), 3);
+    assert!(counts.values().all(|count| *count == 2), "{:?}", counts);
+
+    // With nothing in flight, least-in-flight falls back to taking turns.
+    let config = esme_pool_config_new(
+        vec![endpoint(&smsc, "b", 2, 1)],
+        LoadBalancing::LeastInFlight,
+    );
+    let pool = EsmePool::connect(config, None).unwrap();
+    assert_eq!(spread(&pool, 4).len(), 2);
+    assert!(pool.members().iter().all(|member| member.in_flight == 0));
+}
+
+#[test]
+fn test_esme_pool_weighted() {
+    let smsc = start_smsc(0);
+    let config = esme_pool_config_new(
+        vec![endpoint(&smsc, "a", 1, 3), endpoint(&smsc, "b", 1, 1)],
+        LoadBalancing::Weighted,
+    );
+    let pool = EsmePool::connect(config, None).unwrap();
+
+    let mut per_endpoint = HashMap::new();
+    for (message_id, count) in spread(&pool, 8) {
+        *per_endpoint.entry(message_id[..1].to_string()).or_insert(0) += count;
+    }
+    assert_eq!(per_endpoint["a"], 6);
+    assert_eq!(per_endpoint["b"], 2);
+}
+
+#[test]
+fn test_esme_pool_replaces_lost_binds() {
+    let healthy = start_smsc(0);
+    let failing = start_smsc(0);
+    let port = failing.local_port();
+    let mut config = esme_pool_config_new(
+        vec![endpoint(&healthy, "a", 1, 1), endpoint(&failing, "b", 1, 1)],
+        LoadBalancing::RoundRobin,
+    );
+    config.rebind_interval_ms = 50;
+    let pool = EsmePool::connect(config, None).unwrap();
+
+    failing.shutdown();
+    let deadline = Instant::now() + Duration::from_secs(5);
+    while pool.bound_count() > 1 {
+        assert!(Instant::now() < deadline, "Lost bind was not removed");
+        thread::sleep(Duration::from_millis(10));
+    }
+    assert!(spread(&pool, 4).keys().all(|id| id.starts_with("a-")));
+
+    let _restarted = start_smsc(port);
+    while pool.bound_count() < 2 {
+        assert!(Instant::now() < deadline, "Lost bind was not replaced");
+        thread::sleep(Duration::from_millis(10));
+    }
+    assert_eq!(spread(&pool, 4).len(), 2);
+    pool.close();
+    assert_eq!(pool.bound_count(), 0);
+}
+
+#[test]
+fn test_esme_pool_does_not_resubmit_sent_requests() {
+    let healthy = start_smsc(0);
+    let mut dropping = endpoint(&healthy, "b", 1, 1);
+    dropping.config.port = start_dropping_smsc();
+    let config = esme_pool_config_new(
+        vec![endpoint(&healthy, "a", 1, 1), dropping],
+        LoadBalancing::RoundRobin,
+    );
+    let pool = EsmePool::connect(config, None).unwrap();
+
+    // One of the two submits reaches the bind that drops without answering;
+    // the SMSC may have accepted it, so it is not sent again on the other bind.
+    let results: Vec<_> = (0..2).map(|_| pool.submit(submit_request())).collect();
+    assert_eq!(results.iter().filter(|result| result.is_err()).count(), 1);
+    let accepted = results.into_iter().find_map(Result::ok).unwrap();
+    assert!(accepted.message_id.starts_with("a-"));
+
+    // With the lost bind gone, submits go to the healthy one.
+    let deadline = Instant::now() + Duration::from_secs(5);
+    while pool.bound_count() > 1 {
+        assert!(Instant::now() < deadline, "Lost bind was not removed");
+        thread::sleep(Duration::from_millis(10));
+    }
+    assert!(pool.submit(submit_request()).is_ok());
+}
+
+#[test]
+fn test_esme_pool_tps_cap() {
+    let smsc = start_smsc(0);
+    let mut config =
+        esme_pool_config_new(vec![endpoint(&smsc, "a", 2, 1)], LoadBalancing::RoundRobin);
+    config.max_per_second = 5;
+    let pool = EsmePool::connect(config, None).unwrap();
+
+    let started = Instant::now();
+    spread(&pool, 8);
+    assert!(started.elapsed() >= Duration::from_millis(500));
+    assert!(pool.throttle_stats().send_rate >= 5);
+}
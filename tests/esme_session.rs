@@ -0,0 +1,660 @@
+use smpp_codec_ffi::address::address_new;
+use smpp_codec_ffi::builders::{DeliverSmBuilder, SubmitSmBuilder};
+use smpp_codec_ffi::common::*;
+use smpp_codec_ffi::delivery_pdu_ffi::{encode_deliver_sm, DeliverSmRequest};
+use smpp_codec_ffi::esme_session::*;
+use smpp_codec_ffi::framer::read_frame;
+use smpp_codec_ffi::pdu::{decode_pdu, Pdu};
+use smpp_codec_ffi::reconnect::*;
+use smpp_codec_ffi::session_pdu_ffi::*;
+use smpp_codec_ffi::session_state::LinkHealth;
+use smpp_codec_ffi::submission_pdu_ffi::{
+    encode_submit_sm_response, SubmitSmRequest, SubmitSmResponse,
+};
+use smpp_codec_ffi::throttle::*;
+use std::io::Write;
+use std::net::{TcpListener, TcpStream};
+use std::sync::mpsc::{self, Sender};
+use std::sync::{Arc, Mutex};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+
+struct HealthRecorder {
+    changes: Mutex<Sender<LinkHealth>>,
+}
+
+impl LinkHealthListener for HealthRecorder {
+    fn on_link_health(&self, health: LinkHealth) {
+        self.changes.lock().unwrap().send(health).unwrap();
+    }
+}
+
+struct Recorder {
+    delivered: Mutex<Sender<DeliverSmRequest>>,
+}
+
+impl DeliverSmListener for Recorder {
+    fn on_deliver_sm(&self, request: DeliverSmRequest) -> u32 {
+        self.delivered.lock().unwrap().send(request).unwrap();
+        COMMAND_STATUS_OK
+    }
+}
+
+fn bind_request() -> BindRequest {
+    BindRequest {
+        sequence_number: 0,
+        mode: BindMode::Transceiver,
+        system_id: "esme".to_string(),
+        password: "secret".to_string(),
+        system_type: String::new(),
+        interface_version: SMPP_INTERFACE_VERSION_34,
+        addr_ton: Ton::Unknown,
+        addr_npi: Npi::Unknown,
+        address_range: String::new(),
+    }
+}
+
+fn bind_response(sequence_number: u32, command_status: u32) -> Vec<u8> {
+    let mut bytes = encode_bind_response(&BindResponse {
+        sequence_number,
+        command_status,
+        system_id: "SMSC".to_string(),
+    });
+    bytes[4..8].copy_from_slice(&(CMD_BIND_TRANSCEIVER | 0x8000_0000).to_be_bytes());
+    bytes
+}
+
+/// Accepts one connection and answers like a minimal SMSC.
+///
+/// Returns every PDU the ESME sent, in order.
+fn stand_in_smsc(listener: TcpListener, bind_status: u32) -> JoinHandle<Vec<Pdu>> {
+    thread::spawn(move || {
+        let (mut stream, _) = listener.accept().unwrap();
+        let mut received = Vec::new();
+        while let Ok(frame) = read_frame(&mut stream) {
+            let pdu = decode_pdu(&frame).unwrap();
+            received.push(pdu.clone());
+            match pdu {
+                Pdu::BindRequest { pdu } => {
+                    write(
+                        &mut stream,
+                        &bind_response(pdu.sequence_number, bind_status),
+                    );
+                }
+                Pdu::SubmitSm { pdu } => {
+                    write(
+                        &mut stream,
+                        &encode_submit_sm_response(&SubmitSmResponse {
+                            sequence_number: pdu.sequence_number,
+                            command_status: COMMAND_STATUS_OK,
+                            message_id: format!("msg-{}", pdu.sequence_number),
+                            status_description: String::new(),
+                        }),
+                    );
+                    write(
+                        &mut stream,
+                        &encode_enquire_link(&EnquireLink {
+                            sequence_number: 900,
+                        }),
+                    );
+                    let mut deliver = DeliverSmBuilder::new()
+                        .source(address_new(
+                            Ton::International,
+                            Npi::Isdn,
+                            "447700900123".to_string(),
+                        ))
+                        .destination(address_new(Ton::Unknown, Npi::Unknown, "1234".to_string()))
+                        .text("Reply".to_string())
+                        .build()
+                        .unwrap();
+                    deliver.sequence_number = 901;
+                    write(&mut stream, &encode_deliver_sm(&deliver));
+                }
+                Pdu::EnquireLink { pdu } => {
+                    write(
+                        &mut stream,
+                        &encode_enquire_link_response(&EnquireLinkResponse {
+                            sequence_number: pdu.sequence_number,
+                            command_status: COMMAND_STATUS_OK,
+                        }),
+                    );
+                }
+                Pdu::Unbind { pdu } => {
+                    write(
+                        &mut stream,
+                        &encode_unbind_response(&UnbindResponse {
+                            sequence_number: pdu.sequence_number,
+                            command_status: COMMAND_STATUS_OK,
+                        }),
+                    );
+                }
+                _ => {}
+            }
+        }
+        received
+    })
+}
+
+fn write(stream: &mut TcpStream, bytes: &[u8]) {
+    stream.write_all(bytes).unwrap();
+}
+
+fn local_config(listener: &TcpListener) -> EsmeSessionConfig {
+    let port = listener.local_addr().unwrap().port();
+    let mut config = esme_session_config_new("127.0.0.1".to_string(), port);
+    config.response_timeout_ms = 5_000;
+    config
+}
+
+#[test]
+fn test_esme_session_round_trip() {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let config = local_config(&listener);
+    let smsc = stand_in_smsc(listener, COMMAND_STATUS_OK);
+
+    let (sender, delivered) = mpsc::channel();
+    let recorder = Arc::new(Recorder {
+        delivered: Mutex::new(sender),
+    });
+    let session = EsmeSession::connect(config, bind_request(), Some(recorder)).unwrap();
+    assert!(session.is_bound());
+
+    let request = SubmitSmBuilder::new()
+        .destination(address_new(
+            Ton::International,
+            Npi::Isdn,
+            "447700900123".to_string(),
+        ))
+        .text("Hello".to_string())
+        .build()
+        .unwrap();
+    let first = session.submit(request.clone()).unwrap();
+    let second = session.submit(request).unwrap();
+    assert_eq!(first.command_status, COMMAND_STATUS_OK);
+    assert_eq!(first.message_id, format!("msg-{}", first.sequence_number));
+    assert_eq!(second.sequence_number, first.sequence_number + 1);
+
+    let deliver = delivered.recv_timeout(Duration::from_secs(5)).unwrap();
+    assert_eq!(deliver.short_message, b"Reply".to_vec());
+
+    session.unbind().unwrap();
+    assert!(!session.is_bound());
+    assert!(session.unbind().is_ok());
+
+    let received = smsc.join().unwrap();
+    assert!(matches!(received[0], Pdu::BindRequest { .. }));
+    assert!(received.iter().any(|pdu| matches!(
+        pdu,
+        Pdu::EnquireLinkResponse { pdu } if pdu.sequence_number == 900
+    )));
+    assert!(received.iter().any(|pdu| matches!(
+        pdu,
+        Pdu::DeliverSmResponse { pdu } if pdu.sequence_number == 901
+    )));
+    assert!(received.iter().any(|pdu| matches!(pdu, Pdu::Unbind { .. })));
+}
+
+#[test]
+fn test_esme_session_bind_rejected() {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let config = local_config(&listener);
+    let smsc = stand_in_smsc(listener, ESME_RINVPASWD);
+
+    let result = EsmeSession::connect(config, bind_request(), None);
+    match result {
+        Err(SmppFfiError::Generic { msg }) => assert!(msg.contains("Bind failed"), "{}", msg),
+        Ok(_) => panic!("Bind should have been rejected"),
+    }
+    assert_eq!(smsc.join().unwrap().len(), 1);
+}
+
+/// Binds, then holds `count` submits and answers them in reverse order,
+/// preceded by a response nobody asked for.
+fn pipelining_smsc(listener: TcpListener, count: usize) -> JoinHandle<()> {
+    thread::spawn(move || {
+        let (mut stream, _) = listener.accept().unwrap();
+        let mut held = Vec::new();
+        while let Ok(frame) = read_frame(&mut stream) {
+            match decode_pdu(&frame).unwrap() {
+                Pdu::BindRequest { pdu } => {
+                    write(&mut stream, &bind_response(pdu.sequence_number, 0));
+                }
+                Pdu::SubmitSm { pdu } => {
+                    held.push(pdu.sequence_number);
+                    if held.len() < count {
+                        continue;
+                    }
+                    let mut responses = vec![9999];
+                    responses.extend(held.drain(..).rev());
+                    for sequence_number in responses {
+                        write(
+                            &mut stream,
+                            &encode_submit_sm_response(&SubmitSmResponse {
+                                sequence_number,
+                                command_status: COMMAND_STATUS_OK,
+                                message_id: format!("msg-{}", sequence_number),
+                                status_description: String::new(),
+                            }),
+                        );
+                    }
+                }
+                Pdu::Unbind { pdu } => {
+                    write(
+                        &mut stream,
+                        &encode_unbind_response(&UnbindResponse {
+                            sequence_number: pdu.sequence_number,
+                            command_status: COMMAND_STATUS_OK,
+                        }),
+                    );
+                }
+                _ => {}
+            }
+        }
+    })
+}
+
+#[test]
+fn test_esme_session_pipelines_submits() {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let mut config = local_config(&listener);
+    config.max_in_flight = 4;
+    let smsc = pipelining_smsc(listener, 4);
+    let session = EsmeSession::connect(config, bind_request(), None).unwrap();
+
+    let submitters: Vec<_> = (0..4)
+        .map(|_| {
+            let session = session.clone();
+            thread::spawn(move || {
+                let request = SubmitSmBuilder::new()
+                    .destination(address_new(Ton::Unknown, Npi::Unknown, "1234".to_string()))
+                    .text("Hello".to_string())
+                    .build()
+                    .unwrap();
+                session.submit(request).unwrap()
+            })
+        })
+        .collect();
+    let mut sequence_numbers = Vec::new();
+    for submitter in submitters {
+        let response = submitter.join().unwrap();
+        assert_eq!(
+            response.message_id,
+            format!("msg-{}", response.sequence_number)
+        );
+        sequence_numbers.push(response.sequence_number);
+    }
+    sequence_numbers.sort_unstable();
+    sequence_numbers.dedup();
+    assert_eq!(sequence_numbers.len(), 4);
+    assert_eq!(session.in_flight(), 0);
+    assert_eq!(session.orphan_responses(), 1);
+
+    session.unbind().unwrap();
+    smsc.join().unwrap();
+}
+
+fn keepalive_config(listener: &TcpListener) -> EsmeSessionConfig {
+    let mut config = local_config(listener);
+    config.enquire_link_interval_ms = 50;
+    config.response_timeout_ms = 100;
+    config.max_missed_enquire_links = 2;
+    config
+}
+
+#[test]
+fn test_esme_session_keepalive() {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let config = keepalive_config(&listener);
+    let smsc = stand_in_smsc(listener, COMMAND_STATUS_OK);
+    let session = EsmeSession::connect(config, bind_request(), None).unwrap();
+
+    thread::sleep(Duration::from_millis(300));
+    assert!(session.is_bound());
+    assert_eq!(session.link_health(), LinkHealth::Healthy);
+
+    session.unbind().unwrap();
+    let received = smsc.join().unwrap();
+    let keepalives = received
+        .iter()
+        .filter(|pdu| matches!(pdu, Pdu::EnquireLink { .. }))
+        .count();
+    assert!(keepalives >= 2, "{} keepalives sent", keepalives);
+}
+
+#[test]
+fn test_esme_session_detects_dead_peer() {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let config = keepalive_config(&listener);
+    // Binds, then never answers again.
+    let smsc = thread::spawn(move || {
+        let (mut stream, _) = listener.accept().unwrap();
+        while let Ok(frame) = read_frame(&mut stream) {
+            if let Pdu::BindRequest { pdu } = decode_pdu(&frame).unwrap() {
+                write(&mut stream, &bind_response(pdu.sequence_number, 0));
+            }
+        }
+    });
+    let session = EsmeSession::connect(config, bind_request(), None).unwrap();
+    let (sender, changes) = mpsc::channel();
+    session.set_link_health_listener(Some(Arc::new(HealthRecorder {
+        changes: Mutex::new(sender),
+    })));
+
+    let timeout = Duration::from_secs(5);
+    assert_eq!(
+        changes.recv_timeout(timeout).unwrap(),
+        LinkHealth::Degraded {
+            missed_responses: 1
+        }
+    );
+    assert_eq!(changes.recv_timeout(timeout).unwrap(), LinkHealth::Dead);
+    smsc.join().unwrap();
+    assert!(!session.is_bound());
+    assert_eq!(session.link_health(), LinkHealth::Dead);
+}
+
+/// How the scripted SMSC treats one connection.
+#[derive(Clone, Copy)]
+enum Script {
+    /// Rejects the bind with this status.
+    Reject(u32),
+    /// Accepts the bind, then hangs up on the first submit.
+    DropOnSubmit,
+    /// Accepts the bind and answers everything.
+    Serve,
+}
+
+/// Accepts one connection per script entry and plays it.
+fn scripted_smsc(listener: TcpListener, scripts: Vec<Script>) -> JoinHandle<()> {
+    thread::spawn(move || {
+        for script in scripts {
+            let (mut stream, _) = listener.accept().unwrap();
+            while let Ok(frame) = read_frame(&mut stream) {
+                match (script, decode_pdu(&frame).unwrap()) {
+                    (Script::Reject(status), Pdu::BindRequest { pdu }) => {
+                        write(&mut stream, &bind_response(pdu.sequence_number, status));
+                    }
+                    (_, Pdu::BindRequest { pdu }) => {
+                        write(&mut stream, &bind_response(pdu.sequence_number, 0));
+                    }
+                    (Script::DropOnSubmit, Pdu::SubmitSm { .. }) => break,
+                    (_, Pdu::SubmitSm { pdu }) => {
+                        write(
+                            &mut stream,
+                            &encode_submit_sm_response(&SubmitSmResponse {
+                                sequence_number: pdu.sequence_number,
+                                command_status: COMMAND_STATUS_OK,
+                                message_id: "resubmitted".to_string(),
+                                status_description: String::new(),
+                            }),
+                        );
+                    }
+                    (_, Pdu::Unbind { pdu }) => {
+                        write(
+                            &mut stream,
+                            &encode_unbind_response(&UnbindResponse {
+                                sequence_number: pdu.sequence_number,
+                                command_status: COMMAND_STATUS_OK,
+                            }),
+                        );
+                    }
+                    _ => {}
+                }
+            }
+        }
+    })
+}
+
+struct StateRecorder {
+    states: Mutex<Sender<ConnectionState>>,
+}
+
+impl ConnectionStateListener for StateRecorder {
+    fn on_connection_state(&self, state: ConnectionState) {
+        let _ = self.states.lock().unwrap().send(state);
+    }
+}
+
+fn reconnect_config(listener: &TcpListener, in_flight: InFlightPolicy) -> EsmeSessionConfig {
+    let mut config = local_config(listener);
+    config.reconnect = Some(ReconnectPolicy {
+        initial_delay_ms: 10,
+        max_delay_ms: 50,
+        jitter_percent: 0,
+        max_attempts: 5,
+        in_flight,
+        ..reconnect_policy_default()
+    });
+    config
+}
+
+fn submit_request() -> SubmitSmRequest {
+    SubmitSmBuilder::new()
+        .destination(address_new(Ton::Unknown, Npi::Unknown, "1234".to_string()))
+        .text("Hello".to_string())
+        .build()
+        .unwrap()
+}
+
+#[test]
+fn test_esme_session_reconnects_and_resubmits() {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let config = reconnect_config(&listener, InFlightPolicy::Resubmit);
+    let smsc = scripted_smsc(
+        listener,
+        vec![
+            Script::DropOnSubmit,
+            Script::Reject(ESME_RBINDFAIL),
+            Script::Serve,
+        ],
+    );
+    let session = EsmeSession::connect(config, bind_request(), None).unwrap();
+    let (sender, states) = mpsc::channel();
+    session.set_connection_state_listener(Some(Arc::new(StateRecorder {
+        states: Mutex::new(sender),
+    })));
+
+    let response = session.submit(submit_request()).unwrap();
+    assert_eq!(response.message_id, "resubmitted");
+    assert!(session.is_bound());
+    session.unbind().unwrap();
+    smsc.join().unwrap();
+
+    let states: Vec<_> = states.try_iter().collect();
+    assert_eq!(
+        states,
+        vec![
+            ConnectionState::Disconnected {
+                reason: "Connection lost".to_string()
+            },
+            ConnectionState::Reconnecting {
+                attempt: 1,
+                delay_ms: 10
+            },
+            ConnectionState::Reconnecting {
+                attempt: 2,
+                delay_ms: 20
+            },
+            ConnectionState::Bound,
+            ConnectionState::Closed {
+                reason: "Closed by the host".to_string()
+            },
+        ]
+    );
+}
+
+#[test]
+fn test_esme_session_fails_in_flight_requests() {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let config = reconnect_config(&listener, InFlightPolicy::Fail);
+    let smsc = scripted_smsc(listener, vec![Script::DropOnSubmit, Script::Serve]);
+    let session = EsmeSession::connect(config, bind_request(), None).unwrap();
+    let (sender, states) = mpsc::channel();
+    session.set_connection_state_listener(Some(Arc::new(StateRecorder {
+        states: Mutex::new(sender),
+    })));
+
+    assert!(session.submit(submit_request()).is_err());
+    while states.recv_timeout(Duration::from_secs(5)).unwrap() != ConnectionState::Bound {}
+    assert!(session.submit(submit_request()).is_ok());
+    session.unbind().unwrap();
+    smsc.join().unwrap();
+}
+
+#[test]
+fn test_esme_session_bind_retries() {
+    // A fatal status ends the session at once.
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let config = reconnect_config(&listener, InFlightPolicy::Fail);
+    let smsc = scripted_smsc(listener, vec![Script::Reject(ESME_RINVPASWD)]);
+    assert!(EsmeSession::connect(config, bind_request(), None).is_err());
+    smsc.join().unwrap();
+
+    // Other statuses are retried until the attempts run out.
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let mut config = reconnect_config(&listener, InFlightPolicy::Fail);
+    if let Some(policy) = config.reconnect.as_mut() {
+        policy.max_attempts = 2;
+    }
+    let smsc = scripted_smsc(listener, vec![Script::Reject(ESME_RALYBND); 3]);
+    match EsmeSession::connect(config, bind_request(), None) {
+        Err(SmppFfiError::Generic { msg }) => assert!(msg.contains("Bind failed"), "{}", msg),
+        Ok(_) => panic!("Bind should have been rejected"),
+    }
+    smsc.join().unwrap();
+}
+
+#[test]
+fn test_esme_session_rate_limit() {
+    for throttle in [
+        throttle_config_new(5),
+        ThrottleConfig {
+            command_limits: vec![CommandRateLimit {
+                command_id: CMD_SUBMIT_SM,
+                max_per_second: 5,
+            }],
+            ..throttle_config_new(0)
+        },
+    ] {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let mut config = local_config(&listener);
+        config.throttle = Some(throttle);
+        let smsc = stand_in_smsc(listener, COMMAND_STATUS_OK);
+        let session = EsmeSession::connect(config, bind_request(), None).unwrap();
+
+        // A full bucket lets the first 5 through at once; the rest come every 200 ms.
+        let started = Instant::now();
+        for _ in 0..8 {
+            session.submit(submit_request()).unwrap();
+        }
+        assert!(started.elapsed() >= Duration::from_millis(500));
+        let stats = session.throttle_stats();
+        assert!(stats.send_rate >= 5, "{:?}", stats);
+        assert_eq!(stats.queue_depth, 0);
+
+        session.unbind().unwrap();
+        smsc.join().unwrap();
+    }
+}
+
+/// Binds, then answers the first `throttled` submits with `ESME_RTHROTTLED`.
+///
+/// Returns when each submit arrived.
+fn throttling_smsc(listener: TcpListener, throttled: usize) -> JoinHandle<Vec<Instant>> {
+    thread::spawn(move || {
+        let (mut stream, _) = listener.accept().unwrap();
+        let mut submits = Vec::new();
+        while let Ok(frame) = read_frame(&mut stream) {
+            match decode_pdu(&frame).unwrap() {
+                Pdu::BindRequest { pdu } => {
+                    write(&mut stream, &bind_response(pdu.sequence_number, 0));
+                }
+                Pdu::SubmitSm { pdu } => {
+                    submits.push(Instant::now());
+                    let command_status = if submits.len() <= throttled {
+                        ESME_RTHROTTLED
+                    } else {
+                        COMMAND_STATUS_OK
+                    };
+                    write(
+                        &mut stream,
+                        &encode_submit_sm_response(&SubmitSmResponse {
+                            sequence_number: pdu.sequence_number,
+                            command_status,
+                            message_id: String::new(),
+                            status_description: String::new(),
+                        }),
+                    );
+                }
+                Pdu::Unbind { pdu } => {
+                    write(
+                        &mut stream,
+                        &encode_unbind_response(&UnbindResponse {
+                            sequence_number: pdu.sequence_number,
+                            command_status: COMMAND_STATUS_OK,
+                        }),
+                    );
+                }
+                _ => {}
+            }
+        }
+        submits
+    })
+}
+
+fn throttled_config(listener: &TcpListener) -> EsmeSessionConfig {
+    let mut config = local_config(listener);
+    config.throttle = Some(ThrottleConfig {
+        throttled_retry_delay_ms: 50,
+        max_throttled_retries: 2,
+        ..throttle_config_new(0)
+    });
+    config
+}
+
+#[test]
+fn test_esme_session_retries_throttled() {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let config = throttled_config(&listener);
+    let smsc = throttling_smsc(listener, 2);
+    let session = EsmeSession::connect(config, bind_request(), None).unwrap();
+
+    let response = session.submit(submit_request()).unwrap();
+    assert_eq!(response.command_status, COMMAND_STATUS_OK);
+    assert_eq!(session.throttle_stats().throttled_responses, 2);
+    session.unbind().unwrap();
+
+    // The pause doubles with every retry.
+    let submits = smsc.join().unwrap();
+    assert_eq!(submits.len(), 3);
+    assert!(submits[1] - submits[0] >= Duration::from_millis(50));
+    assert!(submits[2] - submits[1] >= Duration::from_millis(100));
+}
+
+#[test]
+fn test_esme_session_gives_up_when_throttled() {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let config = throttled_config(&listener);
+    let smsc = throttling_smsc(listener, usize::MAX);
+    let session = EsmeSession::connect(config, bind_request(), None).unwrap();
+
+    let response = session.submit(submit_request()).unwrap();
+    assert_eq!(response.command_status, ESME_RTHROTTLED);
+    assert_eq!(session.throttle_stats().throttled_responses, 3);
+    session.unbind().unwrap();
+    assert_eq!(smsc.join().unwrap().len(), 3);
+}
+
+#[cfg(not(feature = "tls"))]
+#[test]
+fn test_esme_session_tls_requires_feature() {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let config = EsmeSessionConfig {
+        tls: Some(smpp_codec_ffi::tls::tls_client_config_new(String::new())),
+        ..local_config(&listener)
+    };
+    let Err(error) = EsmeSession::connect(config, bind_request(), None) else {
+        panic!("Connected over TLS without the tls feature");
+    };
+    assert!(error.to_string().contains("`tls` feature"), "{}", error);
+}
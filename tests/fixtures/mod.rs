@@ -0,0 +1,77 @@
+//! Listener fixtures shared by the integration tests.
+#![allow(dead_code)]
+
+use smpp_codec_ffi::ancillary_pdu_ffi::QuerySmResponse;
+use smpp_codec_ffi::common::*;
+use smpp_codec_ffi::pdu::Pdu;
+use smpp_codec_ffi::session_pdu_ffi::BindRequest;
+use smpp_codec_ffi::smsc_listener::*;
+use smpp_codec_ffi::submission_pdu_ffi::SubmitSmResponse;
+
+/// Accepts every bind.
+pub struct AcceptAll;
+
+impl BindAuthenticator for AcceptAll {
+    fn authenticate(&self, _peer_addr: String, _request: BindRequest) -> u32 {
+        COMMAND_STATUS_OK
+    }
+}
+
+/// Accepts binds with the password "secret".
+pub struct PasswordCheck;
+
+impl BindAuthenticator for PasswordCheck {
+    fn authenticate(&self, _peer_addr: String, request: BindRequest) -> u32 {
+        if request.password == "secret" {
+            COMMAND_STATUS_OK
+        } else {
+            ESME_RINVPASWD
+        }
+    }
+}
+
+/// Answers `submit_sm` with the message ID `<system_id>-<destination_addr>`
+/// and `query_sm` with the state `Delivered`; rejects everything else.
+pub struct Responder;
+
+impl SessionHandler for Responder {
+    fn on_request(&self, session: SmscSessionInfo, request: Pdu) -> SessionReply {
+        match request {
+            Pdu::SubmitSm { pdu } => Pdu::SubmitSmResponse {
+                pdu: SubmitSmResponse {
+                    sequence_number: 0,
+                    command_status: COMMAND_STATUS_OK,
+                    message_id: format!("{}-{}", session.system_id, pdu.destination_addr),
+                    status_description: String::new(),
+                },
+            }
+            .into(),
+            Pdu::QuerySm { pdu } => Pdu::QuerySmResponse {
+                pdu: QuerySmResponse {
+                    sequence_number: 0,
+                    command_status: COMMAND_STATUS_OK,
+                    message_id: pdu.message_id,
+                    final_date: String::new(),
+                    message_state: 2,
+                    error_code: 0,
+                    status_description: String::new(),
+                },
+            }
+            .into(),
+            _ => SessionReply::Reject {
+                command_status: ESME_RINVCMDID,
+            },
+        }
+    }
+}
+
+/// Rejects every request, for tests where only binds matter.
+pub struct NoRequests;
+
+impl SessionHandler for NoRequests {
+    fn on_request(&self, _session: SmscSessionInfo, _request: Pdu) -> SessionReply {
+        SessionReply::Reject {
+            command_status: ESME_RINVCMDID,
+        }
+    }
+}
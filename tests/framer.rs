@@ -0,0 +1,37 @@
+use smpp_codec_ffi::framer::*;
+use smpp_codec_ffi::session_pdu_ffi::{encode_enquire_link, EnquireLink};
+use std::io::Cursor;
+
+#[test]
+fn test_read_frame_splits_stream() {
+    let first = encode_enquire_link(&EnquireLink { sequence_number: 1 });
+    let second = encode_enquire_link(&EnquireLink { sequence_number: 2 });
+    let mut stream = Cursor::new([first.clone(), second.clone()].concat());
+
+    assert_eq!(read_frame(&mut stream).unwrap(), first);
+    assert_eq!(read_frame(&mut stream).unwrap(), second);
+    assert!(read_frame(&mut stream).is_err());
+}
+
+#[test]
+fn test_read_frame_rejects_bad_length() {
+    let mut stream = Cursor::new(vec![0, 0, 0, 8, 0, 0, 0, 0]);
+    assert!(read_frame(&mut stream).is_err());
+    assert!(check_command_length(MAX_COMMAND_LENGTH + 1).is_err());
+    assert!(check_command_length(16).is_ok());
+}
+
+#[test]
+fn test_frame_decoder_push() {
+    let first = encode_enquire_link(&EnquireLink { sequence_number: 1 });
+    let second = encode_enquire_link(&EnquireLink { sequence_number: 2 });
+    let stream = [first.clone(), second.clone()].concat();
+
+    let decoder = FrameDecoder::new();
+    assert_eq!(decoder.push(stream[..20].to_vec()).unwrap(), vec![first]);
+    assert_eq!(decoder.pending_len(), 4);
+    assert_eq!(decoder.push(stream[20..].to_vec()).unwrap(), vec![second]);
+    assert_eq!(decoder.pending_len(), 0);
+
+    assert!(decoder.push(vec![0, 0, 0, 1]).is_err());
+}
@@ -0,0 +1,302 @@
+use smpp_codec_ffi::address::address_new;
+use smpp_codec_ffi::ancillary_pdu_ffi::*;
+use smpp_codec_ffi::builders::SubmitSmBuilder;
+use smpp_codec_ffi::common::*;
+use smpp_codec_ffi::message_store::*;
+use smpp_codec_ffi::pdu::Pdu;
+use smpp_codec_ffi::smsc_listener::SmscSessionInfo;
+use smpp_codec_ffi::submission_pdu_ffi::SubmitSmRequest;
+use std::collections::HashSet;
+use std::sync::Arc;
+
+fn session(system_id: &str) -> SmscSessionInfo {
+    SmscSessionInfo {
+        session_id: 1,
+        peer_addr: "127.0.0.1:40000".to_string(),
+        system_id: system_id.to_string(),
+        mode: BindMode::Transceiver,
+    }
+}
+
+fn submit_request() -> SubmitSmRequest {
+    SubmitSmBuilder::new()
+        .source(address_new(
+            Ton::International,
+            Npi::Isdn,
+            "4477001".to_string(),
+        ))
+        .destination(address_new(
+            Ton::International,
+            Npi::Isdn,
+            "4477002".to_string(),
+        ))
+        .text("Hello".to_string())
+        .build()
+        .unwrap()
+}
+
+fn query(message_id: &str) -> QuerySmRequest {
+    QuerySmRequest {
+        sequence_number: 2,
+        message_id: message_id.to_string(),
+        source_addr_ton: Ton::International,
+        source_addr_npi: Npi::Isdn,
+        source_addr: "4477001".to_string(),
+    }
+}
+
+fn cancel(message_id: &str) -> CancelSmRequest {
+    CancelSmRequest {
+        sequence_number: 3,
+        service_type: String::new(),
+        message_id: message_id.to_string(),
+        source_addr_ton: Ton::International,
+        source_addr_npi: Npi::Isdn,
+        source_addr: "4477001".to_string(),
+        dest_addr_ton: Ton::International,
+        dest_addr_npi: Npi::Isdn,
+        dest_addr: "4477002".to_string(),
+    }
+}
+
+fn replace(message_id: &str, text: &str) -> ReplaceSmRequest {
+    ReplaceSmRequest {
+        sequence_number: 4,
+        message_id: message_id.to_string(),
+        source_addr_ton: Ton::International,
+        source_addr_npi: Npi::Isdn,
+        source_addr: "4477001".to_string(),
+        schedule_delivery_time: String::new(),
+        validity_period: String::new(),
+        registered_delivery: 1,
+        sm_default_msg_id: 0,
+        short_message: text.as_bytes().to_vec(),
+    }
+}
+
+fn center(store: Arc<dyn MessageStore>) -> Arc<MessageCenter> {
+    MessageCenter::new(store, MessageIdGenerator::new("SMSC".to_string()).unwrap())
+}
+
+#[test]
+fn test_message_id_generator() {
+    let ids = MessageIdGenerator::new("node1-".to_string()).unwrap();
+    let generated: HashSet<String> = (0..10_000).map(|_| ids.next_id()).collect();
+    assert_eq!(generated.len(), 10_000);
+    assert!(generated
+        .iter()
+        .all(|id| id.starts_with("node1-") && id.len() <= MAX_MESSAGE_ID_LEN as usize));
+
+    assert!(MessageIdGenerator::new("x".repeat(49)).is_ok());
+    assert!(MessageIdGenerator::new("x".repeat(50)).is_err());
+}
+
+#[test]
+fn test_message_center_flow() {
+    let store = InMemoryMessageStore::new();
+    let center = center(store.clone().as_message_store());
+
+    let response = center.submit(session("esme"), submit_request());
+    assert_eq!(response.command_status, COMMAND_STATUS_OK);
+    let id = response.message_id;
+    assert_eq!(store.len(), 1);
+
+    let state = center.query(session("esme"), query(&id));
+    assert_eq!(state.command_status, COMMAND_STATUS_OK);
+    assert_eq!(state.message_state, MessageState::Enroute as u8);
+    assert_eq!(state.final_date, "");
+
+    // Other ESMEs and other source addresses do not see the message.
+    assert_eq!(
+        center.query(session("other"), query(&id)).command_status,
+        ESME_RINVMSGID
+    );
+    let mut wrong_source = query(&id);
+    wrong_source.source_addr = "999".to_string();
+    assert_eq!(
+        center.query(session("esme"), wrong_source).command_status,
+        ESME_RINVMSGID
+    );
+    assert_eq!(
+        center
+            .query(session("esme"), query("missing"))
+            .command_status,
+        ESME_RINVMSGID
+    );
+
+    assert_eq!(
+        center
+            .replace(session("esme"), replace(&id, "Updated"))
+            .command_status,
+        COMMAND_STATUS_OK
+    );
+    let stored = center.message(id.clone()).unwrap().unwrap();
+    assert_eq!(stored.short_message, b"Updated");
+    assert_eq!(stored.registered_delivery, 1);
+
+    assert_eq!(
+        center.cancel(session("esme"), cancel(&id)).command_status,
+        COMMAND_STATUS_OK
+    );
+    let state = center.query(session("esme"), query(&id));
+    assert_eq!(state.message_state, MessageState::Deleted as u8);
+    assert_eq!(state.final_date.len(), 16);
+    assert!(state.final_date.ends_with("00+"));
+
+    // Final messages can be neither cancelled nor replaced.
+    assert_eq!(
+        center.cancel(session("esme"), cancel(&id)).command_status,
+        ESME_RCANCELFAIL
+    );
+    assert_eq!(
+        center
+            .replace(session("esme"), replace(&id, "Again"))
+            .command_status,
+        ESME_RREPLACEFAIL
+    );
+    assert_eq!(
+        center.cancel(session("esme"), cancel("")).command_status,
+        ESME_RCANCELFAIL
+    );
+}
+
+#[test]
+fn test_message_center_handle_and_set_state() {
+    let center = center(InMemoryMessageStore::new().as_message_store());
+    let Some(Pdu::SubmitSmResponse { pdu }) = center.handle(
+        session("esme"),
+        Pdu::SubmitSm {
+            pdu: submit_request(),
+        },
+    ) else {
+        panic!("submit_sm was not answered");
+    };
+    let id = pdu.message_id;
+
+    let delivered = center
+        .set_state(id.clone(), MessageState::Delivered, 0)
+        .unwrap()
+        .unwrap();
+    assert!(delivered.done_time_ms.is_some());
+    assert!(message_state_is_final(delivered.state));
+    assert!(center
+        .set_state(id.clone(), MessageState::Undeliverable, 1)
+        .unwrap()
+        .is_none());
+    assert!(center
+        .set_state("missing".to_string(), MessageState::Delivered, 0)
+        .unwrap()
+        .is_none());
+
+    let Some(Pdu::QuerySmResponse { pdu }) =
+        center.handle(session("esme"), Pdu::QuerySm { pdu: query(&id) })
+    else {
+        panic!("query_sm was not answered");
+    };
+    assert_eq!(pdu.message_state, MessageState::Delivered as u8);
+
+    let enquire_link = Pdu::EnquireLink {
+        pdu: smpp_codec_ffi::session_pdu_ffi::EnquireLink { sequence_number: 1 },
+    };
+    assert!(center.handle(session("esme"), enquire_link).is_none());
+}
+
+struct FailingStore;
+
+impl MessageStore for FailingStore {
+    fn save(&self, _message: StoredMessage) -> Result<(), SmppFfiError> {
+        Err(SmppFfiError::Generic {
+            msg: "disk full".to_string(),
+        })
+    }
+
+    fn load(&self, _message_id: String) -> Result<Option<StoredMessage>, SmppFfiError> {
+        Err(SmppFfiError::Generic {
+            msg: "disk full".to_string(),
+        })
+    }
+}
+
+#[test]
+fn test_message_center_store_failures() {
+    let center = center(Arc::new(FailingStore));
+    let response = center.submit(session("esme"), submit_request());
+    assert_eq!(response.command_status, ESME_RSYSERR);
+    assert_eq!(response.message_id, "");
+    assert_eq!(
+        center.query(session("esme"), query("x")).command_status,
+        ESME_RQUERYFAIL
+    );
+    assert_eq!(
+        center.cancel(session("esme"), cancel("x")).command_status,
+        ESME_RCANCELFAIL
+    );
+}
+
+#[test]
+fn test_file_message_store() {
+    let path = std::env::temp_dir().join(format!("smpp-messages-{}.log", std::process::id()));
+    let path = path.to_str().unwrap().to_string();
+    let _ = std::fs::remove_file(&path);
+
+    let store = FileMessageStore::open(path.clone()).unwrap();
+    let center = center(store.clone().as_message_store());
+    let mut request = submit_request();
+    request.service_type = "tab\there".to_string();
+    request.short_message = vec![0x00, 0xff, b'\n'];
+    let first = center.submit(session("esme"), request).message_id;
+    let second = center.submit(session("esme"), submit_request()).message_id;
+    center
+        .set_state(first.clone(), MessageState::Undeliverable, 7)
+        .unwrap();
+    let expected = center.message(first.clone()).unwrap().unwrap();
+    drop(center);
+    drop(store);
+
+    let lines = || std::fs::read_to_string(&path).unwrap().lines().count();
+    assert_eq!(lines(), 3);
+    let reopened = FileMessageStore::open(path.clone()).unwrap();
+    assert_eq!(reopened.len(), 2);
+    assert_eq!(
+        reopened.load(first.clone()).unwrap(),
+        Some(expected.clone())
+    );
+    assert!(reopened.load(second).unwrap().is_some());
+
+    reopened.compact().unwrap();
+    assert_eq!(lines(), 2);
+    reopened
+        .save(StoredMessage {
+            state: MessageState::Delivered,
+            ..expected.clone()
+        })
+        .unwrap();
+    assert_eq!(lines(), 3);
+    drop(reopened);
+    let reopened = FileMessageStore::open(path.clone()).unwrap();
+    assert_eq!(
+        reopened.load(first.clone()).unwrap().unwrap().state,
+        MessageState::Delivered
+    );
+    drop(reopened);
+
+    // A save cut short by a crash leaves an incomplete last line, which is dropped.
+    let log = std::fs::read_to_string(&path).unwrap();
+    let torn = log.lines().next().unwrap();
+    std::fs::write(&path, format!("{}{}", log, &torn[..torn.len() / 2])).unwrap();
+    let reopened = FileMessageStore::open(path.clone()).unwrap();
+    assert_eq!(reopened.len(), 2);
+    assert_eq!(std::fs::read_to_string(&path).unwrap(), log);
+    reopened.save(expected.clone()).unwrap();
+    drop(reopened);
+    let reopened = FileMessageStore::open(path.clone()).unwrap();
+    assert_eq!(reopened.load(first).unwrap(), Some(expected));
+    drop(reopened);
+
+    // A complete last line that does not parse is dropped too, but not one before it.
+    std::fs::write(&path, format!("{}garbage\n", log)).unwrap();
+    assert_eq!(FileMessageStore::open(path.clone()).unwrap().len(), 2);
+    std::fs::write(&path, format!("garbage\n{}", log)).unwrap();
+    assert!(FileMessageStore::open(path.clone()).is_err());
+    let _ = std::fs::remove_file(&path);
+}
@@ -0,0 +1,125 @@
+mod fixtures;
+
+use fixtures::NoRequests;
+use smpp_codec_ffi::common::*;
+use smpp_codec_ffi::esme_session::EsmeSession;
+use smpp_codec_ffi::outbind::*;
+use smpp_codec_ffi::session_pdu_ffi::*;
+use smpp_codec_ffi::smsc_listener::*;
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// Accepts every bind and records its mode.
+struct RecordModes {
+    modes: Mutex<Sender<BindMode>>,
+}
+
+impl BindAuthenticator for RecordModes {
+    fn authenticate(&self, _peer_addr: String, request: BindRequest) -> u32 {
+        self.modes.lock().unwrap().send(request.mode).unwrap();
+        COMMAND_STATUS_OK
+    }
+}
+
+struct Sessions {
+    sessions: Mutex<Sender<Arc<EsmeSession>>>,
+}
+
+impl OutbindHandler for Sessions {
+    fn on_session(&self, _peer_addr: String, session: Arc<EsmeSession>) {
+        self.sessions.lock().unwrap().send(session).unwrap();
+    }
+}
+
+fn start_smsc() -> (Arc<SmscListener>, Receiver<BindMode>) {
+    let (sender, modes) = mpsc::channel();
+    let listener = SmscListener::start(
+        smsc_listener_config_new("127.0.0.1".to_string(), 0),
+        Arc::new(RecordModes {
+            modes: Mutex::new(sender),
+        }),
+        Arc::new(NoRequests),
+    )
+    .unwrap();
+    (listener, modes)
+}
+
+fn start_esme() -> (Arc<OutbindListener>, Receiver<Arc<EsmeSession>>) {
+    let bind = BindRequest {
+        sequence_number: 0,
+        mode: BindMode::Transceiver,
+        system_id: "esme".to_string(),
+        password: "esmepw".to_string(),
+        system_type: String::new(),
+        interface_version: SMPP_INTERFACE_VERSION_34,
+        addr_ton: Ton::Unknown,
+        addr_npi: Npi::Unknown,
+        address_range: String::new(),
+    };
+    let config = outbind_listener_config_new(
+        "127.0.0.1".to_string(),
+        0,
+        "SMSC".to_string(),
+        "secret".to_string(),
+        bind,
+    );
+    let (sender, sessions) = mpsc::channel();
+    let listener = OutbindListener::start(
+        config,
+        None,
+        Arc::new(Sessions {
+            sessions: Mutex::new(sender),
+        }),
+    )
+    .unwrap();
+    (listener, sessions)
+}
+
+#[test]
+fn test_outbind_binds_receiver() {
+    let (smsc, modes) = start_smsc();
+    let (esme, sessions) = start_esme();
+
+    smsc.initiate_outbind(
+        "127.0.0.1".to_string(),
+        esme.local_port(),
+        "SMSC".to_string(),
+        "secret".to_string(),
+        None,
+    )
+    .unwrap();
+    let session = sessions.recv_timeout(Duration::from_secs(5)).unwrap();
+    assert!(session.is_bound());
+    assert_eq!(modes.recv().unwrap(), BindMode::Receiver);
+    assert_eq!(smsc.connection_count(), 1);
+
+    session.unbind().unwrap();
+    esme.shutdown();
+    smsc.shutdown();
+}
+
+#[test]
+fn test_outbind_rejects_credentials() {
+    let (smsc, modes) = start_smsc();
+    let (esme, sessions) = start_esme();
+
+    smsc.initiate_outbind(
+        "127.0.0.1".to_string(),
+        esme.local_port(),
+        "SMSC".to_string(),
+        "wrong".to_string(),
+        None,
+    )
+    .unwrap();
+
+    // The ESME hangs up without binding.
+    let deadline = Instant::now() + Duration::from_secs(5);
+    while smsc.connection_count() > 0 {
+        assert!(Instant::now() < deadline, "Connection was not closed");
+        thread::sleep(Duration::from_millis(10));
+    }
+    assert!(sessions.try_recv().is_err());
+    assert!(modes.try_recv().is_err());
+}
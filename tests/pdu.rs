@@ -0,0 +1,90 @@
+use smpp_codec_ffi::address::address_new;
+use smpp_codec_ffi::broadcast_pdu_ffi::BroadcastSmResponse;
+use smpp_codec_ffi::builders::SubmitSmBuilder;
+use smpp_codec_ffi::common::{Npi, Ton, CMD_SUBMIT_SM};
+use smpp_codec_ffi::pdu::*;
+use smpp_codec_ffi::session_pdu_ffi::{encode_enquire_link, EnquireLink};
+use smpp_codec_ffi::submission_pdu_ffi::encode_submit_sm_request;
+
+#[test]
+fn test_decode_pdu_header() {
+    let encoded = encode_enquire_link(&EnquireLink { sequence_number: 9 });
+    let header = decode_pdu_header(&encoded).unwrap();
+    assert_eq!(header.command_length, 16);
+    assert_eq!(header.command_id, 0x00000015);
+    assert_eq!(header.command_status, 0);
+    assert_eq!(header.sequence_number, 9);
+
+    assert!(decode_pdu_header(&encoded[..10]).is_err());
+}
+
+#[test]
+fn test_get_command_name() {
+    assert_eq!(get_command_name(CMD_SUBMIT_SM), Some("submit_sm"));
+    assert_eq!(get_command_name(0x80000015), Some("enquire_link_resp"));
+    assert_eq!(get_command_name(0x00000999), None);
+}
+
+#[test]
+fn test_decode_pdu() {
+    let request = SubmitSmBuilder::new()
+        .sequence_number(42)
+        .destination(address_new(Ton::Unknown, Npi::Unknown, "123".to_string()))
+        .text("Hello".to_string())
+        .build()
+        .unwrap();
+    let encoded = encode_submit_sm_request(&request);
+    assert_eq!(
+        decode_pdu_header(&encoded).unwrap().command_id,
+        CMD_SUBMIT_SM
+    );
+
+    let pdu = decode_pdu(&encoded).unwrap();
+    assert_eq!(pdu.sequence_number(), 42);
+    assert_eq!(pdu, Pdu::SubmitSm { pdu: request });
+
+    let pdu = decode_pdu(&encode_enquire_link(&EnquireLink { sequence_number: 3 })).unwrap();
+    assert_eq!(
+        pdu,
+        Pdu::EnquireLink {
+            pdu: EnquireLink { sequence_number: 3 }
+        }
+    );
+}
+
+#[test]
+fn test_decode_pdu_unknown_command() {
+    let mut encoded = encode_enquire_link(&EnquireLink { sequence_number: 1 });
+    encoded[4..8].copy_from_slice(&0x00000999u32.to_be_bytes());
+    assert!(decode_pdu(&encoded).is_err());
+}
+
+#[test]
+fn test_encode_pdu_round_trip() {
+    let request = SubmitSmBuilder::new()
+        .sequence_number(5)
+        .destination(address_new(Ton::Unknown, Npi::Unknown, "123".to_string()))
+        .text("Hello".to_string())
+        .build()
+        .unwrap();
+    let encoded = encode_submit_sm_request(&request);
+    let pdu = Pdu::SubmitSm { pdu: request };
+    assert_eq!(encode_pdu(&pdu), encoded);
+    assert_eq!(decode_pdu(&encoded).unwrap(), pdu);
+}
+
+#[test]
+fn test_encode_pdu_broadcast_sm_resp_round_trip() {
+    let pdu = Pdu::BroadcastSmResponse {
+        pdu: BroadcastSmResponse {
+            sequence_number: 4,
+            command_status: 0,
+            status_description: "ESME_ROK".to_string(),
+            message_id: "B1".to_string(),
+            optional_params: vec![],
+        },
+    };
+    let encoded = encode_pdu(&pdu);
+    assert_eq!(decode_pdu_header(&encoded).unwrap().command_id, 0x80000111);
+    assert_eq!(decode_pdu(&encoded).unwrap(), pdu);
+}
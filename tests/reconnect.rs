@@ -0,0 +1,39 @@
+use smpp_codec_ffi::common::*;
+use smpp_codec_ffi::reconnect::*;
+
+#[test]
+fn test_reconnect_delay_backoff() {
+    let policy = ReconnectPolicy {
+        initial_delay_ms: 100,
+        max_delay_ms: 1_000,
+        jitter_percent: 0,
+        ..reconnect_policy_default()
+    };
+    let delays: Vec<u64> = (1..=6)
+        .map(|attempt| reconnect_delay_ms(&policy, attempt))
+        .collect();
+    assert_eq!(delays, vec![100, 200, 400, 800, 1_000, 1_000]);
+    assert_eq!(reconnect_delay_ms(&policy, u32::MAX), 1_000);
+}
+
+#[test]
+fn test_reconnect_delay_jitter() {
+    let policy = ReconnectPolicy {
+        initial_delay_ms: 1_000,
+        jitter_percent: 20,
+        ..reconnect_policy_default()
+    };
+    for _ in 0..100 {
+        let delay = reconnect_delay_ms(&policy, 1);
+        assert!((800..=1_200).contains(&delay), "{}", delay);
+    }
+}
+
+#[test]
+fn test_reconnect_policy_default() {
+    let policy = reconnect_policy_default();
+    assert_eq!(policy.max_attempts, 0);
+    assert_eq!(policy.in_flight, InFlightPolicy::Fail);
+    assert!(policy.fatal_bind_statuses.contains(&ESME_RINVPASWD));
+    assert!(!policy.fatal_bind_statuses.contains(&ESME_RBINDFAIL));
+}
@@ -0,0 +1,85 @@
+use smpp_codec_ffi::common::*;
+use smpp_codec_ffi::pdu::PduHeader;
+use smpp_codec_ffi::request_window::*;
+
+fn header(command_id: u32, sequence_number: u32) -> PduHeader {
+    PduHeader {
+        command_length: 16,
+        command_id,
+        command_status: COMMAND_STATUS_OK,
+        sequence_number,
+    }
+}
+
+#[test]
+fn test_request_window_capacity() {
+    let window = RequestWindow::new(RequestWindowConfig {
+        max_in_flight: 2,
+        response_timeout_ms: 1_000,
+    });
+    window.insert(1, CMD_SUBMIT_SM, 0).unwrap();
+    assert!(window.insert(1, CMD_SUBMIT_SM, 0).is_err());
+    window.insert(2, CMD_QUERY_SM, 10).unwrap();
+    assert!(window.is_full());
+    assert!(window.insert(3, CMD_SUBMIT_SM, 20).is_err());
+
+    assert_eq!(window.remove(2).map(|r| r.command_id), Some(CMD_QUERY_SM));
+    assert_eq!(window.in_flight(), 1);
+    window.insert(3, CMD_SUBMIT_SM, 20).unwrap();
+}
+
+#[test]
+fn test_request_window_matching() {
+    let window = RequestWindow::new(request_window_config_default());
+    window.insert(5, CMD_SUBMIT_SM, 0).unwrap();
+    window.insert(6, CMD_DATA_SM, 0).unwrap();
+
+    // The command ID must pair with the request.
+    let mismatched = header(CMD_QUERY_SM_RESP, 5);
+    assert_eq!(
+        window.complete(mismatched),
+        ResponseMatch::Orphan { header: mismatched }
+    );
+    assert_eq!(
+        window.complete(header(CMD_SUBMIT_SM_RESP, 5)),
+        ResponseMatch::Matched {
+            request: InFlightRequest {
+                sequence_number: 5,
+                command_id: CMD_SUBMIT_SM,
+                sent_at_ms: 0,
+            }
+        }
+    );
+    assert!(matches!(
+        window.complete(header(CMD_SUBMIT_SM_RESP, 5)),
+        ResponseMatch::Orphan { .. }
+    ));
+    assert!(matches!(
+        window.complete(header(GENERIC_NACK, 6)),
+        ResponseMatch::Matched { .. }
+    ));
+    assert_eq!(window.in_flight(), 0);
+}
+
+#[test]
+fn test_request_window_expiry() {
+    let window = RequestWindow::new(RequestWindowConfig {
+        max_in_flight: 10,
+        response_timeout_ms: 100,
+    });
+    assert_eq!(window.next_deadline_ms(), None);
+    window.insert(2, CMD_SUBMIT_SM, 50).unwrap();
+    window.insert(1, CMD_SUBMIT_SM, 0).unwrap();
+    assert_eq!(window.next_deadline_ms(), Some(100));
+
+    assert!(window.expire(99).is_empty());
+    let expired = window.expire(150);
+    assert_eq!(
+        expired
+            .iter()
+            .map(|r| r.sequence_number)
+            .collect::<Vec<_>>(),
+        vec![1, 2]
+    );
+    assert_eq!(window.in_flight(), 0);
+}
@@ -0,0 +1,62 @@
+use smpp_codec_ffi::sequence::*;
+use std::collections::HashSet;
+use std::thread;
+
+#[test]
+fn test_sequence_generator_wraps() {
+    let generator = SequenceGenerator::new();
+    assert_eq!(generator.next_value(), 1);
+    assert_eq!(generator.next_value(), 2);
+
+    let generator = SequenceGenerator::with_seed(MAX_SEQUENCE_NUMBER - 1).unwrap();
+    assert_eq!(generator.next_value(), MAX_SEQUENCE_NUMBER - 1);
+    assert_eq!(generator.next_value(), MAX_SEQUENCE_NUMBER);
+    assert_eq!(generator.next_value(), MIN_SEQUENCE_NUMBER);
+
+    assert!(SequenceGenerator::with_seed(0).is_err());
+    assert!(SequenceGenerator::with_seed(0x8000_0000).is_err());
+}
+
+#[test]
+fn test_sequence_generator_is_thread_safe() {
+    let generator = SequenceGenerator::new();
+    let workers: Vec<_> = (0..8)
+        .map(|_| {
+            let generator = generator.clone();
+            thread::spawn(move || {
+                (0..1000)
+                    .map(|_| generator.next_value())
+                    .collect::<Vec<_>>()
+            })
+        })
+        .collect();
+    let mut seen = HashSet::new();
+    for worker in workers {
+        for value in worker.join().unwrap() {
+            assert!(seen.insert(value), "Duplicate sequence number {}", value);
+        }
+    }
+    assert_eq!(seen.len(), 8000);
+    assert_eq!(generator.peek(), 8001);
+}
+
+#[test]
+fn test_sequence_generator_persistence() {
+    let path = std::env::temp_dir().join(format!("smpp-sequence-{}", std::process::id()));
+    let path = path.to_string_lossy().to_string();
+    let _ = std::fs::remove_file(&path);
+
+    assert_eq!(SequenceGenerator::load(path.clone()).unwrap().peek(), 1);
+
+    let generator = SequenceGenerator::with_seed(41).unwrap();
+    generator.next_value();
+    generator.save(path.clone()).unwrap();
+    assert_eq!(
+        SequenceGenerator::load(path.clone()).unwrap().next_value(),
+        42
+    );
+
+    std::fs::write(&path, "0\n").unwrap();
+    assert!(SequenceGenerator::load(path.clone()).is_err());
+    std::fs::remove_file(&path).unwrap();
+}
@@ -39,6 +39,7 @@ fn test_outbind() {
     let req = Outbind {
         sequence_number: 3,
         system_id: "sys".to_string(),
+        password: "pw".to_string(),
     };
     let encoded = encode_outbind(&req);
     assert!(!encoded.is_empty());
@@ -46,6 +47,7 @@ fn test_outbind() {
     let decoded = decode_outbind(&encoded).expect("Failed to decode Outbind");
     assert_eq!(decoded.sequence_number, 3);
     assert_eq!(decoded.system_id, "sys");
+    assert_eq!(decoded.password, "pw");
 }
 
 #[test]
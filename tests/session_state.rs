@@ -0,0 +1,373 @@
+use smpp_codec_ffi::address::address_new;
+use smpp_codec_ffi::builders::SubmitSmBuilder;
+use smpp_codec_ffi::common::*;
+use smpp_codec_ffi::pdu::*;
+use smpp_codec_ffi::sequence::SequenceGenerator;
+use smpp_codec_ffi::session_pdu_ffi::*;
+use smpp_codec_ffi::session_state::*;
+use smpp_codec_ffi::submission_pdu_ffi::SubmitSmResponse;
+use std::sync::Arc;
+
+fn bind(mode: BindMode) -> Pdu {
+    Pdu::BindRequest {
+        pdu: BindRequest {
+            sequence_number: 0,
+            mode,
+            system_id: "esme".to_string(),
+            password: "secret".to_string(),
+            system_type: String::new(),
+            interface_version: SMPP_INTERFACE_VERSION_34,
+            addr_ton: Ton::Unknown,
+            addr_npi: Npi::Unknown,
+            address_range: String::new(),
+        },
+    }
+}
+
+fn submit() -> Pdu {
+    Pdu::SubmitSm {
+        pdu: SubmitSmBuilder::new()
+            .destination(address_new(Ton::Unknown, Npi::Unknown, "1234".to_string()))
+            .text("Hello".to_string())
+            .build()
+            .unwrap(),
+    }
+}
+
+fn request_received(output: &SessionOutput) -> Pdu {
+    output
+        .events
+        .iter()
+        .find_map(|event| match event {
+            SessionEvent::RequestReceived { pdu } => Some(pdu.clone()),
+            _ => None,
+        })
+        .expect("No request received")
+}
+
+/// Binds an ESME and an SMSC state machine to each other.
+fn bound_pair(mode: BindMode) -> (Arc<SessionState>, Arc<SessionState>) {
+    bound_pair_with(session_state_config_new(SessionRole::Esme), mode)
+}
+
+fn bound_pair_with(
+    esme_config: SessionStateConfig,
+    mode: BindMode,
+) -> (Arc<SessionState>, Arc<SessionState>) {
+    let esme = SessionState::new(esme_config);
+    let smsc = SessionState::new(session_state_config_new(SessionRole::Smsc));
+
+    let sent = esme.send_request(bind(mode)).unwrap();
+    let received = smsc.receive(sent.output.outbound);
+    let request = request_received(&received);
+    assert_eq!(request.sequence_number(), sent.sequence_number);
+
+    let response = smsc
+        .send_response(Pdu::BindResponse {
+            pdu: BindResponse {
+                sequence_number: request.sequence_number(),
+                command_status: COMMAND_STATUS_OK,
+                system_id: "SMSC".to_string(),
+            },
+        })
+        .unwrap();
+    esme.receive(response.outbound);
+    (esme, smsc)
+}
+
+#[test]
+fn test_session_state_bind() {
+    let esme = SessionState::new(session_state_config_new(SessionRole::Esme));
+    let smsc = SessionState::new(session_state_config_new(SessionRole::Smsc));
+    assert_eq!(esme.status(), SessionStatus::Open);
+
+    let sent = esme.send_request(bind(BindMode::Transceiver)).unwrap();
+    assert_eq!(
+        sent.output.timers,
+        vec![TimerInstruction::Start {
+            timer: SessionTimer::Response {
+                sequence_number: sent.sequence_number
+            },
+            delay_ms: 30_000,
+        }]
+    );
+
+    let received = smsc.receive(sent.output.outbound);
+    let request = request_received(&received);
+    let response = smsc
+        .send_response(Pdu::BindResponse {
+            pdu: BindResponse {
+                sequence_number: request.sequence_number(),
+                command_status: COMMAND_STATUS_OK,
+                system_id: "SMSC".to_string(),
+            },
+        })
+        .unwrap();
+    assert_eq!(smsc.status(), SessionStatus::BoundTrx);
+    let header = decode_pdu_header(&response.outbound).unwrap();
+    assert_eq!(header.command_id, CMD_BIND_TRANSCEIVER_RESP);
+
+    let output = esme.receive(response.outbound);
+    assert_eq!(esme.status(), SessionStatus::BoundTrx);
+    assert_eq!(esme.outstanding_requests(), 0);
+    assert!(output.events.contains(&SessionEvent::StateChanged {
+        state: SessionStatus::BoundTrx
+    }));
+    assert!(output.timers.contains(&TimerInstruction::Cancel {
+        timer: SessionTimer::Response {
+            sequence_number: sent.sequence_number
+        }
+    }));
+    assert!(output.timers.contains(&TimerInstruction::Start {
+        timer: SessionTimer::EnquireLink,
+        delay_ms: 30_000,
+    }));
+}
+
+#[test]
+fn test_session_state_rejects_illegal_pdus() {
+    let esme = SessionState::new(session_state_config_new(SessionRole::Esme));
+    assert!(esme.send_request(submit()).is_err());
+    assert!(esme
+        .send_request(Pdu::GenericNack {
+            pdu: GenericNack {
+                sequence_number: 0,
+                command_status: 0
+            }
+        })
+        .is_err());
+
+    let smsc = SessionState::new(session_state_config_new(SessionRole::Smsc));
+    let output = smsc.receive(encode_pdu(&submit()));
+    let header = decode_pdu_header(&output.outbound).unwrap();
+    assert_eq!(header.command_id, CMD_SUBMIT_SM_RESP);
+    assert_eq!(header.command_status, ESME_RINVBNDSTS);
+    assert!(output.events.is_empty());
+
+    let (esme, smsc) = bound_pair(BindMode::Receiver);
+    assert!(esme.send_request(submit()).is_err());
+    let output = smsc.receive(encode_pdu(&submit()));
+    let header = decode_pdu_header(&output.outbound).unwrap();
+    assert_eq!(header.command_status, ESME_RINVBNDSTS);
+
+    let output = smsc.receive(encode_pdu(&bind(BindMode::Receiver)));
+    let header = decode_pdu_header(&output.outbound).unwrap();
+    assert_eq!(header.command_status, ESME_RALYBND);
+}
+
+#[test]
+fn test_session_state_submit_in_chunks() {
+    let (esme, smsc) = bound_pair(BindMode::Transmitter);
+    let sent = esme.send_request(submit()).unwrap();
+
+    let (first, second) = sent.output.outbound.split_at(10);
+    assert!(smsc.receive(first.to_vec()).events.is_empty());
+    let received = smsc.receive(second.to_vec());
+    let request = request_received(&received);
+    assert_eq!(request.sequence_number(), sent.sequence_number);
+
+    let response = smsc
+        .send_response(Pdu::SubmitSmResponse {
+            pdu: SubmitSmResponse {
+                sequence_number: sent.sequence_number,
+                command_status: COMMAND_STATUS_OK,
+                message_id: "id-1".to_string(),
+                status_description: String::new(),
+            },
+        })
+        .unwrap();
+    assert!(smsc.send_response(submit()).is_err());
+
+    let output = esme.receive(response.outbound);
+    match &output.events[0] {
+        SessionEvent::ResponseReceived {
+            header,
+            pdu: Some(Pdu::SubmitSmResponse { pdu }),
+        } => {
+            assert_eq!(header.sequence_number, sent.sequence_number);
+            assert_eq!(pdu.message_id, "id-1");
+        }
+        other => panic!("Unexpected event: {:?}", other),
+    }
+}
+
+#[test]
+fn test_session_state_generic_nack_response() {
+    let (esme, smsc) = bound_pair(BindMode::Transmitter);
+    let sent = esme.send_request(submit()).unwrap();
+    smsc.receive(sent.output.outbound);
+
+    let response = smsc
+        .send_response(Pdu::GenericNack {
+            pdu: GenericNack {
+                sequence_number: sent.sequence_number,
+                command_status: ESME_RSYSERR,
+            },
+        })
+        .unwrap();
+    let header = decode_pdu_header(&response.outbound).unwrap();
+    assert_eq!(header.command_id, GENERIC_NACK);
+    assert_eq!(header.command_status, ESME_RSYSERR);
+
+    let output = esme.receive(response.outbound);
+    match &output.events[0] {
+        SessionEvent::ResponseReceived { header, .. } => {
+            assert_eq!(header.command_id, GENERIC_NACK);
+            assert_eq!(header.sequence_number, sent.sequence_number);
+        }
+        other => panic!("Unexpected event: {:?}", other),
+    }
+}
+
+#[test]
+fn test_session_state_timers() {
+    let (esme, smsc) = bound_pair(BindMode::Transceiver);
+
+    let output = esme.on_timer(SessionTimer::EnquireLink);
+    let header = decode_pdu_header(&output.outbound).unwrap();
+    assert_eq!(header.command_id, CMD_ENQUIRE_LINK);
+    let reply = smsc.receive(output.outbound);
+    assert!(reply.events.is_empty());
+    let answered = esme.receive(reply.outbound);
+    assert!(answered.events.is_empty());
+
+    let sent = esme.send_request(submit()).unwrap();
+    let output = esme.on_timer(SessionTimer::Response {
+        sequence_number: sent.sequence_number,
+    });
+    assert_eq!(
+        output.events,
+        vec![SessionEvent::RequestTimedOut {
+            sequence_number: sent.sequence_number,
+            command_id: CMD_SUBMIT_SM,
+        }]
+    );
+    assert_eq!(esme.outstanding_requests(), 0);
+}
+
+#[test]
+fn test_session_state_unbind_and_close() {
+    let (esme, smsc) = bound_pair(BindMode::Transceiver);
+
+    let sent = esme
+        .send_request(Pdu::Unbind {
+            pdu: Unbind { sequence_number: 0 },
+        })
+        .unwrap();
+    let reply = smsc.receive(sent.output.outbound);
+    assert_eq!(smsc.status(), SessionStatus::Unbound);
+    esme.receive(reply.outbound);
+    assert_eq!(esme.status(), SessionStatus::Unbound);
+    assert!(esme.send_request(submit()).is_err());
+
+    let output = esme.close();
+    assert_eq!(esme.status(), SessionStatus::Closed);
+    assert!(output.events.contains(&SessionEvent::StateChanged {
+        state: SessionStatus::Closed
+    }));
+
+    let output = smsc.receive(vec![0, 0, 0, 4]);
+    assert_eq!(smsc.status(), SessionStatus::Closed);
+    assert!(matches!(
+        output.events[0],
+        SessionEvent::ProtocolError { .. }
+    ));
+}
+
+#[test]
+fn test_session_state_request_window() {
+    let config = SessionStateConfig {
+        max_in_flight: 2,
+        ..session_state_config_new(SessionRole::Esme)
+    };
+    let (esme, _smsc) = bound_pair_with(config, BindMode::Transmitter);
+
+    let first = esme.send_request(submit()).unwrap();
+    esme.send_request(submit()).unwrap();
+    assert!(esme.send_request(submit()).is_err());
+
+    let orphan = encode_pdu(&Pdu::SubmitSmResponse {
+        pdu: SubmitSmResponse {
+            sequence_number: 777,
+            command_status: COMMAND_STATUS_OK,
+            message_id: String::new(),
+            status_description: String::new(),
+        },
+    });
+    let output = esme.receive(orphan);
+    assert!(matches!(
+        output.events[0],
+        SessionEvent::OrphanResponse { header } if header.sequence_number == 777
+    ));
+
+    esme.on_timer(SessionTimer::Response {
+        sequence_number: first.sequence_number,
+    });
+    assert_eq!(esme.outstanding_requests(), 1);
+    esme.send_request(submit()).unwrap();
+}
+
+#[test]
+fn test_session_state_sequence_generator() {
+    let generator = SequenceGenerator::with_seed(0x7FFF_FFFF).unwrap();
+    let esme = SessionState::with_sequence_generator(
+        session_state_config_new(SessionRole::Esme),
+        generator.clone(),
+    );
+    let sent = esme.send_request(bind(BindMode::Transmitter)).unwrap();
+    assert_eq!(sent.sequence_number, 0x7FFF_FFFF);
+    assert_eq!(esme.sequence_generator().peek(), 1);
+}
+
+#[test]
+fn test_session_state_keepalive() {
+    let config = SessionStateConfig {
+        max_missed_enquire_links: 2,
+        ..session_state_config_new(SessionRole::Esme)
+    };
+    let (esme, smsc) = bound_pair_with(config, BindMode::Transceiver);
+
+    // Sends a keepalive and lets its response timer run out.
+    let expire_keepalive = || {
+        let sent = esme.on_timer(SessionTimer::EnquireLink);
+        let header = decode_pdu_header(&sent.outbound).unwrap();
+        assert_eq!(header.command_id, CMD_ENQUIRE_LINK);
+        esme.on_timer(SessionTimer::Response {
+            sequence_number: header.sequence_number,
+        })
+    };
+
+    let output = expire_keepalive();
+    assert_eq!(
+        output.events,
+        vec![SessionEvent::LinkHealthChanged {
+            health: LinkHealth::Degraded {
+                missed_responses: 1
+            }
+        }]
+    );
+
+    let sent = esme.on_timer(SessionTimer::EnquireLink);
+    let reply = smsc.receive(sent.outbound);
+    let output = esme.receive(reply.outbound);
+    assert_eq!(
+        output.events,
+        vec![SessionEvent::LinkHealthChanged {
+            health: LinkHealth::Healthy
+        }]
+    );
+
+    expire_keepalive();
+    let output = expire_keepalive();
+    assert_eq!(
+        output.events[0],
+        SessionEvent::LinkHealthChanged {
+            health: LinkHealth::Dead
+        }
+    );
+    assert!(output.events.contains(&SessionEvent::StateChanged {
+        state: SessionStatus::Closed
+    }));
+    assert_eq!(esme.status(), SessionStatus::Closed);
+    assert_eq!(esme.link_health(), LinkHealth::Dead);
+}
@@ -0,0 +1,107 @@
+use std::io::{BufRead, BufReader};
+use std::process::{Child, Command, Stdio};
+
+/// A running simulator, killed on drop.
+struct Sim {
+    child: Child,
+    port: u16,
+}
+
+impl Sim {
+    fn start(name: &str, scenario: &str) -> Self {
+        let path =
+            std::env::temp_dir().join(format!("smpp-bench-{}-{}.toml", name, std::process::id()));
+        std::fs::write(&path, format!("[listener]\nport = 0\n\n{}", scenario)).unwrap();
+        let mut child = Command::new(env!("CARGO_BIN_EXE_smpp-sim"))
+            .arg(&path)
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null())
+            .spawn()
+            .unwrap();
+        let mut line = String::new();
+        BufReader::new(child.stdout.take().unwrap())
+            .read_line(&mut line)
+            .unwrap();
+        let port = line.trim().rsplit(':').next().unwrap().parse().unwrap();
+        let _ = std::fs::remove_file(path);
+        Self { child, port }
+    }
+
+    /// Runs `smpp-bench` with space-separated `args` against the simulator and returns its report.
+    fn bench(&self, args: &str) -> String {
+        let output = Command::new(env!("CARGO_BIN_EXE_smpp-bench"))
+            .args(["--port", &self.port.to_string()])
+            .args(args.split_whitespace())
+            .output()
+            .unwrap();
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        assert!(output.status.success(), "{}", stderr);
+        String::from_utf8(output.stdout).unwrap()
+    }
+}
+
+impl Drop for Sim {
+    fn drop(&mut self) {
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+}
+
+/// The value after `label` on the report line starting with `line`.
+fn field<'a>(report: &'a str, line: &str, label: &str) -> &'a str {
+    let line = report
+        .lines()
+        .find(|l| l.trim_start().starts_with(line))
+        .unwrap_or_else(|| panic!("no '{}' line in:\n{}", line, report));
+    let mut words = line.split_whitespace();
+    words.find(|w| *w == label);
+    words.next().unwrap()
+}
+
+#[test]
+fn test_smpp_bench_against_sim() {
+    let sim = Sim::start("ok", "");
+    let report = sim.bench("--binds 2 --window 4 --tps 200 --messages 40 --size 200");
+    // 200 GSM 7-bit characters take two UDH parts.
+    assert_eq!(field(&report, "messages", "messages"), "40", "{}", report);
+    assert_eq!(field(&report, "messages", "submits"), "80", "{}", report);
+    assert!(report.contains("0x00000000 ESME_ROK 80"), "{}", report);
+    let p50: f64 = field(&report, "latency", "p50").parse().unwrap();
+    let p99: f64 = field(&report, "latency", "p99").parse().unwrap();
+    assert!(p50 <= p99);
+    // 40 messages at 200 per second take at least 195 ms.
+    let secs: f64 = field(&report, "messages", "in").parse().unwrap();
+    assert!(secs >= 0.19, "{}", report);
+    assert!(report.contains("histogram"));
+
+    let report = sim.bench("--tps 0 --messages 5 --size 600 --split sar --encoding ucs2");
+    // 1200 UCS-2 octets take five SAR parts of up to 254 octets.
+    assert_eq!(field(&report, "messages", "submits"), "25", "{}", report);
+
+    let report = sim.bench("--tps 0 --messages 3 --size 400 --split payload");
+    assert_eq!(field(&report, "messages", "submits"), "3", "{}", report);
+    assert!(!report.contains("errors"), "{}", report);
+}
+
+#[test]
+fn test_smpp_bench_counts_statuses_and_errors() {
+    let sim = Sim::start("throttled", "[faults]\nthrottle_ratio = 1.0\n");
+    let report = sim.bench("--tps 0 --messages 10 --size 20");
+    assert!(
+        report.contains("0x00000058 ESME_RTHROTTLED 10"),
+        "{}",
+        report
+    );
+
+    let sim = Sim::start("dropped", "[faults]\ndrop_response_ratio = 1.0\n");
+    let report = sim.bench("--tps 0 --messages 2 --size 20 --timeout-ms 200");
+    assert!(report.contains("errors"), "{}", report);
+    assert!(!report.contains("latency"), "{}", report);
+
+    let output = Command::new(env!("CARGO_BIN_EXE_smpp-bench"))
+        .args(["--split", "sideways"])
+        .output()
+        .unwrap();
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("sideways"));
+}
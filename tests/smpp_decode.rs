@@ -0,0 +1,156 @@
+use smpp_codec_ffi::address::address_new;
+use smpp_codec_ffi::ancillary_pdu_ffi::MessageState;
+use smpp_codec_ffi::builders::{DeliverSmBuilder, SubmitSmBuilder};
+use smpp_codec_ffi::common::*;
+use smpp_codec_ffi::delivery_pdu_ffi::encode_deliver_sm;
+use smpp_codec_ffi::session_pdu_ffi::{encode_enquire_link, EnquireLink};
+use smpp_codec_ffi::submission_pdu_ffi::encode_submit_sm_request;
+use smpp_codec_ffi::tlv::{tlv_new, tlv_new_u16, tlv_new_u8, Tags};
+use std::io::Write;
+use std::process::{Command, Output, Stdio};
+
+/// Runs `smpp-decode` with `args`, writing `input` to its stdin.
+fn decode(args: &[&str], input: &[u8]) -> Output {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_smpp-decode"))
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+    child.stdin.take().unwrap().write_all(input).unwrap();
+    child.wait_with_output().unwrap()
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{:02x} ", byte)).collect()
+}
+
+/// The second part of a UDH-concatenated message, then an `enquire_link`.
+fn capture() -> Vec<u8> {
+    let mut short_message = vec![0x05, 0x00, 0x03, 0x2A, 0x02, 0x02];
+    short_message.extend_from_slice(b"world");
+    let submit = SubmitSmBuilder::new()
+        .sequence_number(7)
+        .source(address_new(
+            Ton::Alphanumeric,
+            Npi::Unknown,
+            "SHOP".to_string(),
+        ))
+        .destination(address_new(
+            Ton::International,
+            Npi::Isdn,
+            "447700900123".to_string(),
+        ))
+        .short_message(short_message, 0x00)
+        .esm_class(0x40)
+        .tlv(tlv_new_u16(Tags::SAR_MSG_REF_NUM, 513))
+        .tlv(tlv_new(0x1401, vec![0xAB, 0xCD]))
+        .build()
+        .unwrap();
+    let mut bytes = encode_submit_sm_request(&submit);
+    bytes.extend(encode_enquire_link(&EnquireLink { sequence_number: 8 }));
+    bytes
+}
+
+#[test]
+fn test_smpp_decode_tree() {
+    let bytes = capture();
+    // Spaces, `0x` prefixes and line breaks are all accepted.
+    let input = format!("0x{}\n{}", hex(&bytes[..20]), hex(&bytes[20..]));
+    let output = decode(&[], input.as_bytes());
+    assert!(output.status.success());
+    let tree = String::from_utf8(output.stdout).unwrap();
+    for line in [
+        "PDU 1 at offset 0: submit_sm",
+        "    command_id: 0x00000004",
+        "    sequence_number: 7",
+        "      address: 447700900123",
+        "    esm_class: 0x40 (default mode, UDHI)",
+        "        name: concatenated short message, 8-bit reference",
+        "        reference: 42",
+        "        sequence: 2",
+        "      text: world",
+        "        name: sar_msg_ref_num",
+        "        value: 513",
+        "        name: unknown",
+        "        value: AB CD",
+        "PDU 2 at offset",
+        "    command: enquire_link",
+    ] {
+        assert!(tree.contains(line), "missing '{}' in:\n{}", line, tree);
+    }
+
+    // Binary input decodes the same.
+    let binary = decode(&["--binary"], &bytes);
+    assert!(binary.status.success());
+    assert_eq!(String::from_utf8(binary.stdout).unwrap(), tree);
+}
+
+#[test]
+fn test_smpp_decode_json() {
+    let mut payload = vec![0x00, 0x48, 0x00, 0x69];
+    payload.extend_from_slice(&[0x00, 0x21]);
+    let receipt = DeliverSmBuilder::new()
+        .sequence_number(3)
+        .source(address_new(
+            Ton::International,
+            Npi::Isdn,
+            "447700900123".to_string(),
+        ))
+        .destination(address_new(
+            Ton::Alphanumeric,
+            Npi::Unknown,
+            "SHOP".to_string(),
+        ))
+        .short_message(Vec::new(), 0x08)
+        .esm_class(0x04)
+        .tlv(tlv_new(Tags::MESSAGE_PAYLOAD, payload))
+        .tlv(tlv_new(Tags::RECEIPTED_MESSAGE_ID, b"SIM1\0".to_vec()))
+        .tlv(tlv_new_u8(
+            Tags::MESSAGE_STATE,
+            MessageState::Undeliverable as u8,
+        ))
+        .tlv(tlv_new(Tags::NETWORK_ERROR_CODE, vec![3, 0, 1]))
+        .build()
+        .unwrap();
+    let output = decode(&["--json"], &encode_deliver_sm(&receipt));
+    assert!(output.status.success());
+    let pdus: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    let pdu = &pdus[0];
+    assert_eq!(pdu["header"]["command"], "deliver_sm");
+    assert_eq!(pdu["header"]["status"], "ESME_ROK");
+    let body = &pdu["body"];
+    assert_eq!(body["esm_class"], "0x04 (default mode, delivery receipt)");
+    assert_eq!(body["data_coding"], "0x08 (UCS2)");
+    let tlvs = body["tlvs"].as_array().unwrap();
+    assert_eq!(tlvs[0]["name"], "message_payload");
+    assert_eq!(tlvs[0]["value"]["text"], "Hi!");
+    assert_eq!(tlvs[1]["value"], "SIM1");
+    assert_eq!(tlvs[2]["value"], "5 (Undeliverable)");
+    assert_eq!(tlvs[3]["value"], "type 3, error 1");
+}
+
+#[test]
+fn test_smpp_decode_bad_input() {
+    // A truncated second PDU still prints the first and fails.
+    let bytes = capture();
+    let output = decode(&[], hex(&bytes[..bytes.len() - 4]).as_bytes());
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stdout).contains("PDU 1 at offset 0"));
+    assert!(String::from_utf8_lossy(&output.stderr).contains("12 trailing octets"));
+
+    // An unknown command_id is reported with the raw octets.
+    let unknown = [
+        0x00, 0x00, 0x00, 0x10, 0x00, 0x00, 0x09, 0x99, 0, 0, 0, 0, 0, 0, 0, 1,
+    ];
+    let output = decode(&["--json"], &unknown);
+    assert!(!output.status.success());
+    let pdus: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(pdus[0]["header"]["command"], "unknown");
+    assert!(pdus[0]["error"].is_string());
+
+    let output = decode(&["--hex"], b"00 0g");
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("'g'"));
+}
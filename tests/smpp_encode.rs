@@ -0,0 +1,231 @@
+use smpp_codec_ffi::address::address_new;
+use smpp_codec_ffi::builders::SubmitSmBuilder;
+use smpp_codec_ffi::common::*;
+use smpp_codec_ffi::submission_pdu_ffi::encode_submit_sm_request;
+use smpp_codec_ffi::tlv::{tlv_new, tlv_new_u16, tlv_new_u8, Tags};
+use std::io::{BufRead, BufReader, Write};
+use std::process::{Command, Output, Stdio};
+
+/// Runs `bin` with `args`, writing `input` to its stdin.
+fn run(bin: &str, args: &[&str], input: &[u8]) -> Output {
+    let mut child = Command::new(bin)
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+    child.stdin.take().unwrap().write_all(input).unwrap();
+    child.wait_with_output().unwrap()
+}
+
+fn encode(args: &[&str], input: &str) -> Output {
+    run(env!("CARGO_BIN_EXE_smpp-encode"), args, input.as_bytes())
+}
+
+fn hex(bytes: &[u8]) -> String {
+    let octets: Vec<String> = bytes.iter().map(|byte| format!("{:02X}", byte)).collect();
+    octets.join(" ")
+}
+
+const DESCRIPTION: &str = r#"
+[[pdu]]
+header = { command = "bind_transmitter", sequence_number = 1 }
+body = { system_id = "test", password = "secret" }
+
+[[pdu]]
+header = { command = "submit_sm", sequence_number = 2 }
+
+[pdu.body]
+source = { ton = "Alphanumeric", address = "SHOP" }
+destination = { ton = 1, npi = "isdn", address = "447700900123" }
+esm_class = "0x40 (default mode, UDHI)"
+short_message = { udh = "05 00 03 2A 02 02", text = "world" }
+tlvs = [
+    { name = "sar_msg_ref_num", value = 513 },
+    { name = "message_state", value = "5 (Undeliverable)" },
+    { tag = "0x1401", value = "AB CD" },
+]
+"#;
+
+#[test]
+fn test_smpp_encode_toml() {
+    let mut short_message = vec![0x05, 0x00, 0x03, 0x2A, 0x02, 0x02];
+    short_message.extend_from_slice(b"world");
+    let submit = SubmitSmBuilder::new()
+        .sequence_number(2)
+        .source(address_new(
+            Ton::Alphanumeric,
+            Npi::Unknown,
+            "SHOP".to_string(),
+        ))
+        .destination(address_new(
+            Ton::International,
+            Npi::Isdn,
+            "447700900123".to_string(),
+        ))
+        .short_message(short_message, 0x00)
+        .esm_class(0x40)
+        .tlv(tlv_new_u16(Tags::SAR_MSG_REF_NUM, 513))
+        .tlv(tlv_new_u8(Tags::MESSAGE_STATE, 5))
+        .tlv(tlv_new(0x1401, vec![0xAB, 0xCD]))
+        .build()
+        .unwrap();
+
+    let output = encode(&[], DESCRIPTION);
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    let lines: Vec<&str> = stdout.lines().collect();
+    assert_eq!(lines.len(), 2);
+    assert!(lines[0].starts_with("00 00 00 "));
+    assert_eq!(lines[1], hex(&encode_submit_sm_request(&submit)));
+
+    let binary = encode(&["--binary"], DESCRIPTION);
+    assert!(binary.status.success());
+    assert!(binary.stdout.ends_with(&encode_submit_sm_request(&submit)));
+}
+
+#[test]
+fn test_smpp_encode_round_trips_decoder_json() {
+    let binary = encode(&["--binary"], DESCRIPTION).stdout;
+    let decoded = run(env!("CARGO_BIN_EXE_smpp-decode"), &["--json"], &binary);
+    assert!(decoded.status.success());
+    let json = String::from_utf8(decoded.stdout).unwrap();
+    let output = encode(&["--binary"], &json);
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    assert_eq!(output.stdout, binary);
+}
+
+#[test]
+fn test_smpp_encode_malformed_pdus() {
+    let output = encode(
+        &[],
+        r#"{
+            "header": { "command": "enquire_link", "sequence_number": 9 },
+            "override": { "command_length": 12 }
+        }"#,
+    );
+    assert!(output.status.success());
+    assert_eq!(
+        String::from_utf8(output.stdout).unwrap().trim(),
+        "00 00 00 0C 00 00 00 15 00 00 00 00 00 00 00 09"
+    );
+
+    let output = encode(
+        &[],
+        r#"[{
+            "header": { "command": "data_sm" },
+            "body": { "tlvs": [{ "name": "sar_msg_ref_num", "value": 258, "override_length": 7 }] }
+        }, { "hex": "00 00 00 10 00 00 09 99 00 00 00 00 00 00 00 01" }]"#,
+    );
+    assert!(output.status.success());
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(stdout
+        .lines()
+        .next()
+        .unwrap()
+        .ends_with("02 0C 00 07 01 02"));
+    assert!(stdout.contains("00 00 09 99"));
+
+    let output = encode(
+        &[],
+        r#"{ "header": { "command": "submit_sm" }, "body": { "colour": 1 } }"#,
+    );
+    assert!(!output.status.success());
+    assert!(
+        String::from_utf8_lossy(&output.stderr).contains("PDU 1: Unknown field 'colour' in body")
+    );
+
+    let output = encode(
+        &[],
+        r#"{ "header": { "command": "submit_sm" }, "body": { "tlvs": [{ "name": "sar_msg_ref_num", "value": 70000 }] } }"#,
+    );
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("does not fit in 2 octets"));
+}
+
+#[test]
+fn test_smpp_encode_zero_length_values() {
+    let submit = SubmitSmBuilder::new()
+        .sequence_number(3)
+        .destination(address_new(Ton::Unknown, Npi::Unknown, "1234".to_string()))
+        .tlv(tlv_new(Tags::ALERT_ON_MESSAGE_DELIVERY, Vec::new()))
+        .build()
+        .unwrap();
+    let output = encode(
+        &[],
+        r#"{
+            "header": { "command": "submit_sm", "sequence_number": 3 },
+            "body": {
+                "destination": { "address": "1234" },
+                "short_message": { "hex": "" },
+                "tlvs": [{ "name": "alert_on_message_delivery", "value": "" }]
+            }
+        }"#,
+    );
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(stdout.trim().ends_with("13 0C 00 00"), "{}", stdout);
+    assert_eq!(stdout.trim(), hex(&encode_submit_sm_request(&submit)));
+
+    // A whole PDU still needs some hex.
+    let output = encode(&[], r#"{ "hex": "" }"#);
+    assert!(!output.status.success());
+}
+
+#[test]
+fn test_smpp_encode_sends_to_sim() {
+    let path = std::env::temp_dir().join(format!("smpp-encode-sim-{}.toml", std::process::id()));
+    std::fs::write(&path, "[listener]\nport = 0\n").unwrap();
+    let mut sim = Command::new(env!("CARGO_BIN_EXE_smpp-sim"))
+        .arg(&path)
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .spawn()
+        .unwrap();
+    let mut line = String::new();
+    BufReader::new(sim.stdout.take().unwrap())
+        .read_line(&mut line)
+        .unwrap();
+    let port = line.trim().rsplit(':').next().unwrap().to_string();
+    let _ = std::fs::remove_file(path);
+
+    let address = format!("127.0.0.1:{}", port);
+    let output = encode(&["--send", &address], DESCRIPTION);
+    let text = encode(&["--send", &address, "--json"], DESCRIPTION);
+    let _ = sim.kill();
+    let _ = sim.wait();
+
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    for line in [
+        "Sent bind_transmitter, sequence 1",
+        "Received: bind_transmitter_resp",
+        "Sent submit_sm, sequence 2",
+        "Received: submit_sm_resp",
+        "    status: ESME_ROK",
+    ] {
+        assert!(stdout.contains(line), "missing '{}' in:\n{}", line, stdout);
+    }
+
+    assert!(text.status.success());
+    let pdus: serde_json::Value = serde_json::from_slice(&text.stdout).unwrap();
+    assert_eq!(pdus[1]["header"]["command"], "submit_sm_resp");
+    assert!(pdus[1]["body"]["message_id"].is_string());
+}
@@ -0,0 +1,316 @@
+use smpp_codec_ffi::address::address_new;
+use smpp_codec_ffi::ancillary_pdu_ffi::*;
+use smpp_codec_ffi::builders::SubmitSmBuilder;
+use smpp_codec_ffi::common::*;
+use smpp_codec_ffi::delivery_pdu_ffi::{DeliverSmRequest, ESM_CLASS_DELIVERY_RECEIPT};
+use smpp_codec_ffi::esme_session::*;
+use smpp_codec_ffi::framer::read_frame;
+use smpp_codec_ffi::pdu::{decode_pdu, encode_pdu, Pdu};
+use smpp_codec_ffi::session_pdu_ffi::BindRequest;
+use smpp_codec_ffi::submission_pdu_ffi::SubmitSmRequest;
+use smpp_codec_ffi::throttle::ThrottleConfig;
+use std::io::{BufRead, BufReader, Write};
+use std::net::TcpStream;
+use std::process::{Child, Command, Stdio};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+/// A running simulator, killed on drop.
+struct Sim {
+    child: Child,
+    port: u16,
+}
+
+impl Sim {
+    fn start(name: &str, scenario: &str) -> Self {
+        let path =
+            std::env::temp_dir().join(format!("smpp-sim-{}-{}.toml", name, std::process::id()));
+        std::fs::write(&path, scenario).unwrap();
+        let mut child = Command::new(env!("CARGO_BIN_EXE_smpp-sim"))
+            .arg(&path)
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null())
+            .spawn()
+            .unwrap();
+        let mut line = String::new();
+        BufReader::new(child.stdout.take().unwrap())
+            .read_line(&mut line)
+            .unwrap();
+        let port = line.trim().rsplit(':').next().unwrap().parse().unwrap();
+        let _ = std::fs::remove_file(path);
+        Self { child, port }
+    }
+
+    /// A session configuration that returns `ESME_RTHROTTLED` instead of retrying.
+    fn config(&self) -> EsmeSessionConfig {
+        EsmeSessionConfig {
+            response_timeout_ms: 500,
+            throttle: Some(ThrottleConfig {
+                max_throttled_retries: 0,
+                ..ThrottleConfig::default()
+            }),
+            ..esme_session_config_new("127.0.0.1".to_string(), self.port)
+        }
+    }
+}
+
+impl Drop for Sim {
+    fn drop(&mut self) {
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+}
+
+fn bind(mode: BindMode, password: &str, address_range: &str) -> BindRequest {
+    BindRequest {
+        sequence_number: 0,
+        mode,
+        system_id: "esme".to_string(),
+        password: password.to_string(),
+        system_type: String::new(),
+        interface_version: SMPP_INTERFACE_VERSION_34,
+        addr_ton: Ton::Unknown,
+        addr_npi: Npi::Unknown,
+        address_range: address_range.to_string(),
+    }
+}
+
+fn submit_request() -> SubmitSmRequest {
+    let mut request = SubmitSmBuilder::new()
+        .source(address_new(
+            Ton::Alphanumeric,
+            Npi::Unknown,
+            "SHOP".to_string(),
+        ))
+        .destination(address_new(
+            Ton::International,
+            Npi::Isdn,
+            "447700900123".to_string(),
+        ))
+        .text("Hello".to_string())
+        .build()
+        .unwrap();
+    request.registered_delivery = 1;
+    request
+}
+
+struct Recorder(Mutex<Sender<DeliverSmRequest>>);
+
+impl DeliverSmListener for Recorder {
+    fn on_deliver_sm(&self, request: DeliverSmRequest) -> u32 {
+        let _ = self.0.lock().unwrap().send(request);
+        COMMAND_STATUS_OK
+    }
+}
+
+fn recorder() -> (Arc<dyn DeliverSmListener>, Receiver<DeliverSmRequest>) {
+    let (sender, received) = mpsc::channel();
+    (Arc::new(Recorder(Mutex::new(sender))), received)
+}
+
+#[test]
+fn test_smpp_sim_receipts_and_mo() {
+    let sim = Sim::start(
+        "receipts",
+        r#"
+        [listener]
+        port = 0
+
+        [[accounts]]
+        system_id = "esme"
+        password = "secret"
+
+        [receipts]
+        delay_ms = 100
+
+        [[mo]]
+        destination = "1234"
+        text = "Ping"
+        interval_ms = 200
+        "#,
+    );
+    let Err(error) =
+        EsmeSession::connect(sim.config(), bind(BindMode::Transceiver, "wrong", ""), None)
+    else {
+        panic!("Bound with a wrong password");
+    };
+    assert!(error.to_string().contains("0x0000000E"), "{}", error);
+
+    let (listener, received) = recorder();
+    let session = EsmeSession::connect(
+        sim.config(),
+        bind(BindMode::Transceiver, "secret", "^1234$"),
+        Some(listener),
+    )
+    .unwrap();
+    let response = session.submit(submit_request()).unwrap();
+    assert!(response.message_id.starts_with("SIM"));
+
+    let mut receipt = None;
+    let mut mo = None;
+    while receipt.is_none() || mo.is_none() {
+        let pdu = received.recv_timeout(Duration::from_secs(5)).unwrap();
+        if pdu.esm_class == ESM_CLASS_DELIVERY_RECEIPT {
+            receipt = Some(pdu);
+        } else {
+            mo = Some(pdu);
+        }
+    }
+    let receipt = receipt.unwrap();
+    assert_eq!(receipt.dest_addr, "SHOP");
+    let text = String::from_utf8(receipt.short_message).unwrap();
+    assert!(
+        text.starts_with(&format!("id:{} ", response.message_id)),
+        "{}",
+        text
+    );
+    assert!(text.contains("stat:DELIVRD"), "{}", text);
+    let mo = mo.unwrap();
+    assert_eq!(mo.dest_addr, "1234");
+    assert_eq!(mo.short_message, b"Ping");
+
+    // A receiver may not submit.
+    let receiver =
+        EsmeSession::connect(sim.config(), bind(BindMode::Receiver, "secret", ""), None).unwrap();
+    let response = receiver.submit(submit_request()).unwrap();
+    assert_eq!(response.command_status, ESME_RINVBNDSTS);
+    session.unbind().unwrap();
+}
+
+/// Sends one PDU and returns the next one the simulator sends back.
+fn exchange(stream: &mut TcpStream, pdu: Pdu) -> Pdu {
+    stream.write_all(&encode_pdu(&pdu)).unwrap();
+    decode_pdu(&read_frame(stream).unwrap()).unwrap()
+}
+
+#[test]
+fn test_smpp_sim_cancelled_message_keeps_its_state() {
+    let sim = Sim::start(
+        "cancel",
+        "[listener]\nport = 0\n\n[receipts]\ndelay_ms = 100\n",
+    );
+    let mut stream = TcpStream::connect(("127.0.0.1", sim.port)).unwrap();
+    stream
+        .set_read_timeout(Some(Duration::from_secs(5)))
+        .unwrap();
+    let Pdu::BindResponse { pdu } = exchange(
+        &mut stream,
+        Pdu::BindRequest {
+            pdu: bind(BindMode::Transceiver, "", ""),
+        },
+    ) else {
+        panic!("bind was not answered");
+    };
+    assert_eq!(pdu.command_status, COMMAND_STATUS_OK);
+
+    let Pdu::SubmitSmResponse { pdu } = exchange(
+        &mut stream,
+        Pdu::SubmitSm {
+            pdu: submit_request(),
+        },
+    ) else {
+        panic!("submit_sm was not answered");
+    };
+    let message_id = pdu.message_id;
+    let Pdu::CancelSmResponse { pdu } = exchange(
+        &mut stream,
+        Pdu::CancelSm {
+            pdu: CancelSmRequest {
+                sequence_number: 3,
+                service_type: String::new(),
+                message_id: message_id.clone(),
+                source_addr_ton: Ton::Alphanumeric,
+                source_addr_npi: Npi::Unknown,
+                source_addr: "SHOP".to_string(),
+                dest_addr_ton: Ton::International,
+                dest_addr_npi: Npi::Isdn,
+                dest_addr: "447700900123".to_string(),
+            },
+        },
+    ) else {
+        panic!("cancel_sm was not answered");
+    };
+    assert_eq!(pdu.command_status, COMMAND_STATUS_OK);
+
+    // Once the receipt was due, the next PDU is still the query_sm_resp rather than a receipt.
+    std::thread::sleep(Duration::from_millis(400));
+    let reply = exchange(
+        &mut stream,
+        Pdu::QuerySm {
+            pdu: QuerySmRequest {
+                sequence_number: 4,
+                message_id,
+                source_addr_ton: Ton::Alphanumeric,
+                source_addr_npi: Npi::Unknown,
+                source_addr: "SHOP".to_string(),
+            },
+        },
+    );
+    let Pdu::QuerySmResponse { pdu } = reply else {
+        panic!("Expected a query_sm_resp, got {:?}", reply);
+    };
+    assert_eq!(pdu.message_state, MessageState::Deleted as u8);
+}
+
+fn fault_sim(name: &str, faults: &str) -> Sim {
+    Sim::start(
+        name,
+        &format!("[listener]\nport = 0\n\n[faults]\n{}\n", faults),
+    )
+}
+
+/// Submits once, returning the response status or the error.
+fn submit_once(sim: &Sim) -> Result<u32, String> {
+    let session =
+        EsmeSession::connect(sim.config(), bind(BindMode::Transmitter, "", ""), None).unwrap();
+    session
+        .submit(submit_request())
+        .map(|response| response.command_status)
+        .map_err(|error| error.to_string())
+}
+
+#[test]
+fn test_smpp_sim_faults() {
+    assert_eq!(
+        submit_once(&fault_sim("throttle", "throttle_ratio = 1.0")),
+        Ok(ESME_RTHROTTLED)
+    );
+
+    let error = submit_once(&fault_sim("nack", "generic_nack_ratio = 1.0")).unwrap_err();
+    assert!(error.contains("0x00000008"), "{}", error);
+
+    let error = submit_once(&fault_sim("drop", "drop_response_ratio = 1.0")).unwrap_err();
+    assert!(error.to_lowercase().contains("timed out"), "{}", error);
+
+    let sim = fault_sim("disconnect", "disconnect_ratio = 1.0");
+    let session =
+        EsmeSession::connect(sim.config(), bind(BindMode::Transmitter, "", ""), None).unwrap();
+    assert!(session.submit(submit_request()).is_err());
+    std::thread::sleep(Duration::from_millis(100));
+    assert!(!session.is_bound());
+
+    let sim = fault_sim("tps", "max_tps = 2");
+    let session =
+        EsmeSession::connect(sim.config(), bind(BindMode::Transmitter, "", ""), None).unwrap();
+    let statuses: Vec<u32> = (0..3)
+        .map(|_| session.submit(submit_request()).unwrap().command_status)
+        .collect();
+    assert_eq!(
+        statuses,
+        [COMMAND_STATUS_OK, COMMAND_STATUS_OK, ESME_RTHROTTLED]
+    );
+}
+
+#[test]
+fn test_smpp_sim_rejects_bad_scenario() {
+    let path = std::env::temp_dir().join(format!("smpp-sim-bad-{}.toml", std::process::id()));
+    std::fs::write(&path, "[faults]\nunknown_fault = 1\n").unwrap();
+    let output = Command::new(env!("CARGO_BIN_EXE_smpp-sim"))
+        .arg(&path)
+        .output()
+        .unwrap();
+    let _ = std::fs::remove_file(path);
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("unknown_fault"));
+}
@@ -0,0 +1,209 @@
+mod fixtures;
+
+use fixtures::{PasswordCheck, Responder};
+use smpp_codec_ffi::address::address_new;
+use smpp_codec_ffi::builders::SubmitSmBuilder;
+use smpp_codec_ffi::common::*;
+use smpp_codec_ffi::delivery_pdu_ffi::DeliverSmRequest;
+use smpp_codec_ffi::esme_session::*;
+use smpp_codec_ffi::framer::read_frame;
+use smpp_codec_ffi::pdu::{decode_pdu_header, Pdu};
+use smpp_codec_ffi::session_pdu_ffi::*;
+use smpp_codec_ffi::smsc_listener::*;
+use smpp_codec_ffi::submission_pdu_ffi::encode_submit_sm_request;
+use std::io::Write;
+use std::net::TcpStream;
+use std::sync::Arc;
+
+fn start_listener() -> Arc<SmscListener> {
+    SmscListener::start(
+        smsc_listener_config_new("127.0.0.1".to_string(), 0),
+        Arc::new(PasswordCheck),
+        Arc::new(Responder),
+    )
+    .unwrap()
+}
+
+fn bind_request(mode: BindMode, password: &str) -> BindRequest {
+    BindRequest {
+        sequence_number: 1,
+        mode,
+        system_id: "esme".to_string(),
+        password: password.to_string(),
+        system_type: String::new(),
+        interface_version: SMPP_INTERFACE_VERSION_34,
+        addr_ton: Ton::Unknown,
+        addr_npi: Npi::Unknown,
+        address_range: String::new(),
+    }
+}
+
+fn submit_bytes(sequence_number: u32) -> Vec<u8> {
+    let mut request = SubmitSmBuilder::new()
+        .destination(address_new(Ton::Unknown, Npi::Unknown, "1234".to_string()))
+        .text("Hello".to_string())
+        .build()
+        .unwrap();
+    request.sequence_number = sequence_number;
+    encode_submit_sm_request(&request)
+}
+
+/// Sends one PDU and returns the header of the reply.
+fn exchange(stream: &mut TcpStream, bytes: &[u8]) -> (u32, u32, u32) {
+    stream.write_all(bytes).unwrap();
+    let header = decode_pdu_header(&read_frame(stream).unwrap()).unwrap();
+    (
+        header.command_id,
+        header.command_status,
+        header.sequence_number,
+    )
+}
+
+#[test]
+fn test_smsc_listener_serves_esme_session() {
+    let listener = start_listener();
+    let config = esme_session_config_new("127.0.0.1".to_string(), listener.local_port());
+    let session =
+        EsmeSession::connect(config, bind_request(BindMode::Transceiver, "secret"), None).unwrap();
+
+    let request = SubmitSmBuilder::new()
+        .destination(address_new(Ton::Unknown, Npi::Unknown, "1234".to_string()))
+        .text("Hello".to_string())
+        .build()
+        .unwrap();
+    let response = session.submit(request).unwrap();
+    assert_eq!(response.command_status, COMMAND_STATUS_OK);
+    assert_eq!(response.message_id, "esme-1234");
+
+    session.unbind().unwrap();
+    listener.shutdown();
+}
+
+#[test]
+fn test_smsc_listener_rejects_bad_password() {
+    let listener = start_listener();
+    let config = esme_session_config_new("127.0.0.1".to_string(), listener.local_port());
+    let result = EsmeSession::connect(config, bind_request(BindMode::Transmitter, "wrong"), None);
+    assert!(result.is_err());
+}
+
+#[test]
+fn test_smsc_listener_enforces_bind_state() {
+    let listener = start_listener();
+    let mut stream = TcpStream::connect(("127.0.0.1", listener.local_port())).unwrap();
+
+    let reply = exchange(&mut stream, &submit_bytes(7));
+    assert_eq!(reply, (CMD_SUBMIT_SM_RESP, ESME_RINVBNDSTS, 7));
+
+    let reply = exchange(
+        &mut stream,
+        &encode_bind_request(&bind_request(BindMode::Receiver, "secret")),
+    );
+    assert_eq!(reply, (CMD_BIND_RECEIVER_RESP, COMMAND_STATUS_OK, 1));
+
+    let reply = exchange(&mut stream, &submit_bytes(8));
+    assert_eq!(reply, (CMD_SUBMIT_SM_RESP, ESME_RINVBNDSTS, 8));
+
+    let reply = exchange(
+        &mut stream,
+        &encode_bind_request(&bind_request(BindMode::Receiver, "secret")),
+    );
+    assert_eq!(reply, (CMD_BIND_RECEIVER_RESP, ESME_RALYBND, 1));
+
+    let reply = exchange(
+        &mut stream,
+        &encode_enquire_link(&EnquireLink { sequence_number: 9 }),
+    );
+    assert_eq!(reply, (CMD_ENQUIRE_LINK_RESP, COMMAND_STATUS_OK, 9));
+
+    let unknown = [0, 0, 0, 16, 0, 0, 0x0A, 0xBC, 0, 0, 0, 0, 0, 0, 0, 10];
+    let reply = exchange(&mut stream, &unknown);
+    assert_eq!(reply, (GENERIC_NACK, ESME_RINVCMDID, 10));
+
+    let reply = exchange(
+        &mut stream,
+        &encode_unbind(&Unbind {
+            sequence_number: 11,
+        }),
+    );
+    assert_eq!(reply, (CMD_UNBIND_RESP, COMMAND_STATUS_OK, 11));
+    assert!(read_frame(&mut stream).is_err());
+}
+
+/// Misbehaves depending on the sequence number of the request.
+struct Faults;
+
+impl SessionHandler for Faults {
+    fn on_request(&self, _session: SmscSessionInfo, request: Pdu) -> SessionReply {
+        match request.sequence_number() {
+            2 => SessionReply::Reject {
+                command_status: ESME_RTHROTTLED,
+            },
+            3 => SessionReply::NoResponse,
+            4 => Pdu::GenericNack {
+                pdu: GenericNack {
+                    sequence_number: 0,
+                    command_status: ESME_RSYSERR,
+                },
+            }
+            .into(),
+            _ => SessionReply::Disconnect,
+        }
+    }
+}
+
+#[test]
+fn test_smsc_listener_session_replies() {
+    let listener = SmscListener::start(
+        smsc_listener_config_new("127.0.0.1".to_string(), 0),
+        Arc::new(PasswordCheck),
+        Arc::new(Faults),
+    )
+    .unwrap();
+    let mut stream = TcpStream::connect(("127.0.0.1", listener.local_port())).unwrap();
+    let reply = exchange(
+        &mut stream,
+        &encode_bind_request(&bind_request(BindMode::Transceiver, "secret")),
+    );
+    assert_eq!(reply, (CMD_BIND_TRANSCEIVER_RESP, COMMAND_STATUS_OK, 1));
+
+    let reply = exchange(&mut stream, &submit_bytes(2));
+    assert_eq!(reply, (CMD_SUBMIT_SM_RESP, ESME_RTHROTTLED, 2));
+
+    // The dropped response is never sent, so the next reply answers sequence 4.
+    stream.write_all(&submit_bytes(3)).unwrap();
+    let reply = exchange(&mut stream, &submit_bytes(4));
+    assert_eq!(reply, (GENERIC_NACK, ESME_RSYSERR, 4));
+
+    let session_id = listener.receivers()[0].session_id;
+    let deliver = DeliverSmRequest {
+        sequence_number: 0,
+        service_type: String::new(),
+        source_addr_ton: Ton::International,
+        source_addr_npi: Npi::Isdn,
+        source_addr: "447700900123".to_string(),
+        dest_addr_ton: Ton::Unknown,
+        dest_addr_npi: Npi::Unknown,
+        dest_addr: "1234".to_string(),
+        esm_class: 0,
+        protocol_id: 0,
+        priority_flag: 0,
+        schedule_delivery_time: None,
+        validity_period: None,
+        registered_delivery: 0,
+        replace_if_present_flag: 0,
+        data_coding: 0,
+        sm_default_msg_id: 0,
+        short_message: b"Hi".to_vec(),
+        optional_params: Vec::new(),
+    };
+    assert!(listener
+        .deliver_to(session_id + 1, deliver.clone())
+        .is_err());
+    listener.deliver_to(session_id, deliver).unwrap();
+    let header = decode_pdu_header(&read_frame(&mut stream).unwrap()).unwrap();
+    assert_eq!(header.command_id, CMD_DELIVER_SM);
+
+    stream.write_all(&submit_bytes(5)).unwrap();
+    assert!(read_frame(&mut stream).is_err());
+}
@@ -1,3 +1,4 @@
+use smpp_codec_ffi::address::Address;
 use smpp_codec_ffi::common::{Npi, Ton};
 use smpp_codec_ffi::submission_pdu_ffi::*;
 use smpp_codec_ffi::tlv::tlv_new_u16;
@@ -66,9 +67,11 @@ fn test_submit_sm_with_tlvs() {
 #[test]
 fn test_submit_multi_encoding_decoding() {
     let dest1 = Destination::SmeAddress {
-        ton: Ton::International,
-        npi: Npi::Isdn,
-        address: "12345".to_string(),
+        address: Address {
+            ton: Ton::International,
+            npi: Npi::Isdn,
+            value: "12345".to_string(),
+        },
     };
     let dest2 = Destination::DistributionList {
         name: "MyList".to_string(),
@@ -0,0 +1,277 @@
+#![cfg(feature = "tls")]
+
+mod fixtures;
+
+use fixtures::{AcceptAll, Responder};
+use rcgen::{BasicConstraints, Certificate, CertificateParams, IsCa, KeyPair};
+use smpp_codec_ffi::address::address_new;
+use smpp_codec_ffi::async_session::AsyncEsmeSession;
+use smpp_codec_ffi::builders::SubmitSmBuilder;
+use smpp_codec_ffi::common::*;
+use smpp_codec_ffi::esme_session::*;
+use smpp_codec_ffi::outbind::*;
+use smpp_codec_ffi::session_pdu_ffi::*;
+use smpp_codec_ffi::smsc_listener::*;
+use smpp_codec_ffi::submission_pdu_ffi::SubmitSmRequest;
+use smpp_codec_ffi::tls::*;
+use std::sync::mpsc::{self, Sender};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+struct Authority {
+    certificate: Certificate,
+    key: KeyPair,
+}
+
+impl Authority {
+    fn new() -> Self {
+        let mut params = CertificateParams::new(Vec::new()).unwrap();
+        params.is_ca = IsCa::Ca(BasicConstraints::Unconstrained);
+        let key = KeyPair::generate().unwrap();
+        let certificate = params.self_signed(&key).unwrap();
+        Self { certificate, key }
+    }
+
+    fn pem(&self) -> String {
+        self.certificate.pem()
+    }
+
+    /// Issues a certificate for `name`, returning its PEM chain and key.
+    fn issue(&self, name: &str) -> (String, String) {
+        let params = CertificateParams::new(vec![name.to_string()]).unwrap();
+        let key = KeyPair::generate().unwrap();
+        let certificate = params
+            .signed_by(&key, &self.certificate, &self.key)
+            .unwrap();
+        (certificate.pem(), key.serialize_pem())
+    }
+}
+
+fn start_listener(tls: TlsServerConfig) -> Arc<SmscListener> {
+    SmscListener::start(
+        SmscListenerConfig {
+            tls: Some(tls),
+            ..smsc_listener_config_new("127.0.0.1".to_string(), 0)
+        },
+        Arc::new(AcceptAll),
+        Arc::new(Responder),
+    )
+    .unwrap()
+}
+
+fn session_config(listener: &SmscListener, tls: TlsClientConfig) -> EsmeSessionConfig {
+    EsmeSessionConfig {
+        tls: Some(tls),
+        ..esme_session_config_new("127.0.0.1".to_string(), listener.local_port())
+    }
+}
+
+fn client_config(authority: &Authority, server_name: &str) -> TlsClientConfig {
+    TlsClientConfig {
+        server_name: server_name.to_string(),
+        ..tls_client_config_new(authority.pem())
+    }
+}
+
+fn bind_request() -> BindRequest {
+    BindRequest {
+        sequence_number: 0,
+        mode: BindMode::Transceiver,
+        system_id: "esme".to_string(),
+        password: "secret".to_string(),
+        system_type: String::new(),
+        interface_version: SMPP_INTERFACE_VERSION_34,
+        addr_ton: Ton::Unknown,
+        addr_npi: Npi::Unknown,
+        address_range: String::new(),
+    }
+}
+
+fn submit_request() -> SubmitSmRequest {
+    SubmitSmBuilder::new()
+        .destination(address_new(Ton::Unknown, Npi::Unknown, "1234".to_string()))
+        .text("Hello".to_string())
+        .build()
+        .unwrap()
+}
+
+fn connect(config: EsmeSessionConfig) -> Result<Arc<EsmeSession>, SmppFfiError> {
+    EsmeSession::connect(config, bind_request(), None)
+}
+
+#[test]
+fn test_tls_session() {
+    let authority = Authority::new();
+    let (chain, key) = authority.issue("localhost");
+    let listener = start_listener(tls_server_config_new(chain, key));
+
+    let session = connect(session_config(
+        &listener,
+        client_config(&authority, "localhost"),
+    ))
+    .unwrap();
+    let response = session.submit(submit_request()).unwrap();
+    assert_eq!(response.message_id, "esme-1234");
+    session.unbind().unwrap();
+
+    // A plain client gets no bind out of a TLS listener.
+    let plain = EsmeSessionConfig {
+        response_timeout_ms: 500,
+        ..esme_session_config_new("127.0.0.1".to_string(), listener.local_port())
+    };
+    assert!(connect(plain).is_err());
+}
+
+#[tokio::test]
+async fn test_tls_async_session() {
+    let authority = Authority::new();
+    let (chain, key) = authority.issue("localhost");
+    let listener = start_listener(tls_server_config_new(chain, key));
+
+    let session = AsyncEsmeSession::new(
+        session_config(&listener, client_config(&authority, "localhost")),
+        None,
+    );
+    session.clone().bind(bind_request()).await.unwrap();
+    let response = session.clone().submit(submit_request()).await.unwrap();
+    assert_eq!(response.message_id, "esme-1234");
+    session.unbind().await.unwrap();
+}
+
+#[test]
+fn test_tls_server_verification() {
+    let authority = Authority::new();
+    let (chain, key) = authority.issue("localhost");
+    let listener = start_listener(tls_server_config_new(chain, key));
+
+    let Err(error) = connect(session_config(
+        &listener,
+        client_config(&authority, "smsc.example.com"),
+    )) else {
+        panic!("Connected to a server with a certificate for another name");
+    };
+    assert!(
+        error.to_string().contains("TLS handshake failed"),
+        "{}",
+        error
+    );
+
+    let any_name = TlsClientConfig {
+        verify_hostname: false,
+        ..client_config(&authority, "smsc.example.com")
+    };
+    connect(session_config(&listener, any_name))
+        .unwrap()
+        .unbind()
+        .unwrap();
+
+    // The chain is still checked without hostname verification.
+    let stranger = TlsClientConfig {
+        verify_hostname: false,
+        ..client_config(&Authority::new(), "localhost")
+    };
+    assert!(connect(session_config(&listener, stranger)).is_err());
+}
+
+#[test]
+fn test_tls_client_certificates() {
+    let authority = Authority::new();
+    let (chain, key) = authority.issue("localhost");
+    let listener = start_listener(TlsServerConfig {
+        client_ca_certificates_pem: authority.pem(),
+        ..tls_server_config_new(chain, key)
+    });
+
+    let anonymous = EsmeSessionConfig {
+        response_timeout_ms: 500,
+        ..session_config(&listener, client_config(&authority, "localhost"))
+    };
+    assert!(connect(anonymous).is_err());
+
+    let (client_chain, client_key) = authority.issue("esme");
+    let identified = TlsClientConfig {
+        client_certificate_pem: client_chain,
+        client_key_pem: client_key,
+        ..client_config(&authority, "localhost")
+    };
+    connect(session_config(&listener, identified))
+        .unwrap()
+        .unbind()
+        .unwrap();
+}
+
+struct Sessions(Mutex<Sender<Arc<EsmeSession>>>);
+
+impl OutbindHandler for Sessions {
+    fn on_session(&self, _peer_addr: String, session: Arc<EsmeSession>) {
+        let _ = self.0.lock().unwrap().send(session);
+    }
+}
+
+#[test]
+fn test_tls_outbind() {
+    let authority = Authority::new();
+    let (chain, key) = authority.issue("localhost");
+    let smsc = start_listener(tls_server_config_new(chain.clone(), key.clone()));
+    let (sender, sessions) = mpsc::channel();
+    let esme = OutbindListener::start(
+        OutbindListenerConfig {
+            tls: Some(tls_server_config_new(chain, key)),
+            ..outbind_listener_config_new(
+                "127.0.0.1".to_string(),
+                0,
+                "SMSC".to_string(),
+                "secret".to_string(),
+                bind_request(),
+            )
+        },
+        None,
+        Arc::new(Sessions(Mutex::new(sender))),
+    )
+    .unwrap();
+
+    // A TLS listener does not send outbind credentials in the clear.
+    let plain = smsc.initiate_outbind(
+        "127.0.0.1".to_string(),
+        esme.local_port(),
+        "SMSC".to_string(),
+        "secret".to_string(),
+        None,
+    );
+    assert!(plain.is_err());
+
+    smsc.initiate_outbind(
+        "127.0.0.1".to_string(),
+        esme.local_port(),
+        "SMSC".to_string(),
+        "secret".to_string(),
+        Some(client_config(&authority, "localhost")),
+    )
+    .unwrap();
+    let session = sessions.recv_timeout(Duration::from_secs(5)).unwrap();
+    // The listener registers the receiver just after sending the bind_resp.
+    let deadline = Instant::now() + Duration::from_secs(5);
+    while smsc.receivers().is_empty() {
+        assert!(Instant::now() < deadline, "Receiver was not registered");
+        thread::sleep(Duration::from_millis(10));
+    }
+    session.unbind().unwrap();
+    esme.shutdown();
+}
+
+#[test]
+fn test_tls_rejects_bad_settings() {
+    let result = SmscListener::start(
+        SmscListenerConfig {
+            tls: Some(tls_server_config_new(
+                "not a certificate".to_string(),
+                String::new(),
+            )),
+            ..smsc_listener_config_new("127.0.0.1".to_string(), 0)
+        },
+        Arc::new(AcceptAll),
+        Arc::new(Responder),
+    );
+    assert!(result.is_err());
+}
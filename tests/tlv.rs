@@ -77,4 +77,11 @@ fn test_tag_names() {
         get_tag_by_name("dest_addr_subunit"),
         Tags::DEST_ADDR_SUBUNIT
     );
+    assert_eq!(get_tag_name(Tags::MESSAGE_STATE), Some("message_state"));
+    assert_eq!(get_tag_name(0x1401), None);
+    assert_eq!(get_tag_value_length(Tags::SAR_MSG_REF_NUM), Some((2, 2)));
+    assert_eq!(
+        get_tag_value_length(Tags::RECEIPTED_MESSAGE_ID),
+        Some((1, 65))
+    );
 }
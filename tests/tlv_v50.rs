@@ -0,0 +1,180 @@
+use smpp_codec_ffi::tlv::*;
+use smpp_codec_ffi::tlv_v50::*;
+
+#[test]
+fn test_v50_tags() {
+    assert_eq!(Tags::BROADCAST_REP_NUM, 0x0604);
+    assert_eq!(Tags::BROADCAST_FREQUENCY_INTERVAL, 0x0605);
+    assert_eq!(get_tag_by_name("broadcast_rep_num"), 0x0604);
+    assert_eq!(get_tag_by_name("BILLING_IDENTIFICATION"), 0x060B);
+    assert_eq!(get_tag_by_name("dest_addr_np_country"), 0x0613);
+
+    let tlv = tlv_new_u8_from_name("broadcast_message_class", 2);
+    assert_eq!(tlv.tag, Tags::BROADCAST_MESSAGE_CLASS);
+}
+
+#[test]
+fn test_billing_identification() {
+    let billing = BillingIdentification {
+        format: 0x01,
+        value: b"ACCT42".to_vec(),
+    };
+    let tlv = tlv_new_billing_identification(billing.clone());
+    assert_eq!(tlv.tag, Tags::BILLING_IDENTIFICATION);
+    assert_eq!(tlv.value, b"\x01ACCT42".to_vec());
+
+    let decoded = tlv_decode(&tlv_encode(&tlv)).unwrap();
+    assert_eq!(
+        tlv_value_as_billing_identification(&decoded).unwrap(),
+        billing
+    );
+    assert!(
+        tlv_value_as_billing_identification(&tlv_new(Tags::BILLING_IDENTIFICATION, vec![]))
+            .is_err()
+    );
+}
+
+#[test]
+fn test_network_and_node_ids() {
+    let tlv = tlv_new_dest_network_id("operator-a".to_string()).unwrap();
+    assert_eq!(tlv.value, b"operator-a\0".to_vec());
+    assert_eq!(tlv_value_as_network_id(&tlv).unwrap(), "operator-a");
+    assert!(tlv_new_source_network_id("x".repeat(65)).is_err());
+
+    let tlv = tlv_new_source_node_id("012345".to_string()).unwrap();
+    assert_eq!(tlv.tag, Tags::SOURCE_NODE_ID);
+    assert_eq!(tlv.length, 6);
+    assert_eq!(tlv_value_as_node_id(&tlv).unwrap(), "012345");
+    assert!(tlv_new_dest_node_id("12345".to_string()).is_err());
+    assert!(tlv_new_dest_node_id("12345A".to_string()).is_err());
+
+    // Wrong tag
+    assert!(tlv_value_as_node_id(&tlv_new_u8(Tags::SAR_TOTAL_SEGMENTS, 1)).is_err());
+}
+
+#[test]
+fn test_number_portability() {
+    let tlv = tlv_new_dest_addr_np_resolution(NumberPortabilityResolution::Ported);
+    assert_eq!(tlv.value, vec![2]);
+    assert_eq!(
+        tlv_value_as_dest_addr_np_resolution(&tlv).unwrap(),
+        NumberPortabilityResolution::Ported
+    );
+
+    let tlv = tlv_new_dest_addr_np_country(44).unwrap();
+    assert_eq!(tlv.value, vec![0, 0, 0, 0, 44]);
+    assert_eq!(tlv_value_as_dest_addr_np_country(&tlv).unwrap(), 44);
+    assert!(tlv_new_dest_addr_np_country(1000).is_err());
+
+    let tlv = tlv_new_dest_addr_np_information(vec![7; 10]).unwrap();
+    assert_eq!(
+        tlv_value_as_dest_addr_np_information(&tlv).unwrap(),
+        vec![7; 10]
+    );
+    assert!(tlv_new_dest_addr_np_information(vec![7; 9]).is_err());
+}
+
+#[test]
+fn test_congestion_and_ussd() {
+    let tlv = tlv_new_congestion_state(85).unwrap();
+    assert_eq!(tlv_value_as_congestion_state(&tlv).unwrap(), 85);
+    assert!(tlv_new_congestion_state(101).is_err());
+
+    let tlv = tlv_new_ussd_service_op(UssdServiceOp::UssrConfirm);
+    assert_eq!(tlv.value, vec![18]);
+    assert_eq!(
+        tlv_value_as_ussd_service_op(&tlv).unwrap(),
+        UssdServiceOp::UssrConfirm
+    );
+    let vendor = tlv_new_u8(Tags::USSD_SERVICE_OP, 40);
+    assert_eq!(
+        tlv_value_as_ussd_service_op(&vendor).unwrap(),
+        UssdServiceOp::Other { value: 40 }
+    );
+}
+
+#[test]
+fn test_broadcast_scalars() {
+    let tlv = tlv_new_broadcast_channel_indicator(BroadcastChannelIndicator::Extended);
+    assert_eq!(
+        tlv_value_as_broadcast_channel_indicator(&tlv).unwrap(),
+        BroadcastChannelIndicator::Extended
+    );
+
+    let tlv = tlv_new_broadcast_message_class(BroadcastMessageClass::Class2);
+    assert_eq!(
+        tlv_value_as_broadcast_message_class(&tlv).unwrap(),
+        BroadcastMessageClass::Class2
+    );
+
+    let tlv = tlv_new_broadcast_rep_num(5);
+    assert_eq!((tlv.tag, tlv.value), (Tags::BROADCAST_REP_NUM, vec![0, 5]));
+
+    let tlv = tlv_new_broadcast_area_success(None).unwrap();
+    assert_eq!(tlv.value, vec![255]);
+    assert_eq!(tlv_value_as_broadcast_area_success(&tlv).unwrap(), None);
+    let tlv = tlv_new_broadcast_area_success(Some(60)).unwrap();
+    assert_eq!(tlv_value_as_broadcast_area_success(&tlv).unwrap(), Some(60));
+
+    let tlv = tlv_new_broadcast_end_time("251231235959000+".to_string()).unwrap();
+    assert_eq!(tlv.length, 17);
+    assert_eq!(
+        tlv_value_as_broadcast_end_time(&tlv).unwrap(),
+        "251231235959000+"
+    );
+    assert!(tlv_new_broadcast_end_time("000001000000000R".to_string()).is_err());
+
+    let tlv = tlv_new_broadcast_error_status(0x0000_0008);
+    assert_eq!(tlv_value_as_broadcast_error_status(&tlv).unwrap(), 8);
+    assert_eq!(
+        tlv_value_as_u32(&tlv_new_u32(Tags::QOS_TIME_TO_LIVE, 60)).unwrap(),
+        60
+    );
+}
+
+#[test]
+fn test_broadcast_structures() {
+    let area = BroadcastAreaIdentifier {
+        format: BroadcastAreaFormat::Polygon,
+        area: vec![0x10, 0x20, 0x30],
+    };
+    let tlv = tlv_new_broadcast_area_identifier(area.clone());
+    assert_eq!(tlv.tag, Tags::BROADCAST_AREA_IDENTIFIER);
+    assert_eq!(tlv.value, vec![0x02, 0x10, 0x20, 0x30]);
+    let decoded = tlv_decode(&tlv_encode(&tlv)).unwrap();
+    assert_eq!(
+        tlv_value_as_broadcast_area_identifier(&decoded).unwrap(),
+        area
+    );
+
+    let content_type = BroadcastContentType {
+        network_type: BroadcastNetworkType::Gsm,
+        service: 0x1001,
+    };
+    let tlv = tlv_new_broadcast_content_type(content_type);
+    assert_eq!(tlv.value, vec![0x01, 0x10, 0x01]);
+    assert_eq!(
+        tlv_value_as_broadcast_content_type(&tlv).unwrap(),
+        content_type
+    );
+
+    let interval = BroadcastFrequencyInterval {
+        unit: BroadcastFrequencyUnit::Hours,
+        value: 2,
+    };
+    let tlv = tlv_new_broadcast_frequency_interval(interval);
+    assert_eq!(tlv.tag, Tags::BROADCAST_FREQUENCY_INTERVAL);
+    assert_eq!(tlv.value, vec![0x0A, 0x00, 0x02]);
+    assert_eq!(
+        tlv_value_as_broadcast_frequency_interval(&tlv).unwrap(),
+        interval
+    );
+
+    // Unknown unit and wrong length
+    let bad = tlv_new(Tags::BROADCAST_FREQUENCY_INTERVAL, vec![0x05, 0x00, 0x01]);
+    assert!(tlv_value_as_broadcast_frequency_interval(&bad).is_err());
+    let bad = tlv_new(Tags::BROADCAST_CONTENT_TYPE, vec![0x01, 0x00]);
+    assert!(tlv_value_as_broadcast_content_type(&bad).is_err());
+    let bad = tlv_new(Tags::BROADCAST_AREA_IDENTIFIER, vec![]);
+    assert!(tlv_value_as_broadcast_area_identifier(&bad).is_err());
+}
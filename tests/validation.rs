@@ -0,0 +1,158 @@
+use smpp_codec_ffi::address::address_new;
+use smpp_codec_ffi::builders::SubmitSmBuilder;
+use smpp_codec_ffi::common::*;
+use smpp_codec_ffi::pdu::Pdu;
+use smpp_codec_ffi::session_pdu_ffi::BindRequest;
+use smpp_codec_ffi::submission_pdu_ffi::SubmitSmRequest;
+use smpp_codec_ffi::tlv::{tlv_new, tlv_new_u16, tlv_new_u8, Tags};
+use smpp_codec_ffi::validation::*;
+
+fn submit_sm() -> SubmitSmRequest {
+    SubmitSmBuilder::new()
+        .destination(address_new(Ton::Unknown, Npi::Unknown, "123".to_string()))
+        .text("Hello".to_string())
+        .build()
+        .unwrap()
+}
+
+fn statuses(pdu: SubmitSmRequest, version: SmppVersion) -> Vec<u32> {
+    validate_pdu(&Pdu::SubmitSm { pdu }, version)
+        .into_iter()
+        .map(|issue| issue.command_status)
+        .collect()
+}
+
+#[test]
+fn test_smpp_version_from_interface_version() {
+    assert_eq!(smpp_version_from_interface_version(0x33), SmppVersion::V33);
+    assert_eq!(smpp_version_from_interface_version(0x34), SmppVersion::V34);
+    assert_eq!(smpp_version_from_interface_version(0x50), SmppVersion::V50);
+    assert_eq!(SmppVersion::V50.interface_version(), 0x50);
+}
+
+#[test]
+fn test_validate_valid_submit_sm() {
+    assert!(statuses(submit_sm(), SmppVersion::V34).is_empty());
+}
+
+#[test]
+fn test_validate_bind_request_lengths() {
+    let bind = BindRequest {
+        sequence_number: 1,
+        mode: BindMode::Transmitter,
+        system_id: "a".repeat(16),
+        password: "b".repeat(9),
+        system_type: String::new(),
+        interface_version: 0x34,
+        addr_ton: Ton::Unknown,
+        addr_npi: Npi::Unknown,
+        address_range: String::new(),
+    };
+    let issues = validate_pdu(&Pdu::BindRequest { pdu: bind }, SmppVersion::V34);
+    assert_eq!(issues.len(), 2);
+    assert_eq!(issues[0].field, "system_id");
+    assert_eq!(issues[0].command_status, ESME_RINVSYSID);
+    assert_eq!(issues[1].command_status, ESME_RINVPASWD);
+}
+
+#[test]
+fn test_validate_submit_sm_fields() {
+    let mut pdu = submit_sm();
+    pdu.service_type = "TOOLONG".to_string();
+    pdu.destination_addr = "1".repeat(21);
+    pdu.registered_delivery = 0x21;
+    pdu.esm_class = 0x04;
+    pdu.priority_flag = 4;
+    pdu.replace_if_present_flag = 2;
+    pdu.validity_period = Some("tomorrow".to_string());
+    pdu.short_message = vec![0x41; 255];
+    assert_eq!(
+        statuses(pdu.clone(), SmppVersion::V34),
+        vec![
+            ESME_RINVSERVICETYPE,
+            ESME_RINVDSTADR,
+            ESME_RINVESMCLASS,
+            ESME_RINVPRTFLG,
+            ESME_RINVEXPIRY,
+            ESME_RINVREGDLVFLG,
+            ESME_RINVREPFLAG,
+            ESME_RINVMSGLEN,
+        ]
+    );
+
+    // Priority 4 is allowed by v5.0
+    assert!(!statuses(pdu, SmppVersion::V50).contains(&ESME_RINVPRTFLG));
+}
+
+#[test]
+fn test_validate_submit_sm_tlvs() {
+    let mut pdu = submit_sm();
+    pdu.tlvs = vec![tlv_new(Tags::MESSAGE_PAYLOAD, b"Hello".to_vec())];
+    assert_eq!(
+        statuses(pdu.clone(), SmppVersion::V34),
+        vec![ESME_ROPTPARNOTALLWD]
+    );
+
+    pdu.short_message = Vec::new();
+    assert!(statuses(pdu.clone(), SmppVersion::V34).is_empty());
+    assert_eq!(
+        statuses(pdu.clone(), SmppVersion::V33),
+        vec![ESME_ROPTPARNOTALLWD]
+    );
+
+    // Not allowed on submit_sm, v5.0 only, wrong length, vendor specific
+    pdu.tlvs = vec![
+        tlv_new_u8(Tags::DPF_RESULT, 1),
+        tlv_new_u16(Tags::BROADCAST_REP_NUM, 1),
+        tlv_new_u8(Tags::USER_MESSAGE_REFERENCE, 1),
+        tlv_new(0x1400, vec![1, 2, 3]),
+    ];
+    let issues = validate_pdu(&Pdu::SubmitSm { pdu }, SmppVersion::V34);
+    assert_eq!(issues.len(), 3);
+    assert_eq!(issues[0].field, "tlv:dpf_result");
+    assert_eq!(issues[1].field, "tlv:broadcast_rep_num");
+    assert_eq!(issues[2].command_status, ESME_RINVPARLEN);
+}
+
+#[test]
+fn test_validate_broadcast_requires_v50() {
+    let pdu = Pdu::CancelBroadcastSmResponse {
+        pdu: smpp_codec_ffi::ancillary_pdu_ffi::CancelBroadcastSmResponse {
+            sequence_number: 1,
+            command_status: 0,
+            status_description: "ESME_ROK".to_string(),
+        },
+    };
+    assert_eq!(
+        validate_pdu(&pdu, SmppVersion::V34)[0].command_status,
+        ESME_RINVCMDID
+    );
+    assert!(validate_pdu(&pdu, SmppVersion::V50).is_empty());
+}
+
+#[test]
+fn test_validate_broadcast_sm_mandatory_tlvs() {
+    let pdu = Pdu::BroadcastSm {
+        pdu: smpp_codec_ffi::broadcast_pdu_ffi::BroadcastSmRequest {
+            sequence_number: 1,
+            service_type: String::new(),
+            source_addr_ton: Ton::Unknown,
+            source_addr_npi: Npi::Unknown,
+            source_addr: String::new(),
+            message_id: String::new(),
+            priority_flag: 0,
+            schedule_delivery_time: None,
+            validity_period: None,
+            replace_if_present_flag: 0,
+            data_coding: 0,
+            sm_default_msg_id: 0,
+            optional_params: vec![tlv_new_u16(Tags::BROADCAST_REP_NUM, 1)],
+        },
+    };
+    let issues = validate_pdu(&pdu, SmppVersion::V50);
+    assert_eq!(issues.len(), 3);
+    assert!(issues
+        .iter()
+        .all(|issue| issue.command_status == ESME_RMISSINGOPTPARAM));
+    assert_eq!(issues[0].field, "tlv:broadcast_area_identifier");
+}